@@ -0,0 +1,4 @@
+-- Add failed_blocks column to papers table
+-- JSON array of analysis blocks whose output failed schema validation,
+-- e.g. [{"blockId": "related_papers", "error": "..."}]. NULL when every block succeeded.
+ALTER TABLE papers ADD COLUMN failed_blocks TEXT;
@@ -0,0 +1,33 @@
+-- Migration: User-defined analysis blocks and per-block results
+-- Custom blocks are rendered into the analysis prompt next to the built-in
+-- blocks. Their outputs have no papers column and are stored per block.
+
+CREATE TABLE IF NOT EXISTS custom_analysis_blocks (
+    id TEXT PRIMARY KEY,               -- Block ID, also the reply field name
+    name_en TEXT NOT NULL,
+    name_zh TEXT NOT NULL,
+    description_en TEXT NOT NULL DEFAULT '',
+    description_zh TEXT NOT NULL DEFAULT '',
+    instructions TEXT NOT NULL,        -- Task instruction shown to the LLM
+    json_schema TEXT NOT NULL,         -- JSON Schema of the block's output
+    default_enabled INTEGER NOT NULL DEFAULT 1,
+    default_mode TEXT NOT NULL DEFAULT 'both',  -- 'standard', 'full', 'both'
+    block_order INTEGER NOT NULL DEFAULT 100,
+    depends_on TEXT,                   -- JSON array of block IDs
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+-- One row per block output of an analysis run
+CREATE TABLE IF NOT EXISTS paper_analysis_blocks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    paper_id TEXT NOT NULL,
+    block_id TEXT NOT NULL,
+    depth TEXT NOT NULL,               -- 'standard' or 'full'
+    language TEXT NOT NULL,
+    model TEXT,
+    json TEXT NOT NULL,                -- Block output as JSON
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_paper_analysis_blocks_paper ON paper_analysis_blocks(paper_id, block_id, created_at DESC);
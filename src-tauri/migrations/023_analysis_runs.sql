@@ -0,0 +1,24 @@
+-- Migration: Analysis run history
+-- One row per analysis of a paper, so re-running an analysis keeps the
+-- previous output, model and prompt around for comparison and restore.
+
+CREATE TABLE IF NOT EXISTS analysis_runs (
+    id TEXT PRIMARY KEY,
+    paper_id TEXT NOT NULL,
+    provider TEXT NOT NULL,            -- 'glm', 'claude', 'openai'
+    model TEXT NOT NULL,
+    depth TEXT NOT NULL,               -- 'standard' or 'full'
+    language TEXT NOT NULL,
+    prompt_hash TEXT NOT NULL,
+    result TEXT NOT NULL,              -- JSON object of every accepted reply field
+    failed_blocks TEXT,                -- JSON array, NULL when every block succeeded
+    restored_from TEXT,                -- Run this one was restored from
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_analysis_runs_paper ON analysis_runs(paper_id, created_at DESC);
+
+-- Block outputs belong to the run that produced them
+ALTER TABLE paper_analysis_blocks ADD COLUMN run_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_paper_analysis_blocks_run ON paper_analysis_blocks(run_id);
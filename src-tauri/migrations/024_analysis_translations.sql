@@ -0,0 +1,7 @@
+-- Migration: Translated analysis runs
+-- A run made by translating stored block outputs records the language it
+-- was translated from. NULL for runs that analyzed the paper itself.
+
+ALTER TABLE analysis_runs ADD COLUMN translated_from TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_paper_analysis_blocks_language ON paper_analysis_blocks(paper_id, language, block_id);
@@ -0,0 +1,29 @@
+-- Migration: LLM token usage and model prices
+-- Every LLM call records its token counts so spend can be reported per day,
+-- per topic and per fetch. Costs are computed from llm_model_prices when
+-- reporting, so a price entered later also applies to earlier calls.
+
+CREATE TABLE IF NOT EXISTS llm_usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    analysis_type TEXT NOT NULL,       -- 'relevance', 'standard', 'full', ...
+    paper_id TEXT,
+    fetch_id TEXT,                     -- fetch_history.id of the fetch that made the call
+    topics TEXT NOT NULL DEFAULT '[]', -- JSON array of topic keys
+    prompt_tokens INTEGER NOT NULL,
+    completion_tokens INTEGER NOT NULL,
+    estimated INTEGER NOT NULL DEFAULT 0, -- 1 when the provider reported no usage
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_llm_usage_created_at ON llm_usage(created_at);
+CREATE INDEX IF NOT EXISTS idx_llm_usage_fetch ON llm_usage(fetch_id);
+CREATE INDEX IF NOT EXISTS idx_llm_usage_paper ON llm_usage(paper_id);
+
+CREATE TABLE IF NOT EXISTS llm_model_prices (
+    model TEXT PRIMARY KEY,
+    input_per_million REAL NOT NULL,   -- USD per million prompt tokens
+    output_per_million REAL NOT NULL,  -- USD per million completion tokens
+    updated_at TEXT NOT NULL
+);
@@ -0,0 +1,15 @@
+-- Migration: Citations
+-- References parsed from the bibliography of a paper's LaTeX source or HTML.
+-- Cited works are matched against the library by arXiv ID or title when
+-- queried, so papers added to the library later are linked as well.
+
+CREATE TABLE IF NOT EXISTS citations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    citing_id TEXT NOT NULL,           -- papers.id of the citing paper
+    cited_arxiv_id TEXT,               -- without version suffix
+    cited_title TEXT,
+    doi TEXT                           -- lowercase
+);
+
+CREATE INDEX IF NOT EXISTS idx_citations_citing ON citations(citing_id);
+CREATE INDEX IF NOT EXISTS idx_citations_cited_arxiv ON citations(cited_arxiv_id);
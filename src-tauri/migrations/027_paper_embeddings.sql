@@ -0,0 +1,16 @@
+-- Migration: Paper embeddings
+-- One vector per paper and embedding model, computed from the title,
+-- abstract and AI summary. Vectors of different models are never compared,
+-- so switching models keeps the old vectors for switching back.
+
+CREATE TABLE IF NOT EXISTS paper_embeddings (
+    paper_id TEXT NOT NULL,
+    model TEXT NOT NULL,               -- 'local-hash-v1' or 'api:{model}'
+    dimensions INTEGER NOT NULL,
+    vector BLOB NOT NULL,              -- little-endian f32 values
+    content_hash TEXT NOT NULL,        -- hash of the embedded text, to skip unchanged papers
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (paper_id, model)
+);
+
+CREATE INDEX IF NOT EXISTS idx_paper_embeddings_model ON paper_embeddings(model);
@@ -0,0 +1,10 @@
+-- Migration: Paper opens
+-- How often and when each paper was opened in the app. Opening a paper is
+-- one of the signals the recommender learns from, with collections, deep
+-- analysis and spam.
+
+CREATE TABLE IF NOT EXISTS paper_opens (
+    paper_id TEXT PRIMARY KEY,
+    open_count INTEGER NOT NULL DEFAULT 0,
+    last_opened_at TEXT NOT NULL
+);
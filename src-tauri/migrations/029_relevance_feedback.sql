@@ -0,0 +1,15 @@
+-- Migration: Relevance feedback
+-- Explicit thumbs-up/down on whether a paper belongs to a topic. Used as
+-- few-shot examples in the relevance prompt, to calibrate per-topic
+-- thresholds and to measure past relevance decisions.
+
+CREATE TABLE IF NOT EXISTS relevance_feedback (
+    paper_id TEXT NOT NULL,
+    topic TEXT NOT NULL,               -- topic key
+    relevant BOOLEAN NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (paper_id, topic)
+);
+
+CREATE INDEX IF NOT EXISTS idx_relevance_feedback_topic ON relevance_feedback(topic, updated_at DESC);
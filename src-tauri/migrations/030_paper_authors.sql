@@ -0,0 +1,16 @@
+-- Migration: Paper authors
+-- One row per author of a paper, in byline order, so papers can be looked
+-- up by author without scanning the authors JSON. The surname is folded
+-- to lowercase without diacritics and narrows down the candidates that
+-- full name matching (initials, name order) is applied to.
+
+CREATE TABLE IF NOT EXISTS paper_authors (
+    paper_id TEXT NOT NULL,
+    position INTEGER NOT NULL,         -- 0-based position in the byline
+    name TEXT NOT NULL,
+    affiliation TEXT,
+    surname TEXT NOT NULL,
+    PRIMARY KEY (paper_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_paper_authors_surname ON paper_authors(surname);
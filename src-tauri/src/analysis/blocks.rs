@@ -26,6 +26,7 @@ pub fn ai_summary_block() -> AnalysisBlockConfig {
         order: 1,
         depends_on: None,
         output_schema: OutputSchema::SingleString,
+        custom: None,
     }
 }
 
@@ -48,6 +49,7 @@ pub fn topics_block() -> AnalysisBlockConfig {
         order: 2,
         depends_on: None,
         output_schema: OutputSchema::StringArray,
+        custom: None,
     }
 }
 
@@ -74,6 +76,7 @@ pub fn key_insights_block() -> AnalysisBlockConfig {
         order: 3,
         depends_on: None,
         output_schema: OutputSchema::StringArray,
+        custom: None,
     }
 }
 
@@ -96,6 +99,7 @@ pub fn quality_assessment_block() -> AnalysisBlockConfig {
         order: 4,
         depends_on: Some(vec!["ai_summary".to_string()]),
         output_schema: OutputSchema::StructuredQuality,
+        custom: None,
     }
 }
 
@@ -118,6 +122,7 @@ pub fn code_links_block() -> AnalysisBlockConfig {
         order: 5,
         depends_on: None,
         output_schema: OutputSchema::CodeLinks,
+        custom: None,
     }
 }
 
@@ -140,6 +145,7 @@ pub fn engineering_notes_block() -> AnalysisBlockConfig {
         order: 6,
         depends_on: None,
         output_schema: OutputSchema::SingleString,
+        custom: None,
     }
 }
 
@@ -166,6 +172,7 @@ pub fn algorithms_block() -> AnalysisBlockConfig {
         order: 7,
         depends_on: None,
         output_schema: OutputSchema::AlgorithmList,
+        custom: None,
     }
 }
 
@@ -188,6 +195,7 @@ pub fn complexity_block() -> AnalysisBlockConfig {
         order: 8,
         depends_on: None,
         output_schema: OutputSchema::SingleString,
+        custom: None,
     }
 }
 
@@ -210,6 +218,7 @@ pub fn flowchart_block() -> AnalysisBlockConfig {
         order: 9,
         depends_on: Some(vec!["algorithms".to_string()]),
         output_schema: OutputSchema::Flowchart,
+        custom: None,
     }
 }
 
@@ -232,6 +241,7 @@ pub fn formulas_block() -> AnalysisBlockConfig {
         order: 10,
         depends_on: None,
         output_schema: OutputSchema::FormulaList,
+        custom: None,
     }
 }
 
@@ -258,6 +268,7 @@ pub fn related_papers_block() -> AnalysisBlockConfig {
         order: 11,
         depends_on: Some(vec!["ai_summary".to_string()]),
         output_schema: OutputSchema::PaperReferenceList,
+        custom: None,
     }
 }
 
@@ -0,0 +1,246 @@
+//! Map-reduce analysis for papers longer than the context window
+//! Groups of sections are summarized one call each ("map"), then the analysis
+//! blocks run over the combined summaries ("reduce")
+
+use crate::analysis::context::{self, ContentSection, FittedContent};
+use crate::analysis::AnalysisDepth;
+use crate::llm::{LlmClient, LlmError};
+use crate::llm_cache::LlmCache;
+
+/// Analysis type of the summary calls (sizes the reply, picks the deep model)
+pub const SUMMARY_ANALYSIS_TYPE: &str = "summary";
+
+/// Largest group summarized in one call; bigger groups give vaguer summaries
+const MAX_GROUP_TOKENS: usize = 32_000;
+
+/// Smallest group worth a summary call; below it the context window is too
+/// small to chunk and the content is only fitted
+const MIN_GROUP_TOKENS: usize = 1_000;
+
+/// Bounds of the summary length asked for per group, in words
+const MIN_SUMMARY_WORDS: usize = 200;
+const MAX_SUMMARY_WORDS: usize = 2_000;
+
+/// Consecutive sections summarized in one call
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionGroup {
+    pub names: Vec<String>,
+    pub text: String,
+}
+
+/// Paper content for the analysis prompt
+///
+/// Content that fits is used as-is. For full analysis of a paper that does
+/// not fit, and with `chunked` on, each group of sections is summarized
+/// first and the summaries are fitted instead. Summaries are cached per
+/// group, so a rerun over the same content makes no summary calls. A window
+/// that leaves less than `MIN_GROUP_TOKENS` per group is not chunked.
+pub async fn prepare_content(
+    client: &LlmClient,
+    paper_id: &str,
+    title: &str,
+    depth: AnalysisDepth,
+    scaffold: &str,
+    content: &str,
+    chunked: bool,
+) -> Result<FittedContent, LlmError> {
+    let fitted = context::fit_content(client, depth, scaffold, content);
+    if fitted.is_complete() || !chunked || depth != AnalysisDepth::Full {
+        return Ok(fitted);
+    }
+
+    let count = |text: &str| client.count_tokens(SUMMARY_ANALYSIS_TYPE, text);
+    let group_budget = context::content_budget(client, SUMMARY_ANALYSIS_TYPE, &build_summary_prompt(title, "", 1, 1, &[], 0))
+        .min(MAX_GROUP_TOKENS);
+    if group_budget < MIN_GROUP_TOKENS {
+        eprintln!("[prepare_content] {} does not fit, but {} tokens per group are too few to summarize it",
+            paper_id, group_budget);
+        return Ok(fitted);
+    }
+    let sections = context::select_sections(context::split_sections(content), depth);
+    let groups = group_sections(&sections, group_budget, count);
+
+    // Share the reduce prompt evenly between the summaries (about 4 words per 3 tokens)
+    let reduce_budget = context::content_budget(client, depth.as_str(), scaffold);
+    let words = (reduce_budget / groups.len().max(1) * 3 / 4).clamp(MIN_SUMMARY_WORDS, MAX_SUMMARY_WORDS);
+
+    eprintln!("[prepare_content] {} does not fit ({} sections dropped), summarizing {} groups of sections",
+        paper_id, fitted.dropped.len(), groups.len());
+
+    let cache = LlmCache::new().ok();
+    let mut summaries = Vec::with_capacity(groups.len());
+    for (i, group) in groups.iter().enumerate() {
+        let prompt = build_summary_prompt(title, &group.text, i + 1, groups.len(), &group.names, words);
+        let summary = summarize_group(client, cache.as_ref(), paper_id, i, &prompt).await?;
+        summaries.push(format!("## Summary of {}\n\n{}", group.names.join(", "), summary.trim()));
+    }
+
+    Ok(context::fit_content(client, depth, scaffold, &summaries.join("\n\n")))
+}
+
+/// Summary of one group, from the cache when the prompt is unchanged
+async fn summarize_group(
+    client: &LlmClient,
+    cache: Option<&LlmCache>,
+    paper_id: &str,
+    index: usize,
+    prompt: &str,
+) -> Result<String, LlmError> {
+    let mode = format!("{}_{}", SUMMARY_ANALYSIS_TYPE, index);
+    if let Some(summary) = cache.and_then(|cache| cache.load(paper_id, &mode, prompt).ok()) {
+        eprintln!("[summarize_group] Using cached summary {} of {}", index, paper_id);
+        return Ok(summary);
+    }
+
+    let summary = client.send_chat_request(prompt, SUMMARY_ANALYSIS_TYPE).await?;
+    if let Some(cache) = cache {
+        let model = client.model_for(SUMMARY_ANALYSIS_TYPE);
+        if let Err(e) = cache.save(paper_id, &mode, &summary, prompt, client.provider().as_str(), Some(model)) {
+            eprintln!("[summarize_group] Warning: Failed to save summary to cache: {}", e);
+        }
+    }
+    Ok(summary)
+}
+
+/// Group consecutive sections into chunks of at most `budget` tokens
+///
+/// A section larger than the budget is split into parts of its own.
+pub fn group_sections(
+    sections: &[ContentSection],
+    budget: usize,
+    count: impl Fn(&str) -> usize,
+) -> Vec<SectionGroup> {
+    let mut groups = Vec::new();
+    let mut current = SectionGroup { names: Vec::new(), text: String::new() };
+    let mut current_tokens = 0;
+
+    for section in sections {
+        let name = section.title.clone().unwrap_or_else(|| "Front matter".to_string());
+        let tokens = count(&section.text);
+
+        if current_tokens + tokens > budget && !current.names.is_empty() {
+            groups.push(std::mem::replace(&mut current, SectionGroup { names: Vec::new(), text: String::new() }));
+            current_tokens = 0;
+        }
+
+        if tokens > budget {
+            let parts = context::split_to_fit(&section.text, budget, &count);
+            let total = parts.len();
+            groups.extend(parts.into_iter().enumerate().map(|(i, text)| SectionGroup {
+                names: vec![format!("{} (part {} of {})", name, i + 1, total)],
+                text,
+            }));
+            continue;
+        }
+
+        current.names.push(name);
+        current.text.push_str(&section.text);
+        current_tokens += tokens;
+    }
+
+    if !current.names.is_empty() {
+        groups.push(current);
+    }
+    groups
+}
+
+/// Build the prompt that summarizes part `part` of `total` of a paper
+pub fn build_summary_prompt(
+    title: &str,
+    content: &str,
+    part: usize,
+    total: usize,
+    sections: &[String],
+    words: usize,
+) -> String {
+    format!(
+        "You are a research assistant condensing a long paper so it can be analyzed as a whole. \
+        SUMMARIZE PAPER PART {} of {}.\n\n\
+        Paper Title:\n{}\n\n\
+        Sections: {}\n\n\
+        ===== SUMMARY RULES =====\n\
+        1. Keep the problem, methods, algorithms, key equations (in LaTeX), experimental setup, datasets and quantitative results\n\
+        2. Keep names of models, datasets, baselines, frameworks and code links exactly as written\n\
+        3. Write plain English prose of at most {} words - no JSON, no markdown headings\n\n\
+        ===== CONTENT =====\n{}",
+        part,
+        total,
+        title,
+        sections.join(", "),
+        words,
+        content
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::LLMProvider;
+
+    fn words(text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+
+    fn section(title: &str, words: usize) -> ContentSection {
+        ContentSection {
+            title: Some(title.to_string()),
+            text: format!("## {}\n\n{}\n\n", title, "Word. ".repeat(words)),
+        }
+    }
+
+    #[test]
+    fn test_group_sections() {
+        let sections = vec![
+            section("Introduction", 40),
+            section("Method", 40),
+            section("Experiments", 250),
+            section("Conclusion", 20),
+        ];
+        let groups = group_sections(&sections, 100, words);
+        let names: Vec<Vec<&str>> = groups.iter().map(|g| g.names.iter().map(String::as_str).collect()).collect();
+        assert_eq!(
+            names,
+            vec![
+                vec!["Introduction", "Method"],
+                vec!["Experiments (part 1 of 3)"],
+                vec!["Experiments (part 2 of 3)"],
+                vec!["Experiments (part 3 of 3)"],
+                vec!["Conclusion"],
+            ]
+        );
+        assert!(groups.iter().all(|g| words(&g.text) <= 100));
+        assert!(groups[0].text.contains("## Method"));
+    }
+
+    #[test]
+    fn test_summary_prompt() {
+        let prompt = build_summary_prompt(
+            "Sparse Routing",
+            "## Method\n\nWe route tokens.",
+            2,
+            3,
+            &["Method".to_string()],
+            500,
+        );
+        assert!(prompt.contains("SUMMARIZE PAPER PART 2 of 3"));
+        assert!(prompt.contains("Sections: Method"));
+        assert!(prompt.contains("at most 500 words"));
+        assert!(prompt.ends_with("We route tokens."));
+    }
+    #[tokio::test]
+    async fn test_small_window_is_fitted_without_summaries() {
+        // The key is not real, so a summary call would fail the test
+        let client = LlmClient::new(LLMProvider::Glm, "test-key".to_string(), None, None)
+            .unwrap()
+            .with_context_window(Some(1_200));
+        let content = (1..=10)
+            .map(|i| format!("## Section {}\n\n{}", i, "Word. ".repeat(400)))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let fitted = prepare_content(&client, "2401.00001", "Sparse Routing", AnalysisDepth::Full, "", &content, true)
+            .await
+            .unwrap();
+        assert!(!fitted.is_complete());
+    }
+}
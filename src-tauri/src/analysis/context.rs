@@ -0,0 +1,352 @@
+//! Fitting paper content into the model's context window
+//!
+//! Content is split into sections (Markdown `##` headings from the HTML
+//! parser or LaTeX `\section`s) and filled in by importance: introduction and
+//! conclusion first, related work and front matter last. Sections that do not
+//! fit are dropped whole; only the last section taken may be cut, and then at
+//! the end of a sentence.
+
+use crate::analysis::AnalysisDepth;
+use crate::llm::LlmClient;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// A section is only cut short if at least this many tokens of it fit
+const MIN_PARTIAL_TOKENS: usize = 256;
+
+/// Share of the context window kept free for counting errors (1/20 = 5%)
+const SAFETY_MARGIN_DIVISOR: usize = 20;
+
+/// One section of the paper content, including its heading line
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentSection {
+    /// `None` for text before the first heading
+    pub title: Option<String>,
+    pub text: String,
+}
+
+impl ContentSection {
+    fn name(&self) -> &str {
+        self.title.as_deref().unwrap_or("front matter")
+    }
+}
+
+/// Content that fits the token budget
+#[derive(Debug, Clone, Default)]
+pub struct FittedContent {
+    pub text: String,
+    pub tokens: usize,
+    /// Sections left out, in document order
+    pub dropped: Vec<String>,
+    /// Section cut at a sentence boundary to use up the budget
+    pub truncated: Option<String>,
+}
+
+impl FittedContent {
+    /// True when nothing had to be left out
+    pub fn is_complete(&self) -> bool {
+        self.dropped.is_empty() && self.truncated.is_none()
+    }
+}
+
+fn heading_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?m)^(?:##[ \t]+(.+?)[ \t]*$|\\section\*?\{([^}]*)\})").expect("valid heading regex")
+    })
+}
+
+/// Split content at Markdown level-2 and LaTeX `\section` headings
+///
+/// Subsections stay part of their section.
+pub fn split_sections(content: &str) -> Vec<ContentSection> {
+    let headings: Vec<(usize, String)> = heading_regex()
+        .captures_iter(content)
+        .map(|caps| {
+            let start = caps.get(0).map_or(0, |m| m.start());
+            let title = caps.get(1).or_else(|| caps.get(2)).map_or("", |m| m.as_str());
+            (start, title.trim().to_string())
+        })
+        .collect();
+
+    let mut sections = Vec::new();
+    let first_start = headings.first().map_or(content.len(), |(start, _)| *start);
+    if !content[..first_start].trim().is_empty() {
+        sections.push(ContentSection { title: None, text: content[..first_start].to_string() });
+    }
+    for (i, (start, title)) in headings.iter().enumerate() {
+        let end = headings.get(i + 1).map_or(content.len(), |(next, _)| *next);
+        sections.push(ContentSection { title: Some(title.clone()), text: content[*start..end].to_string() });
+    }
+    sections
+}
+
+/// Importance of a section, lower is kept first
+fn section_rank(section: &ContentSection) -> u8 {
+    let Some(title) = &section.title else {
+        return 7;
+    };
+    let title = title.to_lowercase();
+    let has = |words: &[&str]| words.iter().any(|word| title.contains(word));
+
+    if has(&["introduction"]) {
+        0
+    } else if has(&["conclusion", "concluding", "future work"]) {
+        1
+    } else if has(&["related work", "background", "preliminar", "prior work", "acknowledg", "reference", "appendix"]) {
+        6
+    } else if has(&["method", "approach", "framework", "algorithm", "model", "design", "architecture"]) {
+        2
+    } else if has(&["experiment", "result", "evaluation", "benchmark", "ablation"]) {
+        3
+    } else if has(&["discussion", "limitation", "analysis"]) {
+        4
+    } else {
+        5
+    }
+}
+
+/// Sections worth sending for `depth`
+///
+/// The abstract is already part of the prompt. Standard analysis reads the
+/// introduction and conclusion when the paper has them.
+pub fn select_sections(sections: Vec<ContentSection>, depth: AnalysisDepth) -> Vec<ContentSection> {
+    let sections: Vec<ContentSection> = sections
+        .into_iter()
+        .filter(|section| !section.title.as_deref().is_some_and(|t| t.eq_ignore_ascii_case("abstract")))
+        .collect();
+
+    if depth == AnalysisDepth::Standard && sections.iter().any(|s| section_rank(s) <= 1) {
+        sections.into_iter().filter(|s| section_rank(s) <= 1).collect()
+    } else {
+        sections
+    }
+}
+
+/// Fit `sections` into `budget` tokens as counted by `count`
+///
+/// Sections are taken by importance and rendered in document order, followed
+/// by a note naming the ones left out.
+pub fn fit_sections(
+    sections: &[ContentSection],
+    budget: usize,
+    count: impl Fn(&str) -> usize,
+) -> FittedContent {
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    order.sort_by_key(|&i| (section_rank(&sections[i]), i));
+
+    let mut kept: Vec<Option<String>> = vec![None; sections.len()];
+    let mut truncated = None;
+    let mut remaining = budget;
+    for i in order {
+        let text = &sections[i].text;
+        let tokens = count(text);
+        if tokens <= remaining {
+            remaining -= tokens;
+            kept[i] = Some(text.clone());
+        } else if truncated.is_none() && remaining >= MIN_PARTIAL_TOKENS {
+            let marker = " [...]";
+            let budget = remaining.saturating_sub(count(marker));
+            if let Some(end) = sentence_prefix_len(text, budget, &count) {
+                let prefix = format!("{}{}", text[..end].trim_end(), marker);
+                remaining = remaining.saturating_sub(count(&prefix));
+                kept[i] = Some(prefix);
+                truncated = Some(sections[i].name().to_string());
+            }
+        }
+    }
+
+    let dropped: Vec<String> = sections
+        .iter()
+        .zip(&kept)
+        .filter(|(_, kept)| kept.is_none())
+        .map(|(section, _)| section.name().to_string())
+        .collect();
+
+    let mut parts: Vec<String> = kept.into_iter().flatten().map(|text| text.trim_end().to_string()).collect();
+    if !dropped.is_empty() {
+        parts.push(format!("[Omitted to fit the context window: {}]", dropped.join(", ")));
+    }
+    let text = parts.join("\n\n");
+    let tokens = count(&text);
+
+    FittedContent { text, tokens, dropped, truncated }
+}
+
+/// Length in bytes of the longest prefix of `text` that ends at a sentence
+/// boundary and fits `budget` tokens
+fn sentence_prefix_len(text: &str, budget: usize, count: &impl Fn(&str) -> usize) -> Option<usize> {
+    let ends = sentence_ends(text);
+
+    // Binary search over the sentence ends: token counts grow with the prefix
+    let (mut low, mut high) = (0, ends.len());
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if count(&text[..ends[mid - 1]]) <= budget {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    (low > 0).then(|| ends[low - 1])
+}
+
+/// Split `text` into consecutive pieces of at most `budget` tokens
+///
+/// Pieces end at sentence boundaries; a single sentence longer than the
+/// budget is cut at a character boundary.
+pub fn split_to_fit(text: &str, budget: usize, count: impl Fn(&str) -> usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut rest = text;
+    loop {
+        let tokens = count(rest);
+        if tokens <= budget {
+            break;
+        }
+        let end = sentence_prefix_len(rest, budget, &count).unwrap_or_else(|| {
+            let mut end = (rest.len() * budget / tokens).max(1);
+            while !rest.is_char_boundary(end) {
+                end += 1;
+            }
+            end
+        });
+        pieces.push(rest[..end].to_string());
+        rest = &rest[end..];
+    }
+    if !rest.trim().is_empty() {
+        pieces.push(rest.to_string());
+    }
+    pieces
+}
+
+/// Byte offsets just past each sentence end (`.`, `?`, `!` before whitespace, or a blank line)
+fn sentence_ends(text: &str) -> Vec<usize> {
+    let bytes = text.as_bytes();
+    let mut ends = Vec::new();
+    for (i, &byte) in bytes.iter().enumerate() {
+        let next_is_space = bytes.get(i + 1).is_some_and(|b| b.is_ascii_whitespace());
+        let is_end = matches!(byte, b'.' | b'?' | b'!') && next_is_space
+            || byte == b'\n' && bytes.get(i + 1) == Some(&b'\n');
+        if is_end {
+            ends.push(i + 1);
+        }
+    }
+    ends
+}
+
+/// Tokens left for content in a prompt of `analysis_type`
+///
+/// `scaffold` is the prompt without content. The reply limit (at most a
+/// quarter of the window) and a 5% margin are kept free.
+pub fn content_budget(client: &LlmClient, analysis_type: &str, scaffold: &str) -> usize {
+    let window = client.context_window(analysis_type);
+    let reply = client.max_output_tokens(analysis_type).min(window / 4);
+    let prompt = client.count_tokens(analysis_type, scaffold);
+    window.saturating_sub(reply + prompt + window / SAFETY_MARGIN_DIVISOR)
+}
+
+/// Fit paper content into what the prompt leaves of the context window
+pub fn fit_content(client: &LlmClient, depth: AnalysisDepth, scaffold: &str, content: &str) -> FittedContent {
+    let analysis_type = depth.as_str();
+    let budget = content_budget(client, analysis_type, scaffold);
+
+    let sections = select_sections(split_sections(content), depth);
+    let fitted = fit_sections(&sections, budget, |text| client.count_tokens(analysis_type, text));
+
+    eprintln!(
+        "[fit_content] {} of {} tokens used for content, dropped: {:?}, truncated: {:?}",
+        fitted.tokens, budget, fitted.dropped, fitted.truncated
+    );
+    fitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One token per word keeps the expected budgets readable
+    fn words(text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+
+    fn paper() -> String {
+        let body = |word: &str| format!("{} sentence one. {} sentence two.\n", word, word).repeat(100);
+        format!(
+            "## Abstract\n\nShort abstract.\n\n## Introduction\n\n{}\n## Related Work\n\n{}\n## Method\n\n{}\n## Conclusion\n\n{}",
+            body("Intro"),
+            body("Related"),
+            body("Method"),
+            body("Conclusion")
+        )
+    }
+
+    #[test]
+    fn test_split_sections() {
+        let latex = "\\documentclass{article}\n\\section{Introduction}\nIntro.\n\\subsection{Setup}\nSetup.\n\\section*{Conclusion}\nDone.";
+        let sections = split_sections(latex);
+        let titles: Vec<_> = sections.iter().map(|s| s.title.as_deref()).collect();
+        assert_eq!(titles, vec![None, Some("Introduction"), Some("Conclusion")]);
+        assert!(sections[1].text.contains("\\subsection{Setup}"));
+
+        let markdown = "## Introduction\n\nIntro.\n\n### Setup\n\nSetup.\n\n## Conclusion\n\nDone.";
+        assert_eq!(split_sections(markdown).len(), 2);
+    }
+
+    #[test]
+    fn test_everything_fits() {
+        let sections = select_sections(split_sections(&paper()), AnalysisDepth::Full);
+        let fitted = fit_sections(&sections, 10_000, words);
+        assert!(fitted.dropped.is_empty());
+        assert!(fitted.truncated.is_none());
+        assert!(!fitted.text.contains("Short abstract"));
+        assert!(fitted.text.find("## Related Work") < fitted.text.find("## Method"));
+    }
+
+    #[test]
+    fn test_least_important_sections_are_dropped() {
+        let sections = select_sections(split_sections(&paper()), AnalysisDepth::Full);
+        // Sections take 602 words each, so related work does not fit
+        let fitted = fit_sections(&sections, 1_900, words);
+        assert_eq!(fitted.dropped, vec!["Related Work".to_string()]);
+        assert!(fitted.text.contains("Method sentence two."));
+        assert!(fitted.text.ends_with("[Omitted to fit the context window: Related Work]"));
+    }
+
+    #[test]
+    fn test_section_is_cut_at_sentence_end() {
+        let sections = select_sections(split_sections(&paper()), AnalysisDepth::Full);
+        // Intro and conclusion fit, 300 words are left for the method
+        let fitted = fit_sections(&sections, 2 * 602 + 300, words);
+        assert_eq!(fitted.truncated.as_deref(), Some("Method"));
+        assert_eq!(fitted.dropped, vec!["Related Work".to_string()]);
+        assert!(fitted.text.contains("sentence one. [...]") || fitted.text.contains("sentence two. [...]"));
+        // Only the omission note goes over the budget
+        assert!(fitted.tokens <= 2 * 602 + 300 + 8);
+    }
+
+    #[test]
+    fn test_split_to_fit() {
+        let text = "One two three. Four five six. Seven eight nine.";
+        assert_eq!(split_to_fit(text, 10, words), vec![text.to_string()]);
+        assert_eq!(
+            split_to_fit(text, 6, words),
+            vec!["One two three. Four five six.", " Seven eight nine."]
+        );
+        // A sentence longer than the budget is cut anyway
+        let pieces = split_to_fit("a b c d e f g h", 4, words);
+        assert!(pieces.len() >= 2);
+        assert_eq!(pieces.concat(), "a b c d e f g h");
+    }
+
+    #[test]
+    fn test_standard_keeps_intro_and_conclusion() {
+        let sections = select_sections(split_sections(&paper()), AnalysisDepth::Standard);
+        let titles: Vec<_> = sections.iter().filter_map(|s| s.title.as_deref()).collect();
+        assert_eq!(titles, vec!["Introduction", "Conclusion"]);
+
+        // Without either, every section is read
+        let sections = select_sections(split_sections("## Method\n\nA.\n\n## Results\n\nB."), AnalysisDepth::Standard);
+        assert_eq!(sections.len(), 2);
+    }
+}
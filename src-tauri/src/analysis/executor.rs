@@ -0,0 +1,358 @@
+//! Per-block analysis execution
+//! Each block is its own LLM call (the basic blocks share one), started once
+//! the blocks it `depends_on` have finished; independent calls run concurrently
+
+use crate::analysis::partial::{self, BlockOutcome};
+use crate::analysis::{is_basic_block, prompt, schema, AnalysisBlockConfig, PromptContext};
+use crate::llm::{LlmClient, LlmError};
+use crate::models::FailedBlock;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+/// Maximum LLM calls in flight for one paper
+pub const MAX_CONCURRENT_BLOCK_CALLS: usize = 3;
+
+/// How the enabled blocks are sent to the LLM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockExecutionMode {
+    /// Every block in one prompt (default)
+    Combined,
+    /// One call per block, in dependency order
+    PerBlock,
+}
+
+impl BlockExecutionMode {
+    /// Parse the `block_execution_mode` setting
+    pub fn from_setting(value: Option<&str>) -> Self {
+        match value {
+            Some("per_block") => BlockExecutionMode::PerBlock,
+            _ => BlockExecutionMode::Combined,
+        }
+    }
+}
+
+/// One LLM call: a single block, or the basic blocks together
+#[derive(Debug, Clone)]
+pub struct CallUnit {
+    pub blocks: Vec<AnalysisBlockConfig>,
+    /// Indices of the units this one waits for
+    pub depends_on: Vec<usize>,
+}
+
+/// Group blocks into calls and resolve `depends_on` between them
+///
+/// Units are ordered by block order. Dependencies on blocks that are not
+/// enabled are ignored.
+pub fn plan_calls(blocks: &[AnalysisBlockConfig]) -> Vec<CallUnit> {
+    let mut sorted_blocks = blocks.to_vec();
+    sorted_blocks.sort_by_key(|b| b.order);
+
+    let mut units: Vec<CallUnit> = Vec::new();
+    let mut basic_unit: Option<usize> = None;
+    let mut unit_of: HashMap<String, usize> = HashMap::new();
+
+    for block in sorted_blocks {
+        let index = match basic_unit {
+            Some(index) if is_basic_block(&block.id) => index,
+            _ => {
+                units.push(CallUnit { blocks: Vec::new(), depends_on: Vec::new() });
+                if is_basic_block(&block.id) {
+                    basic_unit = Some(units.len() - 1);
+                }
+                units.len() - 1
+            }
+        };
+        unit_of.insert(block.id.clone(), index);
+        units[index].blocks.push(block);
+    }
+
+    for (index, unit) in units.iter_mut().enumerate() {
+        let mut depends_on: Vec<usize> = unit
+            .blocks
+            .iter()
+            .flat_map(|b| b.depends_on.iter().flatten())
+            .filter_map(|id| unit_of.get(id).copied())
+            .filter(|&dep| dep != index)
+            .collect();
+        depends_on.sort_unstable();
+        depends_on.dedup();
+        unit.depends_on = depends_on;
+    }
+
+    units
+}
+
+/// Run every block as its own call, in dependency order
+///
+/// Each call validates its reply per block and re-requests failed blocks once.
+/// Blocks whose call failed, that depend on a failed block, or that sit in a
+/// dependency cycle are recorded as failed. Returns the last request error
+/// only if no block succeeded.
+pub async fn run_per_block(
+    client: &LlmClient,
+    context: Arc<PromptContext>,
+    blocks: &[AnalysisBlockConfig],
+    structured: bool,
+) -> Result<BlockOutcome, LlmError> {
+    let units = plan_calls(blocks);
+    eprintln!("[run_per_block] {} blocks in {} calls", blocks.len(), units.len());
+
+    let mut waiting_on: Vec<usize> = units.iter().map(|u| u.depends_on.len()).collect();
+    let mut started = vec![false; units.len()];
+    let mut outputs: HashMap<String, Map<String, Value>> = HashMap::new();
+    let mut outcome = BlockOutcome::default();
+    let mut last_error: Option<LlmError> = None;
+    let mut tasks = JoinSet::new();
+    let mut unit_of_task = HashMap::new();
+
+    loop {
+        // Start ready units, lowest block order first
+        let mut index = 0;
+        while index < units.len() {
+            if started[index] || waiting_on[index] > 0 {
+                index += 1;
+                continue;
+            }
+
+            // Without its upstream output a block would be analysed on missing context
+            if let Some(block_id) = failed_upstream(&units, index, &outputs) {
+                started[index] = true;
+                eprintln!("[run_per_block] Skipping {:?}: upstream block {} failed", block_ids(&units[index]), block_id);
+                outcome.merge(BlockOutcome {
+                    accepted: Vec::new(),
+                    failed: fail_unit(&units[index], &format!("upstream block {} failed", block_id)),
+                });
+                release_dependents(&units, index, &mut waiting_on);
+                // Released units may come earlier in the order
+                index = 0;
+                continue;
+            }
+
+            if tasks.len() >= MAX_CONCURRENT_BLOCK_CALLS {
+                break;
+            }
+            started[index] = true;
+
+            let upstream = upstream_context(&units, index, &outputs);
+            let client = client.clone();
+            let context = context.clone();
+            let unit_blocks = units[index].blocks.clone();
+            let task = tasks.spawn(async move {
+                run_unit(&client, &context, &unit_blocks, upstream, structured).await
+            });
+            unit_of_task.insert(task.id(), index);
+            index += 1;
+        }
+
+        let Some(joined) = tasks.join_next_with_id().await else {
+            break;
+        };
+        let index = match &joined {
+            Ok((id, _)) => unit_of_task[id],
+            Err(e) => unit_of_task[&e.id()],
+        };
+
+        match joined {
+            Ok((_, Ok(unit_outcome))) => {
+                for (block_id, fields) in &unit_outcome.accepted {
+                    outputs.insert(block_id.clone(), fields.clone());
+                }
+                outcome.merge(unit_outcome);
+            }
+            Ok((_, Err(e))) => {
+                eprintln!("[run_per_block] Call for {:?} failed: {}", block_ids(&units[index]), e);
+                outcome.merge(BlockOutcome {
+                    accepted: Vec::new(),
+                    failed: fail_unit(&units[index], &e.to_string()),
+                });
+                last_error = Some(e);
+            }
+            Err(e) => {
+                eprintln!("[run_per_block] Task for {:?} failed: {}", block_ids(&units[index]), e);
+                outcome.merge(BlockOutcome {
+                    accepted: Vec::new(),
+                    failed: fail_unit(&units[index], &format!("analysis task failed: {}", e)),
+                });
+            }
+        }
+
+        release_dependents(&units, index, &mut waiting_on);
+    }
+
+    // Anything never started is waiting on a cycle
+    for (index, unit) in units.iter().enumerate() {
+        if !started[index] {
+            eprintln!("[run_per_block] Skipping {:?}: dependency cycle", block_ids(unit));
+            outcome.merge(BlockOutcome {
+                accepted: Vec::new(),
+                failed: fail_unit(unit, "dependency cycle in depends_on"),
+            });
+        }
+    }
+
+    match last_error {
+        Some(e) if outcome.accepted.is_empty() => Err(e),
+        _ => Ok(outcome),
+    }
+}
+
+/// One call: prompt, parse per block, follow up on failed blocks
+async fn run_unit(
+    client: &LlmClient,
+    context: &PromptContext,
+    blocks: &[AnalysisBlockConfig],
+    upstream: Option<Value>,
+    structured: bool,
+) -> Result<BlockOutcome, LlmError> {
+    let prompt = prompt::build_block_prompt(context, blocks, upstream.as_ref());
+    let analysis_type = context.depth.as_str();
+
+    let response = if structured {
+        let output_schema = schema::build_output_schema(blocks);
+        client.send_structured_request(&prompt, analysis_type, &output_schema).await?.text
+    } else {
+        client.send_chat_request(&prompt, analysis_type).await?
+    };
+
+    let mut outcome = partial::parse_reply(&client.prepare_json_response(&response), blocks);
+    if !outcome.failed.is_empty() {
+        let followup = prompt::build_followup_prompt(context, &outcome.failed);
+        partial::retry_failed_blocks(client, &followup, context.depth, structured, &mut outcome).await;
+    }
+    Ok(outcome)
+}
+
+/// Accepted fields of the units `index` depends on, as one JSON object
+fn upstream_context(
+    units: &[CallUnit],
+    index: usize,
+    outputs: &HashMap<String, Map<String, Value>>,
+) -> Option<Value> {
+    let mut context = Map::new();
+    for &dep in &units[index].depends_on {
+        for block in &units[dep].blocks {
+            if let Some(fields) = outputs.get(&block.id) {
+                context.extend(fields.clone());
+            }
+        }
+    }
+
+    if context.is_empty() {
+        None
+    } else {
+        Some(Value::Object(context))
+    }
+}
+
+/// First block that a block of unit `index` depends on and that has no output
+///
+/// Only called once all of the unit's dependencies have finished, so a
+/// missing output means the upstream block failed.
+fn failed_upstream<'a>(
+    units: &'a [CallUnit],
+    index: usize,
+    outputs: &HashMap<String, Map<String, Value>>,
+) -> Option<&'a str> {
+    let needed: Vec<&String> = units[index]
+        .blocks
+        .iter()
+        .flat_map(|b| b.depends_on.iter().flatten())
+        .collect();
+
+    units[index]
+        .depends_on
+        .iter()
+        .flat_map(|&dep| &units[dep].blocks)
+        .find(|block| needed.contains(&&block.id) && !outputs.contains_key(&block.id))
+        .map(|block| block.id.as_str())
+}
+
+/// Count unit `index` as finished for the units waiting on it
+fn release_dependents(units: &[CallUnit], index: usize, waiting_on: &mut [usize]) {
+    for (dependent, unit) in units.iter().enumerate() {
+        if unit.depends_on.contains(&index) {
+            waiting_on[dependent] -= 1;
+        }
+    }
+}
+
+fn fail_unit(unit: &CallUnit, error: &str) -> Vec<FailedBlock> {
+    unit.blocks
+        .iter()
+        .map(|b| FailedBlock { block_id: b.id.clone(), error: error.to_string() })
+        .collect()
+}
+
+fn block_ids(unit: &CallUnit) -> Vec<&str> {
+    unit.blocks.iter().map(|b| b.id.as_str()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::blocks;
+
+    #[test]
+    fn test_plan_calls_groups_basic_blocks_and_resolves_dependencies() {
+        let units = plan_calls(&[
+            blocks::flowchart_block(),
+            blocks::quality_assessment_block(),
+            blocks::topics_block(),
+            blocks::algorithms_block(),
+            blocks::ai_summary_block(),
+        ]);
+
+        let ids: Vec<Vec<&str>> = units.iter().map(block_ids).collect();
+        assert_eq!(
+            ids,
+            vec![
+                vec!["ai_summary", "topics"],
+                vec!["quality_assessment"],
+                vec!["algorithms"],
+                vec!["flowchart"],
+            ]
+        );
+        assert_eq!(units[0].depends_on, Vec::<usize>::new());
+        assert_eq!(units[1].depends_on, vec![0]);
+        assert_eq!(units[3].depends_on, vec![2]);
+    }
+
+    #[test]
+    fn test_dependencies_on_disabled_blocks_are_ignored() {
+        let units = plan_calls(&[blocks::flowchart_block(), blocks::related_papers_block()]);
+        assert!(units.iter().all(|u| u.depends_on.is_empty()));
+    }
+
+    #[test]
+    fn test_upstream_context_merges_dependency_outputs() {
+        let units = plan_calls(&[blocks::algorithms_block(), blocks::flowchart_block()]);
+        let mut outputs = HashMap::new();
+        let mut fields = Map::new();
+        fields.insert("algorithms".to_string(), serde_json::json!([{"name": "Router"}]));
+        outputs.insert("algorithms".to_string(), fields);
+
+        assert!(upstream_context(&units, 0, &outputs).is_none());
+        let upstream = upstream_context(&units, 1, &outputs).unwrap();
+        assert_eq!(upstream["algorithms"][0]["name"], "Router");
+    }
+
+    #[test]
+    fn test_failed_upstream_finds_missing_dependency_output() {
+        let units = plan_calls(&[blocks::algorithms_block(), blocks::flowchart_block()]);
+        let mut outputs = HashMap::new();
+        assert_eq!(failed_upstream(&units, 0, &outputs), None);
+        assert_eq!(failed_upstream(&units, 1, &outputs), Some("algorithms"));
+
+        outputs.insert("algorithms".to_string(), Map::new());
+        assert_eq!(failed_upstream(&units, 1, &outputs), None);
+    }
+
+    #[test]
+    fn test_execution_mode_setting() {
+        assert_eq!(BlockExecutionMode::from_setting(Some("per_block")), BlockExecutionMode::PerBlock);
+        assert_eq!(BlockExecutionMode::from_setting(Some("combined")), BlockExecutionMode::Combined);
+        assert_eq!(BlockExecutionMode::from_setting(None), BlockExecutionMode::Combined);
+    }
+}
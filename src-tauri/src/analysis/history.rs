@@ -0,0 +1,178 @@
+//! Analysis run history
+//! Every analysis is recorded as a run; runs can be compared field by field
+//! and an older run can be restored onto the paper
+
+use crate::analysis::partial::BlockOutcome;
+use crate::analysis::AnalysisDepth;
+use crate::llm::LlmClient;
+use crate::llm_cache::LlmCache;
+use crate::models::{
+    AnalysisRun, FieldChange, FieldChangeKind, NewAnalysisRun, Paper, PaperAnalysisBlock,
+};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+/// Metadata of a run made with `client`
+///
+/// `prompt` is the full combined analysis prompt, so runs over the same
+/// content, blocks and template share a prompt hash whatever the execution mode.
+pub fn new_run(
+    client: &LlmClient,
+    paper_id: &str,
+    depth: AnalysisDepth,
+    language: &str,
+    prompt: &str,
+) -> NewAnalysisRun {
+    NewAnalysisRun {
+        paper_id: paper_id.to_string(),
+        provider: client.provider().as_str().to_string(),
+        model: client.model_for(depth.as_str()).to_string(),
+        depth: depth.as_str().to_string(),
+        language: language.to_string(),
+        prompt_hash: LlmCache::hash_prompt(prompt),
+        restored_from: None,
+        translated_from: None,
+    }
+}
+
+/// Metadata of a run that re-applies `run`
+pub fn restored_run(run: &AnalysisRun) -> NewAnalysisRun {
+    NewAnalysisRun {
+        paper_id: run.paper_id.clone(),
+        provider: run.provider.clone(),
+        model: run.model.clone(),
+        depth: run.depth.clone(),
+        language: run.language.clone(),
+        prompt_hash: run.prompt_hash.clone(),
+        restored_from: Some(run.id.clone()),
+        translated_from: run.translated_from.clone(),
+    }
+}
+
+/// Rebuild the block outcome of a stored run from its block outputs
+pub fn outcome_from_run(run: &AnalysisRun, blocks: Vec<PaperAnalysisBlock>) -> BlockOutcome {
+    BlockOutcome {
+        failed: run.failed_blocks.clone().unwrap_or_default(),
+        ..outcome_from_blocks(blocks)
+    }
+}
+
+/// Block outcome made of stored block outputs, with no failed blocks
+pub fn outcome_from_blocks(blocks: Vec<PaperAnalysisBlock>) -> BlockOutcome {
+    BlockOutcome {
+        accepted: blocks
+            .into_iter()
+            .filter_map(|block| match block.json {
+                Value::Object(fields) => Some((block.block_id, fields)),
+                _ => None,
+            })
+            .collect(),
+        failed: Vec::new(),
+    }
+}
+
+/// Make the paper's analysis fields match a stored run
+///
+/// Fields the run did not produce are cleared rather than left over from
+/// the current analysis. Tags and topics are kept when the run has none.
+pub fn restore_onto(paper: &mut Paper, run: &AnalysisRun, outcome: &BlockOutcome) {
+    clear_analysis(paper);
+    outcome.apply_to(paper);
+    paper.is_deep_analyzed = true;
+    paper.analysis_mode = Some(run.depth.clone());
+}
+
+/// Show the paper with its latest stored outputs in one language
+///
+/// Only the returned value changes; the saved paper keeps its current analysis.
+pub fn view_in_language(paper: &mut Paper, blocks: Vec<PaperAnalysisBlock>) {
+    clear_analysis(paper);
+    outcome_from_blocks(blocks.clone()).apply_to(paper);
+    paper.analysis_blocks = Some(blocks);
+}
+
+/// Reset every analysis field that a block outcome can set
+fn clear_analysis(paper: &mut Paper) {
+    paper.ai_summary = None;
+    paper.key_insights = None;
+    paper.engineering_notes = None;
+    paper.code_available = false;
+    paper.code_links = None;
+    paper.novelty_score = None;
+    paper.novelty_reason = None;
+    paper.effectiveness_score = None;
+    paper.effectiveness_reason = None;
+    paper.experiment_completeness_score = None;
+    paper.experiment_completeness_reason = None;
+    paper.algorithm_flowchart = None;
+    paper.time_complexity = None;
+    paper.space_complexity = None;
+    paper.related_papers = None;
+}
+
+/// Compare the results of two runs field by field
+pub fn diff_results(before: &Value, after: &Value) -> Vec<FieldChange> {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let old = before.get(field);
+            let new = after.get(field);
+            let kind = match (old, new) {
+                (None, Some(_)) => FieldChangeKind::Added,
+                (Some(_), None) => FieldChangeKind::Removed,
+                (Some(old), Some(new)) if old != new => FieldChangeKind::Changed,
+                _ => return None,
+            };
+            Some(FieldChange {
+                field: field.clone(),
+                kind,
+                before: old.cloned(),
+                after: new.cloned(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_results() {
+        let before = json!({
+            "ai_summary": "Old summary",
+            "novelty_score": 6,
+            "code_available": true,
+            "time_complexity": "O(n)"
+        });
+        let after = json!({
+            "ai_summary": "New summary",
+            "novelty_score": 6,
+            "code_available": true,
+            "key_insights": ["One"]
+        });
+
+        let changes = diff_results(&before, &after);
+        let summary: Vec<(&str, FieldChangeKind)> =
+            changes.iter().map(|c| (c.field.as_str(), c.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("ai_summary", FieldChangeKind::Changed),
+                ("key_insights", FieldChangeKind::Added),
+                ("time_complexity", FieldChangeKind::Removed),
+            ]
+        );
+        assert_eq!(changes[0].before, Some(json!("Old summary")));
+        assert_eq!(changes[1].before, None);
+        assert_eq!(changes[2].after, None);
+
+        assert!(diff_results(&before, &before).is_empty());
+    }
+}
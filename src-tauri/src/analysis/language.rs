@@ -0,0 +1,73 @@
+//! Languages analysis output can be written in
+//! Analyses are stored per language, so one paper can carry an English and a
+//! Chinese (or Japanese, German, ...) version side by side
+
+use serde::Serialize;
+
+/// A language analysis output can be requested in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisLanguage {
+    /// Language code stored with every analysis run, e.g. "en"
+    pub code: &'static str,
+    /// Name used in prompts, e.g. "Japanese"
+    pub english_name: &'static str,
+    /// Name shown to the user, e.g. "日本語"
+    pub native_name: &'static str,
+}
+
+/// Default analysis language
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Every supported analysis language
+pub const SUPPORTED_LANGUAGES: &[AnalysisLanguage] = &[
+    AnalysisLanguage { code: "en", english_name: "English", native_name: "English" },
+    AnalysisLanguage { code: "zh", english_name: "Chinese", native_name: "中文" },
+    AnalysisLanguage { code: "ja", english_name: "Japanese", native_name: "日本語" },
+    AnalysisLanguage { code: "ko", english_name: "Korean", native_name: "한국어" },
+    AnalysisLanguage { code: "de", english_name: "German", native_name: "Deutsch" },
+    AnalysisLanguage { code: "fr", english_name: "French", native_name: "Français" },
+    AnalysisLanguage { code: "es", english_name: "Spanish", native_name: "Español" },
+];
+
+impl AnalysisLanguage {
+    pub fn is_english(&self) -> bool {
+        self.code == DEFAULT_LANGUAGE
+    }
+}
+
+/// Look up a language by code
+///
+/// Codes are matched case-insensitively and region suffixes are ignored,
+/// so "zh-CN" and "ZH" both resolve to Chinese.
+pub fn find_language(code: &str) -> Option<&'static AnalysisLanguage> {
+    let primary = code
+        .trim()
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    SUPPORTED_LANGUAGES.iter().find(|lang| lang.code == primary)
+}
+
+/// Resolve a language code, rejecting unsupported languages
+pub fn parse_language(code: &str) -> Result<&'static AnalysisLanguage, String> {
+    find_language(code).ok_or_else(|| {
+        let supported: Vec<&str> = SUPPORTED_LANGUAGES.iter().map(|lang| lang.code).collect();
+        format!("Unsupported analysis language '{}' (supported: {})", code, supported.join(", "))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_language() {
+        assert_eq!(find_language("ja").unwrap().english_name, "Japanese");
+        assert_eq!(find_language("zh-CN").unwrap().code, "zh");
+        assert_eq!(find_language(" DE ").unwrap().code, "de");
+        assert!(find_language("xx").is_none());
+        assert!(parse_language("").unwrap_err().contains("supported: en, zh"));
+    }
+}
@@ -2,11 +2,21 @@
 //! Each analysis block can be enabled/disabled by users
 
 pub mod blocks;
+pub mod chunked;
+pub mod context;
+pub mod executor;
+pub mod history;
+pub mod language;
+pub mod partial;
 pub mod prompt;
 pub mod registry;
+pub mod schema;
+pub mod translate;
 
 use serde::{Deserialize, Serialize};
-use crate::models::TopicConfig;
+use crate::models::{FailedBlock, TopicConfig};
+
+pub use prompt::PromptContext;
 
 /// Analysis depth mode (standard/full)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
@@ -50,6 +60,7 @@ pub enum BlockCategory {
     Core,        // Main analysis outputs
     Technical,   // In-depth technical analysis
     Engineering, // Implementation details
+    Custom,      // User-defined blocks
 }
 
 /// Output schema type for a block
@@ -64,6 +75,7 @@ pub enum OutputSchema {
     Flowchart,                 // algorithm_flowchart
     PaperReferenceList,        // related_papers
     CodeLinks,                 // code_links
+    Custom,                    // user-defined blocks, see CustomBlockSpec
 }
 
 /// Internationalized text
@@ -74,6 +86,7 @@ pub struct I18nText {
 }
 
 impl I18nText {
+    /// Text in `lang`; languages without a translation fall back to English
     pub fn get(&self, lang: &str) -> &str {
         match lang {
             "zh" => &self.zh,
@@ -96,6 +109,20 @@ pub struct AnalysisBlockConfig {
     pub order: usize,
     pub depends_on: Option<Vec<String>>,
     pub output_schema: OutputSchema,
+    /// Instructions and reply schema of a user-defined block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom: Option<CustomBlockSpec>,
+}
+
+/// What a user-defined block asks the LLM for
+///
+/// The reply carries the block's output in a single field named after the
+/// block ID, validated against `json_schema`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomBlockSpec {
+    pub instructions: String,
+    pub json_schema: serde_json::Value,
 }
 
 /// User's configuration for a block
@@ -149,6 +176,24 @@ pub fn build_analysis_prompt(
     prompt::build_prompt(title, summary, topics, latex_content, language, user_config, depth)
 }
 
+/// Build a follow-up prompt for blocks that failed validation
+pub fn build_followup_prompt(context: &PromptContext, failed: &[FailedBlock]) -> String {
+    prompt::build_followup_prompt(context, failed)
+}
+
+/// Blocks that run for `depth` under the user's config
+pub fn enabled_blocks(user_config: &UserAnalysisConfig, depth: AnalysisDepth) -> Vec<AnalysisBlockConfig> {
+    prompt::get_enabled_blocks(user_config, depth)
+}
+
+/// Build the JSON Schema of the analysis reply for the enabled blocks
+pub fn build_analysis_output_schema(
+    user_config: &UserAnalysisConfig,
+    depth: AnalysisDepth,
+) -> serde_json::Value {
+    schema::build_output_schema(&prompt::get_enabled_blocks(user_config, depth))
+}
+
 /// Ensure basic blocks (ai_summary, topics) always have mode=Both
 pub fn fix_basic_blocks_mode(config: UserAnalysisConfig) -> UserAnalysisConfig {
     let blocks = config.blocks.into_iter().map(|mut block| {
@@ -0,0 +1,297 @@
+//! Block-by-block acceptance of analysis replies
+//! Each block's fields are validated against its `OutputSchema`; valid blocks
+//! are applied to the paper and failed ones are re-requested on their own
+
+use crate::analysis::registry::REGISTRY;
+use crate::analysis::{schema, AnalysisBlockConfig, AnalysisDepth};
+use crate::llm::{LlmClient, LlmError};
+use crate::models::{FailedBlock, Paper, RelatedPaper};
+use serde_json::{Map, Value};
+
+/// Result of parsing an analysis reply block by block
+#[derive(Debug, Default)]
+pub struct BlockOutcome {
+    /// Fields of each block that passed validation, keyed by block ID
+    pub accepted: Vec<(String, Map<String, Value>)>,
+    pub failed: Vec<FailedBlock>,
+}
+
+impl BlockOutcome {
+    /// Fold a follow-up outcome into this one
+    ///
+    /// Blocks accepted by the follow-up are no longer failed; blocks that
+    /// failed again keep the follow-up's error.
+    pub fn merge(&mut self, followup: BlockOutcome) {
+        for (block_id, fields) in followup.accepted {
+            self.failed.retain(|f| f.block_id != block_id);
+            self.accepted.push((block_id, fields));
+        }
+        for failure in followup.failed {
+            self.failed.retain(|f| f.block_id != failure.block_id);
+            self.failed.push(failure);
+        }
+    }
+
+    /// Failed blocks and their errors, one `block: error` entry each
+    pub fn failure_summary(&self) -> String {
+        self.failed
+            .iter()
+            .map(|f| format!("{}: {}", f.block_id, f.error))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// Reply fields of every accepted block as one JSON object, keyed by block ID
+    ///
+    /// This is what `paper_analysis_blocks` stores for each run.
+    pub fn block_outputs(&self) -> Vec<(String, Value)> {
+        self.accepted
+            .iter()
+            .map(|(block_id, fields)| (block_id.clone(), Value::Object(fields.clone())))
+            .collect()
+    }
+
+    /// Copy accepted blocks onto the paper and record the failed ones
+    pub fn apply_to(&self, paper: &mut Paper) {
+        for (_, fields) in &self.accepted {
+            for (name, value) in fields {
+                apply_field(paper, name, value);
+            }
+        }
+
+        paper.failed_blocks = if self.failed.is_empty() {
+            None
+        } else {
+            Some(self.failed.clone())
+        };
+    }
+}
+
+/// Parse a reply into per-block results
+///
+/// A block is accepted only if all of its fields are present and valid;
+/// a reply that is not a JSON object fails every block.
+pub fn parse_reply(reply: &str, blocks: &[AnalysisBlockConfig]) -> BlockOutcome {
+    let object = match serde_json::from_str::<Value>(reply) {
+        Ok(Value::Object(object)) => object,
+        Ok(_) => return fail_all(blocks, "reply is not a JSON object"),
+        Err(e) => return fail_all(blocks, &format!("reply is not valid JSON: {}", e)),
+    };
+
+    let mut outcome = BlockOutcome::default();
+    for block in blocks {
+        let mut fields = Map::new();
+        let mut errors = Vec::new();
+
+        for (name, field_schema) in schema::block_fields(block) {
+            match object.get(&name) {
+                Some(value) => {
+                    let field_errors = schema::validate(value, &field_schema);
+                    if field_errors.is_empty() {
+                        fields.insert(name, value.clone());
+                    } else {
+                        errors.extend(field_errors.into_iter().map(|e| e.replacen('$', &name, 1)));
+                    }
+                }
+                None => errors.push(format!("missing field '{}'", name)),
+            }
+        }
+
+        if errors.is_empty() {
+            outcome.accepted.push((block.id.clone(), fields));
+        } else {
+            eprintln!("[parse_reply] Block '{}' failed validation: {:?}", block.id, errors);
+            outcome.failed.push(FailedBlock {
+                block_id: block.id.clone(),
+                error: errors.join("; "),
+            });
+        }
+    }
+    outcome
+}
+
+fn fail_all(blocks: &[AnalysisBlockConfig], error: &str) -> BlockOutcome {
+    eprintln!("[parse_reply] All {} blocks failed: {}", blocks.len(), error);
+    BlockOutcome {
+        accepted: Vec::new(),
+        failed: blocks
+            .iter()
+            .map(|block| FailedBlock { block_id: block.id.clone(), error: error.to_string() })
+            .collect(),
+    }
+}
+
+/// Re-request only the failed blocks with a targeted follow-up prompt
+///
+/// `prompt` comes from `build_followup_prompt`. Request errors are logged and
+/// leave the outcome unchanged, so the blocks stay recorded as failed.
+pub async fn retry_failed_blocks(
+    client: &LlmClient,
+    prompt: &str,
+    depth: AnalysisDepth,
+    structured: bool,
+    outcome: &mut BlockOutcome,
+) {
+    let blocks = failed_block_configs(&outcome.failed);
+    if blocks.is_empty() {
+        return;
+    }
+    eprintln!("[retry_failed_blocks] Re-requesting {} failed blocks: {:?}",
+        blocks.len(), blocks.iter().map(|b| b.id.as_str()).collect::<Vec<_>>());
+
+    let response: Result<String, LlmError> = if structured {
+        let output_schema = schema::build_output_schema(&blocks);
+        client.send_structured_request(prompt, depth.as_str(), &output_schema).await.map(|reply| reply.text)
+    } else {
+        client.send_chat_request(prompt, depth.as_str()).await
+    };
+
+    match response {
+        Ok(response) => {
+            let followup = parse_reply(&client.prepare_json_response(&response), &blocks);
+            outcome.merge(followup);
+        }
+        Err(e) => {
+            eprintln!("[retry_failed_blocks] Follow-up request failed: {}", e);
+        }
+    }
+}
+
+/// Block definitions for failed blocks, skipping unknown IDs
+pub(crate) fn failed_block_configs(failed: &[FailedBlock]) -> Vec<AnalysisBlockConfig> {
+    failed
+        .iter()
+        .filter_map(|f| REGISTRY.get(&f.block_id))
+        .collect()
+}
+
+/// Copy one validated reply field onto the paper
+fn apply_field(paper: &mut Paper, name: &str, value: &Value) {
+    let string = || value.as_str().map(str::to_string);
+    let strings = || -> Vec<String> {
+        value
+            .as_array()
+            .map(|items| items.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+            .unwrap_or_default()
+    };
+    let score = || value.as_i64().map(|n| n as i32);
+
+    match name {
+        "ai_summary" => paper.ai_summary = string(),
+        "suggested_tags" => {
+            let tags = strings();
+            if !tags.is_empty() {
+                paper.tags = tags;
+            }
+        }
+        "suggested_topics" => {
+            let topics = strings();
+            if !topics.is_empty() {
+                paper.topics = topics;
+            }
+        }
+        "key_insights" => paper.key_insights = Some(strings()),
+        "engineering_notes" => paper.engineering_notes = string(),
+        "novelty_score" => paper.novelty_score = score(),
+        "novelty_reason" => paper.novelty_reason = string(),
+        "effectiveness_score" => paper.effectiveness_score = score(),
+        "effectiveness_reason" => paper.effectiveness_reason = string(),
+        "experiment_completeness_score" => paper.experiment_completeness_score = score(),
+        "experiment_completeness_reason" => paper.experiment_completeness_reason = string(),
+        "code_available" => paper.code_available = value.as_bool().unwrap_or(false),
+        "code_links" => {
+            let links = strings();
+            paper.code_links = if links.is_empty() { None } else { Some(links) };
+        }
+        "algorithm_flowchart" => paper.algorithm_flowchart = string(),
+        "time_complexity" => paper.time_complexity = string(),
+        "space_complexity" => paper.space_complexity = string(),
+        "related_papers" => {
+            let related: Vec<RelatedPaper> = serde_json::from_value(value.clone()).unwrap_or_default();
+            paper.related_papers = if related.is_empty() { None } else { Some(related) };
+        }
+        // key_formulas, algorithms and custom blocks only live in paper_analysis_blocks
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::blocks;
+    use crate::models::{ArxivPaper, AuthorInfo};
+    use chrono::Utc;
+
+    fn test_paper() -> Paper {
+        Paper::from_arxiv(ArxivPaper {
+            id: "2401.00001".to_string(),
+            title: "Test".to_string(),
+            authors: vec![AuthorInfo { name: "A".to_string(), affiliation: None }],
+            summary: "Abstract".to_string(),
+            published: Utc::now(),
+            updated: Utc::now(),
+            categories: vec!["cs.LG".to_string()],
+            arxiv_url: String::new(),
+            pdf_url: String::new(),
+            primary_category: "cs.LG".to_string(),
+        })
+    }
+
+    #[test]
+    fn test_invalid_block_does_not_discard_valid_ones() {
+        let blocks = vec![
+            blocks::ai_summary_block(),
+            blocks::quality_assessment_block(),
+            blocks::related_papers_block(),
+        ];
+        let reply = r#"{
+            "ai_summary": "Summary",
+            "novelty_score": "high",
+            "novelty_reason": "New",
+            "effectiveness_score": 7,
+            "effectiveness_reason": "Works",
+            "experiment_completeness_score": 6,
+            "experiment_completeness_reason": "Broad",
+            "related_papers": [{
+                "arxivId": "2301.12345",
+                "title": "Prior work",
+                "relationship": "builds_on",
+                "relevanceScore": 8,
+                "reason": "Extends it"
+            }]
+        }"#;
+
+        let outcome = parse_reply(reply, &blocks);
+        let accepted: Vec<&str> = outcome.accepted.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(accepted, vec!["ai_summary", "related_papers"]);
+        assert_eq!(outcome.failed.len(), 1);
+        assert_eq!(outcome.failed[0].block_id, "quality_assessment");
+        assert!(outcome.failed[0].error.starts_with("novelty_score: expected integer"));
+
+        let mut paper = test_paper();
+        outcome.apply_to(&mut paper);
+        assert_eq!(paper.ai_summary.as_deref(), Some("Summary"));
+        assert_eq!(paper.related_papers.map(|p| p.len()), Some(1));
+        assert_eq!(paper.effectiveness_score, None);
+        assert_eq!(paper.failed_blocks.map(|f| f.len()), Some(1));
+    }
+
+    #[test]
+    fn test_merge_followup() {
+        let blocks = vec![blocks::ai_summary_block(), blocks::code_links_block()];
+        let mut outcome = parse_reply("not json at all", &blocks);
+        assert_eq!(outcome.failed.len(), 2);
+        assert_eq!(failed_block_configs(&outcome.failed).len(), 2);
+
+        let followup = parse_reply(
+            r#"{"ai_summary": "Summary", "code_available": "yes", "code_links": []}"#,
+            &blocks,
+        );
+        outcome.merge(followup);
+
+        assert_eq!(outcome.accepted.len(), 1);
+        assert_eq!(outcome.failed.len(), 1);
+        assert_eq!(outcome.failed[0].block_id, "code_links");
+        assert!(outcome.failed[0].error.contains("code_available"));
+    }
+}
@@ -2,7 +2,9 @@
 //! Each block contributes its own instructions and output schema
 
 use crate::analysis::{AnalysisDepth, UserAnalysisConfig, AnalysisBlockConfig, BlockRunMode, OutputSchema};
-use crate::models::TopicConfig;
+use crate::analysis::language::find_language;
+use crate::analysis::registry::REGISTRY;
+use crate::models::{FailedBlock, TopicConfig};
 
 /// Build analysis prompt based on enabled blocks (modular implementation)
 pub fn build_prompt(
@@ -21,9 +23,78 @@ pub fn build_prompt(
     build_modular_prompt(title, summary, topics, latex_content, language, &enabled_blocks, depth)
 }
 
+/// Paper-level inputs shared by every prompt of one analysis
+#[derive(Debug, Clone)]
+pub struct PromptContext {
+    pub title: String,
+    pub summary: String,
+    pub topics: Vec<TopicConfig>,
+    pub latex_content: Option<String>,
+    pub language: String,
+    pub depth: AnalysisDepth,
+}
+
+impl PromptContext {
+    /// Prompt for `blocks` only, using the same layout as the full prompt
+    fn blocks_prompt(&self, blocks: &[AnalysisBlockConfig]) -> String {
+        build_modular_prompt(
+            &self.title,
+            &self.summary,
+            &self.topics,
+            self.latex_content.as_deref(),
+            &self.language,
+            blocks,
+            self.depth,
+        )
+    }
+}
+
+/// Build a follow-up prompt that asks again for the failed blocks only
+pub fn build_followup_prompt(context: &PromptContext, failed: &[FailedBlock]) -> String {
+    let blocks = crate::analysis::partial::failed_block_configs(failed);
+    let problems = failed
+        .iter()
+        .map(|f| format!("- {}: {}", f.block_id, f.error))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "{}\n\n\
+        ===== RETRY FAILED FIELDS =====\n\
+        A previous answer for this paper could not be used for these blocks:\n\
+        {}\n\
+        Answer again with ONLY the fields in the JSON format above, using exactly the types shown.",
+        context.blocks_prompt(&blocks),
+        problems
+    )
+}
+
+/// Build the prompt for one call of per-block execution
+///
+/// `upstream` holds the accepted fields of the blocks this call depends on.
+pub fn build_block_prompt(
+    context: &PromptContext,
+    blocks: &[AnalysisBlockConfig],
+    upstream: Option<&serde_json::Value>,
+) -> String {
+    let prompt = context.blocks_prompt(blocks);
+    match upstream {
+        Some(upstream) => format!(
+            "{}\n\n\
+            ===== UPSTREAM ANALYSIS =====\n\
+            Results already produced for this paper by earlier analysis steps. \
+            Use them as context and stay consistent with them, but do NOT repeat these fields:\n\
+            {}",
+            prompt,
+            serde_json::to_string_pretty(upstream).unwrap_or_default()
+        ),
+        None => prompt,
+    }
+}
+
 /// Get blocks enabled for a specific depth based on user config
-fn get_enabled_blocks(config: &UserAnalysisConfig, depth: AnalysisDepth) -> Vec<AnalysisBlockConfig> {
-    let all_blocks = crate::analysis::blocks::get_all_blocks();
+pub(crate) fn get_enabled_blocks(config: &UserAnalysisConfig, depth: AnalysisDepth) -> Vec<AnalysisBlockConfig> {
+    let all_blocks = REGISTRY.get_all();
     let enabled_map: std::collections::HashMap<String, &crate::analysis::UserBlockConfig> = config
         .blocks
         .iter()
@@ -128,10 +199,14 @@ fn build_modular_prompt(
 }
 
 /// Build language instruction based on enabled blocks
+///
+/// English needs no instruction; unknown codes are treated as English.
 fn build_language_instruction(language: &str, blocks: &[AnalysisBlockConfig], depth: AnalysisDepth) -> String {
-    if language != "zh" {
-        return String::new();
-    }
+    let lang = match find_language(language) {
+        Some(lang) if !lang.is_english() => lang,
+        _ => return String::new(),
+    };
+    let name = lang.english_name;
 
     let has_complexity = blocks.iter().any(|b| b.id == "complexity");
     let has_algorithms = blocks.iter().any(|b| b.id == "algorithms");
@@ -142,48 +217,52 @@ fn build_language_instruction(language: &str, blocks: &[AnalysisBlockConfig], de
     let mut additional_requirements = String::new();
 
     if has_complexity {
-        additional_requirements.push_str("- **Complexity analysis**: Provide big-O notation in English (e.g., O(n log n)), but explain the meaning in Chinese\n");
+        additional_requirements.push_str(&format!("- **Complexity analysis**: Provide big-O notation in English (e.g., O(n log n)), but explain the meaning in {}\n", name));
     }
     if has_algorithms {
-        additional_requirements.push_str("- **Algorithms**: Algorithm names and step descriptions in Chinese, but complexity notation in English (e.g., O(n log n))\n");
+        additional_requirements.push_str(&format!("- **Algorithms**: Algorithm names and step descriptions in {}, but complexity notation in English (e.g., O(n log n))\n", name));
     }
     if has_formulas {
-        additional_requirements.push_str("- **Formulas**: Formula names and explanations in Chinese, LaTeX notation can stay in English\n");
+        additional_requirements.push_str(&format!("- **Formulas**: Formula names and explanations in {}, LaTeX notation can stay in English\n", name));
     }
     if has_related_papers {
-        additional_requirements.push_str("- **Related papers**: Keep paper titles in English (original titles), relationship types in English as specified in schema, and reasons in Chinese\n");
+        additional_requirements.push_str(&format!("- **Related papers**: Keep paper titles in English (original titles), relationship types in English as specified in schema, and reasons in {}\n", name));
+    }
+    let custom_ids: Vec<&str> = blocks.iter().filter(|b| b.custom.is_some()).map(|b| b.id.as_str()).collect();
+    if !custom_ids.is_empty() {
+        additional_requirements.push_str(&format!(
+            "- **Custom blocks** ({}): Text in {}, but field names and enum values exactly as in the JSON format\n",
+            custom_ids.join(", "),
+            name
+        ));
     }
 
     format!(
         "\n\n===== LANGUAGE REQUIREMENTS =====\n\
-        - Respond in Chinese (中文) for: ai_summary, key_insights, novelty_reason, effectiveness_reason{}engineering_notes\n\
+        - Respond in {} ({}) for: ai_summary, key_insights, novelty_reason, effectiveness_reason{}engineering_notes\n\
         - Keep in ENGLISH for: code_links (repo names, URLs), suggested_tags, suggested_topics\n\
-        - Engineering notes: Describe in Chinese, but keep project/framework names in English (e.g., verl, vllm, tensorrt-llm)\n\
+        - Engineering notes: Describe in {}, but keep project/framework names in English (e.g., verl, vllm, tensorrt-llm)\n\
         {}\
         - Tags and topics must be technical terms in English (e.g., \"reinforcement-learning\", \"LLM\", \"transformer\")",
+        name,
+        lang.native_name,
         if has_experiments { ", experiment_completeness_reason" } else { "" },
+        name,
         if additional_requirements.is_empty() { String::new() } else { format!("\n{}", additional_requirements) }
     )
 }
 
 /// Build content section based on depth
-fn build_content_section(latex_content: Option<&str>, depth: AnalysisDepth) -> String {
-    match (depth, latex_content) {
-        (AnalysisDepth::Standard, Some(latex)) => {
-            let intro = crate::latex_parser::extract_intro_conclusion(latex);
-            format!(
-                "LaTeX Content (Introduction + Conclusion, truncated to 15000 chars):\n{}",
-                intro.chars().take(15000).collect::<String>()
-            )
+fn build_content_section(content: Option<&str>, depth: AnalysisDepth) -> String {
+    match (depth, content) {
+        (AnalysisDepth::Standard, Some(content)) => {
+            format!("Paper Content:\n{}", content)
         }
-        (AnalysisDepth::Standard, None) => {
-            "LaTeX Content: Not available - using abstract only".to_string()
+        (AnalysisDepth::Full, Some(content)) => {
+            format!("Full Paper Content:\n{}", content)
         }
-        (AnalysisDepth::Full, Some(latex)) => {
-            format!("Full LaTeX Content:\n{}", latex)
-        }
-        (AnalysisDepth::Full, None) => {
-            "LaTeX Content: Not available - using abstract only".to_string()
+        (_, None) => {
+            "Paper Content: Not available - using abstract only".to_string()
         }
     }
 }
@@ -226,7 +305,11 @@ fn build_tasks_section(blocks: &[AnalysisBlockConfig]) -> String {
 }
 
 /// Get task instruction for a specific block
-fn get_block_task_instruction(block: &AnalysisBlockConfig) -> &'static str {
+fn get_block_task_instruction(block: &AnalysisBlockConfig) -> &str {
+    if let Some(custom) = &block.custom {
+        return &custom.instructions;
+    }
+
     match block.id.as_str() {
         "ai_summary" => "Provide 2-3 sentence summary of the paper's core contributions",
         "topics" => "Suggest relevant tags and match the paper to user's research topics",
@@ -301,6 +384,11 @@ fn build_json_schema(blocks: &[AnalysisBlockConfig]) -> String {
 
 /// Get JSON schema for a specific block
 fn get_block_json_schema(block: &AnalysisBlockConfig) -> String {
+    if let Some(custom) = &block.custom {
+        let example = crate::analysis::schema::example_value(&custom.json_schema);
+        return format!("\"{}\": {}", block.id, example);
+    }
+
     match block.output_schema {
         OutputSchema::SingleString => {
             match block.id.as_str() {
@@ -377,5 +465,6 @@ fn get_block_json_schema(block: &AnalysisBlockConfig) -> String {
     }
   ]"#
         }
+        OutputSchema::Custom => "",
     }.to_string()
 }
@@ -2,6 +2,8 @@
 
 use crate::analysis::AnalysisBlockConfig;
 use crate::analysis::blocks::get_all_blocks;
+use crate::database::CustomBlockRepository;
+use sqlx::SqlitePool;
 use std::collections::HashMap;
 use std::sync::RwLock;
 
@@ -23,18 +25,19 @@ impl AnalysisRegistry {
         }
     }
 
-    /// Get all registered blocks
+    /// Get all registered blocks, ordered by block order
     pub fn get_all(&self) -> Vec<AnalysisBlockConfig> {
-        self.blocks
+        let mut blocks: Vec<AnalysisBlockConfig> = self.blocks
             .read()
             .unwrap()
             .values()
             .cloned()
-            .collect()
+            .collect();
+        blocks.sort_by(|a, b| a.order.cmp(&b.order).then_with(|| a.id.cmp(&b.id)));
+        blocks
     }
 
     /// Get a specific block by ID
-    #[allow(dead_code)]
     pub fn get(&self, id: &str) -> Option<AnalysisBlockConfig> {
         self.blocks
             .read()
@@ -51,10 +54,94 @@ impl AnalysisRegistry {
             .unwrap()
             .contains_key(id)
     }
+
+    /// Register a custom block, replacing an earlier version with the same ID
+    ///
+    /// Built-in blocks cannot be replaced.
+    pub fn register(&self, block: AnalysisBlockConfig) -> Result<(), String> {
+        let mut blocks = self.blocks.write().unwrap();
+        if block.custom.is_none() {
+            return Err(format!("Block '{}' is not a custom block", block.id));
+        }
+        if blocks.get(&block.id).is_some_and(|b| b.custom.is_none()) {
+            return Err(format!("'{}' is a built-in block", block.id));
+        }
+        blocks.insert(block.id.clone(), block);
+        Ok(())
+    }
+
+    /// Remove a custom block; returns false if no custom block has this ID
+    pub fn unregister(&self, id: &str) -> bool {
+        let mut blocks = self.blocks.write().unwrap();
+        if blocks.get(id).is_some_and(|b| b.custom.is_some()) {
+            blocks.remove(id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Replace all registered custom blocks with `custom_blocks`
+    pub fn set_custom_blocks(&self, custom_blocks: Vec<AnalysisBlockConfig>) {
+        let mut blocks = self.blocks.write().unwrap();
+        blocks.retain(|_, b| b.custom.is_none());
+        for block in custom_blocks {
+            if block.custom.is_some() && !blocks.contains_key(&block.id) {
+                blocks.insert(block.id.clone(), block);
+            }
+        }
+    }
 }
 
 // Global lazy instance
 lazy_static::lazy_static! {
     /// Global analysis block registry
     pub static ref REGISTRY: AnalysisRegistry = AnalysisRegistry::new();
-}
\ No newline at end of file
+}
+
+/// Load the user's custom blocks from the database into `REGISTRY`
+pub async fn sync_custom_blocks(pool: &SqlitePool) -> Result<usize, String> {
+    let custom_blocks = CustomBlockRepository::new(pool).get_all().await?;
+    let count = custom_blocks.len();
+    REGISTRY.set_custom_blocks(custom_blocks.iter().map(|b| b.to_block_config()).collect());
+    eprintln!("[sync_custom_blocks] Registered {} custom blocks", count);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{blocks, CustomBlockSpec, OutputSchema};
+
+    fn custom_block(id: &str) -> AnalysisBlockConfig {
+        AnalysisBlockConfig {
+            id: id.to_string(),
+            order: 100,
+            default_enabled: false,
+            output_schema: OutputSchema::Custom,
+            custom: Some(CustomBlockSpec {
+                instructions: "List the datasets".to_string(),
+                json_schema: serde_json::json!({ "type": "string" }),
+            }),
+            ..blocks::ai_summary_block()
+        }
+    }
+
+    #[test]
+    fn test_register_custom_blocks() {
+        let registry = AnalysisRegistry::new();
+        registry.register(custom_block("dataset_inventory")).unwrap();
+        assert!(registry.has("dataset_inventory"));
+        assert_eq!(registry.get_all().last().unwrap().id, "dataset_inventory");
+
+        // Built-in blocks can be neither shadowed nor removed
+        assert!(registry.register(custom_block("ai_summary")).is_err());
+        assert!(registry.register(blocks::topics_block()).is_err());
+        assert!(!registry.unregister("ai_summary"));
+
+        registry.set_custom_blocks(vec![custom_block("threats_to_validity")]);
+        assert!(!registry.has("dataset_inventory"));
+        assert!(registry.unregister("threats_to_validity"));
+        assert_eq!(registry.get_all().len(), blocks::get_all_blocks().len());
+    }
+}
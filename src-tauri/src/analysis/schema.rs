@@ -0,0 +1,371 @@
+//! JSON Schema for analysis replies, built from the enabled blocks
+//! Used for provider-native structured output and to validate replies
+
+use crate::analysis::{AnalysisBlockConfig, AnalysisDepth, OutputSchema};
+use serde_json::{json, Map, Value};
+
+/// JSON Schema fields a block contributes to the analysis reply
+///
+/// Field names match the JSON format section of the prompt. A custom block
+/// contributes one field named after its ID.
+pub fn block_fields(block: &AnalysisBlockConfig) -> Vec<(String, Value)> {
+    if let Some(custom) = &block.custom {
+        return vec![(block.id.clone(), custom.json_schema.clone())];
+    }
+    builtin_fields(block)
+        .into_iter()
+        .map(|(name, schema)| (name.to_string(), schema))
+        .collect()
+}
+
+fn builtin_fields(block: &AnalysisBlockConfig) -> Vec<(&'static str, Value)> {
+    let string = || json!({ "type": "string" });
+    let string_array = || json!({ "type": "array", "items": { "type": "string" } });
+    let score = || json!({ "type": "integer", "minimum": 0, "maximum": 10 });
+
+    match block.output_schema {
+        OutputSchema::SingleString => match block.id.as_str() {
+            "ai_summary" => vec![("ai_summary", string())],
+            "engineering_notes" => vec![("engineering_notes", string())],
+            "complexity" => vec![("time_complexity", string()), ("space_complexity", string())],
+            _ => vec![],
+        },
+        OutputSchema::StringArray => match block.id.as_str() {
+            "topics" => vec![("suggested_tags", string_array()), ("suggested_topics", string_array())],
+            "key_insights" => vec![("key_insights", string_array())],
+            _ => vec![],
+        },
+        OutputSchema::StructuredQuality => {
+            let mut fields = vec![
+                ("novelty_score", score()),
+                ("novelty_reason", string()),
+                ("effectiveness_score", score()),
+                ("effectiveness_reason", string()),
+            ];
+            if block.supported_modes.contains(&AnalysisDepth::Full) {
+                fields.push(("experiment_completeness_score", score()));
+                fields.push(("experiment_completeness_reason", string()));
+            }
+            fields
+        }
+        OutputSchema::CodeLinks => vec![
+            ("code_available", json!({ "type": "boolean" })),
+            ("code_links", string_array()),
+        ],
+        OutputSchema::Flowchart => vec![("algorithm_flowchart", string())],
+        OutputSchema::FormulaList => vec![(
+            "key_formulas",
+            array_of(object(&[("latex", string()), ("name", string()), ("description", string())])),
+        )],
+        OutputSchema::PaperReferenceList => vec![(
+            "related_papers",
+            array_of(object(&[
+                ("arxivId", string()),
+                ("title", string()),
+                (
+                    "relationship",
+                    json!({
+                        "type": "string",
+                        "enum": ["builds_on", "improves_upon", "competing_with", "cited_by", "similar_to"]
+                    }),
+                ),
+                ("relevanceScore", score()),
+                ("reason", string()),
+            ])),
+        )],
+        OutputSchema::AlgorithmList => vec![(
+            "algorithms",
+            array_of(object(&[
+                ("name", string()),
+                ("steps", string_array()),
+                ("complexity", string()),
+            ])),
+        )],
+        OutputSchema::Custom => vec![],
+    }
+}
+
+/// Build the reply schema for a set of enabled blocks
+pub fn build_output_schema(blocks: &[AnalysisBlockConfig]) -> Value {
+    let mut sorted_blocks = blocks.to_vec();
+    sorted_blocks.sort_by_key(|b| b.order);
+
+    let fields: Vec<(String, Value)> = sorted_blocks
+        .iter()
+        .flat_map(block_fields)
+        .collect();
+
+    object(&fields)
+}
+
+/// Object schema with every property required and no extras
+fn object<S: AsRef<str>>(fields: &[(S, Value)]) -> Value {
+    let properties: Map<String, Value> = fields
+        .iter()
+        .map(|(name, schema)| (name.as_ref().to_string(), schema.clone()))
+        .collect();
+    let required: Vec<&str> = fields.iter().map(|(name, _)| name.as_ref()).collect();
+
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+fn array_of(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+/// Check a user-supplied block schema and make it strict
+///
+/// Only the subset understood by `validate` is accepted. Like the built-in
+/// blocks, every object property becomes required and extra properties are
+/// rejected, which strict structured output modes also demand.
+pub fn normalize_custom_schema(schema: &Value) -> Result<Value, String> {
+    let mut schema = schema.clone();
+    normalize_at("$", &mut schema)?;
+    Ok(schema)
+}
+
+fn normalize_at(path: &str, schema: &mut Value) -> Result<(), String> {
+    let object = schema
+        .as_object_mut()
+        .ok_or_else(|| format!("{}: schema must be a JSON object", path))?;
+
+    match object.get("type").and_then(Value::as_str) {
+        Some("object") => {
+            let properties = object
+                .get_mut("properties")
+                .and_then(Value::as_object_mut)
+                .ok_or_else(|| format!("{}: object schema needs 'properties'", path))?;
+            for (name, property) in properties.iter_mut() {
+                normalize_at(&format!("{}.{}", path, name), property)?;
+            }
+            let required: Vec<Value> = properties.keys().cloned().map(Value::String).collect();
+            object.insert("required".to_string(), Value::Array(required));
+            object.insert("additionalProperties".to_string(), Value::Bool(false));
+        }
+        Some("array") => {
+            let items = object
+                .get_mut("items")
+                .ok_or_else(|| format!("{}: array schema needs 'items'", path))?;
+            normalize_at(&format!("{}[]", path), items)?;
+        }
+        Some("string" | "integer" | "number" | "boolean") => {}
+        Some(other) => return Err(format!("{}: unsupported type '{}'", path, other)),
+        None => return Err(format!("{}: schema needs a 'type'", path)),
+    }
+    Ok(())
+}
+
+/// Example value for a schema, shown in the JSON format section of the prompt
+///
+/// Uses the schema's `description` for strings and the first `enum` entry
+/// when there is one.
+pub fn example_value(schema: &Value) -> Value {
+    if let Some(first) = schema.get("enum").and_then(Value::as_array).and_then(|e| e.first()) {
+        return first.clone();
+    }
+
+    match schema.get("type").and_then(Value::as_str) {
+        Some("object") => {
+            let properties = schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .map(|p| p.iter().map(|(name, s)| (name.clone(), example_value(s))).collect())
+                .unwrap_or_default();
+            Value::Object(properties)
+        }
+        Some("array") => {
+            let item = schema.get("items").map(example_value).unwrap_or(Value::Null);
+            Value::Array(vec![item])
+        }
+        Some("integer") => json!(0),
+        Some("number") => json!(0.0),
+        Some("boolean") => json!(true),
+        _ => {
+            let description = schema.get("description").and_then(Value::as_str).unwrap_or("text");
+            Value::String(description.to_string())
+        }
+    }
+}
+
+/// Validate a value against a schema built by this module
+///
+/// Supports the subset of JSON Schema used here (type, properties, required,
+/// additionalProperties, items, enum, minimum, maximum). Returns one message
+/// per violation; an empty list means the value is valid.
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at("$", value, schema, &mut errors);
+    errors
+}
+
+fn validate_at(path: &str, value: &Value, schema: &Value, errors: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        if !matches_type(value, expected) {
+            errors.push(format!("{}: expected {}, got {}", path, expected, type_name(value)));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(format!("{}: {} is not one of {}", path, value, Value::Array(allowed.clone())));
+        }
+    }
+
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if n < min {
+                errors.push(format!("{}: {} is below the minimum of {}", path, n, min));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+            if n > max {
+                errors.push(format!("{}: {} is above the maximum of {}", path, n, max));
+            }
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        let properties = schema.get("properties").and_then(Value::as_object);
+
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(format!("{}: missing required field '{}'", path, name));
+                }
+            }
+        }
+
+        for (name, field_value) in object {
+            match properties.and_then(|p| p.get(name)) {
+                Some(field_schema) => {
+                    validate_at(&format!("{}.{}", path, name), field_value, field_schema, errors);
+                }
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    errors.push(format!("{}: unexpected field '{}'", path, name));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let (Some(items), Some(item_schema)) = (value.as_array(), schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_at(&format!("{}[{}]", path, i), item, item_schema, errors);
+        }
+    }
+}
+
+fn matches_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::blocks;
+
+    #[test]
+    fn test_schema_follows_enabled_blocks() {
+        let schema = build_output_schema(&[
+            blocks::code_links_block(),
+            blocks::ai_summary_block(),
+        ]);
+
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        // Ordered by block order, one entry per JSON field
+        assert_eq!(required, vec!["ai_summary", "code_available", "code_links"]);
+        assert_eq!(schema["properties"]["code_available"]["type"], "boolean");
+    }
+
+    #[test]
+    fn test_custom_schema_is_made_strict() {
+        let schema = normalize_custom_schema(&json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "Dataset name" },
+                    "public": { "type": "boolean" }
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(schema["items"]["required"], json!(["name", "public"]));
+        assert_eq!(schema["items"]["additionalProperties"], false);
+        assert_eq!(example_value(&schema), json!([{ "name": "Dataset name", "public": true }]));
+
+        let error = normalize_custom_schema(&json!({ "type": "array" })).unwrap_err();
+        assert!(error.contains("needs 'items'"), "{}", error);
+        assert!(normalize_custom_schema(&json!({ "type": "object" })).is_err());
+        assert!(normalize_custom_schema(&json!("string")).is_err());
+    }
+
+    #[test]
+    fn test_validate_reply() {
+        let schema = build_output_schema(&[
+            blocks::ai_summary_block(),
+            blocks::quality_assessment_block(),
+            blocks::related_papers_block(),
+        ]);
+
+        let valid = json!({
+            "ai_summary": "Summary",
+            "novelty_score": 8,
+            "novelty_reason": "New",
+            "effectiveness_score": 7,
+            "effectiveness_reason": "Works",
+            "experiment_completeness_score": 6,
+            "experiment_completeness_reason": "Broad",
+            "related_papers": [{
+                "arxivId": "2301.12345",
+                "title": "Prior work",
+                "relationship": "builds_on",
+                "relevanceScore": 8,
+                "reason": "Extends it"
+            }]
+        });
+        assert!(validate(&valid, &schema).is_empty());
+
+        let mut invalid = valid.clone();
+        invalid["novelty_score"] = json!(11);
+        invalid["related_papers"][0]["relationship"] = json!("inspired_by");
+        invalid.as_object_mut().unwrap().remove("ai_summary");
+
+        let errors = validate(&invalid, &schema);
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors.iter().any(|e| e.contains("missing required field 'ai_summary'")));
+        assert!(errors.iter().any(|e| e.starts_with("$.novelty_score")));
+        assert!(errors.iter().any(|e| e.starts_with("$.related_papers[0].relationship")));
+    }
+}
@@ -0,0 +1,216 @@
+//! Translation of stored analyses into another language
+//! Only the stored block outputs are sent, never the paper content, so adding
+//! a language costs far less than analyzing the paper again
+
+use crate::analysis::language::{find_language, parse_language, AnalysisLanguage};
+use crate::analysis::partial::{self, BlockOutcome};
+use crate::analysis::registry::REGISTRY;
+use crate::analysis::{schema, AnalysisBlockConfig, AnalysisDepth};
+use crate::database::{AnalysisBlockRepository, AnalysisRunRepository, PaperRepository};
+use crate::llm::{LlmClient, LlmError};
+use crate::llm_cache::LlmCache;
+use crate::models::{AnalysisRun, NewAnalysisRun, PaperAnalysisBlock};
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
+
+/// Reply fields that are never translated: scores, flags, links and the
+/// English tags and topics. They are copied from the source as-is.
+const UNTRANSLATED_FIELDS: &[&str] = &[
+    "suggested_tags",
+    "suggested_topics",
+    "code_available",
+    "code_links",
+    "novelty_score",
+    "effectiveness_score",
+    "experiment_completeness_score",
+];
+
+/// Translate the latest analysis of a paper into `target_language`
+///
+/// The source is `source_language` or, when not given, the language of the
+/// paper's most recent run in another language. The translation is recorded
+/// as a new run in the target language; the saved paper is left unchanged.
+pub async fn translate_analysis(
+    pool: &SqlitePool,
+    client: &LlmClient,
+    paper_id: &str,
+    source_language: Option<&str>,
+    target_language: &str,
+    structured: bool,
+) -> Result<AnalysisRun, String> {
+    let target = parse_language(target_language)?;
+    let runs = AnalysisRunRepository::new(pool);
+    let source = match source_language {
+        Some(code) => parse_language(code)?,
+        None => latest_other_language(&runs, paper_id, target).await?,
+    };
+    if source == target {
+        return Err(format!("Analysis is already in {}", target.english_name));
+    }
+
+    let stored = AnalysisBlockRepository::new(pool)
+        .get_latest_for_language(paper_id, source.code)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (blocks, sources) = translatable_blocks(stored);
+    if blocks.is_empty() {
+        return Err(format!("No {} analysis stored for paper {}", source.english_name, paper_id));
+    }
+
+    let paper = PaperRepository::new(pool).get_by_id(paper_id).await.map_err(|e| e.to_string())?;
+    let client = client.for_paper(paper_id, &paper.topics);
+    let analysis = merged_fields(&sources);
+    let prompt = build_translation_prompt(&paper.title, source, target, &analysis);
+
+    // Newest source output decides the depth, which also sizes the reply limit
+    let depth = sources
+        .iter()
+        .max_by_key(|block| block.id)
+        .and_then(|block| AnalysisDepth::from_str(&block.depth))
+        .unwrap_or(AnalysisDepth::Standard);
+
+    eprintln!("[translate_analysis] Translating {} blocks of {} from {} to {}",
+        blocks.len(), paper_id, source.code, target.code);
+
+    let response: Result<String, LlmError> = if structured {
+        let output_schema = schema::build_output_schema(&blocks);
+        client.send_structured_request(&prompt, depth.as_str(), &output_schema).await.map(|reply| reply.text)
+    } else {
+        client.send_chat_request(&prompt, depth.as_str()).await
+    };
+    let response = response.map_err(|e| format!("Translation request failed: {}", e))?;
+
+    let mut outcome = partial::parse_reply(&client.prepare_json_response(&response), &blocks);
+    keep_untranslated_fields(&mut outcome, &analysis);
+    if outcome.accepted.is_empty() {
+        return Err(format!("Failed to parse translation: {}", outcome.failure_summary()));
+    }
+
+    let run = NewAnalysisRun {
+        paper_id: paper_id.to_string(),
+        provider: client.provider().as_str().to_string(),
+        model: client.model_for(depth.as_str()).to_string(),
+        depth: depth.as_str().to_string(),
+        language: target.code.to_string(),
+        prompt_hash: LlmCache::hash_prompt(&prompt),
+        restored_from: None,
+        translated_from: Some(source.code.to_string()),
+    };
+    runs.record(&run, &outcome.block_outputs(), &outcome.failed)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Language of the paper's most recent run that is not in `target`
+async fn latest_other_language(
+    runs: &AnalysisRunRepository,
+    paper_id: &str,
+    target: &AnalysisLanguage,
+) -> Result<&'static AnalysisLanguage, String> {
+    let languages = runs.languages_for_paper(paper_id).await.map_err(|e| e.to_string())?;
+    languages
+        .iter()
+        .filter_map(|code| find_language(code))
+        .find(|lang| *lang != target)
+        .ok_or_else(|| format!("No analysis of paper {} to translate into {}", paper_id, target.english_name))
+}
+
+/// Stored outputs whose block is still registered, with their definitions
+fn translatable_blocks(stored: Vec<PaperAnalysisBlock>) -> (Vec<AnalysisBlockConfig>, Vec<PaperAnalysisBlock>) {
+    stored
+        .into_iter()
+        .filter(|block| block.json.is_object())
+        .filter_map(|block| REGISTRY.get(&block.block_id).map(|config| (config, block)))
+        .unzip()
+}
+
+/// Reply fields of all blocks as one JSON object
+fn merged_fields(blocks: &[PaperAnalysisBlock]) -> Value {
+    let mut fields = Map::new();
+    for block in blocks {
+        if let Some(object) = block.json.as_object() {
+            fields.extend(object.clone());
+        }
+    }
+    Value::Object(fields)
+}
+
+/// Put back the source value of every field that must not be translated
+fn keep_untranslated_fields(outcome: &mut BlockOutcome, source: &Value) {
+    for (_, fields) in &mut outcome.accepted {
+        for name in UNTRANSLATED_FIELDS {
+            if let (Some(field), Some(original)) = (fields.get_mut(*name), source.get(*name)) {
+                *field = original.clone();
+            }
+        }
+    }
+}
+
+/// Build the prompt that translates an analysis from `source` to `target`
+pub fn build_translation_prompt(
+    title: &str,
+    source: &AnalysisLanguage,
+    target: &AnalysisLanguage,
+    analysis: &Value,
+) -> String {
+    format!(
+        "You are a technical translator for research paper analyses. \
+        TRANSLATE ANALYSIS from {} to {} ({}).\n\n\
+        Paper Title:\n{}\n\n\
+        ===== TRANSLATION RULES =====\n\
+        1. Translate every text value into {}\n\
+        2. Keep field names, enum values, numbers, booleans and URLs exactly as they are\n\
+        3. Keep in ENGLISH: suggested_tags, suggested_topics, paper titles, project/framework names (e.g., vllm, verl), big-O notation, LaTeX and mermaid syntax\n\
+        4. Keep the same JSON structure and the same number of array elements\n\
+        5. Respond ONLY with valid JSON - no markdown, no code blocks\n\n\
+        ===== ANALYSIS =====\n{}",
+        source.english_name,
+        target.english_name,
+        target.native_name,
+        title,
+        target.english_name,
+        serde_json::to_string_pretty(analysis).unwrap_or_default()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_untranslated_fields_keep_source_values() {
+        let source = json!({
+            "ai_summary": "A load-balanced router",
+            "code_available": true,
+            "code_links": ["https://github.com/example/moe-router"]
+        });
+        let mut translated = Map::new();
+        translated.insert("code_available".to_string(), json!(false));
+        translated.insert("code_links".to_string(), json!(["https://github.com/例/moe-router"]));
+        let mut outcome = BlockOutcome {
+            accepted: vec![("code_links".to_string(), translated)],
+            failed: Vec::new(),
+        };
+
+        keep_untranslated_fields(&mut outcome, &source);
+        assert_eq!(outcome.accepted[0].1["code_available"], json!(true));
+        assert_eq!(outcome.accepted[0].1["code_links"], source["code_links"]);
+    }
+
+    #[test]
+    fn test_translation_prompt_carries_analysis_only() {
+        let source = find_language("en").unwrap();
+        let target = find_language("ja").unwrap();
+        let prompt = build_translation_prompt(
+            "Sparse Routing",
+            source,
+            target,
+            &json!({ "ai_summary": "A load-balanced router" }),
+        );
+
+        assert!(prompt.contains("TRANSLATE ANALYSIS from English to Japanese (日本語)"));
+        assert!(prompt.contains("\"ai_summary\": \"A load-balanced router\""));
+        assert!(!prompt.contains("LaTeX Content"));
+    }
+}
@@ -8,6 +8,12 @@ use thiserror::Error;
 use crate::models::ArxivPaper;
 use std::path::Path;
 
+/// ArXiv API host
+pub const ARXIV_API_BASE_URL: &str = "http://export.arxiv.org";
+
+/// ArXiv site host (HTML, e-print and PDF downloads)
+pub const ARXIV_SITE_BASE_URL: &str = "https://arxiv.org";
+
 /// Errors that can occur during ArXiv API operations
 #[derive(Debug, Error)]
 pub enum ArxivError {
@@ -35,6 +41,9 @@ pub struct ArxivEntry {
     pub links: Vec<ArxivLink>,
     pub authors: Vec<ArxivAuthor>,
     pub categories: Vec<ArxivCategory>,
+    /// Host this entry was fetched from when not the real ArXiv (e.g. a local
+    /// stand-in in tests); HTML, LaTeX and PDF downloads go to the same host
+    pub base_url: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -67,6 +76,8 @@ pub struct FetchOptions {
     pub fetch_by_id: bool,
     /// List of arXiv IDs for fetch-by-ID mode
     pub arxiv_ids: Option<Vec<String>>,
+    /// Override for the ArXiv host, `None` uses export.arxiv.org / arxiv.org
+    pub base_url: Option<String>,
 }
 
 impl Default for FetchOptions {
@@ -79,6 +90,7 @@ impl Default for FetchOptions {
             date_to: None,
             fetch_by_id: false,
             arxiv_ids: None,
+            base_url: None,
         }
     }
 }
@@ -89,6 +101,12 @@ pub async fn fetch_papers(options: &FetchOptions) -> Result<Vec<ArxivEntry>, Arx
         .user_agent("PaperFuse/0.1 (https://github.com/paperfuse)")
         .build()?;
 
+    let api_base = options
+        .base_url
+        .as_deref()
+        .map(|url| url.trim_end_matches('/'))
+        .unwrap_or(ARXIV_API_BASE_URL);
+
     let url = if options.fetch_by_id {
         // Fetch by ID mode: use id_list parameter
         // Format: http://export.arxiv.org/api/query?id_list=2301.12345,2301.67890
@@ -98,7 +116,8 @@ pub async fn fetch_papers(options: &FetchOptions) -> Result<Vec<ArxivEntry>, Arx
             }
             let id_list = ids.join(",");
             format!(
-                "http://export.arxiv.org/api/query?id_list={}",
+                "{}/api/query?id_list={}",
+                api_base,
                 urlencoding::encode(&id_list)
             )
         } else {
@@ -132,7 +151,8 @@ pub async fn fetch_papers(options: &FetchOptions) -> Result<Vec<ArxivEntry>, Arx
             );
 
             format!(
-                "http://export.arxiv.org/api/query?search_query={}&max_results={}&sortBy=submittedDate&sortOrder=descending",
+                "{}/api/query?search_query={}&max_results={}&sortBy=submittedDate&sortOrder=descending",
+                api_base,
                 urlencoding::encode(&date_query),
                 options.max_results
             )
@@ -150,14 +170,16 @@ pub async fn fetch_papers(options: &FetchOptions) -> Result<Vec<ArxivEntry>, Arx
             );
 
             format!(
-                "http://export.arxiv.org/api/query?search_query={}&max_results={}&sortBy=submittedDate&sortOrder=descending",
+                "{}/api/query?search_query={}&max_results={}&sortBy=submittedDate&sortOrder=descending",
+                api_base,
                 urlencoding::encode(&date_query),
                 options.max_results
             )
         } else {
             // No date filter
             format!(
-                "http://export.arxiv.org/api/query?search_query={}&max_results={}&sortBy=submittedDate&sortOrder=descending",
+                "{}/api/query?search_query={}&max_results={}&sortBy=submittedDate&sortOrder=descending",
+                api_base,
                 urlencoding::encode(&query),
                 options.max_results
             )
@@ -178,7 +200,16 @@ pub async fn fetch_papers(options: &FetchOptions) -> Result<Vec<ArxivEntry>, Arx
     let xml = response.text().await?;
 
     // Parse XML response
-    parse_arxiv_xml(&xml)
+    let mut entries = parse_arxiv_xml(&xml)?;
+
+    // Remember a non-default host so downloads follow it
+    if options.base_url.is_some() {
+        for entry in entries.iter_mut() {
+            entry.base_url = Some(api_base.to_string());
+        }
+    }
+
+    Ok(entries)
 }
 
 /// Parse ArXiv XML response using manual parsing
@@ -187,6 +218,8 @@ fn parse_arxiv_xml(xml: &str) -> Result<Vec<ArxivEntry>, ArxivError> {
 
     let mut reader = quick_xml::Reader::from_str(xml);
     reader.config_mut().trim_text(true);
+    // ArXiv writes <link/> and <category/> as empty elements
+    reader.config_mut().expand_empty_elements = true;
 
     let mut entries = Vec::new();
     let mut current_entry: Option<ArxivEntry> = None;
@@ -197,7 +230,8 @@ fn parse_arxiv_xml(xml: &str) -> Result<Vec<ArxivEntry>, ArxivError> {
         match reader.read_event_into(&mut buffer) {
             Ok(Event::Eof) => break,
             Ok(Event::Start(e)) => {
-                match e.name().as_ref() {
+                // Local names, so that <arxiv:affiliation> is read too
+                match e.local_name().as_ref() {
                     b"entry" => {
                         current_entry = Some(ArxivEntry {
                             id: String::new(),
@@ -208,10 +242,11 @@ fn parse_arxiv_xml(xml: &str) -> Result<Vec<ArxivEntry>, ArxivError> {
                             links: Vec::new(),
                             authors: Vec::new(),
                             categories: Vec::new(),
+                            base_url: None,
                         });
                     }
                     b"title" | b"summary" | b"id" | b"published" | b"updated" | b"name" | b"term" | b"affiliation" => {
-                        current_field = Some(String::from_utf8_lossy(e.name().as_ref()).to_string());
+                        current_field = Some(String::from_utf8_lossy(e.local_name().as_ref()).to_string());
                     }
                     b"link" => {
                         // Parse link attributes
@@ -334,7 +369,16 @@ impl ArxivEntry {
             .to_string()
     }
 
+    /// Host for HTML, e-print and PDF downloads
+    fn site_base_url(&self) -> &str {
+        self.base_url.as_deref().unwrap_or(ARXIV_SITE_BASE_URL)
+    }
+
     pub fn get_pdf_url(&self) -> String {
+        if let Some(base_url) = &self.base_url {
+            return format!("{}/pdf/{}.pdf", base_url, self.get_arxiv_id());
+        }
+
         // Find PDF link or construct it
         self.links
             .iter()
@@ -354,7 +398,7 @@ impl ArxivEntry {
     /// Format: https://arxiv.org/html/{arxiv_id}
     pub fn get_html_url(&self) -> String {
         let id = self.get_arxiv_id();
-        format!("https://arxiv.org/html/{}", id)
+        format!("{}/html/{}", self.site_base_url(), id)
     }
 
     /// Download HTML content from arxiv.org
@@ -439,11 +483,6 @@ impl ArxivEntry {
     /// Returns the path to the downloaded LaTeX source file
     /// If the LaTeX source already exists locally, skips downloading and uses cached version
     pub async fn download_latex_source(&self, download_dir: &Path) -> Result<String, ArxivError> {
-        use std::fs;
-        use std::io::Write;
-        use flate2::read::GzDecoder;
-        use tar::Archive;
-
         let arxiv_id = self.get_arxiv_id();
 
         // Check if LaTeX source already exists locally
@@ -451,10 +490,10 @@ impl ArxivEntry {
         if extract_dir.exists() {
             eprintln!("[download_latex_source] LaTeX source already exists at: {}", extract_dir.display());
 
-            // Try to find the main .tex file in the existing directory
-            if let Some(main_tex) = find_main_tex_file(&extract_dir) {
-                eprintln!("[download_latex_source] Using cached LaTeX: {}", main_tex);
-                return Ok(main_tex);
+            // Try to find the root .tex file in the existing directory
+            if let Some(main_tex) = crate::latex_project::find_root_file(&extract_dir) {
+                eprintln!("[download_latex_source] Using cached LaTeX: {}", main_tex.display());
+                return Ok(main_tex.to_string_lossy().to_string());
             } else {
                 eprintln!("[download_latex_source] Cache directory exists but no .tex files found, re-downloading");
             }
@@ -467,7 +506,7 @@ impl ArxivEntry {
 
         // Construct the source download URL
         // Format: https://arxiv.org/e-print/ARXIV_ID
-        let url = format!("https://arxiv.org/e-print/{}", arxiv_id);
+        let url = format!("{}/e-print/{}", self.site_base_url(), arxiv_id);
         eprintln!("[download_latex_source] Downloading from: {}", url);
 
         let response = client.get(&url).send().await?;
@@ -481,44 +520,15 @@ impl ArxivEntry {
 
         let bytes = response.bytes().await?;
 
-        // Create download directory if it doesn't exist
-        fs::create_dir_all(download_dir).map_err(|e| {
-            ArxivError::LatexDownloadError(format!("Failed to create download directory: {}", e))
-        })?;
-
-        // Save the downloaded tar.gz file
-        let tar_gz_path = download_dir.join(format!("{}.tar.gz", arxiv_id));
-        let mut file = fs::File::create(&tar_gz_path).map_err(|e| {
-            ArxivError::LatexDownloadError(format!("Failed to create tar.gz file: {}", e))
-        })?;
-        file.write_all(&bytes).map_err(|e| {
-            ArxivError::LatexDownloadError(format!("Failed to write tar.gz file: {}", e))
-        })?;
-
-        // Extract the tar.gz file
-        let tar_gz = fs::File::open(&tar_gz_path).map_err(|e| {
-            ArxivError::LatexDownloadError(format!("Failed to open tar.gz file: {}", e))
+        // E-prints are a tarball or a single gzipped .tex file
+        crate::latex_project::unpack_eprint(&bytes, &extract_dir, &arxiv_id).map_err(|e| {
+            ArxivError::LatexDownloadError(format!("Failed to extract e-print: {}", e))
         })?;
-        let decoder = GzDecoder::new(tar_gz);
-        let mut archive = Archive::new(decoder);
 
-        let extract_dir = download_dir.join(&arxiv_id);
-        fs::create_dir_all(&extract_dir).map_err(|e| {
-            ArxivError::LatexDownloadError(format!("Failed to create extract directory: {}", e))
-        })?;
-
-        archive.unpack(&extract_dir).map_err(|e| {
-            ArxivError::LatexDownloadError(format!("Failed to extract archive: {}", e))
-        })?;
-
-        // Clean up the tar.gz file
-        fs::remove_file(&tar_gz_path).ok();
-
-        // Find the main .tex file (usually the first .tex file in the directory)
-        let main_tex = find_main_tex_file(&extract_dir).unwrap_or_else(|| {
-            // If no specific main file found, return the directory path
-            extract_dir.to_string_lossy().to_string()
-        });
+        // Find the root .tex file, falling back to the directory itself
+        let main_tex = crate::latex_project::find_root_file(&extract_dir)
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_else(|| extract_dir.to_string_lossy().to_string());
 
         eprintln!("[download_latex_source] Downloaded LaTeX to: {}", main_tex);
 
@@ -526,42 +536,6 @@ impl ArxivEntry {
     }
 }
 
-/// Find the main .tex file in a directory
-/// Usually files with names like "main.tex", "paper.tex", or the first .tex file
-fn find_main_tex_file(dir: &Path) -> Option<String> {
-    let entries: Vec<std::fs::DirEntry> = std::fs::read_dir(dir)
-        .ok()?
-        .filter_map(|e| e.ok())
-        .collect();
-
-    // First look for common main file names
-    for entry in &entries {
-        let path = entry.path();
-        if path.is_file() {
-            if let Some(name) = path.file_name() {
-                let name_str = name.to_string_lossy();
-                if name_str == "main.tex" || name_str == "paper.tex" {
-                    return Some(path.to_string_lossy().to_string());
-                }
-            }
-        }
-    }
-
-    // Fallback: return first .tex file
-    for entry in &entries {
-        let path = entry.path();
-        if path.is_file() {
-            if let Some(ext) = path.extension() {
-                if ext == "tex" {
-                    return Some(path.to_string_lossy().to_string());
-                }
-            }
-        }
-    }
-
-    None
-}
-
 impl ArxivEntry {
     /// Download PDF for this paper to a directory
     /// Returns the path to the downloaded PDF file
@@ -667,6 +641,7 @@ mod tests {
             links: vec![],
             authors: vec![],
             categories: vec![],
+            base_url: None,
         };
 
         assert_eq!(entry.get_arxiv_id(), "2301.12345");
@@ -683,6 +658,7 @@ mod tests {
             links: vec![],
             authors: vec![],
             categories: vec![],
+            base_url: None,
         };
 
         assert_eq!(
@@ -702,9 +678,46 @@ mod tests {
             links: vec![],
             authors: vec![],
             categories: vec![],
+            base_url: None,
         };
 
         let result = entry.parse_published_date();
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_parse_empty_link_and_category_elements() {
+        let xml = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+          <entry>
+            <id>http://arxiv.org/abs/2301.12345v1</id>
+            <title>Test Paper</title>
+            <link title="pdf" href="http://arxiv.org/pdf/2301.12345v1" rel="related" type="application/pdf"/>
+            <category term="cs.LG" scheme="http://arxiv.org/schemas/atom"/>
+            <category term="cs.AI" scheme="http://arxiv.org/schemas/atom"/>
+          </entry>
+        </feed>"#;
+
+        let entries = parse_arxiv_xml(xml).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].get_categories(), vec!["cs.LG", "cs.AI"]);
+        assert_eq!(entries[0].get_pdf_url(), "http://arxiv.org/pdf/2301.12345v1");
+    }
+
+    #[test]
+    fn test_parse_namespaced_affiliation() {
+        let xml = r#"<feed xmlns="http://www.w3.org/2005/Atom" xmlns:arxiv="http://arxiv.org/schemas/atom">
+          <entry>
+            <id>http://arxiv.org/abs/2301.12345v1</id>
+            <author>
+              <name>Ada Lovelace</name>
+              <arxiv:affiliation>Analytical Engine Lab</arxiv:affiliation>
+            </author>
+            <author><name>Alan Turing</name></author>
+          </entry>
+        </feed>"#;
+
+        let authors = &parse_arxiv_xml(xml).unwrap()[0].authors;
+        assert_eq!(authors[0].affiliation.as_deref(), Some("Analytical Engine Lab"));
+        assert_eq!(authors[1].affiliation, None);
+    }
 }
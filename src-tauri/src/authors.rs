@@ -0,0 +1,202 @@
+//! Author names and watchlists
+//!
+//! Names are compared on their surname and given names, ignoring case,
+//! punctuation and diacritics, and an initial matches any given name it
+//! starts ("G. E. Hinton" is "Geoffrey Hinton", "Schölkopf, B." is
+//! "Bernhard Scholkopf"). Watchlists of followed authors and affiliations
+//! keep their papers past the relevance threshold, or boost their score,
+//! during fetch.
+
+use crate::arxiv::ArxivAuthor;
+use crate::models::WatchlistConfig;
+use crate::prefilter::{contains_phrase, tokenize};
+
+/// Name particles that are not given names ("Ludwig van Beethoven")
+const PARTICLES: &[&str] = &["van", "von", "der", "den", "de", "da", "di", "del", "du", "la", "le"];
+/// Generational suffixes dropped before finding the surname
+const SUFFIXES: &[&str] = &["jr", "sr", "ii", "iii", "iv"];
+
+/// Lowercase text with diacritics removed
+///
+/// Covers Latin-1 and Latin Extended-A, plus combining marks of decomposed
+/// input.
+pub fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.to_lowercase().chars() {
+        let plain = match c {
+            '\u{0300}'..='\u{036f}' => continue,
+            'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+            'æ' => {
+                folded.push_str("ae");
+                continue;
+            }
+            'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+            'ď' | 'đ' | 'ð' => 'd',
+            'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+            'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+            'ĥ' | 'ħ' => 'h',
+            'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => 'i',
+            'ĵ' => 'j',
+            'ķ' => 'k',
+            'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+            'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+            'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+            'œ' => {
+                folded.push_str("oe");
+                continue;
+            }
+            'ŕ' | 'ŗ' | 'ř' => 'r',
+            'ś' | 'ŝ' | 'ş' | 'š' => 's',
+            'ß' => {
+                folded.push_str("ss");
+                continue;
+            }
+            'ţ' | 'ť' | 'ŧ' => 't',
+            'þ' => {
+                folded.push_str("th");
+                continue;
+            }
+            'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+            'ŵ' => 'w',
+            'ý' | 'ÿ' | 'ŷ' => 'y',
+            'ź' | 'ż' | 'ž' => 'z',
+            other => other,
+        };
+        folded.push(plain);
+    }
+    folded
+}
+
+/// A person's name split into surname and given names, folded to lowercase
+/// ASCII where possible
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersonName {
+    pub surname: String,
+    given: Vec<String>,
+}
+
+impl PersonName {
+    /// Parse "First Middle Last" or "Last, First"; `None` without any letters
+    pub fn parse(name: &str) -> Option<Self> {
+        let folded = fold(name);
+        let ordered = match folded.split_once(',') {
+            Some((last, first)) if !is_suffix_only(first) => format!("{} {}", first, last),
+            Some((full, _suffix)) => full.to_string(),
+            None => folded,
+        };
+
+        let mut words = tokenize(&ordered);
+        while words.len() > 1 && words.last().is_some_and(|w| SUFFIXES.contains(&w.as_str())) {
+            words.pop();
+        }
+        let surname = words.pop()?;
+        let given = words.into_iter().filter(|w| !PARTICLES.contains(&w.as_str())).collect();
+        Some(Self { surname, given })
+    }
+
+    /// Same surname, and given names that agree as far as both go
+    pub fn matches(&self, other: &PersonName) -> bool {
+        self.surname == other.surname
+            && self.given.iter().zip(&other.given).all(|(a, b)| given_names_match(a, b))
+    }
+}
+
+fn is_suffix_only(text: &str) -> bool {
+    let words = tokenize(text);
+    words.iter().all(|w| SUFFIXES.contains(&w.as_str()))
+}
+
+/// Equal names, or an initial and a name starting with it
+fn given_names_match(a: &str, b: &str) -> bool {
+    a == b || (a.len() == 1 && b.starts_with(a)) || (b.len() == 1 && a.starts_with(b))
+}
+
+/// Why a paper's authors are on the watchlist, `None` when they are not
+pub fn watchlist_hit(watchlist: &WatchlistConfig, authors: &[ArxivAuthor]) -> Option<String> {
+    let watched: Vec<PersonName> = watchlist.authors.iter().filter_map(|name| PersonName::parse(name)).collect();
+    for author in authors {
+        let Some(name) = PersonName::parse(&author.name) else {
+            continue;
+        };
+        if watched.iter().any(|w| w.matches(&name)) {
+            return Some(format!("watched author {}", author.name));
+        }
+    }
+
+    for author in authors {
+        let Some(affiliation) = &author.affiliation else {
+            continue;
+        };
+        let tokens = tokenize(&fold(affiliation));
+        if let Some(watched_affiliation) = watchlist
+            .affiliations
+            .iter()
+            .find(|a| contains_phrase(&tokens, &fold(a)))
+        {
+            return Some(format!("{} at watched affiliation {}", author.name, watched_affiliation));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names_match(a: &str, b: &str) -> bool {
+        match (PersonName::parse(a), PersonName::parse(b)) {
+            (Some(a), Some(b)) => a.matches(&b),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn test_fold_removes_diacritics() {
+        assert_eq!(fold("Schölkopf"), "scholkopf");
+        assert_eq!(fold("Łukasz Kaiser"), "lukasz kaiser");
+        assert_eq!(fold("Straße"), "strasse");
+        // Decomposed: e followed by a combining acute accent
+        assert_eq!(fold("Ame\u{301}lie"), "amelie");
+    }
+
+    #[test]
+    fn test_names_match_initials_and_order() {
+        assert!(names_match("Geoffrey E. Hinton", "G. Hinton"));
+        assert!(names_match("G.E. Hinton", "Geoffrey Everest Hinton"));
+        assert!(names_match("Schölkopf, Bernhard", "B. Scholkopf"));
+        assert!(names_match("Ludwig van Beethoven", "L. Beethoven"));
+        assert!(names_match("Martin Luther King, Jr.", "M. L. King"));
+        assert!(names_match("Hinton", "Geoffrey Hinton"));
+
+        assert!(!names_match("Geoffrey Hinton", "E. Hinton"));
+        assert!(!names_match("Geoffrey Hinton", "Geoffrey Hunt"));
+        assert!(!names_match("", "Hinton"));
+    }
+
+    #[test]
+    fn test_watchlist_hit() {
+        let author = |name: &str, affiliation: Option<&str>| ArxivAuthor {
+            name: name.to_string(),
+            affiliation: affiliation.map(str::to_string),
+        };
+        let watchlist = WatchlistConfig {
+            authors: vec!["Y. LeCun".to_string()],
+            affiliations: vec!["Max Planck Institute".to_string()],
+            ..Default::default()
+        };
+
+        let hit = watchlist_hit(&watchlist, &[author("A. Other", None), author("Yann LeCun", None)]);
+        assert_eq!(hit.as_deref(), Some("watched author Yann LeCun"));
+
+        let hit = watchlist_hit(
+            &watchlist,
+            &[author("Jana Nováková", Some("Max-Planck-Institute for Intelligent Systems"))],
+        );
+        assert_eq!(
+            hit.as_deref(),
+            Some("Jana Nováková at watched affiliation Max Planck Institute")
+        );
+
+        assert_eq!(watchlist_hit(&watchlist, &[author("Yann Other", Some("Planck Lab"))]), None);
+    }
+}
@@ -0,0 +1,453 @@
+//! Reference lists of papers
+//! Parses the `.bbl`/`.bib` files of a LaTeX source and the LaTeXML
+//! bibliography of the arXiv HTML. Each reference keeps the arXiv ID, DOI and
+//! title found in its entry, which is what the citation graph matches library
+//! papers on.
+
+use crate::latex_parser::plain_text;
+use crate::latex_project::{files_with_extension, read_group, read_lossy};
+use regex::Regex;
+use select::document::Document;
+use select::predicate::{Class, Name};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Shortest title key matched against library titles, so that generic
+/// titles like "Introduction" do not link unrelated papers
+const MIN_TITLE_KEY_LEN: usize = 16;
+
+lazy_static::lazy_static! {
+    static ref ARXIV_RE: Regex = Regex::new(
+        r"(?i)(?:arxiv[:\s]\s*|arxiv\.org/(?:abs|pdf)/|\babs/|10\.48550/arxiv\.)(\d{4}\.\d{4,5}|[a-z][a-z\-]*(?:\.[a-z]{2})?/\d{7})"
+    ).unwrap();
+    static ref BARE_ARXIV_RE: Regex = Regex::new(r"^(\d{4}\.\d{4,5}|[a-z][a-z\-]*(?:\.[A-Z]{2})?/\d{7})(?:v\d+)?$").unwrap();
+    static ref DOI_RE: Regex = Regex::new(r"\b(10\.\d{4,9}/[^\s\x22<>{}\\]+)").unwrap();
+    static ref QUOTED_RE: Regex = Regex::new(r"(?:``|“|\x22)([^\x22”']{8,}?)[,.]?(?:''|”|\x22)").unwrap();
+    static ref BIBITEM_RE: Regex = Regex::new(r"\\bibitem\s*(?:\[[^\]]*\])?\s*\{[^}]*\}").unwrap();
+    static ref BIB_END_RE: Regex = Regex::new(r"\\end\{thebibliography\}").unwrap();
+    static ref NEWBLOCK_RE: Regex = Regex::new(r"\\newblock\b").unwrap();
+    static ref FIELD_RE: Regex = Regex::new(r"\\field\{(\w+)\}").unwrap();
+    static ref VERB_RE: Regex = Regex::new(r"(?s)\\verb\{(\w+)\}\s*\\verb\s(.*?)\s*\\endverb").unwrap();
+    static ref BIB_ENTRY_RE: Regex = Regex::new(r"@(\w+)\s*\{").unwrap();
+    static ref BIB_FIELD_RE: Regex = Regex::new(r"^[\s,]*([\w\-:.]+)\s*=\s*").unwrap();
+    static ref CITE_RE: Regex = Regex::new(r"\\(?:no)?cite\w*\*?\s*(?:\[[^\]]*\]\s*){0,2}\{([^}]*)\}").unwrap();
+    static ref BIB_FILES_RE: Regex = Regex::new(r"\\(?:bibliography|addbibresource)\s*(?:\[[^\]]*\])?\s*\{([^}]+)\}").unwrap();
+}
+
+/// Entry of a reference list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reference {
+    pub title: Option<String>,
+    /// Without version suffix
+    pub arxiv_id: Option<String>,
+    /// Lowercase
+    pub doi: Option<String>,
+}
+
+impl Reference {
+    /// Reference with the identifiers found in `text`
+    ///
+    /// Without a `title`, a quoted title in the text is used.
+    fn from_text(title: Option<String>, text: &str) -> Self {
+        let doi = find_doi(text);
+        Reference {
+            title: title
+                .or_else(|| QUOTED_RE.captures(text).map(|c| c[1].to_string()))
+                .and_then(|t| clean_title(&t)),
+            arxiv_id: find_arxiv_id(text).or_else(|| doi.as_deref().and_then(find_arxiv_id)),
+            doi,
+        }
+    }
+
+    /// Whether anything identifies the cited work
+    pub fn is_identified(&self) -> bool {
+        self.arxiv_id.is_some() || self.doi.is_some() || self.title.is_some()
+    }
+}
+
+/// arXiv ID of an `arXiv:…`, `arxiv.org/abs/…` or `abs/…` mention
+pub fn find_arxiv_id(text: &str) -> Option<String> {
+    ARXIV_RE.captures(text).map(|c| c[1].to_string())
+}
+
+/// First DOI in `text`, lowercase and without trailing punctuation
+pub fn find_doi(text: &str) -> Option<String> {
+    DOI_RE.captures(text).map(|c| {
+        c[1].trim_end_matches(['.', ',', ';', ':', ')', ']']).to_lowercase()
+    })
+}
+
+/// Key two titles are compared on: lowercase words of letters and digits,
+/// `None` when too short to identify a paper
+pub fn title_key(title: &str) -> Option<String> {
+    let key = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (key.len() >= MIN_TITLE_KEY_LEN).then_some(key)
+}
+
+/// Title with whitespace collapsed and surrounding punctuation removed
+fn clean_title(title: &str) -> Option<String> {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    let title = title.trim_matches(|c: char| {
+        c.is_whitespace() || matches!(c, '.' | ',' | ';' | ':' | '"' | '\'' | '`' | '“' | '”')
+    });
+    (title.split_whitespace().count() >= 2).then(|| title.to_string())
+}
+
+/// References in the LaTeXML bibliography of an arXiv HTML page
+///
+/// The title is LaTeXML's `ltx_bib_title` when the source had a `.bib`, and
+/// otherwise the second block of the entry (authors, title, venue).
+pub fn from_html(html: &str) -> Vec<Reference> {
+    let document = Document::from(html);
+    let references = document
+        .find(Class("ltx_bibitem"))
+        .map(|item| {
+            let blocks: Vec<String> = item.find(Class("ltx_bibblock")).map(|b| b.text()).collect();
+            let title = item
+                .find(Class("ltx_bib_title"))
+                .next()
+                .map(|t| t.text())
+                .or_else(|| blocks.get(1).cloned());
+            let links: Vec<&str> = item.find(Name("a")).filter_map(|a| a.attr("href")).collect();
+            Reference::from_text(title, &format!("{} {}", item.text(), links.join(" ")))
+        })
+        .collect();
+    dedup(references)
+}
+
+/// References of a LaTeX project
+///
+/// `source` is the loaded project. An inline `thebibliography` is used first,
+/// then the project's `.bbl` files, then the `.bib` files it names,
+/// restricted to the keys the source cites.
+pub fn from_latex_project(root: &Path, source: &str) -> Vec<Reference> {
+    let inline = parse_bbl(source);
+    if !inline.is_empty() {
+        return dedup(inline);
+    }
+
+    let dir = if root.is_dir() { root } else { root.parent().unwrap_or(root) };
+    let from_bbl: Vec<Reference> = files_with_extension(dir, "bbl")
+        .iter()
+        .filter_map(|path| read_lossy(path).ok())
+        .flat_map(|bbl| parse_bbl(&bbl))
+        .collect();
+    if !from_bbl.is_empty() {
+        return dedup(from_bbl);
+    }
+
+    let named: HashSet<String> = BIB_FILES_RE
+        .captures_iter(source)
+        .flat_map(|c| c[1].split(',').map(|n| n.trim().trim_end_matches(".bib").to_string()).collect::<Vec<_>>())
+        .collect();
+    let bib_files: Vec<_> = files_with_extension(dir, "bib")
+        .into_iter()
+        .filter(|path| {
+            let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            named.is_empty() || named.iter().any(|n| n == &stem || n.ends_with(&format!("/{}", stem)))
+        })
+        .collect();
+
+    let cited = cited_keys(source);
+    let from_bib = bib_files
+        .iter()
+        .filter_map(|path| read_lossy(path).ok())
+        .flat_map(|bib| parse_bib(&bib, cited.as_ref()))
+        .collect();
+    dedup(from_bib)
+}
+
+/// Keys cited in a LaTeX source, `None` for `\nocite{*}` (everything)
+fn cited_keys(source: &str) -> Option<HashSet<String>> {
+    let mut keys = HashSet::new();
+    for captures in CITE_RE.captures_iter(source) {
+        for key in captures[1].split(',').map(str::trim).filter(|k| !k.is_empty()) {
+            if key == "*" {
+                return None;
+            }
+            keys.insert(key.to_string());
+        }
+    }
+    Some(keys)
+}
+
+/// References of a `.bbl` file or an inline `thebibliography`
+///
+/// Handles `\bibitem` lists (BibTeX styles, with the title as the second
+/// `\newblock`) and biblatex `\entry` lists.
+pub fn parse_bbl(bbl: &str) -> Vec<Reference> {
+    if bbl.contains("\\entry{") {
+        return parse_biblatex_bbl(bbl);
+    }
+
+    let items: Vec<_> = BIBITEM_RE.find_iter(bbl).collect();
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let end = items
+                .get(i + 1)
+                .map(|next| next.start())
+                .or_else(|| BIB_END_RE.find_at(bbl, item.end()).map(|m| m.start()))
+                .unwrap_or(bbl.len());
+            let entry = &bbl[item.end()..end];
+            let title = NEWBLOCK_RE.split(entry).nth(1).map(plain_text);
+            Reference::from_text(title, &entry.replace("\\_", "_"))
+        })
+        .filter(Reference::is_identified)
+        .collect()
+}
+
+/// References of a biblatex `.bbl`: `\field{title}{…}`, `\field{eprint}{…}`
+/// and `\verb{doi}` of each `\entry`
+fn parse_biblatex_bbl(bbl: &str) -> Vec<Reference> {
+    bbl.split("\\entry{")
+        .skip(1)
+        .map(|entry| {
+            let entry = entry.split("\\endentry").next().unwrap_or(entry);
+            let field = |name: &str| {
+                FIELD_RE
+                    .captures_iter(entry)
+                    .find(|c| &c[1] == name)
+                    .and_then(|c| read_group(entry, c.get(0).unwrap().end()))
+                    .map(|(value, _)| value.to_string())
+            };
+            let verbs: Vec<String> = VERB_RE.captures_iter(entry).map(|c| c[2].to_string()).collect();
+
+            let mut reference = Reference::from_text(
+                field("title").map(|t| plain_text(&t)),
+                &format!("{} {}", entry, verbs.join(" ")),
+            );
+            let is_arxiv = field("eprinttype").is_some_and(|t| t.eq_ignore_ascii_case("arxiv"));
+            if let Some(eprint) = field("eprint").filter(|_| is_arxiv) {
+                reference.arxiv_id = bare_arxiv_id(&eprint).or(reference.arxiv_id);
+            }
+            reference
+        })
+        .filter(Reference::is_identified)
+        .collect()
+}
+
+/// arXiv ID given on its own, as in an `eprint` field
+fn bare_arxiv_id(text: &str) -> Option<String> {
+    BARE_ARXIV_RE.captures(text.trim()).map(|c| c[1].to_string())
+}
+
+/// References of a BibTeX file
+///
+/// With `cited` set, entries whose key is not in it are skipped.
+pub fn parse_bib(bib: &str, cited: Option<&HashSet<String>>) -> Vec<Reference> {
+    let mut references = Vec::new();
+    for entry_start in BIB_ENTRY_RE.captures_iter(bib) {
+        let kind = entry_start[1].to_lowercase();
+        if matches!(kind.as_str(), "comment" | "string" | "preamble") {
+            continue;
+        }
+        let brace = entry_start.get(0).unwrap().end() - 1;
+        let Some((body, _)) = read_group(bib, brace) else {
+            continue;
+        };
+        let (key, fields) = body.split_once(',').unwrap_or((body, ""));
+        if cited.is_some_and(|keys| !keys.contains(key.trim())) {
+            continue;
+        }
+
+        let fields = parse_bib_fields(fields);
+        let field = |name: &str| fields.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+        let text = fields.iter().map(|(_, v)| v.as_str()).collect::<Vec<_>>().join(" ");
+
+        let mut reference = Reference::from_text(field("title").map(plain_text), &text);
+        let is_arxiv = ["archiveprefix", "eprinttype"]
+            .iter()
+            .any(|n| field(n).is_some_and(|v| v.eq_ignore_ascii_case("arxiv")));
+        if let Some(eprint) = field("eprint").filter(|_| is_arxiv) {
+            reference.arxiv_id = bare_arxiv_id(eprint).or(reference.arxiv_id);
+        }
+        if reference.is_identified() {
+            references.push(reference);
+        }
+    }
+    references
+}
+
+/// `name = value` pairs of a BibTeX entry, names lowercase
+///
+/// Values are brace groups, quoted strings or bare words (numbers and
+/// `@string` names); `#` concatenations are joined.
+fn parse_bib_fields(fields: &str) -> Vec<(String, String)> {
+    let mut parsed = Vec::new();
+    let mut pos = 0;
+    while let Some(name) = BIB_FIELD_RE.captures(&fields[pos..]) {
+        let field_name = name[1].to_lowercase();
+        pos += name.get(0).unwrap().end();
+
+        let mut value = String::new();
+        loop {
+            let rest = &fields[pos..];
+            if rest.starts_with('{') {
+                let Some((group, end)) = read_group(fields, pos) else {
+                    return parsed;
+                };
+                value.push_str(group);
+                pos = end;
+            } else if let Some(quoted) = rest.strip_prefix('"') {
+                let mut depth = 0;
+                let end = quoted.char_indices().find(|&(_, c)| {
+                    match c {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        '"' if depth == 0 => return true,
+                        _ => {}
+                    }
+                    false
+                });
+                let Some((end, _)) = end else {
+                    return parsed;
+                };
+                value.push_str(&quoted[..end]);
+                pos += end + 2;
+            } else {
+                let end = rest.find([',', '#', '}']).unwrap_or(rest.len());
+                value.push_str(rest[..end].trim());
+                pos += end;
+            }
+
+            let rest = &fields[pos..];
+            let trimmed = rest.trim_start();
+            match trimmed.strip_prefix('#') {
+                Some(after) => pos += rest.len() - after.len() + (after.len() - after.trim_start().len()),
+                None => break,
+            }
+        }
+        parsed.push((field_name, value));
+    }
+    parsed
+}
+
+/// Drop repeated references, keeping the first
+fn dedup(references: Vec<Reference>) -> Vec<Reference> {
+    let mut seen = HashSet::new();
+    references
+        .into_iter()
+        .filter(Reference::is_identified)
+        .filter(|r| {
+            let key = r
+                .arxiv_id
+                .clone()
+                .or_else(|| r.doi.clone())
+                .or_else(|| r.title.as_deref().map(str::to_lowercase));
+            key.is_none_or(|k| seen.insert(k))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_identifiers() {
+        assert_eq!(find_arxiv_id("arXiv preprint arXiv:1706.03762, 2017."), Some("1706.03762".to_string()));
+        assert_eq!(find_arxiv_id("https://arxiv.org/abs/2401.00001v2"), Some("2401.00001".to_string()));
+        assert_eq!(find_arxiv_id("CoRR, abs/1512.03385"), Some("1512.03385".to_string()));
+        assert_eq!(find_arxiv_id("arXiv:hep-th/9711200"), Some("hep-th/9711200".to_string()));
+        assert_eq!(find_arxiv_id("In NeurIPS, 2017."), None);
+        assert_eq!(find_doi("doi:10.1145/3292500.3330701."), Some("10.1145/3292500.3330701".to_string()));
+        assert_eq!(
+            Reference::from_text(None, "https://doi.org/10.48550/arXiv.2303.08774").arxiv_id,
+            Some("2303.08774".to_string())
+        );
+    }
+
+    #[test]
+    fn test_title_key() {
+        assert_eq!(
+            title_key("Attention Is All You\n  Need."),
+            Some("attention is all you need".to_string())
+        );
+        assert_eq!(title_key("Introduction"), None);
+    }
+
+    #[test]
+    fn test_parse_bbl() {
+        let source = include_str!("../tests/fixtures/latex/routing.tex");
+        let references = parse_bbl(source);
+        assert_eq!(references, vec![
+            Reference { title: Some("Outrageously large neural networks".to_string()), arxiv_id: None, doi: None },
+        ]);
+
+        let biblatex = r"\entry{vaswani}{inproceedings}{}
+  \field{title}{Attention is {All} you Need}
+  \field{eprinttype}{arXiv}
+  \field{eprint}{1706.03762v5}
+  \verb{doi}
+  \verb 10.5555/3295222.3295349
+  \endverb
+\endentry";
+        assert_eq!(parse_bbl(biblatex), vec![Reference {
+            title: Some("Attention is All you Need".to_string()),
+            arxiv_id: Some("1706.03762".to_string()),
+            doi: Some("10.5555/3295222.3295349".to_string()),
+        }]);
+    }
+
+    #[test]
+    fn test_parse_bib() {
+        let bib = r#"@string{neurips = "NeurIPS"}
+@article{he2016,
+  title = {Deep Residual Learning for {Image} Recognition},
+  journal = {CoRR}, volume = {abs/1512.03385},
+}
+@misc{openai2023,
+  title = "{GPT-4} Technical Report",
+  eprint = {2303.08774}, archivePrefix = {arXiv},
+  booktitle = neurips # " 2023",
+}
+@inproceedings{uncited, title = {Never Cited Anywhere}, doi = {10.1000/x}}
+"#;
+        let cited: HashSet<String> = ["he2016", "openai2023"].iter().map(|k| k.to_string()).collect();
+        assert_eq!(parse_bib(bib, Some(&cited)), vec![
+            Reference {
+                title: Some("Deep Residual Learning for Image Recognition".to_string()),
+                arxiv_id: Some("1512.03385".to_string()),
+                doi: None,
+            },
+            Reference {
+                title: Some("GPT-4 Technical Report".to_string()),
+                arxiv_id: Some("2303.08774".to_string()),
+                doi: None,
+            },
+        ]);
+        assert_eq!(parse_bib(bib, None).len(), 3);
+    }
+
+    #[test]
+    fn test_from_html() {
+        let html = r#"<ul class="ltx_biblist">
+<li id="bib.bib1" class="ltx_bibitem"><span class="ltx_tag ltx_tag_bibitem">[1]</span>
+<span class="ltx_bibblock">Ashish Vaswani, Noam Shazeer, et al.</span>
+<span class="ltx_bibblock">Attention is all you need.</span>
+<span class="ltx_bibblock"><a href="https://arxiv.org/abs/1706.03762" class="ltx_ref">arXiv:1706.03762</a>, 2017.</span></li>
+<li id="bib.bib2" class="ltx_bibitem"><span class="ltx_bibblock">K. He et al., “Deep residual learning for image recognition,” in CVPR, 2016.
+<a href="https://doi.org/10.1109/CVPR.2016.90">doi</a></span></li>
+<li id="bib.bib3" class="ltx_bibitem"><span class="ltx_bibblock">2017.</span></li>
+</ul>"#;
+        assert_eq!(from_html(html), vec![
+            Reference {
+                title: Some("Attention is all you need".to_string()),
+                arxiv_id: Some("1706.03762".to_string()),
+                doi: None,
+            },
+            Reference {
+                title: Some("Deep residual learning for image recognition".to_string()),
+                arxiv_id: None,
+                doi: Some("10.1109/cvpr.2016.90".to_string()),
+            },
+        ]);
+    }
+}
@@ -0,0 +1,268 @@
+//! Spending budgets for LLM calls
+//!
+//! Spend is measured from the token usage recorded for every call (see
+//! `LlmUsageRepository`). As a budget runs low the fetch pipeline degrades in
+//! two steps: past the deep analysis cutoff only relevance scoring runs, and
+//! once a limit is reached no LLM calls are made at all.
+
+use crate::database::LlmUsageRepository;
+use crate::models::{BudgetConfig, UsageSummary};
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::{Arc, OnceLock};
+
+/// How much LLM work the remaining budget allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetStage {
+    /// Relevance scoring and deep analysis
+    Available,
+    /// Past the deep analysis cutoff: relevance scoring only
+    RelevanceOnly,
+    /// A limit is reached: no LLM calls
+    Exhausted,
+}
+
+/// Spend against one configured limit
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetLimitUsage {
+    /// "daily" or "monthly"
+    pub period: &'static str,
+    /// "tokens" or "cost" (USD)
+    pub unit: &'static str,
+    pub used: f64,
+    pub limit: f64,
+}
+
+impl BudgetLimitUsage {
+    /// Share of the limit used; a limit of zero is always used up
+    pub fn ratio(&self) -> f64 {
+        if self.limit <= 0.0 {
+            f64::INFINITY
+        } else {
+            self.used / self.limit
+        }
+    }
+
+    /// e.g. "Daily cost budget" or "Monthly token budget"
+    fn name(&self) -> String {
+        let period = if self.period == "daily" { "Daily" } else { "Monthly" };
+        let unit = if self.unit == "cost" { "cost" } else { "token" };
+        format!("{} {} budget", period, unit)
+    }
+
+    /// e.g. "$1.02 of $1.00" or "850000 of 1000000 tokens"
+    fn amounts(&self) -> String {
+        if self.unit == "cost" {
+            format!("${:.2} of ${:.2}", self.used, self.limit)
+        } else {
+            format!("{} of {} tokens", self.used as i64, self.limit as i64)
+        }
+    }
+}
+
+/// Spend against every configured limit and what it still allows
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+    pub stage: BudgetStage,
+    pub limits: Vec<BudgetLimitUsage>,
+    /// Why the stage is not `Available`
+    pub reason: Option<String>,
+}
+
+/// Compare today's and this month's usage with the configured limits
+///
+/// The limit closest to (or furthest past) its end decides the stage.
+pub fn evaluate(config: &BudgetConfig, today: &UsageSummary, this_month: &UsageSummary) -> BudgetStatus {
+    let tokens = |usage: &UsageSummary| (usage.prompt_tokens + usage.completion_tokens) as f64;
+    let configured = [
+        ("daily", "tokens", config.daily_token_limit.map(|l| l as f64), tokens(today)),
+        ("monthly", "tokens", config.monthly_token_limit.map(|l| l as f64), tokens(this_month)),
+        ("daily", "cost", config.daily_cost_limit, today.cost),
+        ("monthly", "cost", config.monthly_cost_limit, this_month.cost),
+    ];
+    let limits: Vec<BudgetLimitUsage> = configured
+        .into_iter()
+        .filter_map(|(period, unit, limit, used)| {
+            limit.map(|limit| BudgetLimitUsage { period, unit, used, limit })
+        })
+        .collect();
+
+    let tightest = limits.iter().max_by(|a, b| a.ratio().total_cmp(&b.ratio()));
+    let (stage, reason) = match tightest {
+        Some(limit) if limit.ratio() >= 1.0 => (
+            BudgetStage::Exhausted,
+            Some(format!("{} exhausted ({})", limit.name(), limit.amounts())),
+        ),
+        Some(limit) if limit.ratio() >= config.deep_analysis_cutoff => (
+            BudgetStage::RelevanceOnly,
+            Some(format!(
+                "{} {:.0}% used ({}), deep analysis paused",
+                limit.name(),
+                limit.ratio() * 100.0,
+                limit.amounts()
+            )),
+        ),
+        _ => (BudgetStage::Available, None),
+    };
+
+    BudgetStatus { stage, limits, reason }
+}
+
+/// Current spend against `config`, measured in UTC days and months
+pub async fn current_status(pool: &SqlitePool, config: &BudgetConfig) -> Result<BudgetStatus, sqlx::Error> {
+    let now = Utc::now();
+    let usage = LlmUsageRepository::new(pool);
+    let today = usage.totals_since(&now.format("%Y-%m-%d").to_string()).await?;
+    let this_month = usage.totals_since(&now.format("%Y-%m").to_string()).await?;
+    Ok(evaluate(config, &today, &this_month))
+}
+
+/// Budget checks shared by all workers of one fetch
+///
+/// A fetch that ran out of budget stays stopped, even if a new day starts
+/// while it runs. The fetch's `LlmClient` checks the guard before every
+/// request (see `LlmClient::with_budget`); calls already in flight when a
+/// limit is reached still complete, so the overrun is bounded by the number
+/// of concurrent workers.
+#[derive(Clone)]
+pub struct BudgetGuard {
+    inner: Arc<GuardState>,
+}
+
+struct GuardState {
+    pool: SqlitePool,
+    config: Option<BudgetConfig>,
+    exhausted: OnceLock<String>,
+    deep_analysis_paused: OnceLock<String>,
+}
+
+impl BudgetGuard {
+    pub fn new(pool: &SqlitePool, config: Option<BudgetConfig>) -> Self {
+        Self {
+            inner: Arc::new(GuardState {
+                pool: pool.clone(),
+                config,
+                exhausted: OnceLock::new(),
+                deep_analysis_paused: OnceLock::new(),
+            }),
+        }
+    }
+
+    /// What the budget allows right now
+    ///
+    /// Usage that cannot be read counts as an exhausted budget rather than
+    /// risking an overrun.
+    pub async fn stage(&self) -> BudgetStage {
+        if self.inner.exhausted.get().is_some() {
+            return BudgetStage::Exhausted;
+        }
+        let Some(config) = &self.inner.config else {
+            return BudgetStage::Available;
+        };
+
+        let (stage, reason) = match current_status(&self.inner.pool, config).await {
+            Ok(status) => (status.stage, status.reason),
+            Err(e) => (BudgetStage::Exhausted, Some(format!("Could not read token usage: {}", e))),
+        };
+        let reason = reason.unwrap_or_default();
+        match stage {
+            BudgetStage::Available => {}
+            BudgetStage::RelevanceOnly => {
+                let _ = self.inner.deep_analysis_paused.set(reason);
+            }
+            BudgetStage::Exhausted => {
+                eprintln!("[BudgetGuard::stage] {}", reason);
+                let _ = self.inner.exhausted.set(reason);
+            }
+        }
+        stage
+    }
+
+    /// Check before an LLM call; the error is the exhausted limit
+    pub async fn check(&self) -> Result<(), String> {
+        match self.stage().await {
+            BudgetStage::Exhausted => Err(self.exhausted_reason().unwrap_or_default().to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check before deep analysis
+    pub async fn allows_deep_analysis(&self) -> bool {
+        self.stage().await == BudgetStage::Available
+    }
+
+    /// The limit that stopped the fetch, if any
+    pub fn exhausted_reason(&self) -> Option<&str> {
+        self.inner.exhausted.get().map(String::as_str)
+    }
+
+    /// Messages for every budget limit the fetch ran into
+    pub fn messages(&self) -> Vec<String> {
+        let mut messages = Vec::new();
+        if let Some(reason) = self.inner.deep_analysis_paused.get() {
+            messages.push(reason.clone());
+        }
+        if let Some(reason) = self.inner.exhausted.get() {
+            messages.push(format!("{}, fetch stopped", reason));
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(tokens: i64, cost: f64) -> UsageSummary {
+        UsageSummary {
+            key: None,
+            calls: 1,
+            prompt_tokens: tokens,
+            completion_tokens: 0,
+            cost,
+            unpriced_calls: 0,
+            first_call_at: String::new(),
+            last_call_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_evaluate_degrades_in_steps() {
+        let config = BudgetConfig {
+            daily_cost_limit: Some(1.0),
+            monthly_token_limit: Some(1_000_000),
+            ..Default::default()
+        };
+
+        let status = evaluate(&config, &usage(1_000, 0.5), &usage(10_000, 0.5));
+        assert_eq!(status.stage, BudgetStage::Available);
+        assert_eq!(status.limits.len(), 2);
+        assert!(status.reason.is_none());
+
+        let status = evaluate(&config, &usage(1_000, 0.5), &usage(850_000, 0.5));
+        assert_eq!(status.stage, BudgetStage::RelevanceOnly);
+        assert_eq!(
+            status.reason.as_deref(),
+            Some("Monthly token budget 85% used (850000 of 1000000 tokens), deep analysis paused")
+        );
+
+        let status = evaluate(&config, &usage(1_000, 1.02), &usage(850_000, 1.02));
+        assert_eq!(status.stage, BudgetStage::Exhausted);
+        assert_eq!(status.reason.as_deref(), Some("Daily cost budget exhausted ($1.02 of $1.00)"));
+    }
+
+    #[test]
+    fn test_evaluate_without_limits() {
+        let status = evaluate(&BudgetConfig::default(), &usage(10_000_000, 100.0), &usage(10_000_000, 100.0));
+        assert_eq!(status.stage, BudgetStage::Available);
+        assert!(status.limits.is_empty());
+
+        // A zero limit allows nothing
+        let config = BudgetConfig { daily_token_limit: Some(0), ..Default::default() };
+        assert_eq!(evaluate(&config, &usage(0, 0.0), &usage(0, 0.0)).stage, BudgetStage::Exhausted);
+    }
+}
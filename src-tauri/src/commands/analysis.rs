@@ -5,12 +5,17 @@
 //! analysis modes (standard vs full).
 
 use crate::arxiv::{fetch_papers, FetchOptions};
-use crate::database::PaperRepository;
-use crate::llm::{LlmClient, StandardAnalysisResult, FullAnalysisResult};
+use crate::database::{AnalysisBlockRepository, AnalysisRunRepository, PaperRepository};
+use crate::llm::{LlmClient, StructuredReply, UsageContext};
 use crate::llm_cache::LlmCache;
-use crate::models::{Paper, LLMProvider};
+use crate::models::{AnalysisRun, AnalysisRunDiff, Paper, PaperAnalysisBlock, Settings};
 use crate::analysis::AnalysisDepth;
+use crate::analysis::executor::BlockExecutionMode;
+use crate::analysis::history;
+use crate::analysis::language::{parse_language, AnalysisLanguage, DEFAULT_LANGUAGE, SUPPORTED_LANGUAGES};
+use crate::fetch::{fetch_pdf_content, latex_paper_content, store_references, ContentMode};
 use sqlx::SqlitePool;
+use std::sync::Arc;
 use tauri::State;
 
 /// Analysis result
@@ -21,6 +26,29 @@ pub struct BatchAnalysisResult {
     pub failed_ids: Vec<String>,
 }
 
+/// LLM client for the configured provider
+///
+/// Models come from the configured provider to avoid using
+/// GLM provider with Claude models or vice versa.
+fn client_from_settings(settings: &Settings) -> Result<LlmClient, String> {
+    let provider = settings.llm_provider.clone();
+    let backend = crate::llm::get_backend(&provider).map_err(|e| e.to_string())?;
+    let provider_config = backend.provider_config(settings);
+
+    let api_key = provider_config.api_key.clone().ok_or_else(|| "No API key configured".to_string())?;
+
+    let client = LlmClient::new(
+        provider,
+        api_key,
+        provider_config.quick_model.clone(),
+        provider_config.deep_model.clone(),
+    )
+    .map_err(|e| format!("Failed to create LLM client: {}", e))?;
+    Ok(client
+        .with_base_url(provider_config.base_url.clone())
+        .with_context_window(settings.context_window))
+}
+
 /// Analyze a single paper with the specified mode
 #[tauri::command]
 pub async fn analyze_paper(
@@ -32,8 +60,8 @@ pub async fn analyze_paper(
     #[allow(non_snake_case)]
     analysisLanguage: Option<String>,
 ) -> Result<Paper, String> {
-    // Use provided language or default to Chinese
-    let analysis_language = analysisLanguage.as_deref().unwrap_or("zh");
+    // Use provided language or default to English, like fetches do
+    let analysis_language = parse_language(analysisLanguage.as_deref().unwrap_or(DEFAULT_LANGUAGE))?.code;
     eprintln!("[analyze_paper] ENTRY - Received parameters:");
     eprintln!("  paperId: {:?}", paperId);
     eprintln!("  analysisMode: {:?}", analysisMode);
@@ -61,6 +89,7 @@ pub async fn analyze_paper(
         date_to: None,
         fetch_by_id: true,
         arxiv_ids: Some(vec![paper.arxiv_id.clone()]),
+        base_url: None,
     };
 
     let entries = fetch_papers(&fetch_options).await
@@ -83,40 +112,17 @@ pub async fn analyze_paper(
             e.to_string()
         })?;
 
-    // Create LLM client
-    let provider = settings.llm_provider.clone();
-    let provider_str = format!("{:?}", provider);
-
-    let api_key = match provider {
-        LLMProvider::Glm => settings.glm_api_key,
-        LLMProvider::Claude => settings.claude_api_key,
-    }.ok_or_else(|| {
-        eprintln!("[analyze_paper] No API key configured");
-        "No API key configured".to_string()
-    })?;
-
-    // Select models based on the configured provider to avoid using
-    // GLM provider with Claude models or vice versa
-    let (quick_model, deep_model) = match provider {
-        LLMProvider::Glm => (
-            settings.glm_quick_model.clone(),
-            settings.glm_deep_model.clone(),
-        ),
-        LLMProvider::Claude => (
-            settings.claude_quick_model.clone(),
-            settings.claude_deep_model.clone(),
-        ),
-    };
-
-    let client = LlmClient::new(
-        provider.clone(),
-        api_key,
-        quick_model,
-        deep_model,
-    ).map_err(|e| {
-        eprintln!("[analyze_paper] Failed to create LLM client: {}", e);
-        e.to_string()
-    })?;
+    let client = client_from_settings(&settings)
+        .map_err(|e| {
+            eprintln!("[analyze_paper] {}", e);
+            e
+        })?
+        .with_usage_tracking(pool.inner(), UsageContext {
+            paper_id: Some(paper_id.clone()),
+            topics: paper.topics.clone(),
+            ..Default::default()
+        });
+    let provider_str = format!("{:?}", settings.llm_provider);
 
     // Get topics and analysis config from settings
     let topics = settings.topics.clone();
@@ -130,16 +136,20 @@ pub async fn analyze_paper(
     // Get LaTeX download path from settings
     let latex_path = settings.latex_download_path.as_deref();
 
+    let mode = if analysis_mode == "full" { ContentMode::Full } else { ContentMode::Standard };
+
     // Download LaTeX if path is configured
     let latex_content = if let Some(download_path) = latex_path {
         use std::path::Path;
         let path = Path::new(download_path);
         match entry.download_latex_source(path).await {
             Ok(latex_file_path) => {
-                match std::fs::read_to_string(&latex_file_path) {
+                match crate::latex_project::load_project(Path::new(&latex_file_path)) {
                     Ok(content) => {
                         eprintln!("[analyze_paper] LaTeX downloaded ({} bytes)", content.len());
-                        Some(content)
+                        let fetched = latex_paper_content(Path::new(&latex_file_path), &content, mode);
+                        store_references(pool.inner(), &paper_id, &fetched.references).await;
+                        Some(fetched.content)
                     }
                     Err(e) => {
                         eprintln!("[analyze_paper] Failed to read LaTeX: {}, falling back to abstract", e);
@@ -157,149 +167,195 @@ pub async fn analyze_paper(
         None
     };
 
+    // LaTeX unavailable: try the text of the PDF
+    let latex_content = match latex_content {
+        Some(content) => Some(content),
+        None => match fetch_pdf_content(pool.inner(), &entry, mode).await {
+            Ok(fetched) => Some(fetched.content),
+            Err(e) => {
+                eprintln!("[analyze_paper] PDF text not available: {}, falling back to abstract", e);
+                None
+            }
+        },
+    };
+
     // Determine analysis depth
     let depth = match analysis_mode.as_str() {
         "full" => AnalysisDepth::Full,
         "standard" | _ => AnalysisDepth::Standard,
     };
 
+    // Fit the content into what the prompt leaves of the context window
+    let scaffold = crate::analysis::build_analysis_prompt(
+        &paper.title, &entry.summary, &topics, None, analysis_language, &analysis_config, depth,
+    );
+    let latex_content = match latex_content {
+        Some(content) => Some(
+            crate::analysis::chunked::prepare_content(
+                &client,
+                &paper_id,
+                &paper.title,
+                depth,
+                &scaffold,
+                &content,
+                settings.chunked_analysis.unwrap_or(true),
+            )
+            .await
+            .map_err(|e| format!("Failed to summarize paper sections: {}", e))?
+            .text,
+        ),
+        None => None,
+    };
+
+    let blocks = crate::analysis::enabled_blocks(&analysis_config, depth);
+    let context = crate::analysis::PromptContext {
+        title: paper.title.clone(),
+        summary: entry.summary.clone(),
+        topics: topics.clone(),
+        latex_content: latex_content.clone(),
+        language: analysis_language.to_string(),
+        depth,
+    };
+    let structured = settings.structured_output.unwrap_or(true);
+    let execution_mode = BlockExecutionMode::from_setting(settings.block_execution_mode.as_deref());
+
+    // Initialize cache
+    let cache = LlmCache::new().map_err(|e| {
+        eprintln!("[analyze_paper] Failed to initialize cache: {}", e);
+        format!("Failed to initialize cache: {}", e)
+    })?;
+
     // Build modular prompt using new system
     let prompt = crate::analysis::build_analysis_prompt(
         &paper.title,
         &entry.summary,
         &topics,
         latex_content.as_deref(),
-        &analysis_language,
+        analysis_language,
         &analysis_config,
         depth,
     );
 
     eprintln!("[analyze_paper] Generated modular prompt ({} chars)", prompt.chars().count());
 
-    // Initialize cache
-    let cache = LlmCache::new().map_err(|e| {
-        eprintln!("[analyze_paper] Failed to initialize cache: {}", e);
-        format!("Failed to initialize cache: {}", e)
-    })?;
+    // Recorded with the outcome in the analysis run history
+    let run = history::new_run(&client, &paper_id, depth, analysis_language, &prompt);
 
-    let model_for_cache = settings.glm_quick_model.clone()
-        .or(settings.claude_quick_model.clone())
-        .or(settings.glm_deep_model.clone())
-        .or(settings.claude_deep_model.clone());
+    let outcome = match execution_mode {
+        // One call per block in dependency order (not cached)
+        BlockExecutionMode::PerBlock => {
+            crate::analysis::executor::run_per_block(&client, Arc::new(context), &blocks, structured)
+                .await
+                .map_err(|e| {
+                    tracing::error!(
+                        paper_id = %paper_id,
+                        analysis_mode = %analysis_mode,
+                        error = %e,
+                        "LLM request failed for per-block paper analysis"
+                    );
+                    eprintln!("[analyze_paper] Per-block analysis failed: {}", e);
+                    e.to_string()
+                })?
+        }
+        BlockExecutionMode::Combined => {
+            // Ask for a schema-constrained reply unless the user turned it off
+            let output_schema = structured
+                .then(|| crate::analysis::build_analysis_output_schema(&analysis_config, depth));
+
+            // The model that answers this analysis, recorded with the cached reply
+            let model_for_cache = Some(client.model_for(depth.as_str()).to_string());
+
+            // Try to load from cache first
+            let response = if let Ok(cached_response) = cache.load(&paper_id, &analysis_mode, &prompt) {
+                eprintln!("[analyze_paper] Using cached LLM response");
+                cached_response
+            } else {
+                eprintln!("[analyze_paper] No cache hit, calling LLM API...");
+
+                // Send request to LLM
+                let llm_response = match &output_schema {
+                    Some(schema) => client.send_structured_request(&prompt, depth.as_str(), schema).await,
+                    None => client
+                        .send_chat_request(&prompt, depth.as_str())
+                        .await
+                        .map(|text| StructuredReply { text, schema_errors: Vec::new() }),
+                }
+                    .map_err(|e| {
+                        tracing::error!(
+                            paper_id = %paper_id,
+                            analysis_mode = %analysis_mode,
+                            error = %e,
+                            "LLM request failed for paper analysis"
+                        );
+                        eprintln!("[analyze_paper] LLM request failed: {}", e);
+                        e.to_string()
+                    })?;
+
+                // Save response to cache immediately (before parsing), unless it does
+                // not match the schema and should be requested again next time
+                if !llm_response.schema_errors.is_empty() {
+                    eprintln!("[analyze_paper] Not caching reply with schema errors: {:?}", llm_response.schema_errors);
+                } else if let Err(e) = cache.save(
+                    &paper_id,
+                    &analysis_mode,
+                    &llm_response.text,
+                    &prompt,
+                    &provider_str,
+                    model_for_cache.as_deref(),
+                ) {
+                    eprintln!("[analyze_paper] Warning: Failed to save to cache: {}", e);
+                }
 
-    // Try to load from cache first
-    let response = if let Ok(cached_response) = cache.load(&paper_id, &analysis_mode, &prompt) {
-        eprintln!("[analyze_paper] Using cached LLM response");
-        cached_response
-    } else {
-        eprintln!("[analyze_paper] No cache hit, calling LLM API...");
+                llm_response.text
+            };
 
-        // Send request to LLM
-        let llm_response = client
-            .send_chat_request(&prompt, depth.as_str())
-            .await
-            .map_err(|e| {
-                tracing::error!(
-                    paper_id = %paper_id,
-                    analysis_mode = %analysis_mode,
-                    error = %e,
-                    "LLM request failed for paper analysis"
-                );
-                eprintln!("[analyze_paper] LLM request failed: {}", e);
-                e.to_string()
-            })?;
-
-        // Save response to cache immediately (before parsing)
-        if let Err(e) = cache.save(
-            &paper_id,
-            &analysis_mode,
-            &llm_response,
-            &prompt,
-            &provider_str,
-            model_for_cache.as_deref(),
-        ) {
-            eprintln!("[analyze_paper] Warning: Failed to save to cache: {}", e);
-        }
+            eprintln!("[analyze_paper] Raw response from LLM ({} chars): {}", response.len(), &response.chars().take(500).collect::<String>());
 
-        llm_response
-    };
+            // Structured replies are used as-is; free-form ones are cleaned and repaired
+            let fixed_response = client.prepare_json_response(&response);
+            if response != fixed_response {
+                eprintln!("[analyze_paper] Applied markdown cleanup and JSON formatting fixes");
+            }
 
-    eprintln!("[analyze_paper] Raw response from LLM ({} chars): {}", response.len(), &response.chars().take(500).collect::<String>());
+            eprintln!("[analyze_paper] Fixed response ({} chars): {}", fixed_response.len(), &fixed_response.chars().take(500).collect::<String>());
 
-    // Clean the response (remove markdown code blocks)
-    let cleaned_response = client.clean_response(&response);
-    if response != cleaned_response {
-        eprintln!("[analyze_paper] Cleaned markdown blocks from response");
-    }
+            // Validate block by block so one bad block doesn't discard the rest
+            let mut outcome = crate::analysis::partial::parse_reply(&fixed_response, &blocks);
 
-    // Fix common JSON formatting errors
-    let fixed_response = client.fix_json_formatting(&cleaned_response);
-    if cleaned_response != fixed_response {
-        eprintln!("[analyze_paper] Applied JSON formatting fixes");
-    }
+            if !outcome.failed.is_empty() {
+                eprintln!("[analyze_paper] {} blocks failed validation, re-requesting: {:?}",
+                    outcome.failed.len(), outcome.failed.iter().map(|f| f.block_id.as_str()).collect::<Vec<_>>());
 
-    eprintln!("[analyze_paper] Fixed response ({} chars): {}", fixed_response.len(), &fixed_response.chars().take(500).collect::<String>());
+                let followup_prompt = crate::analysis::build_followup_prompt(&context, &outcome.failed);
+                crate::analysis::partial::retry_failed_blocks(
+                    &client,
+                    &followup_prompt,
+                    depth,
+                    structured,
+                    &mut outcome,
+                ).await;
+            }
 
-    // Parse the JSON response based on depth
-    match depth {
-        AnalysisDepth::Full => {
-            eprintln!("[analyze_paper] Attempting to parse as FullAnalysisResult...");
-            let result: crate::llm::FullAnalysisResult = serde_json::from_str(&fixed_response)
-                .map_err(|e| {
-                    tracing::error!(
-                        paper_id = %paper_id,
-                        analysis_mode = %analysis_mode,
-                        response_length = fixed_response.len(),
-                        error = %e,
-                        response_preview = %fixed_response.chars().take(2000).collect::<String>(),
-                        "Failed to parse full analysis JSON response"
-                    );
-                    eprintln!("[analyze_paper] ===== PARSE ERROR =====");
-                    eprintln!("[analyze_paper] Error: {}", e);
-                    eprintln!("[analyze_paper] Response length: {}", fixed_response.len());
-                    eprintln!("[analyze_paper] First 2000 chars:\n{}", &fixed_response.chars().take(2000).collect::<String>());
-                    if fixed_response.len() > 2000 {
-                        eprintln!("[analyze_paper] Last 500 chars:\n{}", &fixed_response.chars().skip(fixed_response.len().saturating_sub(500)).collect::<String>());
-                    }
-                    eprintln!("[analyze_paper] ===== END PARSE ERROR =====");
-                    format!("Failed to parse full analysis result: {}", e)
-                })?;
-
-            eprintln!("[analyze_paper] Successfully parsed FullAnalysisResult");
-            // Update paper with analysis results
-            update_paper_with_full_analysis(&mut paper, result);
-            paper.analysis_incomplete = latex_content.is_none();
-        }
-        AnalysisDepth::Standard => {
-            eprintln!("[analyze_paper] Attempting to parse as StandardAnalysisResult...");
-            let result: crate::llm::StandardAnalysisResult = serde_json::from_str(&fixed_response)
-                .map_err(|e| {
-                    tracing::error!(
-                        paper_id = %paper_id,
-                        analysis_mode = %analysis_mode,
-                        response_length = fixed_response.len(),
-                        error = %e,
-                        response_preview = %fixed_response.chars().take(2000).collect::<String>(),
-                        "Failed to parse standard analysis JSON response"
-                    );
-                    eprintln!("[analyze_paper] ===== PARSE ERROR =====");
-                    eprintln!("[analyze_paper] Error: {}", e);
-                    eprintln!("[analyze_paper] Response length: {}", fixed_response.len());
-                    eprintln!("[analyze_paper] First 2000 chars:\n{}", &fixed_response.chars().take(2000).collect::<String>());
-                    if fixed_response.len() > 2000 {
-                        eprintln!("[analyze_paper] Last 500 chars:\n{}", &fixed_response.chars().skip(fixed_response.len().saturating_sub(500)).collect::<String>());
-                    }
-                    eprintln!("[analyze_paper] ===== END PARSE ERROR =====");
-                    format!("Failed to parse standard analysis result: {}", e)
-                })?;
-
-            eprintln!("[analyze_paper] Successfully parsed StandardAnalysisResult");
-            // Update paper with analysis results
-            update_paper_with_standard_analysis(&mut paper, result);
-            paper.analysis_incomplete = latex_content.is_none();
+            outcome
         }
+    };
+
+    if outcome.accepted.is_empty() {
+        let reasons = outcome.failure_summary();
+        tracing::error!(
+            paper_id = %paper_id,
+            analysis_mode = %analysis_mode,
+            error = %reasons,
+            "Failed to parse analysis JSON response"
+        );
+        eprintln!("[analyze_paper] No analysis block could be parsed: {}", reasons);
+        return Err(format!("Failed to parse {} analysis result: {}", analysis_mode, reasons));
     }
 
+    // Update paper with the blocks that passed validation
+    outcome.apply_to(&mut paper);
+    paper.analysis_incomplete = latex_content.is_none();
+
     // Mark as deep analyzed
     paper.is_deep_analyzed = true;
     paper.analysis_mode = Some(analysis_mode.clone());
@@ -317,9 +373,26 @@ pub async fn analyze_paper(
             e.to_string()
         })?;
 
+    // Keep this run in the paper's analysis history
+    let stored = AnalysisRunRepository::new(pool.inner())
+        .record(&run, &outcome.block_outputs(), &outcome.failed)
+        .await;
+    match stored {
+        Ok(_) => {
+            paper.analysis_blocks = AnalysisBlockRepository::new(pool.inner())
+                .get_latest_for_paper(&paper_id)
+                .await
+                .ok();
+        }
+        Err(e) => eprintln!("[analyze_paper] Failed to record analysis run: {}", e),
+    }
+
     // Delete cache after successful save
     let _ = cache.delete(&paper_id, &analysis_mode);
 
+    // The new summary changes the paper's embedding text
+    crate::embeddings::spawn_refresh(pool.inner().clone());
+
     eprintln!("[analyze_paper] Analysis completed successfully for: {}", paper_id);
     Ok(paper)
 }
@@ -335,8 +408,9 @@ pub async fn batch_analyze_papers(
     #[allow(non_snake_case)]
     analysisLanguage: Option<String>,
 ) -> Result<BatchAnalysisResult, String> {
-    // Use provided language or default to Chinese
-    let analysis_language = analysisLanguage.as_deref().unwrap_or("zh");
+    // Use provided language or default to English; an unsupported one fails
+    // the batch before any paper is analyzed
+    let analysis_language = parse_language(analysisLanguage.as_deref().unwrap_or(DEFAULT_LANGUAGE))?.code;
     eprintln!("[batch_analyze_papers] ENTRY - Received parameters:");
     eprintln!("  paperIds: {:?}", paperIds);
     eprintln!("  analysisMode: {:?}", analysisMode);
@@ -356,7 +430,7 @@ pub async fn batch_analyze_papers(
         eprintln!("[batch_analyze_papers] Processing {}/{}: {}",
             index + 1, total, paper_id);
 
-        match analyze_paper(pool.clone(), paper_id.clone(), analysis_mode.clone(), analysisLanguage.clone()).await {
+        match analyze_paper(pool.clone(), paper_id.clone(), analysis_mode.clone(), Some(analysis_language.to_string())).await {
             Ok(_) => {
                 successful += 1;
                 eprintln!("[batch_analyze_papers] Successfully analyzed: {}", paper_id);
@@ -379,72 +453,204 @@ pub async fn batch_analyze_papers(
     })
 }
 
-/// Update paper with standard analysis results
-fn update_paper_with_standard_analysis(paper: &mut Paper, result: StandardAnalysisResult) {
-    paper.ai_summary = Some(result.ai_summary);
-    paper.key_insights = Some(result.key_insights);
-    paper.engineering_notes = Some(result.engineering_notes);
-    paper.novelty_score = Some(result.novelty_score);
-    paper.novelty_reason = Some(result.novelty_reason);
-    paper.effectiveness_score = Some(result.effectiveness_score);
-    paper.effectiveness_reason = Some(result.effectiveness_reason);
-
-    // Update code availability
-    paper.code_available = result.code_available;
-    if !result.code_links.is_empty() {
-        paper.code_links = Some(result.code_links);
-    }
+/// Latest stored output of each analysis block of a paper
+#[tauri::command]
+pub async fn get_paper_analysis_blocks(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+) -> Result<Vec<PaperAnalysisBlock>, String> {
+    AnalysisBlockRepository::new(pool.inner())
+        .get_latest_for_paper(&paperId)
+        .await
+        .map_err(|e| e.to_string())
+}
 
-    // Update tags if provided
-    if !result.suggested_tags.is_empty() {
-        paper.tags = result.suggested_tags;
+/// Every stored output of one analysis block of a paper, newest first
+#[tauri::command]
+pub async fn get_analysis_block_history(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+    #[allow(non_snake_case)]
+    blockId: String,
+) -> Result<Vec<PaperAnalysisBlock>, String> {
+    AnalysisBlockRepository::new(pool.inner())
+        .get_history(&paperId, &blockId)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Analysis runs of a paper, newest first
+#[tauri::command]
+pub async fn get_analysis_runs(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+) -> Result<Vec<AnalysisRun>, String> {
+    AnalysisRunRepository::new(pool.inner())
+        .list_for_paper(&paperId)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Make an earlier analysis run the paper's current analysis
+///
+/// The restore is recorded as a new run pointing at the restored one,
+/// so the history itself is never rewritten.
+#[tauri::command]
+pub async fn restore_analysis_run(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+    #[allow(non_snake_case)]
+    runId: String,
+) -> Result<Paper, String> {
+    eprintln!("[restore_analysis_run] Restoring run {} of paper {}", runId, paperId);
+
+    let runs = AnalysisRunRepository::new(pool.inner());
+    let run = runs
+        .get_by_id(&runId)
+        .await
+        .map_err(|e| e.to_string())?
+        .filter(|run| run.paper_id == paperId)
+        .ok_or_else(|| format!("Analysis run not found: {}", runId))?;
+
+    let block_repo = AnalysisBlockRepository::new(pool.inner());
+    let blocks = block_repo.get_for_run(&run.id).await.map_err(|e| e.to_string())?;
+    let outcome = history::outcome_from_run(&run, blocks);
+    if outcome.accepted.is_empty() {
+        return Err(format!("Analysis run {} has no stored block outputs", run.id));
     }
 
-    // Update topics if provided
-    if !result.suggested_topics.is_empty() {
-        paper.topics = result.suggested_topics;
+    let repo = PaperRepository::new(pool.inner());
+    let mut paper = repo.get_by_id(&paperId).await.map_err(|e| e.to_string())?;
+    history::restore_onto(&mut paper, &run, &outcome);
+    repo.save(&paper).await.map_err(|e| {
+        eprintln!("[restore_analysis_run] Failed to save paper: {}", e);
+        e.to_string()
+    })?;
+
+    runs.record(&history::restored_run(&run), &outcome.block_outputs(), &outcome.failed)
+        .await
+        .map_err(|e| e.to_string())?;
+    paper.analysis_blocks = block_repo.get_latest_for_paper(&paperId).await.ok();
+    crate::embeddings::spawn_refresh(pool.inner().clone());
+
+    Ok(paper)
+}
+
+/// Field-by-field comparison of two analysis runs
+#[tauri::command]
+pub async fn diff_analysis_runs(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    fromRunId: String,
+    #[allow(non_snake_case)]
+    toRunId: String,
+) -> Result<AnalysisRunDiff, String> {
+    let runs = AnalysisRunRepository::new(pool.inner());
+    let mut loaded = Vec::with_capacity(2);
+    for id in [fromRunId, toRunId] {
+        let run = runs
+            .get_by_id(&id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Analysis run not found: {}", id))?;
+        loaded.push(run);
     }
+    let to = loaded.pop().unwrap();
+    let from = loaded.pop().unwrap();
 
-    // Update related_papers if provided
-    if !result.related_papers.is_empty() {
-        paper.related_papers = Some(result.related_papers.into_iter().map(|rp| crate::models::RelatedPaper {
-            arxiv_id: rp.arxiv_id,
-            title: rp.title,
-            relationship: match rp.relationship {
-                crate::llm::PaperRelationship::BuildsOn => crate::models::PaperRelationship::BuildsOn,
-                crate::llm::PaperRelationship::ImprovesUpon => crate::models::PaperRelationship::ImprovesUpon,
-                crate::llm::PaperRelationship::CompetingWith => crate::models::PaperRelationship::CompetingWith,
-                crate::llm::PaperRelationship::CitedBy => crate::models::PaperRelationship::CitedBy,
-                crate::llm::PaperRelationship::SimilarTo => crate::models::PaperRelationship::SimilarTo,
-            },
-            relevance_score: rp.relevance_score,
-            reason: rp.reason,
-        }).collect());
+    let changes = history::diff_results(&from.result, &to.result);
+    Ok(AnalysisRunDiff { from, to, changes })
+}
+
+/// Languages analyses can be requested and translated in
+#[tauri::command]
+pub async fn get_supported_languages() -> Result<Vec<AnalysisLanguage>, String> {
+    Ok(SUPPORTED_LANGUAGES.to_vec())
+}
+
+/// Languages a paper has an analysis in, most recent first
+#[tauri::command]
+pub async fn get_paper_analysis_languages(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+) -> Result<Vec<String>, String> {
+    AnalysisRunRepository::new(pool.inner())
+        .languages_for_paper(&paperId)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// A paper with its analysis fields in `language`
+///
+/// Uses the latest stored output of each block in that language; the saved
+/// paper is not changed.
+#[tauri::command]
+pub async fn get_paper_in_language(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+    language: String,
+) -> Result<Paper, String> {
+    let language = parse_language(&language)?;
+    let blocks = AnalysisBlockRepository::new(pool.inner())
+        .get_latest_for_language(&paperId, language.code)
+        .await
+        .map_err(|e| e.to_string())?;
+    if blocks.is_empty() {
+        return Err(format!("No {} analysis stored for paper {}", language.english_name, paperId));
     }
+
+    let mut paper = PaperRepository::new(pool.inner())
+        .get_by_id(&paperId)
+        .await
+        .map_err(|e| e.to_string())?;
+    history::view_in_language(&mut paper, blocks);
+    Ok(paper)
 }
 
-/// Update paper with full analysis results
-fn update_paper_with_full_analysis(paper: &mut Paper, result: FullAnalysisResult) {
-    // First apply all standard fields
-    update_paper_with_standard_analysis(paper, StandardAnalysisResult {
-        novelty_score: result.novelty_score,
-        novelty_reason: result.novelty_reason,
-        effectiveness_score: result.effectiveness_score,
-        effectiveness_reason: result.effectiveness_reason,
-        code_available: result.code_available,
-        code_links: result.code_links,
-        engineering_notes: result.engineering_notes,
-        ai_summary: result.ai_summary,
-        key_insights: result.key_insights,
-        suggested_tags: result.suggested_tags,
-        suggested_topics: result.suggested_topics,
-        related_papers: result.related_papers,
-    });
-
-    // Then add full-mode specific fields
-    paper.experiment_completeness_score = Some(result.experiment_completeness_score);
-    paper.experiment_completeness_reason = Some(result.experiment_completeness_reason);
-    paper.algorithm_flowchart = result.algorithm_flowchart;
-    paper.time_complexity = result.time_complexity;
-    paper.space_complexity = result.space_complexity;
+/// Translate a paper's stored analysis into another language
+///
+/// Only the stored analysis is sent to the LLM, not the paper content.
+/// Without `sourceLanguage` the most recent analysis in another language is used.
+#[tauri::command]
+pub async fn translate_paper_analysis(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+    #[allow(non_snake_case)]
+    targetLanguage: String,
+    #[allow(non_snake_case)]
+    sourceLanguage: Option<String>,
+) -> Result<Paper, String> {
+    eprintln!("[translate_paper_analysis] Translating {} into {} (from {:?})",
+        paperId, targetLanguage, sourceLanguage);
+
+    let settings = crate::database::SettingsRepository::new(pool.inner())
+        .get_all()
+        .await
+        .map_err(|e| e.to_string())?;
+    let client = client_from_settings(&settings)?
+        .with_usage_tracking(pool.inner(), UsageContext::default());
+    let structured = settings.structured_output.unwrap_or(true);
+
+    let run = crate::analysis::translate::translate_analysis(
+        pool.inner(),
+        &client,
+        &paperId,
+        sourceLanguage.as_deref(),
+        &targetLanguage,
+        structured,
+    )
+    .await
+    .map_err(|e| {
+        eprintln!("[translate_paper_analysis] {}", e);
+        e
+    })?;
+
+    get_paper_in_language(pool, paperId, run.language).await
 }
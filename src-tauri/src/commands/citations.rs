@@ -0,0 +1,111 @@
+//! Citation graph commands
+//!
+//! References are stored when a paper's content is fetched for analysis;
+//! `extract_paper_citations` fills them in for papers analyzed before.
+
+use crate::arxiv::{fetch_papers, FetchOptions};
+use crate::database::{CitationRepository, PaperRepository, SettingsRepository};
+use crate::fetch::{fetch_references, store_references};
+use crate::models::{Citation, Paper};
+use sqlx::SqlitePool;
+use tauri::State;
+
+/// Papers in the library that a paper cites
+#[tauri::command]
+pub async fn get_cited_papers(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+) -> Result<Vec<Paper>, String> {
+    CitationRepository::new(pool.inner())
+        .cited_papers(&paperId)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Papers in the library that cite a paper
+#[tauri::command]
+pub async fn get_citing_papers(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+) -> Result<Vec<Paper>, String> {
+    CitationRepository::new(pool.inner())
+        .citing_papers(&paperId)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Stored references of a paper, in bibliography order
+#[tauri::command]
+pub async fn get_paper_citations(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+) -> Result<Vec<Citation>, String> {
+    CitationRepository::new(pool.inner())
+        .get_for_paper(&paperId)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Parse the bibliography of a library paper again and store its references
+///
+/// Returns the number of references found.
+#[tauri::command]
+pub async fn extract_paper_citations(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+) -> Result<usize, String> {
+    let paper = PaperRepository::new(pool.inner())
+        .get_by_id(&paperId)
+        .await
+        .map_err(|e| e.to_string())?;
+    let settings = SettingsRepository::new(pool.inner())
+        .get_all()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let fetch_options = FetchOptions {
+        categories: vec![],
+        max_results: 1,
+        days_back: None,
+        date_from: None,
+        date_to: None,
+        fetch_by_id: true,
+        arxiv_ids: Some(vec![paper.arxiv_id.clone()]),
+        base_url: None,
+    };
+    let entry = fetch_papers(&fetch_options)
+        .await
+        .map_err(|e| format!("Failed to fetch from ArXiv: {}", e))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Paper not found on ArXiv: {}", paper.arxiv_id))?;
+
+    let references = fetch_references(&entry, settings.latex_download_path.as_deref()).await;
+    eprintln!("[extract_paper_citations] Found {} references for {}", references.len(), paperId);
+    store_references(pool.inner(), &paperId, &references).await;
+    Ok(references.len())
+}
+
+/// Citation graph of the library as JSON (`{nodes, edges}`) or Graphviz DOT
+///
+/// `format` is "json" (default) or "dot".
+#[tauri::command]
+pub async fn export_citation_graph(
+    pool: State<'_, SqlitePool>,
+    format: Option<String>,
+) -> Result<String, String> {
+    let graph = CitationRepository::new(pool.inner())
+        .graph()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match format.as_deref().unwrap_or("json") {
+        "json" => serde_json::to_string_pretty(&graph).map_err(|e| e.to_string()),
+        "dot" => Ok(graph.to_dot()),
+        other => Err(format!("Unsupported graph format '{}', expected 'json' or 'dot'", other)),
+    }
+}
@@ -0,0 +1,54 @@
+//! Relevance feedback commands
+
+use crate::database::FeedbackRepository;
+use crate::feedback::{self, DEFAULT_MIN_RELEVANCE};
+use crate::models::{RelevanceFeedback, RelevanceReport};
+use sqlx::SqlitePool;
+use tauri::State;
+
+/// Thumbs-up (`relevant` true) or down on a paper for a topic; `None`
+/// removes the feedback
+#[tauri::command]
+pub async fn set_relevance_feedback(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+    topic: String,
+    relevant: Option<bool>,
+) -> Result<(), String> {
+    let repo = FeedbackRepository::new(pool.inner());
+    let result = match relevant {
+        Some(relevant) => repo.set(&paperId, &topic, relevant).await,
+        None => repo.clear(&paperId, &topic).await.map(|_| ()),
+    };
+    result.map_err(|e| {
+        eprintln!("[set_relevance_feedback] Failed for {} ({}): {}", paperId, topic, e);
+        e.to_string()
+    })
+}
+
+/// Feedback on a paper, by topic
+#[tauri::command]
+pub async fn get_relevance_feedback(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+) -> Result<Vec<RelevanceFeedback>, String> {
+    FeedbackRepository::new(pool.inner())
+        .get_for_paper(&paperId)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Precision and recall of past relevance decisions against the feedback,
+/// at a minimum relevance (default 50)
+#[tauri::command]
+pub async fn get_relevance_report(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    minRelevance: Option<i32>,
+) -> Result<RelevanceReport, String> {
+    feedback::relevance_report(pool.inner(), minRelevance.unwrap_or(DEFAULT_MIN_RELEVANCE))
+        .await
+        .map_err(|e| e.to_string())
+}
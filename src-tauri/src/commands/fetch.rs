@@ -79,6 +79,7 @@ pub async fn start_fetch(
     // FetchGuard inside fetch_papers() provides atomic, race-condition-free locking
     // No separate is_fetching() check needed here (was TOCTOU vulnerability)
     let manager_clone = manager.clone();
+    let pool = pool.inner().clone();
     tauri::async_runtime::spawn(async move {
         eprintln!("[start_fetch] Background task started");
         let result = manager_clone
@@ -89,6 +90,20 @@ pub async fn start_fetch(
 
         // Emit completion event
         let completion_status = match result {
+            Ok(result) if result.budget_exhausted.is_some() => {
+                eprintln!("[start_fetch] Fetch stopped by the budget, emitting error event");
+                let error_info: FetchErrorInfo =
+                    (&FetchError::BudgetExceeded(result.budget_exhausted.unwrap_or_default())).into();
+                serde_json::json!({
+                    "status": "error",
+                    "papers_fetched": result.papers_fetched,
+                    "papers_analyzed": result.papers_analyzed,
+                    "papers_saved": result.papers_saved,
+                    "papers_filtered": result.papers_filtered,
+                    "errors": result.errors,
+                    "error": error_info
+                })
+            }
             Ok(result) => {
                 eprintln!("[start_fetch] Fetch successful, emitting completed event");
                 serde_json::json!({
@@ -123,6 +138,11 @@ pub async fn start_fetch(
 
         eprintln!("[start_fetch] Emitting fetch-complete event: {}", completion_status);
         let _ = app.emit("fetch-complete", completion_status);
+
+        // Embed the saved papers so searches find them by meaning
+        if let Err(e) = crate::embeddings::refresh_embeddings(&pool).await {
+            eprintln!("[start_fetch] Failed to embed papers: {}", e);
+        }
     });
 
     eprintln!("[start_fetch] Background task spawned, returning success");
@@ -5,6 +5,10 @@ pub mod schedule;
 pub mod collections;
 pub mod analysis;
 pub mod platform;
+pub mod usage;
+pub mod citations;
+pub mod recommendations;
+pub mod feedback;
 
 // Re-export all commands
 pub use papers::*;
@@ -14,3 +18,7 @@ pub use schedule::*;
 pub use collections::*;
 pub use analysis::*;
 pub use platform::*;
+pub use usage::*;
+pub use citations::*;
+pub use recommendations::*;
+pub use feedback::*;
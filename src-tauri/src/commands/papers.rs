@@ -1,5 +1,6 @@
-use crate::database::PaperRepository;
-use crate::models::Paper;
+use crate::database::{AuthorRepository, PaperRepository, SettingsRepository};
+use crate::embeddings::Embedder;
+use crate::models::{Paper, SearchHit};
 use sqlx::SqlitePool;
 use tauri::State;
 use std::collections::HashMap;
@@ -58,6 +59,39 @@ pub async fn search_papers(
         .map_err(|e| e.to_string())
 }
 
+/// Search papers by meaning, blending BM25 with embedding similarity
+///
+/// With `hybrid` set to false papers are ranked by similarity alone
+/// (default: hybrid, weighted by the configured semantic weight).
+#[tauri::command]
+pub async fn semantic_search(
+    pool: State<'_, SqlitePool>,
+    query: String,
+    limit: Option<i32>,
+    hybrid: Option<bool>,
+) -> Result<Vec<SearchHit>, String> {
+    let settings = SettingsRepository::new(pool.inner())
+        .get_all()
+        .await
+        .map_err(|e| e.to_string())?;
+    let semantic_weight = if hybrid.unwrap_or(true) {
+        settings.embeddings.clone().unwrap_or_default().semantic_weight
+    } else {
+        1.0
+    };
+
+    let embedder = Embedder::from_settings(&settings)
+        .map_err(|e| e.to_string())?
+        .with_usage_tracking(pool.inner());
+    let limit = limit.unwrap_or(20).max(1) as usize;
+    crate::embeddings::hybrid_search(pool.inner(), &embedder, &query, limit, semantic_weight)
+        .await
+        .map_err(|e| {
+            eprintln!("[semantic_search] Search for '{}' failed: {}", query, e);
+            e.to_string()
+        })
+}
+
 /// Get papers by tag
 #[tauri::command]
 pub async fn get_papers_by_tag(
@@ -72,6 +106,20 @@ pub async fn get_papers_by_tag(
         .map_err(|e| e.to_string())
 }
 
+/// Get papers by author, matching initials and names without diacritics
+#[tauri::command]
+pub async fn get_papers_by_author(
+    pool: State<'_, SqlitePool>,
+    name: String,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<Vec<Paper>, String> {
+    let repo = AuthorRepository::new(pool.inner());
+    repo.papers_by_author(&name, limit.unwrap_or(20), offset.unwrap_or(0))
+        .await
+        .map_err(|e| e.to_string())
+}
+
 /// Get total paper count
 #[tauri::command]
 pub async fn get_paper_count(
@@ -298,6 +346,7 @@ pub async fn download_paper_pdf(
             affiliation: a.affiliation,
         }).collect(),
         categories: paper.tags.clone().into_iter().map(|t| crate::arxiv::ArxivCategory { term: t }).collect(),
+        base_url: None,
     };
 
     eprintln!("[download_paper_pdf] ArxivEntry created, calling download_pdf...");
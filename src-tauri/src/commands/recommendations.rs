@@ -0,0 +1,69 @@
+//! Recommendation commands
+//!
+//! The frontend reports opened papers with `record_paper_opened`; collections,
+//! deep analysis and spam are read from the library itself.
+
+use crate::database::{InteractionRepository, SettingsRepository};
+use crate::embeddings::Embedder;
+use crate::models::Recommendation;
+use crate::recommend::{self, DEFAULT_RECENT_DAYS};
+use sqlx::SqlitePool;
+use tauri::State;
+
+async fn configured_embedder(pool: &SqlitePool) -> Result<Embedder, String> {
+    let settings = SettingsRepository::new(pool)
+        .get_all()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Embedder::from_settings(&settings)
+        .map_err(|e| e.to_string())?
+        .with_usage_tracking(pool))
+}
+
+/// Papers fetched in the last `days` days (default 7), best match to the
+/// kept papers first
+#[tauri::command]
+pub async fn get_recommendations(
+    pool: State<'_, SqlitePool>,
+    days: Option<i64>,
+    limit: Option<i32>,
+) -> Result<Vec<Recommendation>, String> {
+    let embedder = configured_embedder(pool.inner()).await?;
+    let days = days.unwrap_or(DEFAULT_RECENT_DAYS).max(1);
+    recommend::recommend(pool.inner(), &embedder, days, limit.unwrap_or(20).max(1) as usize)
+        .await
+        .map_err(|e| {
+            eprintln!("[get_recommendations] Failed: {}", e);
+            e.to_string()
+        })
+}
+
+/// Papers in the library most similar to a paper
+#[tauri::command]
+pub async fn get_similar_papers(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+    limit: Option<i32>,
+) -> Result<Vec<Recommendation>, String> {
+    let embedder = configured_embedder(pool.inner()).await?;
+    recommend::similar_papers(pool.inner(), &embedder, &paperId, limit.unwrap_or(10).max(1) as usize)
+        .await
+        .map_err(|e| {
+            eprintln!("[get_similar_papers] Failed for {}: {}", paperId, e);
+            e.to_string()
+        })
+}
+
+/// Record that a paper was opened
+#[tauri::command]
+pub async fn record_paper_opened(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+) -> Result<(), String> {
+    InteractionRepository::new(pool.inner())
+        .record_open(&paperId)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -117,15 +117,10 @@ pub async fn enable_schedule(
         return Err("No ArXiv categories configured. Please add ArXiv categories to at least one topic.".to_string());
     }
 
-    // Check API key
-    let api_key = match settings.llm_provider {
-        crate::models::LLMProvider::Glm => &settings.glm_api_key,
-        crate::models::LLMProvider::Claude => &settings.claude_api_key,
-    };
-
-    if api_key.is_none() {
-        return Err("API key is not configured. Please add your API key in the settings.".to_string());
-    }
+    // Check API key or endpoint
+    let backend = crate::llm::get_backend(&settings.llm_provider)
+        .map_err(|e| e.to_string())?;
+    backend.validate_config(&settings)?;
 
     // Check consecutive failures
     let run_repo = ScheduleRunRepository::new(&*pool);
@@ -1,8 +1,9 @@
-use crate::database::{SettingsRepository, ClassificationCacheRepository};
-use crate::models::Settings;
+use crate::database::{SettingsRepository, ClassificationCacheRepository, CustomBlockRepository};
+use crate::models::{CustomBlock, CustomBlockInput, Settings};
 use crate::database::classification_cache::CacheStats;
 use crate::analysis::{AnalysisBlockConfig, UserAnalysisConfig};
 use crate::analysis::registry::REGISTRY;
+use crate::rules::{self, TopicRules};
 use sqlx::SqlitePool;
 use tauri::State;
 
@@ -28,6 +29,9 @@ pub async fn save_settings(
         settings.claude_api_key.is_some()
     );
 
+    // A broken topic rule would otherwise only surface at the next fetch
+    TopicRules::compile(&settings.topics)?;
+
     // Get the old settings to check if topics have changed
     let repo = SettingsRepository::new(pool.inner());
     let old_settings = repo.get_all().await;
@@ -58,6 +62,9 @@ pub async fn save_settings(
         }
     }
 
+    // A new embedding model needs vectors for the whole library
+    crate::embeddings::spawn_refresh(pool.inner().clone());
+
     Ok(())
 }
 
@@ -84,6 +91,16 @@ pub async fn set_setting(
         .map_err(|e| e.to_string())
 }
 
+/// Check a topic rule while it is being edited
+#[tauri::command]
+pub async fn validate_topic_rule(
+    rule: String,
+) -> Result<(), String> {
+    rules::parse(&rule)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
 /// Get classification cache statistics
 #[tauri::command]
 pub async fn get_cache_stats(
@@ -178,3 +195,52 @@ pub async fn save_analysis_config(
     eprintln!("[save_analysis_config] Successfully saved analysis config");
     Ok(())
 }
+
+// ============================================================================
+// Custom Analysis Block Commands
+// ============================================================================
+
+/// Get all user-defined analysis blocks
+#[tauri::command]
+pub async fn get_custom_blocks(
+    pool: State<'_, SqlitePool>,
+) -> Result<Vec<CustomBlock>, String> {
+    CustomBlockRepository::new(pool.inner()).get_all().await
+}
+
+/// Create a user-defined analysis block and make it available right away
+#[tauri::command]
+pub async fn create_custom_block(
+    pool: State<'_, SqlitePool>,
+    block: CustomBlockInput,
+) -> Result<CustomBlock, String> {
+    eprintln!("[create_custom_block] Creating custom block '{}'", block.id);
+    let created = CustomBlockRepository::new(pool.inner()).create(block).await?;
+    REGISTRY.register(created.to_block_config())?;
+    Ok(created)
+}
+
+/// Update a user-defined analysis block
+#[tauri::command]
+pub async fn update_custom_block(
+    pool: State<'_, SqlitePool>,
+    id: String,
+    block: CustomBlockInput,
+) -> Result<CustomBlock, String> {
+    eprintln!("[update_custom_block] Updating custom block '{}'", id);
+    let updated = CustomBlockRepository::new(pool.inner()).update(&id, block).await?;
+    REGISTRY.register(updated.to_block_config())?;
+    Ok(updated)
+}
+
+/// Delete a user-defined analysis block; results it produced are kept
+#[tauri::command]
+pub async fn delete_custom_block(
+    pool: State<'_, SqlitePool>,
+    id: String,
+) -> Result<(), String> {
+    eprintln!("[delete_custom_block] Deleting custom block '{}'", id);
+    CustomBlockRepository::new(pool.inner()).delete(&id).await?;
+    REGISTRY.unregister(&id);
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use crate::budget::{self, BudgetStage, BudgetStatus};
+use crate::database::{LlmUsageRepository, SettingsRepository};
+use crate::models::{LlmUsage, ModelPrice, UsageSummary};
+use sqlx::SqlitePool;
+use tauri::State;
+
+/// Number of fetches reported when no limit is given
+const DEFAULT_FETCH_LIMIT: i64 = 20;
+
+/// Token usage and spend per day, newest first
+///
+/// `from` and `to` are optional inclusive `YYYY-MM-DD` bounds (UTC).
+#[tauri::command]
+pub async fn get_usage_by_day(
+    pool: State<'_, SqlitePool>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<UsageSummary>, String> {
+    LlmUsageRepository::new(pool.inner())
+        .summary_by_day(from.as_deref(), to.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Token usage and spend per topic, most expensive first
+#[tauri::command]
+pub async fn get_usage_by_topic(
+    pool: State<'_, SqlitePool>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<UsageSummary>, String> {
+    LlmUsageRepository::new(pool.inner())
+        .summary_by_topic(from.as_deref(), to.as_deref())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Token usage and spend of the most recent fetches
+#[tauri::command]
+pub async fn get_usage_by_fetch(
+    pool: State<'_, SqlitePool>,
+    limit: Option<i64>,
+) -> Result<Vec<UsageSummary>, String> {
+    LlmUsageRepository::new(pool.inner())
+        .summary_by_fetch(limit.unwrap_or(DEFAULT_FETCH_LIMIT))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Every recorded LLM call for a paper
+#[tauri::command]
+pub async fn get_paper_usage(
+    pool: State<'_, SqlitePool>,
+    #[allow(non_snake_case)]
+    paperId: String,
+) -> Result<Vec<LlmUsage>, String> {
+    LlmUsageRepository::new(pool.inner())
+        .get_for_paper(&paperId)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Spend against the configured daily and monthly budgets
+#[tauri::command]
+pub async fn get_budget_status(pool: State<'_, SqlitePool>) -> Result<BudgetStatus, String> {
+    let settings = SettingsRepository::new(pool.inner())
+        .get_all()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match settings.budget {
+        Some(config) => budget::current_status(pool.inner(), &config)
+            .await
+            .map_err(|e| e.to_string()),
+        None => Ok(BudgetStatus {
+            stage: BudgetStage::Available,
+            limits: Vec::new(),
+            reason: None,
+        }),
+    }
+}
+
+/// Configured model prices
+#[tauri::command]
+pub async fn get_model_prices(pool: State<'_, SqlitePool>) -> Result<Vec<ModelPrice>, String> {
+    LlmUsageRepository::new(pool.inner())
+        .get_prices()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Set the price of a model in USD per million tokens
+#[tauri::command]
+pub async fn set_model_price(
+    pool: State<'_, SqlitePool>,
+    price: ModelPrice,
+) -> Result<(), String> {
+    if price.model.trim().is_empty() {
+        return Err("Model name must not be empty".to_string());
+    }
+    let is_valid = |value: f64| value.is_finite() && value >= 0.0;
+    if !is_valid(price.input_per_million) || !is_valid(price.output_per_million) {
+        return Err(format!("Invalid price for model '{}'", price.model));
+    }
+
+    LlmUsageRepository::new(pool.inner())
+        .set_price(&ModelPrice { model: price.model.trim().to_string(), ..price })
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Remove the price of a model
+#[tauri::command]
+pub async fn delete_model_price(
+    pool: State<'_, SqlitePool>,
+    model: String,
+) -> Result<(), String> {
+    let deleted = LlmUsageRepository::new(pool.inner())
+        .delete_price(&model)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if !deleted {
+        return Err(format!("No price configured for model '{}'", model));
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use crate::models::PaperAnalysisBlock;
+use sqlx::{Row, SqlitePool};
+
+/// Repository for per-block analysis results
+///
+/// Every analysis run adds one row per accepted block, holding the reply
+/// fields that block produced, so older outputs stay available as history.
+/// Rows are written by `AnalysisRunRepository::record`.
+pub struct AnalysisBlockRepository {
+    pool: SqlitePool,
+}
+
+impl AnalysisBlockRepository {
+    pub fn new(pool: &SqlitePool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Latest output of every block stored for a paper, ordered by block ID
+    pub async fn get_latest_for_paper(&self, paper_id: &str) -> Result<Vec<PaperAnalysisBlock>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM paper_analysis_blocks
+             WHERE id IN (
+                 SELECT MAX(id) FROM paper_analysis_blocks WHERE paper_id = ? GROUP BY block_id
+             )
+             ORDER BY block_id"
+        )
+        .bind(paper_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(row_to_block).collect())
+    }
+
+    /// Latest output of every block stored for a paper in one language, ordered by block ID
+    pub async fn get_latest_for_language(
+        &self,
+        paper_id: &str,
+        language: &str,
+    ) -> Result<Vec<PaperAnalysisBlock>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM paper_analysis_blocks
+             WHERE id IN (
+                 SELECT MAX(id) FROM paper_analysis_blocks
+                 WHERE paper_id = ? AND language = ?
+                 GROUP BY block_id
+             )
+             ORDER BY block_id"
+        )
+        .bind(paper_id)
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(row_to_block).collect())
+    }
+
+    /// Every stored output of one block of a paper, newest first
+    pub async fn get_history(&self, paper_id: &str, block_id: &str) -> Result<Vec<PaperAnalysisBlock>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM paper_analysis_blocks
+             WHERE paper_id = ? AND block_id = ?
+             ORDER BY id DESC"
+        )
+        .bind(paper_id)
+        .bind(block_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(row_to_block).collect())
+    }
+
+    /// Block outputs of one analysis run, ordered by block ID
+    pub async fn get_for_run(&self, run_id: &str) -> Result<Vec<PaperAnalysisBlock>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM paper_analysis_blocks WHERE run_id = ? ORDER BY block_id")
+            .bind(run_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(row_to_block).collect())
+    }
+}
+
+fn row_to_block(row: sqlx::sqlite::SqliteRow) -> PaperAnalysisBlock {
+    let json: String = row.get("json");
+    PaperAnalysisBlock {
+        id: row.get("id"),
+        paper_id: row.get("paper_id"),
+        block_id: row.get("block_id"),
+        run_id: row.get("run_id"),
+        depth: row.get("depth"),
+        language: row.get("language"),
+        model: row.get("model"),
+        json: serde_json::from_str(&json).unwrap_or(serde_json::Value::Null),
+        created_at: row.get("created_at"),
+    }
+}
@@ -0,0 +1,145 @@
+use crate::models::{AnalysisRun, FailedBlock, NewAnalysisRun};
+use chrono::Utc;
+use serde_json::{Map, Value};
+use sqlx::{Row, SqlitePool};
+
+/// Repository for the analysis run history
+pub struct AnalysisRunRepository {
+    pool: SqlitePool,
+}
+
+impl AnalysisRunRepository {
+    pub fn new(pool: &SqlitePool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Record a run and the per-block outputs it produced
+    ///
+    /// `outputs` holds the reply fields of each accepted block, keyed by block
+    /// ID; the run's `result` is all of them merged into one object.
+    pub async fn record(
+        &self,
+        run: &NewAnalysisRun,
+        outputs: &[(String, Value)],
+        failed: &[FailedBlock],
+    ) -> Result<AnalysisRun, sqlx::Error> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+
+        let mut result = Map::new();
+        for (_, fields) in outputs {
+            if let Some(fields) = fields.as_object() {
+                result.extend(fields.clone());
+            }
+        }
+        let failed_json = if failed.is_empty() {
+            None
+        } else {
+            serde_json::to_string(failed).ok()
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO analysis_runs
+                (id, paper_id, provider, model, depth, language, prompt_hash, result,
+                 failed_blocks, restored_from, translated_from, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&id)
+        .bind(&run.paper_id)
+        .bind(&run.provider)
+        .bind(&run.model)
+        .bind(&run.depth)
+        .bind(&run.language)
+        .bind(&run.prompt_hash)
+        .bind(Value::Object(result).to_string())
+        .bind(&failed_json)
+        .bind(&run.restored_from)
+        .bind(&run.translated_from)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+
+        for (block_id, json) in outputs {
+            sqlx::query(
+                "INSERT INTO paper_analysis_blocks
+                    (paper_id, block_id, run_id, depth, language, model, json, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&run.paper_id)
+            .bind(block_id)
+            .bind(&id)
+            .bind(&run.depth)
+            .bind(&run.language)
+            .bind(&run.model)
+            .bind(json.to_string())
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        eprintln!("[AnalysisRunRepository::record] Recorded run {} for {} ({} blocks)",
+            id, run.paper_id, outputs.len());
+        self.get_by_id(&id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    /// Get a run by ID
+    pub async fn get_by_id(&self, id: &str) -> Result<Option<AnalysisRun>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM analysis_runs WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(row_to_run))
+    }
+
+    /// All runs of a paper, newest first
+    pub async fn list_for_paper(&self, paper_id: &str) -> Result<Vec<AnalysisRun>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM analysis_runs WHERE paper_id = ? ORDER BY created_at DESC, rowid DESC"
+        )
+        .bind(paper_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(row_to_run).collect())
+    }
+
+    /// Languages a paper has been analyzed or translated in, most recent first
+    pub async fn languages_for_paper(&self, paper_id: &str) -> Result<Vec<String>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT language FROM analysis_runs WHERE paper_id = ?
+             GROUP BY language ORDER BY MAX(created_at) DESC"
+        )
+        .bind(paper_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.get("language")).collect())
+    }
+}
+
+fn row_to_run(row: sqlx::sqlite::SqliteRow) -> AnalysisRun {
+    let result: String = row.get("result");
+    let failed_blocks: Option<String> = row.get("failed_blocks");
+
+    AnalysisRun {
+        id: row.get("id"),
+        paper_id: row.get("paper_id"),
+        provider: row.get("provider"),
+        model: row.get("model"),
+        depth: row.get("depth"),
+        language: row.get("language"),
+        prompt_hash: row.get("prompt_hash"),
+        result: serde_json::from_str(&result).unwrap_or(Value::Null),
+        failed_blocks: failed_blocks.and_then(|json| serde_json::from_str(&json).ok()),
+        restored_from: row.get("restored_from"),
+        translated_from: row.get("translated_from"),
+        created_at: row.get("created_at"),
+    }
+}
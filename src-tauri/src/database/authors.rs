@@ -0,0 +1,107 @@
+use crate::authors::PersonName;
+use crate::database::papers::parse_authors;
+use crate::database::{PaperError, PaperRepository};
+use crate::models::{AuthorInfo, Paper};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashSet;
+
+/// Repository for the authors of papers, one row per author
+///
+/// Rows are looked up by folded surname and then matched on the full name,
+/// so "G. Hinton" finds papers by "Geoffrey E. Hinton".
+pub struct AuthorRepository {
+    pool: SqlitePool,
+}
+
+impl AuthorRepository {
+    pub fn new(pool: &SqlitePool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Replace the authors of a paper
+    pub async fn replace_for_paper(&self, paper_id: &str, authors: &[AuthorInfo]) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM paper_authors WHERE paper_id = ?")
+            .bind(paper_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for (position, author) in authors.iter().enumerate() {
+            let surname = PersonName::parse(&author.name).map(|name| name.surname).unwrap_or_default();
+            sqlx::query(
+                "INSERT INTO paper_authors (paper_id, position, name, affiliation, surname)
+                 VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(paper_id)
+            .bind(position as i64)
+            .bind(&author.name)
+            .bind(&author.affiliation)
+            .bind(surname)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Index the authors of papers saved before the authors table existed
+    ///
+    /// Run once at startup (see `create_schema`); papers saved since are
+    /// indexed as they are saved. Returns the number of papers indexed.
+    pub async fn index_missing(&self) -> Result<usize, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, authors FROM papers p
+             WHERE authors != '[]'
+               AND NOT EXISTS (SELECT 1 FROM paper_authors a WHERE a.paper_id = p.id)"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut indexed = 0;
+        for row in rows {
+            let paper_id: String = row.get("id");
+            let authors_json: String = row.get("authors");
+            match parse_authors(&authors_json) {
+                Ok(authors) if !authors.is_empty() => {
+                    self.replace_for_paper(&paper_id, &authors).await?;
+                    indexed += 1;
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("[AuthorRepository::index_missing] Skipping authors of {}: {}", paper_id, e),
+            }
+        }
+        Ok(indexed)
+    }
+
+    /// Papers with an author matching `name`, newest first, spam excluded
+    pub async fn papers_by_author(&self, name: &str, limit: i32, offset: i32) -> Result<Vec<Paper>, PaperError> {
+        let Some(wanted) = PersonName::parse(name) else {
+            return Ok(vec![]);
+        };
+
+        let rows = sqlx::query(
+            "SELECT p.*, a.name AS author_name FROM paper_authors a
+             JOIN papers p ON p.id = a.paper_id
+             WHERE a.surname = ? AND p.is_spam = 0
+             ORDER BY p.published_date DESC, p.id, a.position"
+        )
+        .bind(&wanted.surname)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // Several authors of a paper may share the surname
+        let mut seen = HashSet::new();
+        rows.into_iter()
+            .filter(|row| {
+                let author_name: String = row.get("author_name");
+                PersonName::parse(&author_name).is_some_and(|author| author.matches(&wanted))
+            })
+            .filter(|row| seen.insert(row.get::<String, _>("id")))
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .map(PaperRepository::row_to_paper)
+            .collect()
+    }
+}
@@ -0,0 +1,222 @@
+use crate::bibliography::{title_key, Reference};
+use crate::database::{PaperError, PaperRepository};
+use crate::models::{Citation, CitationEdge, CitationGraph, CitationNode, Paper};
+use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+
+/// Repository for the references of papers and the citation graph they form
+///
+/// References are matched against library papers when queried, by arXiv ID
+/// and then by title, so a paper added after the ones citing it is linked too.
+pub struct CitationRepository {
+    pool: SqlitePool,
+}
+
+/// Library papers by arXiv ID and title key
+struct LibraryIndex {
+    nodes: HashMap<String, CitationNode>,
+    by_arxiv: HashMap<String, String>,
+    by_title: HashMap<String, String>,
+}
+
+impl LibraryIndex {
+    /// Library paper a reference points to
+    fn resolve(&self, arxiv_id: Option<&str>, title: Option<&str>) -> Option<&str> {
+        arxiv_id
+            .and_then(|id| self.by_arxiv.get(id))
+            .or_else(|| title.and_then(title_key).and_then(|key| self.by_title.get(&key)))
+            .map(String::as_str)
+    }
+}
+
+impl CitationRepository {
+    pub fn new(pool: &SqlitePool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Replace the references of a paper
+    pub async fn replace_for_paper(
+        &self,
+        citing_id: &str,
+        references: &[Reference],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM citations WHERE citing_id = ?")
+            .bind(citing_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for reference in references {
+            sqlx::query(
+                "INSERT INTO citations (citing_id, cited_arxiv_id, cited_title, doi)
+                 VALUES (?, ?, ?, ?)"
+            )
+            .bind(citing_id)
+            .bind(&reference.arxiv_id)
+            .bind(&reference.title)
+            .bind(&reference.doi)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// References of a paper in bibliography order
+    pub async fn get_for_paper(&self, citing_id: &str) -> Result<Vec<Citation>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM citations WHERE citing_id = ? ORDER BY id")
+            .bind(citing_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(row_to_citation).collect())
+    }
+
+    /// Library papers cited by a paper, newest first
+    pub async fn cited_papers(&self, paper_id: &str) -> Result<Vec<Paper>, PaperError> {
+        let ids: Vec<String> = self
+            .library_edges(Some(paper_id))
+            .await?
+            .into_iter()
+            .map(|edge| edge.target)
+            .collect();
+        self.papers_by_ids(&ids).await
+    }
+
+    /// Library papers citing a paper, newest first
+    pub async fn citing_papers(&self, paper_id: &str) -> Result<Vec<Paper>, PaperError> {
+        let ids: Vec<String> = self
+            .library_edges(None)
+            .await?
+            .into_iter()
+            .filter(|edge| edge.target == paper_id)
+            .map(|edge| edge.source)
+            .collect();
+        self.papers_by_ids(&ids).await
+    }
+
+    /// Citations between library papers
+    ///
+    /// Only papers with at least one edge are included.
+    pub async fn graph(&self) -> Result<CitationGraph, sqlx::Error> {
+        let index = self.library_index().await?;
+        let edges = self.edges_in(&index, None).await?;
+
+        let linked: HashSet<&str> = edges
+            .iter()
+            .flat_map(|edge| [edge.source.as_str(), edge.target.as_str()])
+            .collect();
+        let mut nodes: Vec<CitationNode> = index
+            .nodes
+            .values()
+            .filter(|node| linked.contains(node.id.as_str()))
+            .cloned()
+            .collect();
+        nodes.sort_by(|a, b| a.published_date.cmp(&b.published_date).then_with(|| a.id.cmp(&b.id)));
+
+        Ok(CitationGraph { nodes, edges })
+    }
+
+    async fn library_index(&self) -> Result<LibraryIndex, sqlx::Error> {
+        let rows = sqlx::query("SELECT id, arxiv_id, title, published_date FROM papers")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut index = LibraryIndex {
+            nodes: HashMap::new(),
+            by_arxiv: HashMap::new(),
+            by_title: HashMap::new(),
+        };
+        for row in rows {
+            let node = CitationNode {
+                id: row.get("id"),
+                arxiv_id: row.get::<Option<String>, _>("arxiv_id").unwrap_or_default(),
+                title: row.get("title"),
+                published_date: row.get("published_date"),
+            };
+            if !node.arxiv_id.is_empty() {
+                index.by_arxiv.insert(node.arxiv_id.clone(), node.id.clone());
+            }
+            if let Some(key) = title_key(&node.title) {
+                index.by_title.insert(key, node.id.clone());
+            }
+            index.nodes.insert(node.id.clone(), node);
+        }
+        Ok(index)
+    }
+
+    async fn library_edges(&self, citing_id: Option<&str>) -> Result<Vec<CitationEdge>, sqlx::Error> {
+        let index = self.library_index().await?;
+        self.edges_in(&index, citing_id).await
+    }
+
+    /// Edges from library papers to the library papers they cite, without
+    /// self-citations and duplicates
+    async fn edges_in(
+        &self,
+        index: &LibraryIndex,
+        citing_id: Option<&str>,
+    ) -> Result<Vec<CitationEdge>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT citing_id, cited_arxiv_id, cited_title FROM citations
+             WHERE ?1 IS NULL OR citing_id = ?1
+             ORDER BY id"
+        )
+        .bind(citing_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut seen = HashSet::new();
+        let mut edges = Vec::new();
+        for row in rows {
+            let source: String = row.get("citing_id");
+            if !index.nodes.contains_key(&source) {
+                continue;
+            }
+            let arxiv_id: Option<String> = row.get("cited_arxiv_id");
+            let title: Option<String> = row.get("cited_title");
+            let Some(target) = index.resolve(arxiv_id.as_deref(), title.as_deref()) else {
+                continue;
+            };
+            let edge = CitationEdge { source, target: target.to_string() };
+            if edge.source != edge.target && seen.insert(edge.clone()) {
+                edges.push(edge);
+            }
+        }
+        Ok(edges)
+    }
+
+    async fn papers_by_ids(&self, ids: &[String]) -> Result<Vec<Paper>, PaperError> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        let query = format!(
+            "SELECT * FROM papers WHERE id IN ({}) ORDER BY published_date DESC",
+            placeholders
+        );
+        let mut q = sqlx::query(&query);
+        for id in ids {
+            q = q.bind(id);
+        }
+
+        q.fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(PaperRepository::row_to_paper)
+            .collect()
+    }
+}
+
+fn row_to_citation(row: sqlx::sqlite::SqliteRow) -> Citation {
+    Citation {
+        id: row.get("id"),
+        citing_id: row.get("citing_id"),
+        cited_arxiv_id: row.get("cited_arxiv_id"),
+        cited_title: row.get("cited_title"),
+        doi: row.get("doi"),
+    }
+}
@@ -0,0 +1,225 @@
+use crate::analysis::{blocks, schema, BlockRunMode, I18nText};
+use crate::models::analysis_block::DEFAULT_CUSTOM_BLOCK_ORDER;
+use crate::models::{CustomBlock, CustomBlockInput};
+use chrono::Utc;
+use sqlx::{Row, SqlitePool};
+
+/// Repository for user-defined analysis blocks
+pub struct CustomBlockRepository {
+    pool: SqlitePool,
+}
+
+impl CustomBlockRepository {
+    pub fn new(pool: &SqlitePool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Get all custom blocks, ordered by block order
+    pub async fn get_all(&self) -> Result<Vec<CustomBlock>, String> {
+        let rows = sqlx::query("SELECT * FROM custom_analysis_blocks ORDER BY block_order, id")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        rows.into_iter().map(row_to_custom_block).collect()
+    }
+
+    /// Get a custom block by ID
+    pub async fn get_by_id(&self, id: &str) -> Result<CustomBlock, String> {
+        let row = sqlx::query("SELECT * FROM custom_analysis_blocks WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Custom block not found: {}", id))?;
+
+        row_to_custom_block(row)
+    }
+
+    /// Create a custom block
+    pub async fn create(&self, input: CustomBlockInput) -> Result<CustomBlock, String> {
+        let input = validate_input(input)?;
+        let now = Utc::now().to_rfc3339();
+
+        let exists = sqlx::query("SELECT 1 FROM custom_analysis_blocks WHERE id = ?")
+            .bind(&input.id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        if exists.is_some() {
+            return Err(format!("A custom block with ID '{}' already exists", input.id));
+        }
+
+        let description = input.description.clone().unwrap_or_else(empty_text);
+        sqlx::query(
+            "INSERT INTO custom_analysis_blocks
+                (id, name_en, name_zh, description_en, description_zh, instructions, json_schema,
+                 default_enabled, default_mode, block_order, depends_on, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&input.id)
+        .bind(&input.name.en)
+        .bind(&input.name.zh)
+        .bind(&description.en)
+        .bind(&description.zh)
+        .bind(&input.instructions)
+        .bind(input.json_schema.to_string())
+        .bind(input.default_enabled.unwrap_or(true))
+        .bind(mode_to_str(input.default_mode.unwrap_or(BlockRunMode::Both)))
+        .bind(input.order.unwrap_or(DEFAULT_CUSTOM_BLOCK_ORDER) as i64)
+        .bind(depends_on_json(&input.depends_on))
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        self.get_by_id(&input.id).await
+    }
+
+    /// Replace the definition of an existing custom block
+    ///
+    /// The block ID cannot change, since stored results refer to it.
+    pub async fn update(&self, id: &str, input: CustomBlockInput) -> Result<CustomBlock, String> {
+        if input.id != id {
+            return Err(format!("Cannot rename custom block '{}' to '{}'", id, input.id));
+        }
+        let input = validate_input(input)?;
+        let now = Utc::now().to_rfc3339();
+        let description = input.description.clone().unwrap_or_else(empty_text);
+
+        let result = sqlx::query(
+            "UPDATE custom_analysis_blocks SET
+                name_en = ?, name_zh = ?, description_en = ?, description_zh = ?,
+                instructions = ?, json_schema = ?, default_enabled = ?, default_mode = ?,
+                block_order = ?, depends_on = ?, updated_at = ?
+             WHERE id = ?"
+        )
+        .bind(&input.name.en)
+        .bind(&input.name.zh)
+        .bind(&description.en)
+        .bind(&description.zh)
+        .bind(&input.instructions)
+        .bind(input.json_schema.to_string())
+        .bind(input.default_enabled.unwrap_or(true))
+        .bind(mode_to_str(input.default_mode.unwrap_or(BlockRunMode::Both)))
+        .bind(input.order.unwrap_or(DEFAULT_CUSTOM_BLOCK_ORDER) as i64)
+        .bind(depends_on_json(&input.depends_on))
+        .bind(&now)
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        if result.rows_affected() == 0 {
+            return Err(format!("Custom block not found: {}", id));
+        }
+
+        self.get_by_id(id).await
+    }
+
+    /// Delete a custom block (its stored results are kept)
+    pub async fn delete(&self, id: &str) -> Result<(), String> {
+        let result = sqlx::query("DELETE FROM custom_analysis_blocks WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if result.rows_affected() == 0 {
+            return Err(format!("Custom block not found: {}", id));
+        }
+
+        Ok(())
+    }
+}
+
+/// Check a block definition and make its schema strict
+///
+/// The ID doubles as the reply field name, so it may not clash with a
+/// built-in block or any field a built-in block writes.
+fn validate_input(mut input: CustomBlockInput) -> Result<CustomBlockInput, String> {
+    let id_is_valid = input.id.len() <= 64
+        && input.id.starts_with(|c: char| c.is_ascii_lowercase())
+        && input.id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !id_is_valid {
+        return Err(format!(
+            "Invalid block ID '{}': use lowercase letters, digits and underscores, starting with a letter",
+            input.id
+        ));
+    }
+
+    let clashes = blocks::get_all_blocks().iter().any(|block| {
+        block.id == input.id || schema::block_fields(block).iter().any(|(name, _)| *name == input.id)
+    });
+    if clashes {
+        return Err(format!("'{}' is already used by a built-in block", input.id));
+    }
+
+    if input.name.en.trim().is_empty() {
+        return Err("Block name must not be empty".to_string());
+    }
+    if input.name.zh.trim().is_empty() {
+        input.name.zh = input.name.en.clone();
+    }
+    if input.instructions.trim().is_empty() {
+        return Err("Block instructions must not be empty".to_string());
+    }
+    if input.depends_on.as_ref().is_some_and(|deps| deps.contains(&input.id)) {
+        return Err(format!("Block '{}' cannot depend on itself", input.id));
+    }
+
+    input.json_schema = schema::normalize_custom_schema(&input.json_schema)
+        .map_err(|e| format!("Invalid output schema: {}", e))?;
+    Ok(input)
+}
+
+fn row_to_custom_block(row: sqlx::sqlite::SqliteRow) -> Result<CustomBlock, String> {
+    let get_string = |name: &str| row.try_get::<String, _>(name).map_err(|e| e.to_string());
+
+    let json_schema = serde_json::from_str(&get_string("json_schema")?)
+        .map_err(|e| format!("Invalid stored schema: {}", e))?;
+    let depends_on = row
+        .try_get::<Option<String>, _>("depends_on")
+        .map_err(|e| e.to_string())?
+        .and_then(|deps| serde_json::from_str(&deps).ok());
+    let default_mode = match get_string("default_mode")?.as_str() {
+        "standard" => BlockRunMode::Standard,
+        "full" => BlockRunMode::Full,
+        _ => BlockRunMode::Both,
+    };
+    let order: i64 = row.try_get("block_order").map_err(|e| e.to_string())?;
+
+    Ok(CustomBlock {
+        id: get_string("id")?,
+        name: I18nText { en: get_string("name_en")?, zh: get_string("name_zh")? },
+        description: I18nText { en: get_string("description_en")?, zh: get_string("description_zh")? },
+        instructions: get_string("instructions")?,
+        json_schema,
+        default_enabled: row.try_get("default_enabled").map_err(|e| e.to_string())?,
+        default_mode,
+        order: order.max(0) as usize,
+        depends_on,
+        created_at: get_string("created_at")?,
+        updated_at: get_string("updated_at")?,
+    })
+}
+
+fn mode_to_str(mode: BlockRunMode) -> &'static str {
+    match mode {
+        BlockRunMode::Standard => "standard",
+        BlockRunMode::Full => "full",
+        BlockRunMode::Both => "both",
+    }
+}
+
+fn depends_on_json(depends_on: &Option<Vec<String>>) -> Option<String> {
+    depends_on
+        .as_ref()
+        .filter(|deps| !deps.is_empty())
+        .map(|deps| serde_json::to_string(deps).unwrap_or_default())
+}
+
+fn empty_text() -> I18nText {
+    I18nText { en: String::new(), zh: String::new() }
+}
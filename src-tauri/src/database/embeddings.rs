@@ -0,0 +1,114 @@
+use chrono::Utc;
+use sqlx::{Row, SqlitePool};
+
+/// Text of a paper that is embedded, with the hash of its stored vector
+#[derive(Debug, Clone)]
+pub struct EmbeddingSource {
+    pub paper_id: String,
+    pub title: String,
+    pub summary: Option<String>,
+    pub ai_summary: Option<String>,
+    /// `None` when the paper has no vector for the model yet
+    pub content_hash: Option<String>,
+}
+
+/// Repository for paper embedding vectors
+pub struct EmbeddingRepository {
+    pool: SqlitePool,
+}
+
+impl EmbeddingRepository {
+    pub fn new(pool: &SqlitePool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Every paper with the content hash of its vector for `model`
+    pub async fn sources(&self, model: &str) -> Result<Vec<EmbeddingSource>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT p.id, p.title, p.summary, p.ai_summary, e.content_hash
+             FROM papers p
+             LEFT JOIN paper_embeddings e ON e.paper_id = p.id AND e.model = ?
+             ORDER BY p.id"
+        )
+        .bind(model)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| EmbeddingSource {
+                paper_id: row.get("id"),
+                title: row.get("title"),
+                summary: row.get("summary"),
+                ai_summary: row.get("ai_summary"),
+                content_hash: row.get("content_hash"),
+            })
+            .collect())
+    }
+
+    /// Store the vector of a paper for `model`, replacing an older one
+    pub async fn upsert(
+        &self,
+        paper_id: &str,
+        model: &str,
+        vector: &[f32],
+        content_hash: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO paper_embeddings (paper_id, model, dimensions, vector, content_hash, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(paper_id, model) DO UPDATE SET
+                dimensions = excluded.dimensions,
+                vector = excluded.vector,
+                content_hash = excluded.content_hash,
+                updated_at = excluded.updated_at"
+        )
+        .bind(paper_id)
+        .bind(model)
+        .bind(vector.len() as i64)
+        .bind(encode_vector(vector))
+        .bind(content_hash)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// All vectors of `model` by paper ID
+    pub async fn vectors(&self, model: &str) -> Result<Vec<(String, Vec<f32>)>, sqlx::Error> {
+        let rows = sqlx::query("SELECT paper_id, vector FROM paper_embeddings WHERE model = ?")
+            .bind(model)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let bytes: Vec<u8> = row.get("vector");
+                (row.get("paper_id"), decode_vector(&bytes))
+            })
+            .collect())
+    }
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_round_trip() {
+        let vector = vec![0.0, -1.5, 0.25, f32::MIN_POSITIVE];
+        assert_eq!(decode_vector(&encode_vector(&vector)), vector);
+    }
+}
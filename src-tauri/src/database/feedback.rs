@@ -0,0 +1,135 @@
+use crate::llm::ClassificationResult;
+use crate::models::RelevanceFeedback;
+use chrono::Utc;
+use sqlx::{Row, SqlitePool};
+
+/// Relevance decision recorded for a paper by a fetch
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelevanceDecision {
+    pub score: i32,
+    /// Topics the paper was assigned to, empty when unknown
+    pub topics: Vec<String>,
+}
+
+/// Feedback with the paper it is about and the decision made for it
+#[derive(Debug, Clone)]
+pub struct JudgedPaper {
+    pub topic: String,
+    pub relevant: bool,
+    pub title: String,
+    pub summary: Option<String>,
+    pub decision: Option<RelevanceDecision>,
+}
+
+/// Repository for relevance feedback
+pub struct FeedbackRepository {
+    pool: SqlitePool,
+}
+
+impl FeedbackRepository {
+    pub fn new(pool: &SqlitePool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Record a thumbs-up or down, replacing earlier feedback on the same topic
+    pub async fn set(&self, paper_id: &str, topic: &str, relevant: bool) -> Result<(), sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            "INSERT INTO relevance_feedback (paper_id, topic, relevant, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(paper_id, topic) DO UPDATE SET
+                relevant = excluded.relevant,
+                updated_at = excluded.updated_at"
+        )
+        .bind(paper_id)
+        .bind(topic)
+        .bind(relevant)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Remove the feedback on one topic of a paper
+    pub async fn clear(&self, paper_id: &str, topic: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM relevance_feedback WHERE paper_id = ? AND topic = ?")
+            .bind(paper_id)
+            .bind(topic)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Feedback on a paper, by topic
+    pub async fn get_for_paper(&self, paper_id: &str) -> Result<Vec<RelevanceFeedback>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT paper_id, topic, relevant, created_at, updated_at
+             FROM relevance_feedback WHERE paper_id = ? ORDER BY topic"
+        )
+        .bind(paper_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| RelevanceFeedback {
+                paper_id: row.get("paper_id"),
+                topic: row.get("topic"),
+                relevant: row.get("relevant"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            })
+            .collect())
+    }
+
+    /// All feedback on papers in the library, most recent first
+    ///
+    /// The decision is the relevance score and topics saved with the paper,
+    /// or else the latest cached classification of it.
+    pub async fn judged_papers(&self) -> Result<Vec<JudgedPaper>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT f.topic, f.relevant, p.title, p.summary, p.filter_score, p.topics,
+                    (SELECT c.classification_result FROM classification_cache c
+                     WHERE c.arxiv_id IN (p.id, p.arxiv_id)
+                     ORDER BY c.updated_at DESC LIMIT 1) AS cached_result
+             FROM relevance_feedback f
+             INNER JOIN papers p ON p.id = f.paper_id
+             ORDER BY f.updated_at DESC, f.paper_id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let filter_score: Option<i32> = row.get("filter_score");
+                let decision = match filter_score {
+                    Some(score) => {
+                        let topics: Option<String> = row.get("topics");
+                        Some(RelevanceDecision {
+                            score,
+                            topics: topics
+                                .and_then(|json| serde_json::from_str(&json).ok())
+                                .unwrap_or_default(),
+                        })
+                    }
+                    None => row
+                        .get::<Option<String>, _>("cached_result")
+                        .and_then(|json| serde_json::from_str::<ClassificationResult>(&json).ok())
+                        .map(|cached| RelevanceDecision {
+                            score: cached.score,
+                            topics: cached.suggested_topics,
+                        }),
+                };
+                JudgedPaper {
+                    topic: row.get("topic"),
+                    relevant: row.get("relevant"),
+                    title: row.get("title"),
+                    summary: row.get("summary"),
+                    decision,
+                }
+            })
+            .collect())
+    }
+}
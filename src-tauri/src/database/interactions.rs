@@ -0,0 +1,69 @@
+use chrono::Utc;
+use sqlx::{Row, SqlitePool};
+
+/// What the user did with a paper, as far as the recommender is concerned
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PaperSignals {
+    pub paper_id: String,
+    pub created_at: String,
+    pub is_spam: bool,
+    pub is_deep_analyzed: bool,
+    /// Number of collections the paper is in
+    pub collection_count: i64,
+    pub open_count: i64,
+}
+
+/// Repository for paper opens and the other library signals
+pub struct InteractionRepository {
+    pool: SqlitePool,
+}
+
+impl InteractionRepository {
+    pub fn new(pool: &SqlitePool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Count one more open of a paper
+    pub async fn record_open(&self, paper_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO paper_opens (paper_id, open_count, last_opened_at)
+             VALUES (?, 1, ?)
+             ON CONFLICT(paper_id) DO UPDATE SET
+                open_count = open_count + 1,
+                last_opened_at = excluded.last_opened_at"
+        )
+        .bind(paper_id)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Signals of every paper in the library
+    pub async fn signals(&self) -> Result<Vec<PaperSignals>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT p.id, p.created_at,
+                    COALESCE(p.is_spam, 0) AS is_spam,
+                    COALESCE(p.is_deep_analyzed, 0) AS is_deep_analyzed,
+                    (SELECT COUNT(*) FROM collection_papers cp WHERE cp.paper_id = p.id) AS collection_count,
+                    COALESCE(o.open_count, 0) AS open_count
+             FROM papers p
+             LEFT JOIN paper_opens o ON o.paper_id = p.id
+             ORDER BY p.id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PaperSignals {
+                paper_id: row.get("id"),
+                created_at: row.get("created_at"),
+                is_spam: row.get("is_spam"),
+                is_deep_analyzed: row.get("is_deep_analyzed"),
+                collection_count: row.get("collection_count"),
+                open_count: row.get("open_count"),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,234 @@
+use crate::models::{LlmUsage, ModelPrice, NewLlmUsage, UsageSummary};
+use chrono::Utc;
+use sqlx::{Row, SqlitePool};
+
+/// Aggregate columns shared by the usage reports
+///
+/// Calls are joined with `llm_model_prices` as `p`; calls of unpriced models
+/// add nothing to `cost` and are counted in `unpriced_calls` instead.
+const SUMMARY_COLUMNS: &str = "
+    COUNT(*) AS calls,
+    COALESCE(SUM(u.prompt_tokens), 0) AS prompt_tokens,
+    COALESCE(SUM(u.completion_tokens), 0) AS completion_tokens,
+    COALESCE(SUM((u.prompt_tokens * p.input_per_million
+                  + u.completion_tokens * p.output_per_million) / 1000000.0), 0.0) AS cost,
+    COALESCE(SUM(CASE WHEN p.model IS NULL THEN 1 ELSE 0 END), 0) AS unpriced_calls,
+    MIN(u.created_at) AS first_call_at,
+    MAX(u.created_at) AS last_call_at";
+
+/// Repository for LLM token usage and model prices
+pub struct LlmUsageRepository {
+    pool: SqlitePool,
+}
+
+impl LlmUsageRepository {
+    pub fn new(pool: &SqlitePool) -> Self {
+        Self { pool: pool.clone() }
+    }
+
+    /// Record one LLM call
+    pub async fn record(&self, usage: &NewLlmUsage) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO llm_usage
+                (provider, model, analysis_type, paper_id, fetch_id, topics,
+                 prompt_tokens, completion_tokens, estimated, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&usage.provider)
+        .bind(&usage.model)
+        .bind(&usage.analysis_type)
+        .bind(&usage.paper_id)
+        .bind(&usage.fetch_id)
+        .bind(serde_json::to_string(&usage.topics).unwrap_or_else(|_| "[]".to_string()))
+        .bind(usage.usage.prompt_tokens)
+        .bind(usage.usage.completion_tokens)
+        .bind(usage.estimated)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Calls recorded for a paper, oldest first
+    pub async fn get_for_paper(&self, paper_id: &str) -> Result<Vec<LlmUsage>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM llm_usage WHERE paper_id = ? ORDER BY id")
+            .bind(paper_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(row_to_usage).collect())
+    }
+
+    /// Usage per UTC day, newest first
+    ///
+    /// `from` and `to` are inclusive `YYYY-MM-DD` bounds.
+    pub async fn summary_by_day(
+        &self,
+        from: Option<&str>,
+        to: Option<&str>,
+    ) -> Result<Vec<UsageSummary>, sqlx::Error> {
+        let sql = format!(
+            "SELECT substr(u.created_at, 1, 10) AS key, {}
+             FROM llm_usage u
+             LEFT JOIN llm_model_prices p ON p.model = u.model
+             WHERE (?1 IS NULL OR substr(u.created_at, 1, 10) >= ?1)
+               AND (?2 IS NULL OR substr(u.created_at, 1, 10) <= ?2)
+             GROUP BY key
+             ORDER BY key DESC",
+            SUMMARY_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(row_to_summary).collect())
+    }
+
+    /// Usage per topic, most expensive first
+    ///
+    /// A call for a paper in several topics counts toward each of them, so the
+    /// topic totals can add up to more than the overall spend. Calls made
+    /// without a topic are grouped under `None`.
+    pub async fn summary_by_topic(
+        &self,
+        from: Option<&str>,
+        to: Option<&str>,
+    ) -> Result<Vec<UsageSummary>, sqlx::Error> {
+        let sql = format!(
+            "SELECT t.value AS key, {}
+             FROM llm_usage u
+             JOIN json_each(CASE WHEN u.topics = '[]' THEN '[null]' ELSE u.topics END) t
+             LEFT JOIN llm_model_prices p ON p.model = u.model
+             WHERE (?1 IS NULL OR substr(u.created_at, 1, 10) >= ?1)
+               AND (?2 IS NULL OR substr(u.created_at, 1, 10) <= ?2)
+             GROUP BY t.value
+             ORDER BY cost DESC, prompt_tokens + completion_tokens DESC",
+            SUMMARY_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(row_to_summary).collect())
+    }
+
+    /// Usage of the `limit` most recent fetches, newest first
+    pub async fn summary_by_fetch(&self, limit: i64) -> Result<Vec<UsageSummary>, sqlx::Error> {
+        let sql = format!(
+            "SELECT u.fetch_id AS key, {}
+             FROM llm_usage u
+             LEFT JOIN llm_model_prices p ON p.model = u.model
+             WHERE u.fetch_id IS NOT NULL
+             GROUP BY u.fetch_id
+             ORDER BY first_call_at DESC
+             LIMIT ?",
+            SUMMARY_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(row_to_summary).collect())
+    }
+
+    /// Usage of all calls made at or after `since`
+    ///
+    /// `since` is compared with the RFC 3339 call time, so a day ("2026-10-16")
+    /// or a month ("2026-10") selects every call from its start on.
+    pub async fn totals_since(&self, since: &str) -> Result<UsageSummary, sqlx::Error> {
+        let sql = format!(
+            "SELECT NULL AS key, {}
+             FROM llm_usage u
+             LEFT JOIN llm_model_prices p ON p.model = u.model
+             WHERE u.created_at >= ?",
+            SUMMARY_COLUMNS
+        );
+        let row = sqlx::query(&sql)
+            .bind(since)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row_to_summary(row))
+    }
+
+    /// All configured model prices, ordered by model
+    pub async fn get_prices(&self) -> Result<Vec<ModelPrice>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM llm_model_prices ORDER BY model")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ModelPrice {
+                model: row.get("model"),
+                input_per_million: row.get("input_per_million"),
+                output_per_million: row.get("output_per_million"),
+                updated_at: row.get("updated_at"),
+            })
+            .collect())
+    }
+
+    /// Set the price of a model, replacing any earlier price
+    pub async fn set_price(&self, price: &ModelPrice) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO llm_model_prices (model, input_per_million, output_per_million, updated_at)
+             VALUES (?, ?, ?, ?)
+             ON CONFLICT(model) DO UPDATE SET
+                input_per_million = excluded.input_per_million,
+                output_per_million = excluded.output_per_million,
+                updated_at = excluded.updated_at"
+        )
+        .bind(&price.model)
+        .bind(price.input_per_million)
+        .bind(price.output_per_million)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Remove the price of a model; returns false if it had none
+    pub async fn delete_price(&self, model: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM llm_model_prices WHERE model = ?")
+            .bind(model)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn row_to_usage(row: sqlx::sqlite::SqliteRow) -> LlmUsage {
+    let topics: String = row.get("topics");
+    LlmUsage {
+        id: row.get("id"),
+        provider: row.get("provider"),
+        model: row.get("model"),
+        analysis_type: row.get("analysis_type"),
+        paper_id: row.get("paper_id"),
+        fetch_id: row.get("fetch_id"),
+        topics: serde_json::from_str(&topics).unwrap_or_default(),
+        prompt_tokens: row.get("prompt_tokens"),
+        completion_tokens: row.get("completion_tokens"),
+        estimated: row.get("estimated"),
+        created_at: row.get("created_at"),
+    }
+}
+
+fn row_to_summary(row: sqlx::sqlite::SqliteRow) -> UsageSummary {
+    UsageSummary {
+        key: row.get("key"),
+        calls: row.get("calls"),
+        prompt_tokens: row.get("prompt_tokens"),
+        completion_tokens: row.get("completion_tokens"),
+        cost: row.get("cost"),
+        unpriced_calls: row.get("unpriced_calls"),
+        // Both are NULL when no call matched
+        first_call_at: row.get::<Option<String>, _>("first_call_at").unwrap_or_default(),
+        last_call_at: row.get::<Option<String>, _>("last_call_at").unwrap_or_default(),
+    }
+}
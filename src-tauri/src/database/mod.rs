@@ -6,12 +6,30 @@ pub mod settings;
 pub mod classification_cache;
 pub mod collections;
 pub mod fetch_history;
+pub mod custom_blocks;
+pub mod analysis_blocks;
+pub mod analysis_runs;
+pub mod llm_usage;
+pub mod citations;
+pub mod embeddings;
+pub mod interactions;
+pub mod feedback;
+pub mod authors;
 
 pub use papers::{PaperRepository, PaperError};
 pub use settings::SettingsRepository;
 pub use classification_cache::ClassificationCacheRepository;
 pub use collections::CollectionRepository;
 pub use fetch_history::{FetchHistoryRepository, FetchHistoryEntry, PaperSummary};
+pub use custom_blocks::CustomBlockRepository;
+pub use analysis_blocks::AnalysisBlockRepository;
+pub use analysis_runs::AnalysisRunRepository;
+pub use llm_usage::LlmUsageRepository;
+pub use citations::CitationRepository;
+pub use embeddings::EmbeddingRepository;
+pub use interactions::InteractionRepository;
+pub use feedback::FeedbackRepository;
+pub use authors::AuthorRepository;
 
 /// Get the path to the SQLite database file
 /// Platform-specific application data directories:
@@ -159,6 +177,16 @@ pub async fn create_schema(pool: &SqlitePool) -> SqlxResult<()> {
         ("018_add_analysis_config.sql", include_str!("../../migrations/018_add_analysis_config.sql")),
         ("019_add_related_papers.sql", include_str!("../../migrations/019_add_related_papers.sql")),
         ("020_add_content_metadata.sql", include_str!("../../migrations/020_add_content_metadata.sql")),
+        ("021_add_failed_blocks.sql", include_str!("../../migrations/021_add_failed_blocks.sql")),
+        ("022_custom_analysis_blocks.sql", include_str!("../../migrations/022_custom_analysis_blocks.sql")),
+        ("023_analysis_runs.sql", include_str!("../../migrations/023_analysis_runs.sql")),
+        ("024_analysis_translations.sql", include_str!("../../migrations/024_analysis_translations.sql")),
+        ("025_llm_usage.sql", include_str!("../../migrations/025_llm_usage.sql")),
+        ("026_citations.sql", include_str!("../../migrations/026_citations.sql")),
+        ("027_paper_embeddings.sql", include_str!("../../migrations/027_paper_embeddings.sql")),
+        ("028_paper_opens.sql", include_str!("../../migrations/028_paper_opens.sql")),
+        ("029_relevance_feedback.sql", include_str!("../../migrations/029_relevance_feedback.sql")),
+        ("030_paper_authors.sql", include_str!("../../migrations/030_paper_authors.sql")),
     ];
 
     for (migration_name, schema) in migrations.iter() {
@@ -220,6 +248,13 @@ pub async fn create_schema(pool: &SqlitePool) -> SqlxResult<()> {
         eprintln!("[create_schema] Warning: Failed to rebuild FTS5 index: {}", e);
         // Don't fail the whole migration if FTS5 rebuild fails
     }
+
+    // Papers saved before the authors table existed get their author rows
+    match crate::database::AuthorRepository::new(pool).index_missing().await {
+        Ok(0) => {}
+        Ok(indexed) => eprintln!("[create_schema] Indexed the authors of {} papers", indexed),
+        Err(e) => eprintln!("[create_schema] Warning: Failed to index paper authors: {}", e),
+    }
     
     Ok(())
 }
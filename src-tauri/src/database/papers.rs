@@ -1,11 +1,12 @@
 #![allow(dead_code)]
 
+use crate::database::{AnalysisBlockRepository, AuthorRepository};
 use crate::models::{Paper, AuthorInfo};
 use sqlx::{SqlitePool, Row};
 use thiserror::Error;
 
 /// Parse authors from JSON, handling both old format (array of strings) and new format (array of objects)
-fn parse_authors(authors_json: &str) -> serde_json::Result<Vec<AuthorInfo>> {
+pub(crate) fn parse_authors(authors_json: &str) -> serde_json::Result<Vec<AuthorInfo>> {
     // Try parsing as new format (array of objects)
     let result: serde_json::Result<Vec<AuthorInfo>> = serde_json::from_str(authors_json);
 
@@ -44,6 +45,7 @@ struct SerializedPaperData {
     links_json: Option<String>,
     related_papers_json: Option<String>,
     available_sections_json: Option<String>,
+    failed_blocks_json: Option<String>,
 }
 
 /// Serialize paper fields to JSON for database storage
@@ -71,6 +73,10 @@ fn serialize_paper_fields(paper: &Paper) -> Result<SerializedPaperData> {
         .map(|v| serde_json::to_string(v))
         .transpose()
         .map_err(|e| PaperError::Serialization(e.to_string()))?;
+    let failed_blocks_json = paper.failed_blocks.as_ref()
+        .map(|v| serde_json::to_string(v))
+        .transpose()
+        .map_err(|e| PaperError::Serialization(e.to_string()))?;
 
     Ok(SerializedPaperData {
         authors_json,
@@ -80,9 +86,21 @@ fn serialize_paper_fields(paper: &Paper) -> Result<SerializedPaperData> {
         links_json,
         related_papers_json,
         available_sections_json,
+        failed_blocks_json,
     })
 }
 
+/// Tables holding per-paper rows, with the column naming the paper
+const PAPER_TABLES: [(&str, &str); 7] = [
+    ("paper_analysis_blocks", "paper_id"),
+    ("analysis_runs", "paper_id"),
+    ("citations", "citing_id"),
+    ("paper_embeddings", "paper_id"),
+    ("paper_opens", "paper_id"),
+    ("relevance_feedback", "paper_id"),
+    ("paper_authors", "paper_id"),
+];
+
 /// Repository for paper database operations
 #[derive(Clone)]
 pub struct PaperRepository {
@@ -118,8 +136,8 @@ impl PaperRepository {
                 experiment_completeness_score, experiment_completeness_reason,
                 algorithm_flowchart, time_complexity, space_complexity,
                 analysis_mode, analysis_incomplete, is_spam, pdf_local_path, related_papers,
-                content_source, estimated_tokens, available_sections
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                content_source, estimated_tokens, available_sections, failed_blocks
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(id) DO NOTHING
             "#
         )
@@ -162,12 +180,14 @@ impl PaperRepository {
         .bind(&paper.content_source)
         .bind(paper.estimated_tokens)
         .bind(&serialized.available_sections_json)
+        .bind(&serialized.failed_blocks_json)
         .execute(&self.pool)
         .await?;
 
         let was_inserted = result.rows_affected() > 0;
         
         if was_inserted {
+            AuthorRepository::new(&self.pool).replace_for_paper(&paper.id, &paper.authors).await?;
             eprintln!("[PaperRepository::save_if_not_exists] ✓ Successfully inserted new paper: {}", paper.id);
         } else {
             eprintln!("[PaperRepository::save_if_not_exists] ℹ Paper already exists, skipped: {}", paper.id);
@@ -200,8 +220,8 @@ impl PaperRepository {
                 experiment_completeness_score, experiment_completeness_reason,
                 algorithm_flowchart, time_complexity, space_complexity,
                 analysis_mode, analysis_incomplete, is_spam, pdf_local_path, related_papers,
-                content_source, estimated_tokens, available_sections
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                content_source, estimated_tokens, available_sections, failed_blocks
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             ON CONFLICT(id) DO UPDATE SET
                 arxiv_id = excluded.arxiv_id,
                 title = excluded.title,
@@ -235,7 +255,8 @@ impl PaperRepository {
                 related_papers = excluded.related_papers,
                 content_source = excluded.content_source,
                 estimated_tokens = excluded.estimated_tokens,
-                available_sections = excluded.available_sections
+                available_sections = excluded.available_sections,
+                failed_blocks = excluded.failed_blocks
             "#
         )
         .bind(&paper.id)
@@ -277,6 +298,7 @@ impl PaperRepository {
         .bind(&paper.content_source)
         .bind(paper.estimated_tokens)
         .bind(&serialized.available_sections_json)
+        .bind(&serialized.failed_blocks_json)
         .execute(&self.pool)
         .await;
 
@@ -300,6 +322,7 @@ impl PaperRepository {
         }
 
         result?;
+        AuthorRepository::new(&self.pool).replace_for_paper(&paper.id, &paper.authors).await?;
 
         Ok(())
     }
@@ -312,7 +335,10 @@ impl PaperRepository {
             .await?
             .ok_or_else(|| PaperError::NotFound(id.to_string()))?;
 
-        Self::row_to_paper(row)
+        let mut paper = Self::row_to_paper(row)?;
+        let blocks = AnalysisBlockRepository::new(&self.pool).get_latest_for_paper(id).await?;
+        paper.analysis_blocks = if blocks.is_empty() { None } else { Some(blocks) };
+        Ok(paper)
     }
 
     /// Get all papers with pagination (excludes spam papers)
@@ -448,6 +474,34 @@ impl PaperRepository {
             .map(Self::row_to_paper)
             .collect()
     }
+
+    /// BM25 relevance of the papers matching a query, best first
+    ///
+    /// Scores are positive (SQLite's `bm25()` negated). Papers match any
+    /// word of the query by prefix, like in `search`.
+    pub async fn bm25_scores(&self, query: &str, limit: i32) -> Result<Vec<(String, f64)>> {
+        let Some(fts_query) = ranking_query(query) else {
+            return Ok(vec![]);
+        };
+
+        let rows = sqlx::query(
+            "SELECT papers.id AS id, bm25(papers_fts) AS rank FROM papers
+             INNER JOIN papers_fts ON papers.rowid = papers_fts.rowid
+             WHERE papers_fts MATCH ?
+             ORDER BY rank
+             LIMIT ?"
+        )
+        .bind(&fts_query)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("id"), -row.get::<f64, _>("rank")))
+            .collect())
+    }
+
     pub async fn get_by_tag(&self, tag: &str, limit: i32, offset: i32) -> Result<Vec<Paper>> {
         let pattern = format!("%\"{}\"%", tag);
         let rows = sqlx::query(
@@ -491,15 +545,26 @@ impl PaperRepository {
 
     /// Delete a paper by ID
     pub async fn delete(&self, id: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
         let result = sqlx::query("DELETE FROM papers WHERE id = ?")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
 
         if result.rows_affected() == 0 {
             return Err(PaperError::NotFound(id.to_string()));
         }
 
+        // These tables are not tied to the papers table by a foreign key
+        for (table, column) in PAPER_TABLES {
+            sqlx::query(&format!("DELETE FROM {} WHERE {} = ?", table, column))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
         Ok(())
     }
 
@@ -516,10 +581,8 @@ impl PaperRepository {
             row: &'a sqlx::sqlite::SqliteRow,
             name: &str,
         ) -> Option<T> {
-            match row.try_get::<T, _>(name) {
-                Ok(v) => Some(v),
-                Err(_) => None,
-            }
+            // Decode through Option so NULL stays None instead of reading as zero
+            row.try_get::<Option<T>, _>(name).ok().flatten()
         }
 
         fn get_opt_string(row: &sqlx::sqlite::SqliteRow, name: &str) -> Option<String> {
@@ -559,6 +622,8 @@ impl PaperRepository {
         let estimated_tokens: Option<i32> = get_opt_copy(&row, "estimated_tokens");
         let available_sections: Option<String> = get_opt_string(&row, "available_sections");
         let available_sections = available_sections.and_then(|v| serde_json::from_str(&v).ok());
+        let failed_blocks: Option<String> = get_opt_string(&row, "failed_blocks");
+        let failed_blocks = failed_blocks.and_then(|v| serde_json::from_str(&v).ok());
 
         Ok(Paper {
             id: row.get("id"),
@@ -602,10 +667,27 @@ impl PaperRepository {
             content_source,
             estimated_tokens,
             available_sections,
+            failed_blocks,
+            analysis_blocks: None,
         })
     }
 }
 
+/// FTS5 query for `bm25_scores`, matching any word of `query` by prefix,
+/// `None` when the query has no words
+///
+/// Punctuation separates words like the FTS tokenizer does, and words are
+/// lowercased so "AND"/"OR"/"NOT" are not read as operators.
+fn ranking_query(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|word| format!("{}*", word))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" OR "))
+}
+
 /// Check if a paper exists by its arXiv ID
 pub async fn paper_exists_by_arxiv_id(
     pool: &SqlitePool,
@@ -676,6 +758,13 @@ mod tests {
         assert!(matches!(err, PaperError::Database(_)));
     }
 
+    #[test]
+    fn test_ranking_query() {
+        assert_eq!(ranking_query("speculative decoding").as_deref(), Some("speculative* OR decoding*"));
+        assert_eq!(ranking_query("Mixture-of-Experts NOT").as_deref(), Some("mixture* OR of* OR experts* OR not*"));
+        assert_eq!(ranking_query("\"*()\""), None);
+    }
+
     #[test]
     fn test_paper_repository_clone_pool() {
         // Test that PaperRepository clones the pool correctly
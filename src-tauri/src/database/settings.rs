@@ -42,11 +42,9 @@ impl SettingsRepository {
 
             match key.as_str() {
                 "llm_provider" => {
-                    settings.llm_provider = match value.as_str() {
-                        "glm" => LLMProvider::Glm,
-                        "claude" => LLMProvider::Claude,
-                        _ => LLMProvider::Glm,
-                    };
+                    // Stored as the provider's serde name ("glm", "claude", "openai", ...)
+                    settings.llm_provider = serde_json::from_value(serde_json::Value::String(value))
+                        .unwrap_or(LLMProvider::Glm);
                 }
                 "glm_api_key" => settings.glm_api_key = Some(value),
                 "claude_api_key" => settings.claude_api_key = Some(value),
@@ -54,6 +52,10 @@ impl SettingsRepository {
                 "glm_deep_model" => settings.glm_deep_model = Some(value),
                 "claude_quick_model" => settings.claude_quick_model = Some(value),
                 "claude_deep_model" => settings.claude_deep_model = Some(value),
+                "openai_api_key" => settings.openai_api_key = Some(value),
+                "openai_base_url" => settings.openai_base_url = Some(value),
+                "openai_quick_model" => settings.openai_quick_model = Some(value),
+                "openai_deep_model" => settings.openai_deep_model = Some(value),
                 "topics" => {
                     settings.topics = serde_json::from_str(&value)
                         .map_err(|e| SettingsError::Serialization(e.to_string()))?;
@@ -90,12 +92,54 @@ impl SettingsRepository {
                 "deep_analysis_mode" => {
                     settings.deep_analysis_mode = Some(value);
                 }
+                "block_execution_mode" => {
+                    settings.block_execution_mode = Some(value);
+                }
                 "retry_config" => {
                     settings.retry_config = Some(
                         serde_json::from_str(&value)
                             .map_err(|e| SettingsError::Serialization(e.to_string()))?
                     );
                 }
+                "budget" => {
+                    settings.budget = Some(
+                        serde_json::from_str(&value)
+                            .map_err(|e| SettingsError::Serialization(e.to_string()))?
+                    );
+                }
+                "embeddings" => {
+                    settings.embeddings = Some(
+                        serde_json::from_str(&value)
+                            .map_err(|e| SettingsError::Serialization(e.to_string()))?
+                    );
+                }
+                "relevance_feedback" => {
+                    settings.relevance_feedback = Some(
+                        serde_json::from_str(&value)
+                            .map_err(|e| SettingsError::Serialization(e.to_string()))?
+                    );
+                }
+                "prefilter" => {
+                    settings.prefilter = Some(
+                        serde_json::from_str(&value)
+                            .map_err(|e| SettingsError::Serialization(e.to_string()))?
+                    );
+                }
+                "watchlist" => {
+                    settings.watchlist = Some(
+                        serde_json::from_str(&value)
+                            .map_err(|e| SettingsError::Serialization(e.to_string()))?
+                    );
+                }
+                "structured_output" => {
+                    settings.structured_output = Some(value == "true");
+                }
+                "chunked_analysis" => {
+                    settings.chunked_analysis = Some(value == "true");
+                }
+                "context_window" => {
+                    settings.context_window = value.parse().ok();
+                }
                 "analysis_config" => {
                     settings.analysis_config = Some(
                         serde_json::from_str(&value)
@@ -121,6 +165,10 @@ impl SettingsRepository {
                 deep_analysis_count: Some(3),
                 quick_score_threshold: Some(7),
                 keywords: Some(vec!["reinforcement".to_string(), "reinforcement learning".to_string(), "policy gradient".to_string(), "q-learning".to_string(), "actor-critic".to_string(), "ppo".to_string(), "dqn".to_string(), "rlhf".to_string(), "rlaif".to_string()]),
+                negative_keywords: None,
+                allowed_authors: None,
+                category_weights: None,
+                rule: None,
             },
             crate::models::TopicConfig {
                 key: "llm".to_string(),
@@ -133,6 +181,10 @@ impl SettingsRepository {
                 deep_analysis_count: Some(3),
                 quick_score_threshold: Some(7),
                 keywords: Some(vec!["language model".to_string(), "llm".to_string(), "gpt".to_string(), "transformer".to_string(), "attention".to_string(), "pretraining".to_string(), "finetuning".to_string(), "alignment".to_string(), "llm inference".to_string(), "large language".to_string()]),
+                negative_keywords: None,
+                allowed_authors: None,
+                category_weights: None,
+                rule: None,
             },
             crate::models::TopicConfig {
                 key: "inference".to_string(),
@@ -145,6 +197,10 @@ impl SettingsRepository {
                 deep_analysis_count: Some(2),
                 quick_score_threshold: Some(8),
                 keywords: Some(vec!["inference".to_string(), "quantization".to_string(), "distillation".to_string(), "speculative".to_string(), "kv cache".to_string(), "acceleration".to_string(), "optimization".to_string(), "serving".to_string(), "latency".to_string(), "throughput".to_string()]),
+                negative_keywords: None,
+                allowed_authors: None,
+                category_weights: None,
+                rule: None,
             },
             // Additional topics (disabled by default)
             crate::models::TopicConfig {
@@ -158,6 +214,10 @@ impl SettingsRepository {
                 deep_analysis_count: Some(3),
                 quick_score_threshold: Some(7),
                 keywords: Some(vec!["mixture of experts".to_string(), "moe".to_string(), "sparse".to_string(), "expert routing".to_string(), "switch transformer".to_string(), "load balancing".to_string(), "conditional computation".to_string()]),
+                negative_keywords: None,
+                allowed_authors: None,
+                category_weights: None,
+                rule: None,
             },
             crate::models::TopicConfig {
                 key: "embodied".to_string(),
@@ -170,6 +230,10 @@ impl SettingsRepository {
                 deep_analysis_count: Some(3),
                 quick_score_threshold: Some(7),
                 keywords: Some(vec!["embodied".to_string(), "robotics".to_string(), "manipulation".to_string(), "navigation".to_string(), "sim-to-real".to_string(), "vla".to_string(), "vision-language-action".to_string()]),
+                negative_keywords: None,
+                allowed_authors: None,
+                category_weights: None,
+                rule: None,
             },
             crate::models::TopicConfig {
                 key: "world_model".to_string(),
@@ -182,6 +246,10 @@ impl SettingsRepository {
                 deep_analysis_count: Some(3),
                 quick_score_threshold: Some(7),
                 keywords: Some(vec!["world model".to_string(), "model-based".to_string(), "predictive model".to_string(), "dreamer".to_string(), "dynamics".to_string(), "planning".to_string(), "environment model".to_string()]),
+                negative_keywords: None,
+                allowed_authors: None,
+                category_weights: None,
+                rule: None,
             },
             crate::models::TopicConfig {
                 key: "multimodal".to_string(),
@@ -194,6 +262,10 @@ impl SettingsRepository {
                 deep_analysis_count: Some(3),
                 quick_score_threshold: Some(7),
                 keywords: Some(vec!["multimodal".to_string(), "vision-language".to_string(), "clip".to_string(), "vqa".to_string(), "cross-modal".to_string(), "alignment".to_string(), "image generation".to_string()]),
+                negative_keywords: None,
+                allowed_authors: None,
+                category_weights: None,
+                rule: None,
             },
         ];
 
@@ -285,10 +357,9 @@ impl SettingsRepository {
         }
 
         // Save each setting
-        save(&self.pool, &now, "llm_provider", match settings.llm_provider {
-            LLMProvider::Glm => "glm",
-            LLMProvider::Claude => "claude",
-        }).await?;
+        let provider = serde_json::to_value(&settings.llm_provider)
+            .map_err(|e| SettingsError::Serialization(e.to_string()))?;
+        save(&self.pool, &now, "llm_provider", provider.as_str().unwrap_or("glm")).await?;
 
         if let Some(ref key) = settings.glm_api_key {
             save(&self.pool, &now, "glm_api_key", key).await?;
@@ -314,6 +385,22 @@ impl SettingsRepository {
             save(&self.pool, &now, "claude_deep_model", model).await?;
         }
 
+        if let Some(ref key) = settings.openai_api_key {
+            save(&self.pool, &now, "openai_api_key", key).await?;
+        }
+
+        if let Some(ref url) = settings.openai_base_url {
+            save(&self.pool, &now, "openai_base_url", url).await?;
+        }
+
+        if let Some(ref model) = settings.openai_quick_model {
+            save(&self.pool, &now, "openai_quick_model", model).await?;
+        }
+
+        if let Some(ref model) = settings.openai_deep_model {
+            save(&self.pool, &now, "openai_deep_model", model).await?;
+        }
+
         let topics_json = serde_json::to_string(&settings.topics)
             .map_err(|e| SettingsError::Serialization(e.to_string()))?;
         save(&self.pool, &now, "topics", &topics_json).await?;
@@ -356,6 +443,9 @@ impl SettingsRepository {
         if let Some(ref mode) = settings.deep_analysis_mode {
             save(&self.pool, &now, "deep_analysis_mode", mode).await?;
         }
+        if let Some(ref mode) = settings.block_execution_mode {
+            save(&self.pool, &now, "block_execution_mode", mode).await?;
+        }
 
         if let Some(ref config) = settings.retry_config {
             let config_json = serde_json::to_string(config)
@@ -369,6 +459,48 @@ impl SettingsRepository {
             save(&self.pool, &now, "analysis_config", &config_json).await?;
         }
 
+        if let Some(ref budget) = settings.budget {
+            let budget_json = serde_json::to_string(budget)
+                .map_err(|e| SettingsError::Serialization(e.to_string()))?;
+            save(&self.pool, &now, "budget", &budget_json).await?;
+        }
+
+        if let Some(ref embeddings) = settings.embeddings {
+            let embeddings_json = serde_json::to_string(embeddings)
+                .map_err(|e| SettingsError::Serialization(e.to_string()))?;
+            save(&self.pool, &now, "embeddings", &embeddings_json).await?;
+        }
+
+        if let Some(ref relevance_feedback) = settings.relevance_feedback {
+            let relevance_feedback_json = serde_json::to_string(relevance_feedback)
+                .map_err(|e| SettingsError::Serialization(e.to_string()))?;
+            save(&self.pool, &now, "relevance_feedback", &relevance_feedback_json).await?;
+        }
+
+        if let Some(ref prefilter) = settings.prefilter {
+            let prefilter_json = serde_json::to_string(prefilter)
+                .map_err(|e| SettingsError::Serialization(e.to_string()))?;
+            save(&self.pool, &now, "prefilter", &prefilter_json).await?;
+        }
+
+        if let Some(ref watchlist) = settings.watchlist {
+            let watchlist_json = serde_json::to_string(watchlist)
+                .map_err(|e| SettingsError::Serialization(e.to_string()))?;
+            save(&self.pool, &now, "watchlist", &watchlist_json).await?;
+        }
+
+        if let Some(enabled) = settings.structured_output {
+            save(&self.pool, &now, "structured_output", if enabled { "true" } else { "false" }).await?;
+        }
+
+        if let Some(enabled) = settings.chunked_analysis {
+            save(&self.pool, &now, "chunked_analysis", if enabled { "true" } else { "false" }).await?;
+        }
+
+        if let Some(tokens) = settings.context_window {
+            save(&self.pool, &now, "context_window", &tokens.to_string()).await?;
+        }
+
         Ok(())
     }
 
@@ -0,0 +1,179 @@
+//! Embeddings from a server speaking the OpenAI embeddings API
+//! (`POST {base_url}/embeddings`), e.g. OpenAI, GLM or a local Ollama
+
+use super::EmbeddingError;
+use crate::database::LlmUsageRepository;
+use crate::llm::DEFAULT_OPENAI_BASE_URL;
+use crate::models::{NewLlmUsage, TokenUsage};
+use reqwest::Client;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::time::Duration;
+
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+/// Texts sent per request
+pub(crate) const BATCH_SIZE: usize = 64;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+    usage: Option<EmbeddingUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingUsage {
+    prompt_tokens: i64,
+}
+
+/// Client of an OpenAI-compatible embeddings endpoint
+pub struct ApiEmbedder {
+    client: Client,
+    endpoint: String,
+    model: String,
+    api_key: String,
+    usage_pool: Option<SqlitePool>,
+}
+
+impl ApiEmbedder {
+    pub fn new(base_url: Option<&str>, model: &str, api_key: &str) -> Result<Self, EmbeddingError> {
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| EmbeddingError::Request(e.to_string()))?;
+        Ok(Self {
+            client,
+            endpoint: embeddings_endpoint(base_url),
+            model: model.to_string(),
+            api_key: api_key.to_string(),
+            usage_pool: None,
+        })
+    }
+
+    /// Record the tokens of every request in the LLM usage table
+    pub fn with_usage_tracking(mut self, pool: &SqlitePool) -> Self {
+        self.usage_pool = Some(pool.clone());
+        self
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// One vector per text, in order
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            let mut request = self
+                .client
+                .post(&self.endpoint)
+                .json(&serde_json::json!({ "model": self.model, "input": batch }));
+            // Only send credentials when a key is configured
+            if !self.api_key.is_empty() {
+                request = request.header("Authorization", format!("Bearer {}", self.api_key));
+            }
+
+            let response = request
+                .send()
+                .await
+                .map_err(|e| EmbeddingError::Request(e.to_string()))?;
+            let status = response.status();
+            let body = response
+                .text()
+                .await
+                .map_err(|e| EmbeddingError::Request(e.to_string()))?;
+            if !status.is_success() {
+                return Err(EmbeddingError::Request(format!("HTTP {}: {}", status, body)));
+            }
+
+            let (batch_vectors, usage) = parse_response(&body, batch.len())?;
+            self.record_usage(batch, usage).await;
+            vectors.extend(batch_vectors);
+        }
+        Ok(vectors)
+    }
+
+    async fn record_usage(&self, batch: &[String], usage: Option<i64>) {
+        let Some(pool) = &self.usage_pool else {
+            return;
+        };
+        let record = NewLlmUsage {
+            provider: "embeddings".to_string(),
+            model: self.model.clone(),
+            analysis_type: "embedding".to_string(),
+            paper_id: None,
+            fetch_id: None,
+            topics: Vec::new(),
+            usage: TokenUsage {
+                prompt_tokens: usage.unwrap_or_else(|| {
+                    batch.iter().map(|t| crate::tokens::estimate_tokens(t) as i64).sum()
+                }),
+                completion_tokens: 0,
+            },
+            estimated: usage.is_none(),
+        };
+        if let Err(e) = LlmUsageRepository::new(pool).record(&record).await {
+            eprintln!("[ApiEmbedder::record_usage] Failed to record token usage: {}", e);
+        }
+    }
+}
+
+/// Embeddings endpoint for a base URL (`None` or empty uses OpenAI)
+fn embeddings_endpoint(base_url: Option<&str>) -> String {
+    let base_url = base_url
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .unwrap_or(DEFAULT_OPENAI_BASE_URL);
+    format!("{}/embeddings", base_url.trim_end_matches('/'))
+}
+
+/// Vectors ordered by their `index`, and the prompt tokens if reported
+fn parse_response(body: &str, expected: usize) -> Result<(Vec<Vec<f32>>, Option<i64>), EmbeddingError> {
+    let mut response: EmbeddingResponse =
+        serde_json::from_str(body).map_err(|e| EmbeddingError::Response(e.to_string()))?;
+    if response.data.len() != expected {
+        return Err(EmbeddingError::Response(format!(
+            "expected {} embeddings, got {}",
+            expected,
+            response.data.len()
+        )));
+    }
+    response.data.sort_by_key(|d| d.index);
+    let vectors = response.data.into_iter().map(|d| d.embedding).collect();
+    Ok((vectors, response.usage.map(|u| u.prompt_tokens)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embeddings_endpoint() {
+        assert_eq!(embeddings_endpoint(None), "https://api.openai.com/v1/embeddings");
+        assert_eq!(
+            embeddings_endpoint(Some("http://localhost:11434/v1/")),
+            "http://localhost:11434/v1/embeddings"
+        );
+    }
+
+    #[test]
+    fn test_parse_response_orders_by_index() {
+        let body = r#"{"data": [
+            {"object": "embedding", "index": 1, "embedding": [0.0, 1.0]},
+            {"object": "embedding", "index": 0, "embedding": [1.0, 0.0]}
+        ], "usage": {"prompt_tokens": 12, "total_tokens": 12}}"#;
+        let (vectors, usage) = parse_response(body, 2).unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert_eq!(usage, Some(12));
+
+        assert!(matches!(parse_response(body, 3), Err(EmbeddingError::Response(_))));
+    }
+}
@@ -0,0 +1,93 @@
+//! Hybrid ranking of keyword and semantic matches
+//!
+//! BM25 scores and cosine similarities live on different scales, so each is
+//! divided by the best score of its list before they are blended.
+
+use std::collections::HashMap;
+
+/// Scores of one paper in a hybrid ranking
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedPaper {
+    pub paper_id: String,
+    pub score: f64,
+    pub keyword_score: Option<f64>,
+    pub semantic_score: Option<f64>,
+}
+
+/// Blend BM25 scores with cosine similarities, best first
+///
+/// `semantic_weight` (0.0-1.0) is the share of cosine similarity in the
+/// score; a paper missing from one list scores 0 there.
+pub fn blend(
+    bm25: &[(String, f64)],
+    cosine: &[(String, f32)],
+    semantic_weight: f64,
+) -> Vec<RankedPaper> {
+    let semantic_weight = semantic_weight.clamp(0.0, 1.0);
+    let best_bm25 = bm25.iter().map(|(_, s)| *s).fold(0.0, f64::max);
+    let best_cosine = cosine.iter().map(|(_, s)| *s as f64).fold(0.0, f64::max);
+
+    let mut scores: HashMap<String, (Option<f64>, Option<f64>)> = HashMap::new();
+    if best_bm25 > 0.0 {
+        for (paper_id, score) in bm25 {
+            scores.entry(paper_id.clone()).or_default().0 = Some(score / best_bm25);
+        }
+    }
+    if best_cosine > 0.0 {
+        for (paper_id, similarity) in cosine {
+            let similarity = (*similarity as f64).max(0.0) / best_cosine;
+            scores.entry(paper_id.clone()).or_default().1 = Some(similarity);
+        }
+    }
+
+    let mut ranked: Vec<RankedPaper> = scores
+        .into_iter()
+        .map(|(paper_id, (keyword_score, semantic_score))| RankedPaper {
+            score: (1.0 - semantic_weight) * keyword_score.unwrap_or(0.0)
+                + semantic_weight * semantic_score.unwrap_or(0.0),
+            paper_id,
+            keyword_score,
+            semantic_score,
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.paper_id.cmp(&b.paper_id)));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ranked: &[RankedPaper]) -> Vec<&str> {
+        ranked.iter().map(|r| r.paper_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_blend_weights_both_signals() {
+        let bm25 = vec![("keyword".to_string(), 8.0), ("both".to_string(), 4.0)];
+        let cosine = vec![("both".to_string(), 0.8), ("semantic".to_string(), 0.6), ("far".to_string(), -0.2)];
+
+        let ranked = blend(&bm25, &cosine, 0.5);
+        assert_eq!(ids(&ranked), vec!["both", "keyword", "semantic", "far"]);
+        assert_eq!(ranked[0].keyword_score, Some(0.5));
+        assert_eq!(ranked[0].semantic_score, Some(1.0));
+        assert!((ranked[0].score - 0.75).abs() < 1e-9);
+        assert_eq!(ranked[3].score, 0.0);
+
+        // Weight 1.0 ranks by similarity alone
+        assert_eq!(ids(&blend(&bm25, &cosine, 1.0))[..2], ["both", "semantic"]);
+        // Weight 0.0 ranks by BM25 alone
+        assert_eq!(ids(&blend(&bm25, &cosine, 0.0))[..2], ["keyword", "both"]);
+    }
+
+    #[test]
+    fn test_blend_without_keyword_matches() {
+        let ranked = blend(&[], &[("a".to_string(), 0.3)], 0.5);
+        assert_eq!(ranked, vec![RankedPaper {
+            paper_id: "a".to_string(),
+            score: 0.5,
+            keyword_score: None,
+            semantic_score: Some(1.0),
+        }]);
+    }
+}
@@ -0,0 +1,106 @@
+//! Built-in embedding model
+//!
+//! Words, word pairs and character trigrams are hashed into a fixed number of
+//! signed dimensions (the hashing trick), weighted by log term frequency and
+//! L2-normalized. Trigrams let related word forms ("decoding", "decoder")
+//! share dimensions. The model captures shared vocabulary rather than
+//! meaning; paraphrases need an API model.
+
+use std::collections::HashMap;
+
+/// ID stored with the vectors of this model; bump when features change
+pub const LOCAL_MODEL_ID: &str = "local-hash-v1";
+
+const DIMENSIONS: usize = 512;
+const WORD_WEIGHT: f32 = 1.0;
+const PAIR_WEIGHT: f32 = 0.5;
+const TRIGRAM_WEIGHT: f32 = 0.2;
+
+/// Words too common in abstracts to say anything about a paper
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "in", "is", "it", "its",
+    "of", "on", "or", "that", "the", "this", "to", "we", "with", "our", "which", "these",
+    "can", "has", "have", "was", "were", "using", "based", "paper", "propose", "show",
+];
+
+/// Hashed word and subword embedding model
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalEmbedder;
+
+impl LocalEmbedder {
+    /// Unit vector of `text`, all zeros when it has no words
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        let lowered = text.to_lowercase();
+        let words: Vec<&str> = lowered
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty() && !STOPWORDS.contains(w))
+            .collect();
+
+        let mut features: HashMap<String, (f32, u32)> = HashMap::new();
+        let mut add = |feature: String, weight: f32| {
+            features.entry(feature).or_insert((weight, 0)).1 += 1;
+        };
+        for word in &words {
+            add(format!("w:{}", word), WORD_WEIGHT);
+            let padded: Vec<char> = format!("<{}>", word).chars().collect();
+            for trigram in padded.windows(3) {
+                add(format!("t:{}", trigram.iter().collect::<String>()), TRIGRAM_WEIGHT);
+            }
+        }
+        for pair in words.windows(2) {
+            add(format!("p:{} {}", pair[0], pair[1]), PAIR_WEIGHT);
+        }
+
+        let mut vector = vec![0.0f32; DIMENSIONS];
+        for (feature, (weight, count)) in features {
+            let hash = fnv1a(feature.as_bytes());
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[(hash % DIMENSIONS as u64) as usize] += sign * weight * (1.0 + (count as f32).ln());
+        }
+        normalize(&mut vector);
+        vector
+    }
+}
+
+/// 64-bit FNV-1a, stable across builds unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Scale a vector to unit length, leaving a zero vector as is
+pub fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embeddings::cosine_similarity;
+
+    #[test]
+    fn test_embedding_is_unit_length_and_deterministic() {
+        let vector = LocalEmbedder.embed("Speculative decoding with a draft model");
+        assert_eq!(vector.len(), DIMENSIONS);
+        assert!((vector.iter().map(|v| v * v).sum::<f32>() - 1.0).abs() < 1e-5);
+        assert_eq!(vector, LocalEmbedder.embed("speculative DECODING, with a draft model."));
+        assert!(LocalEmbedder.embed("the of and").iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn test_related_texts_are_closer() {
+        let query = LocalEmbedder.embed("speculative decoding");
+        let related = LocalEmbedder.embed(
+            "Faster inference for language models: a draft model proposes tokens and the \
+             target model verifies them in parallel, as in speculative decoders.",
+        );
+        let unrelated = LocalEmbedder.embed(
+            "Diffusion policies for dexterous robotic manipulation from demonstrations.",
+        );
+        assert!(cosine_similarity(&query, &related) > cosine_similarity(&query, &unrelated) + 0.1);
+    }
+}
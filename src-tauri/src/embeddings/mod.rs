@@ -0,0 +1,249 @@
+//! Paper embeddings for semantic search
+//!
+//! Each paper gets a vector per embedding model, computed from its title,
+//! abstract and AI summary. `refresh_embeddings` brings the vectors up to
+//! date after papers are saved or summarized; searches only read them.
+//! `hybrid_search` ranks papers by blending BM25 with cosine similarity.
+
+pub mod api;
+pub mod hybrid;
+pub mod local;
+
+use crate::database::embeddings::EmbeddingSource;
+use crate::database::settings::SettingsError;
+use crate::database::{EmbeddingRepository, PaperRepository, SettingsRepository};
+use crate::llm_cache::LlmCache;
+use crate::models::{EmbeddingBackend, SearchHit, Settings};
+use api::{ApiEmbedder, DEFAULT_EMBEDDING_MODEL};
+use local::{LocalEmbedder, LOCAL_MODEL_ID};
+use sqlx::SqlitePool;
+use thiserror::Error;
+
+/// Candidates taken from each of the keyword and semantic rankings, per
+/// requested result
+const CANDIDATES_PER_RESULT: usize = 5;
+
+/// Held while a refresh runs, so concurrent refreshes do not embed the
+/// same papers twice
+static REFRESH: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[derive(Debug, Error)]
+pub enum EmbeddingError {
+    #[error("Embedding request failed: {0}")]
+    Request(String),
+
+    #[error("Invalid embedding response: {0}")]
+    Response(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Paper error: {0}")]
+    Paper(#[from] crate::database::PaperError),
+
+    #[error("Settings error: {0}")]
+    Settings(#[from] SettingsError),
+}
+
+/// Embedding model selected in the settings
+pub enum Embedder {
+    Local(LocalEmbedder),
+    Api(ApiEmbedder),
+}
+
+impl Embedder {
+    /// Embedder for the configured backend
+    ///
+    /// The API backend falls back to the OpenAI-compatible provider's base
+    /// URL and key.
+    pub fn from_settings(settings: &Settings) -> Result<Self, EmbeddingError> {
+        let config = settings.embeddings.clone().unwrap_or_default();
+        match config.backend {
+            EmbeddingBackend::Local => Ok(Embedder::Local(LocalEmbedder)),
+            EmbeddingBackend::Api => {
+                let base_url = config.base_url.or_else(|| settings.openai_base_url.clone());
+                let api_key = config.api_key.or_else(|| settings.openai_api_key.clone());
+                Ok(Embedder::Api(ApiEmbedder::new(
+                    base_url.as_deref(),
+                    config.model.as_deref().unwrap_or(DEFAULT_EMBEDDING_MODEL),
+                    api_key.as_deref().unwrap_or_default(),
+                )?))
+            }
+        }
+    }
+
+    /// Record the tokens of API requests in the LLM usage table
+    pub fn with_usage_tracking(self, pool: &SqlitePool) -> Self {
+        match self {
+            Embedder::Api(embedder) => Embedder::Api(embedder.with_usage_tracking(pool)),
+            local => local,
+        }
+    }
+
+    /// ID stored with each vector; vectors of different models are never compared
+    pub fn model_id(&self) -> String {
+        match self {
+            Embedder::Local(_) => LOCAL_MODEL_ID.to_string(),
+            Embedder::Api(embedder) => format!("api:{}", embedder.model()),
+        }
+    }
+
+    /// One vector per text, in order
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        match self {
+            Embedder::Local(embedder) => Ok(texts.iter().map(|t| embedder.embed(t)).collect()),
+            Embedder::Api(embedder) => embedder.embed(texts).await,
+        }
+    }
+}
+
+/// Text a paper is embedded from: title, abstract and AI summary
+pub fn embedding_text(title: &str, summary: Option<&str>, ai_summary: Option<&str>) -> String {
+    [Some(title), summary, ai_summary]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Cosine similarity of two vectors, 0 when either is zero or their
+/// lengths differ
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|y| y * y).sum::<f32>().sqrt();
+    if norm > 0.0 { dot / norm } else { 0.0 }
+}
+
+/// Embed the papers whose vector is missing or out of date
+///
+/// Vectors are saved after each request, so an interrupted update keeps
+/// what was done. Returns the number of papers embedded.
+pub async fn update_embeddings(pool: &SqlitePool, embedder: &Embedder) -> Result<usize, EmbeddingError> {
+    let model = embedder.model_id();
+    let repo = EmbeddingRepository::new(pool);
+
+    let stale: Vec<(EmbeddingSource, String, String)> = repo
+        .sources(&model)
+        .await?
+        .into_iter()
+        .filter_map(|source| {
+            let text = embedding_text(&source.title, source.summary.as_deref(), source.ai_summary.as_deref());
+            let hash = LlmCache::hash_prompt(&text);
+            (source.content_hash.as_deref() != Some(hash.as_str())).then_some((source, text, hash))
+        })
+        .collect();
+    if stale.is_empty() {
+        return Ok(0);
+    }
+
+    eprintln!("[update_embeddings] Embedding {} papers with {}", stale.len(), model);
+    for batch in stale.chunks(api::BATCH_SIZE) {
+        let texts: Vec<String> = batch.iter().map(|(_, text, _)| text.clone()).collect();
+        let vectors = embedder.embed(&texts).await?;
+        for ((source, _, hash), vector) in batch.iter().zip(&vectors) {
+            repo.upsert(&source.paper_id, &model, vector, hash).await?;
+        }
+    }
+    Ok(stale.len())
+}
+
+/// Embed stale papers with the model selected in the settings
+///
+/// Returns the number of papers embedded.
+pub async fn refresh_embeddings(pool: &SqlitePool) -> Result<usize, EmbeddingError> {
+    let _running = REFRESH.lock().await;
+    let settings = SettingsRepository::new(pool).get_all().await?;
+    let embedder = Embedder::from_settings(&settings)?.with_usage_tracking(pool);
+    update_embeddings(pool, &embedder).await
+}
+
+/// Run `refresh_embeddings` without waiting for it
+pub fn spawn_refresh(pool: SqlitePool) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = refresh_embeddings(&pool).await {
+            eprintln!("[refresh_embeddings] Failed: {}", e);
+        }
+    });
+}
+
+/// Papers most similar to a query, by cosine similarity alone
+/// (`semantic_weight` 1.0) or blended with BM25
+///
+/// Papers without a stored vector are only found by keyword.
+pub async fn hybrid_search(
+    pool: &SqlitePool,
+    embedder: &Embedder,
+    query: &str,
+    limit: usize,
+    semantic_weight: f64,
+) -> Result<Vec<SearchHit>, EmbeddingError> {
+    if query.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    let candidates = limit * CANDIDATES_PER_RESULT;
+    let query_vector = embedder
+        .embed(&[query.to_string()])
+        .await?
+        .pop()
+        .ok_or_else(|| EmbeddingError::Response("no embedding for the query".to_string()))?;
+
+    let mut cosine: Vec<(String, f32)> = EmbeddingRepository::new(pool)
+        .vectors(&embedder.model_id())
+        .await?
+        .into_iter()
+        .map(|(paper_id, vector)| {
+            let similarity = cosine_similarity(&query_vector, &vector);
+            (paper_id, similarity)
+        })
+        .collect();
+    cosine.sort_by(|a, b| b.1.total_cmp(&a.1));
+    cosine.truncate(candidates);
+
+    let papers = PaperRepository::new(pool);
+    let bm25 = if semantic_weight < 1.0 {
+        papers.bm25_scores(query, candidates as i32).await?
+    } else {
+        Vec::new()
+    };
+
+    let mut hits = Vec::new();
+    let ranked = hybrid::blend(&bm25, &cosine, semantic_weight)
+        .into_iter()
+        .filter(|ranked| ranked.score > 0.0)
+        .take(limit);
+    for ranked in ranked {
+        let paper = papers.get_by_id(&ranked.paper_id).await?;
+        hits.push(SearchHit {
+            paper,
+            score: ranked.score,
+            keyword_score: ranked.keyword_score,
+            semantic_score: ranked.semantic_score,
+        });
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedding_text_skips_missing_parts() {
+        assert_eq!(embedding_text("Title", Some("  Abstract "), None), "Title\n\nAbstract");
+        assert_eq!(embedding_text("Title", Some(""), Some("Summary")), "Title\n\nSummary");
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+    }
+}
@@ -0,0 +1,275 @@
+//! Relevance feedback
+//!
+//! Thumbs-up/down on papers feed back into relevance analysis in two ways:
+//! recent judgments are shown to the relevance model as few-shot examples,
+//! and, when enabled, topics with enough feedback get the threshold that best
+//! separates their thumbs-up from their thumbs-down. The same feedback
+//! measures the precision and recall of past decisions.
+//!
+//! Papers dropped below the threshold are never saved, so recall only
+//! counts the misses that made it into the library (e.g. fetched by ID).
+
+use crate::database::feedback::{JudgedPaper, RelevanceDecision};
+use crate::database::FeedbackRepository;
+use crate::models::{RelevanceExample, RelevanceMetrics, RelevanceReport, TopicConfig};
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, HashMap};
+
+/// Threshold reports are measured at by default, as in scheduled fetches
+pub const DEFAULT_MIN_RELEVANCE: i32 = 50;
+/// Judged papers a topic needs before its threshold is calibrated
+const MIN_CALIBRATION_FEEDBACK: usize = 5;
+/// Abstracts of examples are cut to keep the prompt short
+const EXAMPLE_SUMMARY_CHARS: usize = 400;
+
+/// Whether a decision accepted a paper for `topic` at `threshold`
+///
+/// Decisions without topics count for every topic.
+fn accepted(decision: &RelevanceDecision, topic: &str, threshold: i32) -> bool {
+    decision.score >= threshold && (decision.topics.is_empty() || decision.topics.iter().any(|t| t == topic))
+}
+
+/// Confusion counts of the judged papers with a decision
+fn evaluate<'a>(
+    judged: impl Iterator<Item = &'a JudgedPaper>,
+    threshold: impl Fn(&str) -> i32,
+) -> RelevanceMetrics {
+    let mut metrics = RelevanceMetrics::default();
+    for paper in judged {
+        let Some(decision) = &paper.decision else {
+            continue;
+        };
+        metrics.feedback_count += 1;
+        match (accepted(decision, &paper.topic, threshold(&paper.topic)), paper.relevant) {
+            (true, true) => metrics.true_positives += 1,
+            (true, false) => metrics.false_positives += 1,
+            (false, true) => metrics.false_negatives += 1,
+            (false, false) => metrics.true_negatives += 1,
+        }
+    }
+    let ratio = |hits: usize, misses: usize| (hits + misses > 0).then(|| hits as f64 / (hits + misses) as f64);
+    metrics.precision = ratio(metrics.true_positives, metrics.false_positives);
+    metrics.recall = ratio(metrics.true_positives, metrics.false_negatives);
+    metrics
+}
+
+fn f1(metrics: &RelevanceMetrics) -> f64 {
+    let tp = metrics.true_positives as f64;
+    let denominator = 2.0 * tp + (metrics.false_positives + metrics.false_negatives) as f64;
+    if denominator > 0.0 { 2.0 * tp / denominator } else { 0.0 }
+}
+
+/// Threshold with the best F1 score on a topic's feedback, ties going to
+/// the one closest to `min_relevance`
+///
+/// `None` until the topic has enough judged papers with both verdicts.
+pub fn calibrate_threshold(judged: &[&JudgedPaper], min_relevance: i32) -> Option<i32> {
+    let decided: Vec<&JudgedPaper> = judged.iter().copied().filter(|p| p.decision.is_some()).collect();
+    let has_both = decided.iter().any(|p| p.relevant) && decided.iter().any(|p| !p.relevant);
+    if decided.len() < MIN_CALIBRATION_FEEDBACK || !has_both {
+        return None;
+    }
+
+    let mut candidates: Vec<i32> = decided
+        .iter()
+        .filter_map(|p| p.decision.as_ref())
+        .flat_map(|d| [d.score, d.score + 1])
+        .chain([min_relevance])
+        .map(|t| t.clamp(0, 100))
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    candidates
+        .into_iter()
+        .map(|t| (t, f1(&evaluate(decided.iter().copied(), |_| t))))
+        .max_by(|(a, fa), (b, fb)| {
+            fa.total_cmp(fb)
+                .then_with(|| (b - min_relevance).abs().cmp(&(a - min_relevance).abs()))
+                .then_with(|| b.cmp(a))
+        })
+        .map(|(t, _)| t)
+}
+
+/// Thresholds of the topics with enough feedback to calibrate
+pub async fn calibrated_thresholds(
+    pool: &SqlitePool,
+    min_relevance: i32,
+) -> Result<HashMap<String, i32>, sqlx::Error> {
+    let judged = FeedbackRepository::new(pool).judged_papers().await?;
+    Ok(by_topic(&judged)
+        .into_iter()
+        .filter_map(|(topic, papers)| Some((topic.to_string(), calibrate_threshold(&papers, min_relevance)?)))
+        .collect())
+}
+
+fn by_topic(judged: &[JudgedPaper]) -> BTreeMap<&str, Vec<&JudgedPaper>> {
+    let mut topics: BTreeMap<&str, Vec<&JudgedPaper>> = BTreeMap::new();
+    for paper in judged {
+        topics.entry(paper.topic.as_str()).or_default().push(paper);
+    }
+    topics
+}
+
+/// Precision and recall of past decisions at `min_relevance`, overall and
+/// per topic
+pub fn build_report(judged: &[JudgedPaper], min_relevance: i32) -> RelevanceReport {
+    let topics = by_topic(judged)
+        .into_iter()
+        .map(|(topic, papers)| RelevanceMetrics {
+            topic: Some(topic.to_string()),
+            calibrated_threshold: calibrate_threshold(&papers, min_relevance),
+            ..evaluate(papers.into_iter(), |_| min_relevance)
+        })
+        .collect();
+    RelevanceReport {
+        min_relevance,
+        overall: evaluate(judged.iter(), |_| min_relevance),
+        topics,
+        without_decision: judged.iter().filter(|p| p.decision.is_none()).count(),
+    }
+}
+
+pub async fn relevance_report(pool: &SqlitePool, min_relevance: i32) -> Result<RelevanceReport, sqlx::Error> {
+    let judged = FeedbackRepository::new(pool).judged_papers().await?;
+    Ok(build_report(&judged, min_relevance))
+}
+
+/// Up to `count` of the most recent judgments on enabled topics, half of
+/// them thumbs-up where possible so the model sees both verdicts
+pub fn select_examples(judged: &[JudgedPaper], topics: &[TopicConfig], count: usize) -> Vec<RelevanceExample> {
+    let eligible: Vec<&JudgedPaper> = judged
+        .iter()
+        .filter(|p| topics.iter().any(|t| t.enabled && t.key == p.topic))
+        .collect();
+    let positives: Vec<&JudgedPaper> = eligible.iter().copied().filter(|p| p.relevant).collect();
+    let negatives: Vec<&JudgedPaper> = eligible.iter().copied().filter(|p| !p.relevant).collect();
+
+    let positive_count = positives.len().min(count.div_ceil(2).max(count.saturating_sub(negatives.len())));
+    let negative_count = negatives.len().min(count - positive_count);
+    positives
+        .into_iter()
+        .take(positive_count)
+        .chain(negatives.into_iter().take(negative_count))
+        .map(|p| RelevanceExample {
+            topic: p.topic.clone(),
+            title: p.title.clone(),
+            summary: p
+                .summary
+                .as_deref()
+                .unwrap_or_default()
+                .chars()
+                .take(EXAMPLE_SUMMARY_CHARS)
+                .collect(),
+            relevant: p.relevant,
+        })
+        .collect()
+}
+
+pub async fn relevance_examples(
+    pool: &SqlitePool,
+    topics: &[TopicConfig],
+    count: usize,
+) -> Result<Vec<RelevanceExample>, sqlx::Error> {
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    let judged = FeedbackRepository::new(pool).judged_papers().await?;
+    Ok(select_examples(&judged, topics, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn judged(topic: &str, relevant: bool, score: Option<i32>) -> JudgedPaper {
+        JudgedPaper {
+            topic: topic.to_string(),
+            relevant,
+            title: format!("{} {}", topic, score.unwrap_or(-1)),
+            summary: Some("Abstract".to_string()),
+            decision: score.map(|score| RelevanceDecision { score, topics: vec![topic.to_string()] }),
+        }
+    }
+
+    #[test]
+    fn test_report_counts_decisions_against_feedback() {
+        let mut other_topic = judged("rl", true, Some(90));
+        other_topic.decision.as_mut().unwrap().topics = vec!["cv".to_string()];
+        let papers = vec![
+            judged("rl", true, Some(80)),
+            judged("rl", false, Some(70)),
+            judged("rl", true, Some(40)),
+            judged("rl", false, Some(20)),
+            other_topic,
+            judged("rl", true, None),
+        ];
+
+        let report = build_report(&papers, 50);
+        assert_eq!(report.without_decision, 1);
+        let overall = &report.overall;
+        assert_eq!(overall.feedback_count, 5);
+        assert_eq!(
+            (overall.true_positives, overall.false_positives, overall.false_negatives, overall.true_negatives),
+            (1, 1, 2, 1)
+        );
+        assert_eq!(overall.precision, Some(0.5));
+        assert_eq!(overall.recall, Some(1.0 / 3.0));
+        assert_eq!(report.topics.len(), 1);
+        assert_eq!(report.topics[0].topic.as_deref(), Some("rl"));
+    }
+
+    #[test]
+    fn test_calibrate_threshold() {
+        let papers = [
+            judged("rl", true, Some(85)),
+            judged("rl", true, Some(75)),
+            judged("rl", false, Some(60)),
+            judged("rl", false, Some(55)),
+            judged("rl", false, Some(30)),
+        ];
+        let refs: Vec<&JudgedPaper> = papers.iter().collect();
+        // Anything in 61..=75 separates perfectly; 61 is closest to 50
+        assert_eq!(calibrate_threshold(&refs, 50), Some(61));
+        assert_eq!(calibrate_threshold(&refs, 70), Some(70));
+
+        // Too little feedback, or only one verdict
+        assert_eq!(calibrate_threshold(&refs[..4], 50), None);
+        let positives: Vec<&JudgedPaper> = refs.iter().copied().filter(|p| p.relevant).collect();
+        assert_eq!(calibrate_threshold(&positives, 50), None);
+    }
+
+    #[test]
+    fn test_select_examples_balances_verdicts() {
+        let topic = |key: &str, enabled: bool| TopicConfig {
+            key: key.to_string(),
+            label: key.to_string(),
+            description: String::new(),
+            color: String::new(),
+            enabled,
+            arxiv_categories: None,
+            max_papers_per_day: None,
+            deep_analysis_count: None,
+            quick_score_threshold: None,
+            keywords: None,
+            negative_keywords: None,
+            allowed_authors: None,
+            category_weights: None,
+            rule: None,
+        };
+        let topics = vec![topic("rl", true), topic("cv", false)];
+        let papers = vec![
+            judged("rl", true, Some(1)),
+            judged("rl", true, Some(2)),
+            judged("rl", true, Some(3)),
+            judged("cv", false, Some(4)),
+            judged("rl", false, Some(5)),
+        ];
+
+        let examples = select_examples(&papers, &topics, 4);
+        let titles: Vec<&str> = examples.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(titles, vec!["rl 1", "rl 2", "rl 3", "rl 5"]);
+        assert_eq!(select_examples(&papers, &topics, 2).len(), 2);
+        assert!(select_examples(&papers, &topics, 0).is_empty());
+    }
+}
@@ -3,15 +3,25 @@
 pub mod queue;
 
 use crate::arxiv::{self, ArxivEntry, FetchOptions as ArxivFetchOptions};
+use crate::authors::watchlist_hit;
+use crate::budget::BudgetGuard;
 use crate::analysis::AnalysisDepth;
-use crate::database::{PaperRepository, FetchHistoryRepository, FetchHistoryEntry, PaperSummary, SettingsRepository};
+use crate::analysis::executor::BlockExecutionMode;
+use crate::analysis::partial::BlockOutcome;
+use crate::bibliography::Reference;
+use crate::database::{
+    AnalysisRunRepository, CitationRepository, PaperRepository, FetchHistoryRepository, FetchHistoryEntry, PaperSummary,
+    SettingsRepository,
+};
 use crate::html_parser::extract_sections_by_name;
-use crate::latex_parser::extract_intro_conclusion;
-use crate::llm::{self, LlmClient, LlmError, RelevanceResult};
+use crate::llm::{self, LlmClient, LlmError, RelevanceResult, StructuredReply, UsageContext};
 use crate::llm_cache::LlmCache;
-use crate::models::{FetchOptions, FetchStatus, Paper, TopicConfig};
+use crate::models::{FetchOptions, FetchStatus, NewAnalysisRun, Paper, TopicConfig, WatchlistConfig, WatchlistMode};
+use crate::prefilter::{Prefilter, PrefilterVerdict};
+use crate::rules::{append_reason, RuleOutcome, TopicRules};
 use queue::{TaskQueue, QueuedTask};
 use sqlx::SqlitePool;
+use std::borrow::Cow;
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::Mutex;
@@ -55,6 +65,18 @@ pub enum FetchError {
 
     #[error("LLM error: {0}")]
     LlmError(llm::LlmError),
+
+    #[error("LLM budget exhausted: {0}")]
+    BudgetExceeded(String),
+
+    #[error("PDF error: {0}")]
+    PdfError(#[from] crate::pdf_parser::PdfParseError),
+
+    #[error("File error: {0}")]
+    IoError(String),
+
+    #[error("{0}")]
+    InvalidRule(String),
 }
 
 impl FetchError {
@@ -68,6 +90,10 @@ impl FetchError {
             FetchError::DatabaseError(_) => "database",
             FetchError::Cancelled => "cancelled",
             FetchError::LlmError(_) => "llm",
+            FetchError::BudgetExceeded(_) => "budget",
+            FetchError::PdfError(_) => "pdf",
+            FetchError::IoError(_) => "io",
+            FetchError::InvalidRule(_) => "rule",
         }
     }
 
@@ -81,6 +107,10 @@ impl FetchError {
             FetchError::LlmAuthError => false,
             FetchError::DatabaseError(_) => false,
             FetchError::Cancelled => false,
+            FetchError::BudgetExceeded(_) => false,
+            FetchError::PdfError(_) => false,
+            FetchError::IoError(_) => false,
+            FetchError::InvalidRule(_) => false,
         }
     }
 }
@@ -113,6 +143,7 @@ impl From<LlmError> for FetchError {
                 // Classify request errors as network errors
                 FetchError::NetworkError(e.to_string())
             }
+            LlmError::BudgetExceeded(reason) => FetchError::BudgetExceeded(reason.clone()),
             _ => FetchError::LlmError(err),
         }
     }
@@ -126,9 +157,30 @@ pub struct FetchResult {
     pub papers_saved: usize,
     pub papers_filtered: usize,
     pub papers_cache_hits: usize,
+    /// Papers decided by the local pre-filter instead of the LLM
+    pub llm_calls_saved: usize,
     pub papers_duplicates: usize,
     pub errors: Vec<String>,
     pub saved_papers: Vec<crate::database::PaperSummary>,
+    /// Why the fetch was stopped early by an exhausted budget
+    pub budget_exhausted: Option<String>,
+}
+
+impl FetchResult {
+    /// Skipped and locally decided papers so far for progress messages,
+    /// e.g. "2 duplicates, 1 cached"; empty when there are none
+    fn skip_summary(&self) -> String {
+        [
+            (self.papers_duplicates, "duplicates"),
+            (self.papers_cache_hits, "cached"),
+            (self.llm_calls_saved, "pre-filtered"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{} {}", count, label))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
 }
 
 /// Result of processing a single paper
@@ -138,6 +190,8 @@ struct ProcessedPaperResult {
     saved: bool,
     filtered: bool,
     cache_hit: bool,
+    /// Decided by the local pre-filter without calling the LLM
+    llm_call_saved: bool,
     duplicate: bool,
     paper_summary: Option<PaperSummary>,
 }
@@ -147,6 +201,78 @@ struct ProcessedPaperResult {
 struct RelevanceAnalysisWithCache {
     result: RelevanceResult,
     from_cache: bool,
+    /// Whether the local pre-filter accepted the paper, when it decided
+    prefiltered: Option<bool>,
+}
+
+/// What the local checks decided about a paper before relevance analysis
+struct PreRelevanceGate<'a> {
+    /// How the paper fared against the topic rules, `None` without rules
+    rule_outcome: Option<RuleOutcome>,
+    /// Topics the paper can still be assigned to
+    topics: Cow<'a, [TopicConfig]>,
+    /// The watchlist and the entry the paper matched
+    watched: Option<(&'a WatchlistConfig, String)>,
+    /// Pre-filter verdict, taken instead of an LLM relevance call
+    verdict: Option<PrefilterVerdict>,
+}
+
+/// Apply the topic rules, watchlist and pre-filter to a paper
+///
+/// Returns the rejection reason when the topic rules leave no enabled topic.
+/// Papers requested by ID skip the rules and the pre-filter, and watched
+/// papers skip the pre-filter.
+fn pre_relevance_gate<'a>(
+    entry: &ArxivEntry,
+    topics: &'a [TopicConfig],
+    options: &'a FetchOptions,
+    rules: Option<&TopicRules>,
+    prefilter: Option<&Prefilter>,
+) -> Result<PreRelevanceGate<'a>, String> {
+    let rule_outcome = rules.filter(|_| !options.fetch_by_id).map(|rules| rules.evaluate(entry));
+    if let Some(reason) = rule_outcome.as_ref().and_then(|outcome| outcome.rejection(topics)) {
+        return Err(reason);
+    }
+    let topics = match &rule_outcome {
+        Some(outcome) => Cow::Owned(outcome.allowed_topics(topics)),
+        None => Cow::Borrowed(topics),
+    };
+
+    let watched = options
+        .watchlist
+        .as_ref()
+        .and_then(|watchlist| Some((watchlist, watchlist_hit(watchlist, &entry.authors)?)));
+    let verdict = prefilter
+        .filter(|_| !options.fetch_by_id && watched.is_none())
+        .and_then(|p| p.verdict(entry, &topics));
+
+    Ok(PreRelevanceGate { rule_outcome, topics, watched, verdict })
+}
+
+impl PreRelevanceGate<'_> {
+    /// Relevance score after the watchlist boost
+    fn boost(&self, score: i32) -> i32 {
+        match &self.watched {
+            Some((watchlist, _)) if watchlist.mode == WatchlistMode::Boost => (score + watchlist.boost).min(100),
+            _ => score,
+        }
+    }
+
+    /// Whether the paper is kept whatever its relevance
+    fn bypasses_relevance(&self) -> bool {
+        self.watched.as_ref().is_some_and(|(w, _)| w.mode == WatchlistMode::Bypass)
+    }
+
+    /// Add the matched topic rules and watchlist entry to the filter reason
+    fn annotate(&self, paper: &mut Paper) {
+        if let Some(outcome) = &self.rule_outcome {
+            paper.filter_reason = append_reason(paper.filter_reason.take(), outcome.reason_for(&paper.topics));
+        }
+        if let Some((_, hit)) = &self.watched {
+            eprintln!("[PreRelevanceGate::annotate] {} is on the watchlist: {}", paper.id, hit);
+            paper.filter_reason = append_reason(paper.filter_reason.take(), Some(hit.clone()));
+        }
+    }
 }
 
 /// Content mode for fetching
@@ -156,6 +282,16 @@ pub enum ContentMode {
     Full,     // All sections
 }
 
+impl ContentMode {
+    /// Sections to extract, empty means all sections
+    fn section_names(self) -> &'static [&'static str] {
+        match self {
+            ContentMode::Standard => &["Introduction", "Conclusion"],
+            ContentMode::Full => &[],
+        }
+    }
+}
+
 /// Unified content fetching result
 #[derive(Debug, Clone)]
 pub struct FetchedContent {
@@ -163,10 +299,13 @@ pub struct FetchedContent {
     pub content: String,             // Markdown format
     pub estimated_tokens: usize,
     pub available_sections: Vec<String>,
+    pub references: Vec<Reference>,  // Bibliography, empty for PDFs
 }
 
 /// Fetch paper content with HTML-first fallback to LaTeX
-/// This is the main entry point for content fetching
+/// This is the main entry point for content fetching. Callers fall back to
+/// the LaTeX source (`latex_paper_content`), then the PDF (`fetch_pdf_content`),
+/// then the abstract.
 pub async fn fetch_paper_content(
     entry: &ArxivEntry,
     mode: ContentMode,
@@ -178,10 +317,7 @@ pub async fn fetch_paper_content(
     if let Ok(html) = html_result {
         eprintln!("[fetch_paper_content] HTML available, parsing...");
 
-        let section_names = match mode {
-            ContentMode::Standard => &["Introduction", "Conclusion"][..],
-            ContentMode::Full => &[], // Empty means all sections
-        };
+        let section_names = mode.section_names();
 
         match extract_sections_by_name(&html, section_names) {
             Ok(mut extracted) => {
@@ -193,26 +329,17 @@ pub async fn fetch_paper_content(
                     })?;
                 }
 
-                // Build content with abstract first, then sections
-                let mut content_parts = Vec::new();
-
-                // Add abstract if available (important context for LLM)
-                if let Some(abstract_text) = &extracted.r#abstract {
-                    content_parts.push(format!("## Abstract\n\n{}", abstract_text));
-                }
-
-                // Add sections with their HTML content
-                for section in &extracted.sections {
-                    content_parts.push(section.html_content.clone());
-                }
-
-                let content = content_parts.join("\n\n");
+                let content = render_content(
+                    extracted.r#abstract.as_deref(),
+                    extracted.sections.iter().map(|s| (s.level, s.title.as_str(), s.markdown.as_str())),
+                );
 
                 return Ok(FetchedContent {
                     source: "html".to_string(),
                     content,
                     estimated_tokens: extracted.estimated_tokens,
                     available_sections: extracted.available_sections,
+                    references: crate::bibliography::from_html(&html),
                 });
             }
             Err(e) => {
@@ -231,6 +358,177 @@ pub async fn fetch_paper_content(
     Err(FetchError::NetworkError("HTML not available, use LaTeX fallback".to_string()))
 }
 
+/// Content of a LaTeX source, selected like `fetch_paper_content` selects
+/// HTML sections
+///
+/// `root` is the root file the source was loaded from, next to which the
+/// `.bbl`/`.bib` files are looked up.
+pub fn latex_paper_content(root: &std::path::Path, latex: &str, mode: ContentMode) -> FetchedContent {
+    let document = crate::latex_parser::parse_document(latex);
+    let sections: Vec<(u8, &str, &str)> = document
+        .sections
+        .iter()
+        .map(|s| (s.level, s.title.as_str(), s.body.as_str()))
+        .collect();
+    FetchedContent {
+        references: crate::bibliography::from_latex_project(root, latex),
+        ..document_content("latex", document.r#abstract.as_deref(), &sections, latex, mode)
+    }
+}
+
+/// Content of the paper's PDF, the last source tried before the abstract
+///
+/// The PDF is downloaded to the configured PDF directory, so it is reused by
+/// "Download PDF" and later analyses.
+pub async fn fetch_pdf_content(
+    pool: &SqlitePool,
+    entry: &ArxivEntry,
+    mode: ContentMode,
+) -> Result<FetchedContent, FetchError> {
+    let settings = crate::database::SettingsRepository::new(pool).get_all().await.ok();
+    let download_dir = pdf_download_dir(settings.and_then(|s| s.pdf_download_path).as_deref())
+        .ok_or_else(|| FetchError::IoError("No PDF download directory available".to_string()))?;
+
+    eprintln!("[fetch_pdf_content] Downloading PDF to: {}", download_dir.display());
+    let pdf_path = entry.download_pdf(&download_dir).await?;
+    let bytes = std::fs::read(&pdf_path)
+        .map_err(|e| FetchError::IoError(format!("Failed to read PDF: {}", e)))?;
+
+    let document = crate::pdf_parser::parse_document(&bytes)?;
+    eprintln!("[fetch_pdf_content] Extracted {} chars in {} sections from {}",
+        document.text.len(), document.sections.len(), pdf_path);
+
+    let sections: Vec<(u8, &str, &str)> = document
+        .sections
+        .iter()
+        .map(|s| (s.level, s.title.as_str(), s.body.as_str()))
+        .collect();
+    Ok(document_content("pdf", document.r#abstract.as_deref(), &sections, &document.text, mode))
+}
+
+/// PDF fallback for the analysis paths, `None` (abstract only) on failure
+async fn pdf_fallback(pool: &SqlitePool, entry: &ArxivEntry, mode: ContentMode) -> Option<FetchedContent> {
+    match fetch_pdf_content(pool, entry, mode).await {
+        Ok(content) => Some(content),
+        Err(e) => {
+            eprintln!("[pdf_fallback] PDF text not available for {}: {}", entry.get_arxiv_id(), e);
+            None
+        }
+    }
+}
+
+/// PDF directory from settings (`~` expanded), `~/Documents/PaperFuse/pdfs` by default
+fn pdf_download_dir(configured: Option<&str>) -> Option<std::path::PathBuf> {
+    match configured {
+        Some(path) => match path.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+            None => Some(std::path::PathBuf::from(path)),
+        },
+        None => dirs::home_dir().map(|home| home.join("Documents").join("PaperFuse").join("pdfs")),
+    }
+}
+
+/// References of a paper, from the HTML bibliography or else the LaTeX source
+pub async fn fetch_references(entry: &ArxivEntry, latex_download_path: Option<&str>) -> Vec<Reference> {
+    if let Ok(html) = entry.download_html().await {
+        let references = crate::bibliography::from_html(&html);
+        if !references.is_empty() {
+            return references;
+        }
+    }
+
+    let Some(download_path) = latex_download_path else {
+        return Vec::new();
+    };
+    match entry.download_latex_source(std::path::Path::new(download_path)).await {
+        Ok(latex_path) => {
+            let root = std::path::Path::new(&latex_path);
+            match crate::latex_project::load_project(root) {
+                Ok(content) => crate::bibliography::from_latex_project(root, &content),
+                Err(e) => {
+                    eprintln!("[fetch_references] Failed to read LaTeX file: {}", e);
+                    Vec::new()
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("[fetch_references] Failed to download LaTeX: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Replace the stored references of a paper
+///
+/// Nothing is replaced when none were found, so references parsed from an
+/// earlier source are not lost to an analysis of the PDF or abstract.
+pub async fn store_references(pool: &SqlitePool, paper_id: &str, references: &[Reference]) {
+    if references.is_empty() {
+        return;
+    }
+    match CitationRepository::new(pool).replace_for_paper(paper_id, references).await {
+        Ok(()) => eprintln!("[store_references] Stored {} references of {}", references.len(), paper_id),
+        Err(e) => eprintln!("[store_references] Failed to store references of {}: {}", paper_id, e),
+    }
+}
+
+/// Select sections for `mode` the way HTML sections are selected, and
+/// render them. Sources without sections are used whole.
+fn document_content(
+    source: &str,
+    r#abstract: Option<&str>,
+    sections: &[(u8, &str, &str)],
+    whole: &str,
+    mode: ContentMode,
+) -> FetchedContent {
+    let names = mode.section_names();
+    let mut selected: Vec<&(u8, &str, &str)> = sections
+        .iter()
+        .filter(|(_, title, _)| names.is_empty() || crate::html_parser::matches_section_name(title, names))
+        .collect();
+    // If no sections matched in Standard mode, fall back to all sections
+    if matches!(mode, ContentMode::Standard) && selected.is_empty() {
+        eprintln!("[document_content] No matching {} sections found, using all sections", source);
+        selected = sections.iter().collect();
+    }
+
+    let content = if selected.is_empty() {
+        eprintln!("[document_content] No {} sections found, using the whole text", source);
+        whole.to_string()
+    } else {
+        render_content(r#abstract, selected.into_iter().copied())
+    };
+
+    FetchedContent {
+        source: source.to_string(),
+        estimated_tokens: crate::tokens::estimate_tokens(&content),
+        content,
+        available_sections: sections.iter().map(|(_, title, _)| title.to_string()).collect(),
+        references: Vec::new(),
+    }
+}
+
+/// Abstract first (important context for the LLM), then each section under a
+/// Markdown heading so the context budgeter can tell them apart (h1 and h2
+/// both become `##`)
+fn render_content<'a>(
+    r#abstract: Option<&str>,
+    sections: impl Iterator<Item = (u8, &'a str, &'a str)>,
+) -> String {
+    let mut content_parts = Vec::new();
+
+    if let Some(abstract_text) = r#abstract {
+        content_parts.push(format!("## Abstract\n\n{}", abstract_text));
+    }
+
+    for (level, title, body) in sections {
+        let heading = "#".repeat(level.max(2) as usize);
+        content_parts.push(format!("{} {}\n\n{}", heading, title, body));
+    }
+
+    content_parts.join("\n\n")
+}
+
 /// Helper to create idle fetch status
 fn idle_status() -> FetchStatus {
     FetchStatus {
@@ -243,6 +541,7 @@ fn idle_status() -> FetchStatus {
         papers_filtered: 0,
         papers_duplicates: 0,
         papers_cache_hits: 0,
+        llm_calls_saved: 0,
         errors: vec![],
         queue_size: 0,
         active_tasks: 0,
@@ -313,7 +612,7 @@ async fn perform_modular_analysis(
     let settings_repo = SettingsRepository::new(pool);
     let settings = settings_repo.get_all().await
         .map_err(|e| FetchError::DatabaseError(e.to_string()))?;
-    let analysis_config = settings.analysis_config.unwrap_or_default();
+    let analysis_config = settings.analysis_config.clone().unwrap_or_default();
 
     // Fix basic blocks mode - they should always be Both
     let analysis_config = crate::analysis::fix_basic_blocks_mode(analysis_config);
@@ -321,6 +620,39 @@ async fn perform_modular_analysis(
     eprintln!("[perform_modular_analysis] Using config with {} blocks for {:?} analysis",
         analysis_config.blocks.len(), depth);
 
+    // Fit the content into what the prompt leaves of the context window
+    let scaffold = crate::analysis::build_analysis_prompt(
+        &paper.title, &entry.summary, topics, None, language, &analysis_config, depth,
+    );
+    let fitted = match latex_content {
+        Some(content) => Some(
+            crate::analysis::chunked::prepare_content(
+                client,
+                &paper.id,
+                &paper.title,
+                depth,
+                &scaffold,
+                content,
+                settings.chunked_analysis.unwrap_or(true),
+            )
+            .await
+            .map_err(FetchError::LlmError)?,
+        ),
+        None => None,
+    };
+    let latex_content = fitted.as_ref().map(|fitted| fitted.text.as_str());
+
+    let blocks = crate::analysis::enabled_blocks(&analysis_config, depth);
+    let context = crate::analysis::PromptContext {
+        title: paper.title.clone(),
+        summary: entry.summary.clone(),
+        topics: topics.to_vec(),
+        latex_content: latex_content.map(str::to_string),
+        language: language.to_string(),
+        depth,
+    };
+    let structured = settings.structured_output.unwrap_or(true);
+
     // Build modular prompt
     let prompt = crate::analysis::build_analysis_prompt(
         &paper.title,
@@ -334,6 +666,25 @@ async fn perform_modular_analysis(
 
     eprintln!("[perform_modular_analysis] Generated prompt ({} chars)", prompt.chars().count());
 
+    // Recorded with the outcome in the analysis run history
+    let run = crate::analysis::history::new_run(client, &paper.id, depth, language, &prompt);
+
+    // Per-block mode: one call per block in dependency order (not cached)
+    let execution_mode = BlockExecutionMode::from_setting(settings.block_execution_mode.as_deref());
+    if execution_mode == BlockExecutionMode::PerBlock {
+        let outcome = crate::analysis::executor::run_per_block(client, Arc::new(context), &blocks, structured)
+            .await
+            .map_err(|e| {
+                eprintln!("[perform_modular_analysis] Per-block analysis failed: {}", e);
+                FetchError::LlmError(e)
+            })?;
+        return apply_block_outcome(pool, paper, &run, outcome).await;
+    }
+
+    // Ask for a schema-constrained reply unless the user turned it off
+    let output_schema = structured
+        .then(|| crate::analysis::build_analysis_output_schema(&analysis_config, depth));
+
     // Initialize cache
     let cache = LlmCache::new().map_err(|e| {
         eprintln!("[perform_modular_analysis] Failed to initialize cache: {}", e);
@@ -343,10 +694,11 @@ async fn perform_modular_analysis(
     // Get provider and model info for cache
     let provider = settings.llm_provider.clone();
     let provider_str = format!("{:?}", provider);
-    let model_for_cache = settings.glm_quick_model.clone()
-        .or(settings.claude_quick_model.clone())
-        .or(settings.glm_deep_model.clone())
-        .or(settings.claude_deep_model.clone());
+    let provider_config = crate::llm::get_backend(&provider)
+        .map(|backend| backend.provider_config(&settings))
+        .unwrap_or_default();
+    let model_for_cache = provider_config.quick_model
+        .or(provider_config.deep_model);
 
     let analysis_mode = depth.as_str().to_string();
 
@@ -358,7 +710,13 @@ async fn perform_modular_analysis(
         eprintln!("[perform_modular_analysis] No cache hit, calling LLM API...");
 
         // Send to LLM
-        let llm_response = client.send_chat_request(&prompt, depth.as_str()).await
+        let llm_response = match &output_schema {
+            Some(schema) => client.send_structured_request(&prompt, depth.as_str(), schema).await,
+            None => client
+                .send_chat_request(&prompt, depth.as_str())
+                .await
+                .map(|text| StructuredReply { text, schema_errors: Vec::new() }),
+        }
             .map_err(|e| {
                 tracing::error!(
                     paper_id = %paper.id,
@@ -371,11 +729,14 @@ async fn perform_modular_analysis(
                 FetchError::LlmError(e)
             })?;
 
-        // Save response to cache immediately (before parsing)
-        if let Err(e) = cache.save(
+        // Save response to cache immediately (before parsing), unless it does
+        // not match the schema and should be requested again next time
+        if !llm_response.schema_errors.is_empty() {
+            eprintln!("[perform_modular_analysis] Not caching reply with schema errors: {:?}", llm_response.schema_errors);
+        } else if let Err(e) = cache.save(
             &paper.id,
             &analysis_mode,
-            &llm_response,
+            &llm_response.text,
             &prompt,
             &provider_str,
             model_for_cache.as_deref(),
@@ -383,124 +744,67 @@ async fn perform_modular_analysis(
             eprintln!("[perform_modular_analysis] Warning: Failed to save to cache: {}", e);
         }
 
-        llm_response
+        llm_response.text
     };
 
-    // Clean and fix response
-    let cleaned = client.clean_response(&response);
-    let fixed = client.fix_json_formatting(&cleaned);
-
-    // Parse based on depth
-    match depth {
-        AnalysisDepth::Full => {
-            let result: crate::llm::FullAnalysisResult = serde_json::from_str(&fixed)
-                .map_err(|e| {
-                    tracing::error!(
-                        paper_id = %paper.id,
-                        arxiv_id = %paper.arxiv_id,
-                        analysis_mode = %analysis_mode,
-                        response_length = fixed.len(),
-                        error = %e,
-                        response_preview = %fixed.chars().take(2000).collect::<String>(),
-                        "Failed to parse full analysis JSON during batch fetch"
-                    );
-                    eprintln!("[perform_modular_analysis] Failed to parse full analysis: {}", e);
-                    FetchError::LlmError(crate::llm::LlmError::ParseError(e.to_string()))
-                })?;
-
-            // Update paper with results
-            paper.ai_summary = Some(result.ai_summary);
-            paper.key_insights = Some(result.key_insights);
-            paper.novelty_score = Some(result.novelty_score);
-            paper.novelty_reason = Some(result.novelty_reason);
-            paper.effectiveness_score = Some(result.effectiveness_score);
-            paper.effectiveness_reason = Some(result.effectiveness_reason);
-            paper.experiment_completeness_score = Some(result.experiment_completeness_score);
-            paper.experiment_completeness_reason = Some(result.experiment_completeness_reason);
-            paper.algorithm_flowchart = result.algorithm_flowchart;
-            paper.time_complexity = result.time_complexity;
-            paper.space_complexity = result.space_complexity;
-            paper.code_available = result.code_available;
-            paper.code_links = if result.code_links.is_empty() {
-                None
-            } else {
-                Some(result.code_links)
-            };
-            paper.engineering_notes = Some(result.engineering_notes);
-            paper.tags = result.suggested_tags;
-            paper.topics = result.suggested_topics;
-
-            // Handle related_papers
-            if !result.related_papers.is_empty() {
-                paper.related_papers = Some(result.related_papers.into_iter().map(|rp| crate::models::RelatedPaper {
-                    arxiv_id: rp.arxiv_id,
-                    title: rp.title,
-                    relationship: match rp.relationship {
-                        crate::llm::PaperRelationship::BuildsOn => crate::models::PaperRelationship::BuildsOn,
-                        crate::llm::PaperRelationship::ImprovesUpon => crate::models::PaperRelationship::ImprovesUpon,
-                        crate::llm::PaperRelationship::CompetingWith => crate::models::PaperRelationship::CompetingWith,
-                        crate::llm::PaperRelationship::CitedBy => crate::models::PaperRelationship::CitedBy,
-                        crate::llm::PaperRelationship::SimilarTo => crate::models::PaperRelationship::SimilarTo,
-                    },
-                    relevance_score: rp.relevance_score,
-                    reason: rp.reason,
-                }).collect());
-            }
-        }
-        AnalysisDepth::Standard => {
-            let result: crate::llm::StandardAnalysisResult = serde_json::from_str(&fixed)
-                .map_err(|e| {
-                    tracing::error!(
-                        paper_id = %paper.id,
-                        arxiv_id = %paper.arxiv_id,
-                        analysis_mode = %analysis_mode,
-                        response_length = fixed.len(),
-                        error = %e,
-                        response_preview = %fixed.chars().take(2000).collect::<String>(),
-                        "Failed to parse standard analysis JSON during batch fetch"
-                    );
-                    eprintln!("[perform_modular_analysis] Failed to parse standard analysis: {}", e);
-                    FetchError::LlmError(crate::llm::LlmError::ParseError(e.to_string()))
-                })?;
-
-            // Update paper with results
-            paper.ai_summary = Some(result.ai_summary);
-            paper.key_insights = Some(result.key_insights);
-            paper.novelty_score = Some(result.novelty_score);
-            paper.novelty_reason = Some(result.novelty_reason);
-            paper.effectiveness_score = Some(result.effectiveness_score);
-            paper.effectiveness_reason = Some(result.effectiveness_reason);
-            paper.code_available = result.code_available;
-            paper.code_links = if result.code_links.is_empty() {
-                None
-            } else {
-                Some(result.code_links)
-            };
-            paper.engineering_notes = Some(result.engineering_notes);
-            paper.tags = result.suggested_tags;
-            paper.topics = result.suggested_topics;
-
-            // Handle related_papers
-            if !result.related_papers.is_empty() {
-                paper.related_papers = Some(result.related_papers.into_iter().map(|rp| crate::models::RelatedPaper {
-                    arxiv_id: rp.arxiv_id,
-                    title: rp.title,
-                    relationship: match rp.relationship {
-                        crate::llm::PaperRelationship::BuildsOn => crate::models::PaperRelationship::BuildsOn,
-                        crate::llm::PaperRelationship::ImprovesUpon => crate::models::PaperRelationship::ImprovesUpon,
-                        crate::llm::PaperRelationship::CompetingWith => crate::models::PaperRelationship::CompetingWith,
-                        crate::llm::PaperRelationship::CitedBy => crate::models::PaperRelationship::CitedBy,
-                        crate::llm::PaperRelationship::SimilarTo => crate::models::PaperRelationship::SimilarTo,
-                    },
-                    relevance_score: rp.relevance_score,
-                    reason: rp.reason,
-                }).collect());
-            }
-        }
+    // Structured replies are used as-is; free-form ones go through the repair path
+    let fixed = client.prepare_json_response(&response);
+
+    // Validate block by block so one bad block doesn't discard the rest
+    let mut outcome = crate::analysis::partial::parse_reply(&fixed, &blocks);
+
+    if !outcome.failed.is_empty() {
+        tracing::warn!(
+            paper_id = %paper.id,
+            arxiv_id = %paper.arxiv_id,
+            analysis_mode = %analysis_mode,
+            failed_blocks = outcome.failed.len(),
+            response_preview = %fixed.chars().take(2000).collect::<String>(),
+            "Analysis reply has invalid blocks, re-requesting them"
+        );
+
+        let followup_prompt = crate::analysis::build_followup_prompt(&context, &outcome.failed);
+        crate::analysis::partial::retry_failed_blocks(
+            client,
+            &followup_prompt,
+            depth,
+            structured,
+            &mut outcome,
+        ).await;
     }
 
     // Note: Cache deletion should happen AFTER successful database save
     // Do not delete cache here - only delete after repo.save() succeeds
+    apply_block_outcome(pool, paper, &run, outcome).await
+}
+
+/// Save accepted blocks onto the paper, failing only if no block succeeded
+///
+/// The outcome is also recorded as a run in the analysis history.
+async fn apply_block_outcome(
+    pool: &SqlitePool,
+    paper: &mut Paper,
+    run: &NewAnalysisRun,
+    outcome: BlockOutcome,
+) -> Result<(), FetchError> {
+    if outcome.accepted.is_empty() {
+        let reasons = outcome.failure_summary();
+        eprintln!("[perform_modular_analysis] No analysis block could be parsed: {}", reasons);
+        return Err(FetchError::LlmError(crate::llm::LlmError::ParseError(reasons)));
+    }
+
+    if !outcome.failed.is_empty() {
+        eprintln!("[perform_modular_analysis] Saving partial analysis, failed blocks: {:?}",
+            outcome.failed.iter().map(|f| f.block_id.as_str()).collect::<Vec<_>>());
+    }
+    outcome.apply_to(paper);
+
+    if let Err(e) = AnalysisRunRepository::new(pool)
+        .record(run, &outcome.block_outputs(), &outcome.failed)
+        .await
+    {
+        eprintln!("[perform_modular_analysis] Warning: Failed to record analysis run: {}", e);
+    }
     Ok(())
 }
 
@@ -510,6 +814,8 @@ pub struct FetchManager {
     is_fetching: Arc<Mutex<bool>>,
     current_status: Arc<Mutex<FetchStatus>>,
     cancellation_token: Arc<Mutex<Option<CancellationToken>>>,
+    /// ArXiv host override (`None` uses the real ArXiv)
+    arxiv_base_url: Option<String>,
 }
 
 impl FetchManager {
@@ -519,9 +825,16 @@ impl FetchManager {
             is_fetching: Arc::new(Mutex::new(false)),
             current_status: Arc::new(Mutex::new(idle_status())),
             cancellation_token: Arc::new(Mutex::new(None)),
+            arxiv_base_url: None,
         }
     }
 
+    /// Fetch from a different ArXiv host (e.g. a local stand-in)
+    pub fn with_arxiv_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.arxiv_base_url = Some(base_url.into());
+        self
+    }
+
     /// Check if a fetch is currently in progress
     pub async fn is_fetching(&self) -> bool {
         *self.is_fetching.lock().await
@@ -577,6 +890,7 @@ impl FetchManager {
                 papers_filtered: 0,
                 papers_duplicates: 0,
                 papers_cache_hits: 0,
+                llm_calls_saved: 0,
                 errors: vec![],
                 queue_size: 0,
                 active_tasks: 0,
@@ -586,6 +900,12 @@ impl FetchManager {
             };
         }
 
+        // Refresh custom analysis blocks from the database; the headless
+        // scheduler never goes through the app's startup sync
+        if let Err(e) = crate::analysis::registry::sync_custom_blocks(&self.pool).await {
+            eprintln!("[FetchManager] Warning: Failed to load custom analysis blocks: {}", e);
+        }
+
         // Create fetch history entry
         let fetch_id = Uuid::new_v4().to_string();
         let started_at = chrono::Utc::now().to_rfc3339();
@@ -615,7 +935,7 @@ impl FetchManager {
         // (either normally or via panic/early return)
         eprintln!("[fetch_papers] About to call do_fetch");
         let result = self
-            .do_fetch(options, topics, event_emitter, token.clone())
+            .do_fetch(&fetch_id, options, topics, event_emitter, token.clone())
             .await;
 
         eprintln!("[fetch_papers] do_fetch returned with result: {:?}", result);
@@ -624,15 +944,17 @@ impl FetchManager {
         let completed_at = chrono::Utc::now().to_rfc3339();
         match &result {
             Ok(fetch_result) => {
+                // A fetch stopped by the budget failed, but keeps what it saved
+                let status = if fetch_result.budget_exhausted.is_some() { "failed" } else { "completed" };
                 if let Err(e) = history_repo.update(
                     &fetch_id,
                     &completed_at,
-                    "completed",
+                    status,
                     fetch_result.papers_fetched as i32,
                     fetch_result.papers_analyzed as i32,
                     fetch_result.papers_saved as i32,
                     fetch_result.papers_filtered as i32,
-                    None,
+                    fetch_result.budget_exhausted.as_deref(),
                     Some(fetch_result.saved_papers.clone()),
                 ).await {
                     eprintln!("[fetch_papers] Failed to update fetch history entry: {}", e);
@@ -665,8 +987,11 @@ impl FetchManager {
         entries: &[ArxivEntry],
         repo: &PaperRepository,
         llm_client: Option<&LlmClient>,
+        budget: &BudgetGuard,
         topics: &[TopicConfig],
         options: &FetchOptions,
+        prefilter: Option<Arc<Prefilter>>,
+        rules: Option<Arc<TopicRules>>,
         latex_download_path: Option<String>,
         max_concurrent: usize,
         event_emitter: &Option<Arc<dyn Fn(FetchStatus) + Send + Sync>>,
@@ -695,9 +1020,11 @@ impl FetchManager {
             papers_saved: 0,
             papers_filtered: 0,
             papers_cache_hits: 0,
+            llm_calls_saved: 0,
             papers_duplicates: 0,
             errors: vec![],
             saved_papers: vec![],
+            budget_exhausted: None,
         }));
 
         // Spawn worker tasks
@@ -709,8 +1036,11 @@ impl FetchManager {
             let result_clone = result.clone();
             let repo_clone = repo.clone();
             let llm_client_clone = llm_client.cloned();
+            let budget_clone = budget.clone();
             let topics_vec = topics.to_vec();
             let options_clone = options.clone();
+            let prefilter_clone = prefilter.clone();
+            let rules_clone = rules.clone();
             let latex_download_path_owned = latex_download_path.as_ref().map(|s| s.clone());
             let event_emitter_owned = event_emitter.as_ref().map(|emitter| Arc::clone(emitter));
             let cancel_token_clone = cancel_token.clone();
@@ -724,8 +1054,11 @@ impl FetchManager {
                     result_clone,
                     repo_clone,
                     llm_client_clone,
+                    budget_clone,
                     topics_vec,
                     options_clone,
+                    prefilter_clone,
+                    rules_clone,
                     latex_download_path_owned,
                     event_emitter_owned,
                     cancel_token_clone,
@@ -746,9 +1079,11 @@ impl FetchManager {
             papers_saved: 0,
             papers_filtered: 0,
             papers_cache_hits: 0,
+            llm_calls_saved: 0,
             papers_duplicates: 0,
             errors: vec![],
             saved_papers: vec![],
+            budget_exhausted: None,
         };
 
         for (i, worker) in workers.into_iter().enumerate() {
@@ -762,6 +1097,7 @@ impl FetchManager {
                     final_result.papers_saved += worker_result.papers_saved;
                     final_result.papers_filtered += worker_result.papers_filtered;
                     final_result.papers_cache_hits += worker_result.papers_cache_hits;
+                    final_result.llm_calls_saved += worker_result.llm_calls_saved;
                     final_result.papers_duplicates += worker_result.papers_duplicates;
                     final_result.errors.extend(worker_result.errors);
                     final_result.saved_papers.extend(worker_result.saved_papers);
@@ -788,8 +1124,11 @@ impl FetchManager {
         result: Arc<Mutex<FetchResult>>,
         repo: PaperRepository,
         llm_client: Option<LlmClient>,
+        budget: BudgetGuard,
         topics: Vec<TopicConfig>,
         options: FetchOptions,
+        prefilter: Option<Arc<Prefilter>>,
+        rules: Option<Arc<TopicRules>>,
         latex_download_path: Option<String>,
         event_emitter: Option<Arc<dyn Fn(FetchStatus) + Send + Sync>>,
         cancel_token: CancellationToken,
@@ -805,9 +1144,11 @@ impl FetchManager {
             papers_saved: 0,
             papers_filtered: 0,
             papers_cache_hits: 0,
+            llm_calls_saved: 0,
             papers_duplicates: 0,
             errors: vec![],
             saved_papers: vec![],
+            budget_exhausted: None,
         };
 
         eprintln!("[worker_{}] Entering main loop", worker_id);
@@ -851,6 +1192,7 @@ impl FetchManager {
                         status.papers_saved = result_guard.papers_saved;
                         status.papers_filtered = result_guard.papers_filtered;
                         status.papers_cache_hits = result_guard.papers_cache_hits;
+                        status.llm_calls_saved = result_guard.llm_calls_saved;
                         status.papers_duplicates = result_guard.papers_duplicates;
                         status.completed_tasks = total_processed;
 
@@ -867,18 +1209,7 @@ impl FetchManager {
                         status.progress = calculate_progress(total_processed, total_papers);
 
                         // Create detailed step message
-                        let skip_summary = vec![
-                            if result_guard.papers_duplicates > 0 {
-                                Some(format!("{} duplicates", result_guard.papers_duplicates))
-                            } else {
-                                None
-                            },
-                            if result_guard.papers_cache_hits > 0 {
-                                Some(format!("{} cached", result_guard.papers_cache_hits))
-                            } else {
-                                None
-                            },
-                        ].into_iter().flatten().collect::<Vec<_>>().join(", ");
+                        let skip_summary = result_guard.skip_summary();
 
                         status.current_step = if skip_summary.is_empty() {
                             format!("Analyzing {}/{} ({} workers active)", total_processed, total_papers, status.active_tasks)
@@ -896,9 +1227,12 @@ impl FetchManager {
                     match Self::process_paper_async(
                         &repo,
                         llm_client.as_ref(),
+                        &budget,
                         &task.entry,
                         &topics,
                         &options,
+                        prefilter.as_deref(),
+                        rules.as_deref(),
                         latex_download_path.as_deref(),
                         &pool,
                         worker_id,
@@ -916,6 +1250,9 @@ impl FetchManager {
                             if processed.cache_hit {
                                 local_result.papers_cache_hits += 1;
                             }
+                            if processed.llm_call_saved {
+                                local_result.llm_calls_saved += 1;
+                            }
                             if processed.duplicate {
                                 local_result.papers_duplicates += 1;
                             }
@@ -940,6 +1277,9 @@ impl FetchManager {
                                 if processed.cache_hit {
                                     result_guard.papers_cache_hits += 1;
                                 }
+                                if processed.llm_call_saved {
+                                    result_guard.llm_calls_saved += 1;
+                                }
                                 if processed.duplicate {
                                     result_guard.papers_duplicates += 1;
                                 }
@@ -967,6 +1307,7 @@ impl FetchManager {
                                 status.papers_saved = result_guard.papers_saved;
                                 status.papers_filtered = result_guard.papers_filtered;
                                 status.papers_cache_hits = result_guard.papers_cache_hits;
+                                status.llm_calls_saved = result_guard.llm_calls_saved;
                                 status.papers_duplicates = result_guard.papers_duplicates;
                                 status.completed_tasks = total_processed;
 
@@ -980,18 +1321,7 @@ impl FetchManager {
                                 status.progress = calculate_progress(total_processed, total_papers);
 
                                 // Create detailed step message
-                                let skip_summary = vec![
-                                    if result_guard.papers_duplicates > 0 {
-                                        Some(format!("{} duplicates", result_guard.papers_duplicates))
-                                    } else {
-                                        None
-                                    },
-                                    if result_guard.papers_cache_hits > 0 {
-                                        Some(format!("{} cached", result_guard.papers_cache_hits))
-                                    } else {
-                                        None
-                                    },
-                                ].into_iter().flatten().collect::<Vec<_>>().join(", ");
+                                let skip_summary = result_guard.skip_summary();
 
                                 status.current_step = if skip_summary.is_empty() {
                                     format!("Analyzed {}/{} ({} workers active)", total_processed, total_papers, status.active_tasks)
@@ -1004,6 +1334,11 @@ impl FetchManager {
                                 }
                             }
                         }
+                        Err(FetchError::BudgetExceeded(reason)) => {
+                            // Reported once for the whole fetch by do_fetch
+                            eprintln!("[worker_{}] Stopping: {}", worker_id, reason);
+                            break;
+                        }
                         Err(e) => {
                             let error_msg = format!("Worker {} failed to process '{}': {}", worker_id, task.entry.title, e);
                             eprintln!("[worker_{}] {}", worker_id, error_msg);
@@ -1032,9 +1367,12 @@ impl FetchManager {
     async fn process_paper_async(
         repo: &PaperRepository,
         llm_client: Option<&LlmClient>,
+        budget: &BudgetGuard,
         entry: &ArxivEntry,
         topics: &[TopicConfig],
         options: &FetchOptions,
+        prefilter: Option<&Prefilter>,
+        rules: Option<&TopicRules>,
         latex_download_path: Option<&str>,
         pool: &SqlitePool,
         worker_id: usize,
@@ -1049,11 +1387,30 @@ impl FetchManager {
                 saved: false,
                 filtered: false,
                 cache_hit: false,
+                llm_call_saved: false,
                 duplicate: true,
                 paper_summary: None,
             });
         }
 
+        // Topic rules are checked before any LLM call
+        let gate = match pre_relevance_gate(entry, topics, options, rules, prefilter) {
+            Ok(gate) => gate,
+            Err(reason) => {
+                eprintln!("[process_paper_async][worker_{}] Paper {} dropped by topic rules: {}", worker_id, arxiv_id, reason);
+                return Ok(ProcessedPaperResult {
+                    analyzed: false,
+                    saved: false,
+                    filtered: true,
+                    cache_hit: false,
+                    llm_call_saved: false,
+                    duplicate: false,
+                    paper_summary: None,
+                });
+            }
+        };
+        let topics = &gate.topics[..];
+
         // Convert to paper
         let arxiv_paper = entry.to_arxiv_paper()?;
         let mut paper = Paper::from_arxiv(arxiv_paper);
@@ -1069,36 +1426,53 @@ impl FetchManager {
 
         paper.topics = matched_topics;
 
+        // Token usage of this paper's calls is recorded under its ID and topics
+        let paper_client = llm_client.map(|client| client.for_paper(&paper.id, &paper.topics));
+        let llm_client = paper_client.as_ref();
+
         // LLM analysis (if available)
         let mut analyzed = false;
         let filtered = false;
         let mut cache_hit = false;
+        let mut llm_call_saved = false;
 
         if let Some(client) = llm_client {
             let language = options.language.as_deref().unwrap_or("en");
 
-            // Relevance analysis
-            let relevance_result = Self::perform_relevance_analysis_for_paper(
+            let mut relevance_result = Self::perform_relevance_analysis_for_paper(
                 client,
                 pool,
+                budget,
                 &arxiv_id,
                 &entry.title,
                 &entry.summary,
                 topics,
                 language,
+                gate.verdict.clone(),
             ).await?;
 
             // Check cache hit
             cache_hit = relevance_result.from_cache;
+            llm_call_saved = relevance_result.prefiltered.is_some();
 
             // Check relevance threshold - skip filtering in fetch-by-ID mode
             // since user explicitly requested these papers
-            if !options.fetch_by_id && relevance_result.result.score < options.min_relevance {
+            let decision_topics = if relevance_result.result.suggested_topics.is_empty() {
+                &paper.topics
+            } else {
+                &relevance_result.result.suggested_topics
+            };
+            relevance_result.result.score = gate.boost(relevance_result.result.score);
+            let relevant = gate.bypasses_relevance()
+                || relevance_result.prefiltered
+                    .unwrap_or_else(|| relevance_result.result.score >= options.min_relevance_for(decision_topics));
+            if !options.fetch_by_id && !relevant {
                 return Ok(ProcessedPaperResult {
                     analyzed: true,
                     saved: false,
                     filtered: true,
                     cache_hit,
+                    llm_call_saved,
                     duplicate: false,
                     paper_summary: None,
                 });
@@ -1116,10 +1490,12 @@ impl FetchManager {
                 paper.topics = relevance_result.result.suggested_topics;
             }
 
-            // Deep analysis (if enabled)
+            // Deep analysis (if enabled and the budget allows it)
             if options.deep_analysis {
                 let threshold = options.deep_analysis_threshold.unwrap_or(0);
-                if relevance_result.result.score >= threshold {
+                if relevance_result.result.score >= threshold && !budget.allows_deep_analysis().await {
+                    eprintln!("[process_paper_async][worker_{}] Budget low, skipping deep analysis of {}", worker_id, arxiv_id);
+                } else if relevance_result.result.score >= threshold {
                     Self::perform_deep_analysis_for_paper(
                         pool,
                         client,
@@ -1133,6 +1509,9 @@ impl FetchManager {
             }
         }
 
+        // The reason shows which topic rules and watchlist entries the paper matched
+        gate.annotate(&mut paper);
+
         // Save to database
         let was_inserted = repo.save_if_not_exists(&paper)
             .await
@@ -1163,6 +1542,7 @@ impl FetchManager {
             saved: was_inserted,
             filtered,
             cache_hit,
+            llm_call_saved,
             duplicate: false,
             paper_summary,
         })
@@ -1172,11 +1552,13 @@ impl FetchManager {
     async fn perform_relevance_analysis_for_paper(
         client: &LlmClient,
         pool: &SqlitePool,
+        budget: &BudgetGuard,
         arxiv_id: &str,
         title: &str,
         summary: &str,
         topics: &[TopicConfig],
         language: &str,
+        verdict: Option<PrefilterVerdict>,
     ) -> Result<RelevanceAnalysisWithCache, FetchError> {
         use crate::database::ClassificationCacheRepository;
 
@@ -1192,11 +1574,23 @@ impl FetchManager {
                     suggested_topics: cached_result.suggested_topics,
                 },
                 from_cache: true,
+                prefiltered: None,
+            });
+        }
+
+        // Clear-cut papers are decided locally, without a cache entry
+        if let Some(verdict) = verdict {
+            eprintln!("[perform_relevance_analysis_for_paper] Pre-filter decided {}: {:?}", arxiv_id, verdict);
+            return Ok(RelevanceAnalysisWithCache {
+                prefiltered: Some(verdict.accepted()),
+                result: verdict.into_relevance(),
+                from_cache: false,
             });
         }
 
         // Call LLM for relevance analysis
         eprintln!("[perform_relevance_analysis_for_paper] Cache miss for {}: calling LLM API", arxiv_id);
+        budget.check().await.map_err(FetchError::BudgetExceeded)?;
         let result = client.analyze_relevance(title, summary, topics, language).await?;
 
         // Save to cache
@@ -1215,6 +1609,7 @@ impl FetchManager {
         Ok(RelevanceAnalysisWithCache {
             result,
             from_cache: false,
+            prefiltered: None,
         })
     }
 
@@ -1268,6 +1663,7 @@ impl FetchManager {
             Ok(fetched) => {
                 eprintln!("[perform_full_analysis_impl] Using HTML content ({} bytes, ~{} tokens)",
                     fetched.content.len(), fetched.estimated_tokens);
+                store_references(pool, &paper.id, &fetched.references).await;
                 content_source = Some(fetched.source);
                 estimated_tokens = Some(fetched.estimated_tokens as i32);
                 available_sections = Some(fetched.available_sections);
@@ -1285,10 +1681,10 @@ impl FetchManager {
 
                     match entry.download_latex_source(path).await {
                         Ok(latex_path) => {
-                            match std::fs::read_to_string(&latex_path) {
+                            match crate::latex_project::load_project(std::path::Path::new(&latex_path)) {
                                 Ok(content) => {
                                     eprintln!("[perform_full_analysis_impl] LaTeX downloaded ({} bytes)", content.len());
-                                    Some(content)
+                                    Some(latex_paper_content(std::path::Path::new(&latex_path), &content, ContentMode::Full))
                                 }
                                 Err(e) => {
                                     eprintln!("[perform_full_analysis_impl] Failed to read LaTeX file: {}", e);
@@ -1306,6 +1702,15 @@ impl FetchManager {
                     None
                 };
 
+                // LaTeX unavailable: try the text of the PDF
+                let latex_content = match latex_content {
+                    Some(latex) => Some(latex),
+                    None => pdf_fallback(pool, entry, ContentMode::Full).await,
+                };
+                if let Some(latex) = &latex_content {
+                    store_references(pool, &paper.id, &latex.references).await;
+                }
+
                 // Full mode requires content - mark as incomplete if not available
                 let latex_content = match latex_content {
                     Some(latex) => latex,
@@ -1316,10 +1721,10 @@ impl FetchManager {
                     }
                 };
 
-                content_source = Some("latex".to_string());
-                estimated_tokens = Some((latex_content.len() / 4) as i32);
-                available_sections = None;
-                content_str = latex_content;
+                content_source = Some(latex_content.source);
+                estimated_tokens = Some(latex_content.estimated_tokens as i32);
+                available_sections = Some(latex_content.available_sections);
+                content_str = latex_content.content;
             }
         };
 
@@ -1373,6 +1778,7 @@ impl FetchManager {
             Ok(fetched) => {
                 eprintln!("[perform_standard_analysis_impl] Using HTML content ({} bytes, ~{} tokens)",
                     fetched.content.len(), fetched.estimated_tokens);
+                store_references(pool, &paper.id, &fetched.references).await;
                 content_source = Some(fetched.source);
                 estimated_tokens = Some(fetched.estimated_tokens as i32);
                 available_sections = Some(fetched.available_sections);
@@ -1390,10 +1796,10 @@ impl FetchManager {
 
                     match entry.download_latex_source(path).await {
                         Ok(latex_path) => {
-                            match std::fs::read_to_string(&latex_path) {
+                            match crate::latex_project::load_project(std::path::Path::new(&latex_path)) {
                                 Ok(content) => {
                                     eprintln!("[perform_standard_analysis_impl] LaTeX downloaded ({} bytes), extracting intro+conclusion", content.len());
-                                    Some(extract_intro_conclusion(&content))
+                                    Some(latex_paper_content(std::path::Path::new(&latex_path), &content, ContentMode::Standard))
                                 }
                                 Err(e) => {
                                     eprintln!("[perform_standard_analysis_impl] Failed to read LaTeX file: {}, falling back to abstract", e);
@@ -1411,17 +1817,24 @@ impl FetchManager {
                     None
                 };
 
-                // If LaTeX extraction failed, use abstract
+                // LaTeX unavailable: try the text of the PDF
+                let latex_content = match latex_content {
+                    Some(latex) => Some(latex),
+                    None => pdf_fallback(pool, entry, ContentMode::Standard).await,
+                };
+                if let Some(latex) = &latex_content {
+                    store_references(pool, &paper.id, &latex.references).await;
+                }
+
+                // If LaTeX and PDF extraction failed, use abstract
                 if latex_content.is_none() {
                     eprintln!("[perform_standard_analysis_impl] Using abstract as fallback content");
                 }
 
-                content_source = latex_content.as_ref().map(|_| "latex".to_string()).or_else(|| Some("abstract".to_string()));
-                // Estimate tokens based on content (LaTeX or abstract)
-                estimated_tokens = latex_content.as_ref()
-                    .map(|c| (c.len() / 4) as i32);
-                available_sections = None;
-                content_str = latex_content.unwrap_or_default();
+                content_source = latex_content.as_ref().map(|c| c.source.clone()).or_else(|| Some("abstract".to_string()));
+                estimated_tokens = latex_content.as_ref().map(|c| c.estimated_tokens as i32);
+                available_sections = latex_content.as_ref().map(|c| c.available_sections.clone());
+                content_str = latex_content.map(|c| c.content).unwrap_or_default();
             }
         };
 
@@ -1454,7 +1867,8 @@ impl FetchManager {
     /// Internal fetch implementation
     async fn do_fetch(
         &self,
-        options: FetchOptions,
+        fetch_id: &str,
+        mut options: FetchOptions,
         topics: Vec<TopicConfig>,
         event_emitter: Option<Arc<dyn Fn(FetchStatus) + Send + Sync>>,
         cancel_token: CancellationToken,
@@ -1462,6 +1876,10 @@ impl FetchManager {
         eprintln!("[FetchManager] Starting fetch with options: {:?}", options);
         eprintln!("[FetchManager] Topics ({} items): {:?}", topics.len(), topics);
 
+        // An invalid topic rule stops the fetch before anything is requested
+        let rules = TopicRules::compile(&topics).map_err(FetchError::InvalidRule)?;
+        let rules = (!rules.is_empty()).then(|| Arc::new(rules));
+
         // Step 1: Fetch from ArXiv
         self.update_status(
             "fetching",
@@ -1473,6 +1891,7 @@ impl FetchManager {
             0,
             0,
             0,
+            0,
             &event_emitter,
         )
         .await;
@@ -1510,6 +1929,7 @@ impl FetchManager {
                 0,
                 0,
                 0,
+                0,
                 &event_emitter,
             )
             .await;
@@ -1523,6 +1943,7 @@ impl FetchManager {
             date_to: options.date_to.as_ref().map(|d| d.replace("-", "")),
             fetch_by_id: options.fetch_by_id,
             arxiv_ids: options.arxiv_ids.clone(),
+            base_url: self.arxiv_base_url.clone(),
         };
 
         eprintln!("[FetchManager] Fetching from ArXiv with options: {:?}", arxiv_options);
@@ -1548,6 +1969,7 @@ impl FetchManager {
             0,
             0,
             0,
+            0,
             &event_emitter,
         )
         .await;
@@ -1559,9 +1981,11 @@ impl FetchManager {
                 papers_saved: 0,
                 papers_filtered: 0,
                 papers_cache_hits: 0,
+                llm_calls_saved: 0,
                 papers_duplicates: 0,
                 errors: vec![],
                 saved_papers: vec![],
+                budget_exhausted: None,
             });
         }
 
@@ -1587,6 +2011,7 @@ impl FetchManager {
                 0,
                 0,
                 0,
+                0,
                 &event_emitter,
             )
             .await;
@@ -1606,6 +2031,74 @@ impl FetchManager {
             }
         }
 
+        // Apply base URL for backends with a configurable endpoint (options take precedence over settings)
+        if let Some(client) = llm_client.take() {
+            let base_url = options.base_url.clone().or_else(|| {
+                let settings = settings.as_ref().ok()?;
+                crate::llm::get_backend(&options.llm_provider)
+                    .ok()
+                    .and_then(|backend| backend.provider_config(settings).base_url)
+            });
+            let context_window = settings.as_ref().ok().and_then(|settings| settings.context_window);
+            llm_client = Some(client.with_base_url(base_url).with_context_window(context_window));
+        }
+
+        // Every call of this fetch is recorded with its token usage
+        llm_client = llm_client.map(|client| {
+            client.with_usage_tracking(&self.pool, UsageContext {
+                fetch_id: Some(fetch_id.to_string()),
+                ..Default::default()
+            })
+        });
+
+        // Spending limits are checked against that usage before each call
+        let budget = BudgetGuard::new(
+            &self.pool,
+            settings.as_ref().ok().and_then(|settings| settings.budget.clone()),
+        );
+        llm_client = llm_client.map(|client| client.with_budget(budget.clone()));
+
+        // Thumbs-up/down on earlier papers guide this fetch's relevance decisions
+        let feedback_config = settings
+            .as_ref()
+            .ok()
+            .and_then(|settings| settings.relevance_feedback.clone())
+            .unwrap_or_default();
+        if let Some(client) = llm_client.take() {
+            let examples = crate::feedback::relevance_examples(&self.pool, &topics, feedback_config.few_shot_examples)
+                .await
+                .unwrap_or_else(|e| {
+                    eprintln!("[FetchManager] Failed to load relevance feedback examples: {}", e);
+                    Vec::new()
+                });
+            llm_client = Some(client.with_relevance_examples(examples));
+        }
+        if feedback_config.calibrate_thresholds {
+            match crate::feedback::calibrated_thresholds(&self.pool, options.min_relevance).await {
+                Ok(mut thresholds) => {
+                    eprintln!("[FetchManager] Calibrated topic thresholds: {:?}", thresholds);
+                    // Thresholds passed with the options win over calibrated ones
+                    thresholds.extend(options.topic_thresholds.take().unwrap_or_default());
+                    options.topic_thresholds = Some(thresholds);
+                }
+                Err(e) => eprintln!("[FetchManager] Failed to calibrate topic thresholds: {}", e),
+            }
+        }
+
+        // Papers of followed authors and labs are kept or boosted
+        if options.watchlist.is_none() {
+            options.watchlist = settings.as_ref().ok().and_then(|settings| settings.watchlist.clone());
+        }
+        options.watchlist = options.watchlist.take().filter(|watchlist| !watchlist.is_empty());
+
+        // Clear-cut papers are decided locally from the topics' keywords
+        let prefilter = settings
+            .as_ref()
+            .ok()
+            .and_then(|settings| settings.prefilter.clone())
+            .filter(|config| config.enabled && has_llm)
+            .map(|config| Arc::new(Prefilter::new(config, &topics, &entries)));
+
         // Step 4: Process each paper
         let mut result = FetchResult {
             papers_fetched: entries.len(),
@@ -1613,9 +2106,11 @@ impl FetchManager {
             papers_saved: 0,
             papers_filtered: 0,
             papers_cache_hits: 0,
+            llm_calls_saved: 0,
             papers_duplicates: 0,
             errors: vec![],
             saved_papers: vec![],
+            budget_exhausted: None,
         };
 
         let repo = PaperRepository::new(&self.pool);
@@ -1667,8 +2162,11 @@ impl FetchManager {
                 &entries,
                 &repo,
                 llm_client.as_ref(),
+                &budget,
                 &topics,
                 &options,
+                prefilter,
+                rules,
                 latex_download_path,
                 max_concurrent,
                 &event_emitter,
@@ -1691,18 +2189,7 @@ impl FetchManager {
                 let effective_total = effective_total.max(1);
 
                 // Create skip summary for display
-                let skip_summary = vec![
-                    if result.papers_duplicates > 0 {
-                        Some(format!("{} duplicates", result.papers_duplicates))
-                    } else {
-                        None
-                    },
-                    if result.papers_cache_hits > 0 {
-                        Some(format!("{} cached", result.papers_cache_hits))
-                    } else {
-                        None
-                    },
-                ].into_iter().flatten().collect::<Vec<_>>().join(", ");
+                let skip_summary = result.skip_summary();
 
                 let step_name = if has_llm {
                     if skip_summary.is_empty() {
@@ -1731,6 +2218,7 @@ impl FetchManager {
                     result.papers_saved,
                     result.papers_filtered,
                     result.papers_cache_hits,
+                    result.llm_calls_saved,
                     result.papers_duplicates,
                     &event_emitter,
                 )
@@ -1739,7 +2227,7 @@ impl FetchManager {
                 // Process the paper (with or without LLM analysis)
                 let current_duplicates = result.papers_duplicates;
                 match self
-                    .process_paper(&repo, llm_client.as_ref(), entry, &topics, &options, &mut result, has_llm, latex_download_path.as_deref())
+                    .process_paper(&repo, llm_client.as_ref(), &budget, entry, &topics, &options, prefilter.as_deref(), rules.as_deref(), &mut result, has_llm, latex_download_path.as_deref())
                     .await
                 {
                     Ok(_) => {
@@ -1763,18 +2251,7 @@ impl FetchManager {
 
                         // Show progress with total count (always shows X/10)
                         // Add summary of skips in parentheses
-                        let skip_summary = vec![
-                            if result.papers_duplicates > 0 {
-                                Some(format!("{} duplicates", result.papers_duplicates))
-                            } else {
-                                None
-                            },
-                            if result.papers_cache_hits > 0 {
-                                Some(format!("{} cached", result.papers_cache_hits))
-                            } else {
-                                None
-                            },
-                        ].into_iter().flatten().collect::<Vec<_>>().join(", ");
+                        let skip_summary = result.skip_summary();
 
                         let complete_step = if has_llm {
                             if skip_summary.is_empty() {
@@ -1799,11 +2276,16 @@ impl FetchManager {
                             result.papers_saved,
                             result.papers_filtered,
                             result.papers_cache_hits,
+                            result.llm_calls_saved,
                             result.papers_duplicates,
                             &event_emitter,
                         )
                         .await;
                     }
+                    Err(FetchError::BudgetExceeded(reason)) => {
+                        eprintln!("[FetchManager] Stopping: {}", reason);
+                        break;
+                    }
                     Err(e) => {
                         let error_msg = format!("Failed to process '{}': {}", entry.title, e);
                         result.errors.push(error_msg.clone());
@@ -1813,6 +2295,15 @@ impl FetchManager {
             }
         }
 
+        // Budget limits reached during the fetch are reported in its final status
+        let budget_messages = budget.messages();
+        if !budget_messages.is_empty() {
+            result.errors.extend(budget_messages.iter().cloned());
+            self.report_budget_limits(&result, &budget, budget_messages, &event_emitter).await;
+            result.budget_exhausted = budget.exhausted_reason().map(str::to_string);
+            return Ok(result);
+        }
+
         // Step 4: Complete
         eprintln!("[FetchManager] About to send completed status. Saved: {} papers", result.papers_saved);
         self.update_status(
@@ -1827,6 +2318,7 @@ impl FetchManager {
             result.papers_saved,
             result.papers_filtered,
             result.papers_cache_hits,
+            result.llm_calls_saved,
             result.papers_duplicates,
             &event_emitter,
         )
@@ -1841,9 +2333,12 @@ impl FetchManager {
         &self,
         repo: &PaperRepository,
         llm_client: Option<&LlmClient>,
+        budget: &BudgetGuard,
         entry: &ArxivEntry,
         topics: &[TopicConfig],
         options: &FetchOptions,
+        prefilter: Option<&Prefilter>,
+        rules: Option<&TopicRules>,
         result: &mut FetchResult,
         has_llm: bool,
         latex_download_path: Option<&str>,
@@ -1858,6 +2353,17 @@ impl FetchManager {
             return Ok(());
         }
 
+        // Topic rules are checked before any LLM call
+        let gate = match pre_relevance_gate(entry, topics, options, rules, prefilter) {
+            Ok(gate) => gate,
+            Err(reason) => {
+                eprintln!("[process_paper] Paper {} dropped by topic rules: {}", arxiv_id, reason);
+                result.papers_filtered += 1;
+                return Ok(());
+            }
+        };
+        let topics = &gate.topics[..];
+
         // Convert to paper
         let arxiv_paper = entry.to_arxiv_paper()?;
         let mut paper = Paper::from_arxiv(arxiv_paper);
@@ -1884,26 +2390,40 @@ impl FetchManager {
         eprintln!("[process_paper] Matched topics for {}: {:?}", arxiv_id, matched_topics);
         paper.topics = matched_topics;
 
+        // Token usage of this paper's calls is recorded under its ID and topics
+        let paper_client = llm_client.map(|client| client.for_paper(&paper.id, &paper.topics));
+        let llm_client = paper_client.as_ref();
+
         // If LLM is available, perform two-phase analysis
         if has_llm {
             if let Some(client) = llm_client {
                 let language = options.language.as_deref().unwrap_or("en");
 
                 // ========== PHASE 1: RELEVANCE ANALYSIS (always performed) ==========
-                let relevance = self.perform_relevance_analysis(
+                let analysis = self.perform_relevance_analysis(
                     client,
+                    budget,
                     &arxiv_id,
                     &entry.title,
                     &entry.summary,
                     topics,
                     result,
                     language,
+                    gate.verdict.clone(),
                 ).await?;
+                let mut relevance = analysis.result;
+                relevance.score = gate.boost(relevance.score);
 
                 // Check if paper meets relevance threshold
-                if relevance.score < options.min_relevance {
+                let decision_topics = if relevance.suggested_topics.is_empty() {
+                    &paper.topics
+                } else {
+                    &relevance.suggested_topics
+                };
+                let min_relevance = options.min_relevance_for(decision_topics);
+                if !gate.bypasses_relevance() && !analysis.prefiltered.unwrap_or(relevance.score >= min_relevance) {
                     eprintln!("[process_paper] Paper {} (score: {}) below relevance threshold ({}), filtering",
-                        arxiv_id, relevance.score, options.min_relevance);
+                        arxiv_id, relevance.score, min_relevance);
                     result.papers_filtered += 1;
                     return Ok(());
                 }
@@ -1923,7 +2443,9 @@ impl FetchManager {
                 // ========== PHASE 2: DEEP ANALYSIS (conditional) ==========
                 if options.deep_analysis {
                     let threshold = options.deep_analysis_threshold.unwrap_or(0);
-                    if relevance.score >= threshold {
+                    if relevance.score >= threshold && !budget.allows_deep_analysis().await {
+                        eprintln!("[process_paper] Budget low, skipping deep analysis of {}", arxiv_id);
+                    } else if relevance.score >= threshold {
                         eprintln!("[process_paper] Paper {} (score: {}) meets deep analysis threshold ({}), performing {} analysis",
                             arxiv_id, relevance.score, threshold,
                             options.analysis_mode.as_deref().unwrap_or("standard"));
@@ -1951,6 +2473,9 @@ impl FetchManager {
             paper.filter_reason = None;
         }
 
+        // The reason shows which topic rules and watchlist entries the paper matched
+        gate.annotate(&mut paper);
+
         // Save to database (atomic insert, skip if already exists)
         // This avoids TOCTOU race conditions
         let was_inserted = repo.save_if_not_exists(&paper)
@@ -1984,13 +2509,15 @@ impl FetchManager {
     async fn perform_relevance_analysis(
         &self,
         client: &LlmClient,
+        budget: &BudgetGuard,
         arxiv_id: &str,
         title: &str,
         summary: &str,
         topics: &[TopicConfig],
         result: &mut FetchResult,
         language: &str,
-    ) -> Result<RelevanceResult, FetchError> {
+        verdict: Option<PrefilterVerdict>,
+    ) -> Result<RelevanceAnalysisWithCache, FetchError> {
         use crate::database::ClassificationCacheRepository;
 
         // Check cache first
@@ -1999,16 +2526,32 @@ impl FetchManager {
             eprintln!("[perform_relevance_analysis] Cache hit for {}: using cached relevance", arxiv_id);
             result.papers_cache_hits += 1;
             // Convert cached ClassificationResult to RelevanceResult
-            return Ok(RelevanceResult {
-                score: cached_result.score,
-                reason: cached_result.reason,
-                suggested_tags: cached_result.suggested_tags,
-                suggested_topics: cached_result.suggested_topics,
+            return Ok(RelevanceAnalysisWithCache {
+                result: RelevanceResult {
+                    score: cached_result.score,
+                    reason: cached_result.reason,
+                    suggested_tags: cached_result.suggested_tags,
+                    suggested_topics: cached_result.suggested_topics,
+                },
+                from_cache: true,
+                prefiltered: None,
+            });
+        }
+
+        // Clear-cut papers are decided locally, without a cache entry
+        if let Some(verdict) = verdict {
+            eprintln!("[perform_relevance_analysis] Pre-filter decided {}: {:?}", arxiv_id, verdict);
+            result.llm_calls_saved += 1;
+            return Ok(RelevanceAnalysisWithCache {
+                prefiltered: Some(verdict.accepted()),
+                result: verdict.into_relevance(),
+                from_cache: false,
             });
         }
 
         // Call LLM for relevance analysis
         eprintln!("[perform_relevance_analysis] Cache miss for {}: calling LLM API", arxiv_id);
+        budget.check().await.map_err(FetchError::BudgetExceeded)?;
         let result = client.analyze_relevance(title, summary, topics, language).await?;
 
         // Save to cache (reuse ClassificationResult structure for cache compatibility)
@@ -2024,7 +2567,11 @@ impl FetchManager {
             eprintln!("[perform_relevance_analysis] Failed to save to cache: {}", e);
         }
 
-        Ok(result)
+        Ok(RelevanceAnalysisWithCache {
+            result,
+            from_cache: false,
+            prefiltered: None,
+        })
     }
 
     /// Perform Phase 2 deep analysis
@@ -2090,6 +2637,7 @@ impl FetchManager {
             Ok(fetched) => {
                 eprintln!("[perform_standard_analysis] Using HTML content ({} bytes, ~{} tokens)",
                     fetched.content.len(), fetched.estimated_tokens);
+                store_references(pool, &paper.id, &fetched.references).await;
                 content_source = Some(fetched.source);
                 estimated_tokens = Some(fetched.estimated_tokens as i32);
                 available_sections = Some(fetched.available_sections);
@@ -2101,10 +2649,10 @@ impl FetchManager {
 
                 let latex_content = match latex_result {
                     Some(Ok(latex_path)) => {
-                        match std::fs::read_to_string(&latex_path) {
+                        match crate::latex_project::load_project(std::path::Path::new(&latex_path)) {
                             Ok(content) => {
                                 eprintln!("[perform_standard_analysis] LaTeX downloaded ({} bytes), extracting intro+conclusion", content.len());
-                                Some(extract_intro_conclusion(&content))
+                                Some(latex_paper_content(std::path::Path::new(&latex_path), &content, ContentMode::Standard))
                             }
                             Err(e) => {
                                 eprintln!("[perform_standard_analysis] Failed to read LaTeX file: {}, falling back to abstract", e);
@@ -2119,17 +2667,24 @@ impl FetchManager {
                     None => None
                 };
 
-                // If LaTeX extraction failed, use abstract
+                // LaTeX unavailable: try the text of the PDF
+                let latex_content = match latex_content {
+                    Some(latex) => Some(latex),
+                    None => pdf_fallback(pool, entry, ContentMode::Standard).await,
+                };
+                if let Some(latex) = &latex_content {
+                    store_references(pool, &paper.id, &latex.references).await;
+                }
+
+                // If LaTeX and PDF extraction failed, use abstract
                 if latex_content.is_none() {
                     eprintln!("[perform_standard_analysis] Using abstract as fallback content");
                 }
 
-                content_source = Some("latex".to_string());
-                // Estimate tokens based on content (LaTeX or abstract)
-                estimated_tokens = latex_content.as_ref()
-                    .map(|c| (c.len() / 4) as i32);
-                available_sections = None;
-                content_str = latex_content.unwrap_or_default();
+                content_source = latex_content.as_ref().map(|c| c.source.clone()).or_else(|| Some("abstract".to_string()));
+                estimated_tokens = latex_content.as_ref().map(|c| c.estimated_tokens as i32);
+                available_sections = latex_content.as_ref().map(|c| c.available_sections.clone());
+                content_str = latex_content.map(|c| c.content).unwrap_or_default();
             }
         };
 
@@ -2185,6 +2740,7 @@ impl FetchManager {
             Ok(fetched) => {
                 eprintln!("[perform_full_analysis] Using HTML content ({} bytes, ~{} tokens)",
                     fetched.content.len(), fetched.estimated_tokens);
+                store_references(pool, &paper.id, &fetched.references).await;
                 content_source = Some(fetched.source);
                 estimated_tokens = Some(fetched.estimated_tokens as i32);
                 available_sections = Some(fetched.available_sections);
@@ -2196,10 +2752,10 @@ impl FetchManager {
 
                 let latex_content = match latex_result {
                     Some(Ok(latex_path)) => {
-                        match std::fs::read_to_string(&latex_path) {
+                        match crate::latex_project::load_project(std::path::Path::new(&latex_path)) {
                             Ok(content) => {
                                 eprintln!("[perform_full_analysis] LaTeX downloaded ({} bytes)", content.len());
-                                Some(content)
+                                Some(latex_paper_content(std::path::Path::new(&latex_path), &content, ContentMode::Full))
                             }
                             Err(e) => {
                                 eprintln!("[perform_full_analysis] Failed to read LaTeX file: {}", e);
@@ -2214,7 +2770,16 @@ impl FetchManager {
                     None => None
                 };
 
-                // Full mode requires LaTeX - mark as incomplete if not available
+                // LaTeX unavailable: try the text of the PDF
+                let latex_content = match latex_content {
+                    Some(latex) => Some(latex),
+                    None => pdf_fallback(pool, entry, ContentMode::Full).await,
+                };
+                if let Some(latex) = &latex_content {
+                    store_references(pool, &paper.id, &latex.references).await;
+                }
+
+                // Full mode requires content - mark as incomplete if not available
                 let latex_content = match latex_content {
                     Some(latex) => latex,
                     None => {
@@ -2224,10 +2789,10 @@ impl FetchManager {
                     }
                 };
 
-                content_source = Some("latex".to_string());
-                estimated_tokens = Some((latex_content.len() / 4) as i32);
-                available_sections = None;
-                content_str = latex_content;
+                content_source = Some(latex_content.source);
+                estimated_tokens = Some(latex_content.estimated_tokens as i32);
+                available_sections = Some(latex_content.available_sections);
+                content_str = latex_content.content;
             }
         };
 
@@ -2272,6 +2837,46 @@ impl FetchManager {
         Ok(())
     }
 
+    /// Emit the final status of a fetch that ran into a budget limit
+    ///
+    /// A fetch stopped by an exhausted budget ends in the "error" state; one
+    /// that only skipped deep analysis completes with the limit listed.
+    async fn report_budget_limits(
+        &self,
+        result: &FetchResult,
+        budget: &BudgetGuard,
+        messages: Vec<String>,
+        event_emitter: &Option<Arc<dyn Fn(FetchStatus) + Send + Sync>>,
+    ) {
+        let (status, step) = match budget.exhausted_reason() {
+            Some(reason) => ("error", format!("Stopped after saving {} papers: {}", result.papers_saved, reason)),
+            None => ("completed", format!("Completed! Saved {} papers, deep analysis paused by budget", result.papers_saved)),
+        };
+        let new_status = FetchStatus {
+            status: status.to_string(),
+            progress: 1.0,
+            current_step: step,
+            papers_found: result.papers_fetched,
+            papers_analyzed: result.papers_analyzed,
+            papers_saved: result.papers_saved,
+            papers_filtered: result.papers_filtered,
+            papers_duplicates: result.papers_duplicates,
+            papers_cache_hits: result.papers_cache_hits,
+            llm_calls_saved: result.llm_calls_saved,
+            errors: messages,
+            queue_size: 0,
+            active_tasks: 0,
+            completed_tasks: 0,
+            failed_tasks: 0,
+            async_mode: false,
+        };
+
+        *self.current_status.lock().await = new_status.clone();
+        if let Some(emitter) = event_emitter {
+            emitter(new_status);
+        }
+    }
+
     /// Update fetch status and emit event
     async fn update_status(
         &self,
@@ -2283,6 +2888,7 @@ impl FetchManager {
         saved: usize,
         filtered: usize,
         cache_hits: usize,
+        llm_calls_saved: usize,
         duplicates: usize,
         event_emitter: &Option<Arc<dyn Fn(FetchStatus) + Send + Sync>>,
     ) {
@@ -2296,6 +2902,7 @@ impl FetchManager {
             papers_filtered: filtered,
             papers_duplicates: duplicates,
             papers_cache_hits: cache_hits,
+            llm_calls_saved,
             errors: vec![],
             queue_size: 0,
             active_tasks: 0,
@@ -2326,9 +2933,11 @@ mod tests {
             papers_saved: 0,
             papers_filtered: 0,
             papers_cache_hits: 0,
+            llm_calls_saved: 0,
             papers_duplicates: 0,
             errors: vec![],
             saved_papers: vec![],
+            budget_exhausted: None,
         };
 
         assert_eq!(result.papers_fetched, 0);
@@ -2420,6 +3029,13 @@ mod tests {
         assert!(!err.is_retryable());
     }
 
+    #[test]
+    fn test_fetch_error_io_is_not_retryable() {
+        let err = FetchError::IoError("test error".to_string());
+        assert_eq!(err.error_type(), "io");
+        assert!(!err.is_retryable());
+    }
+
     #[test]
     fn test_fetch_error_from_llm_rate_limit() {
         let llm_err = llm::LlmError::ApiError("HTTP 429 rate limit exceeded".to_string());
@@ -2464,9 +3080,11 @@ mod tests {
             papers_saved: 5,
             papers_filtered: 3,
             papers_cache_hits: 1,
+            llm_calls_saved: 0,
             papers_duplicates: 1,
             errors: vec!["error 1".to_string()],
             saved_papers: vec![],
+            budget_exhausted: None,
         };
 
         assert_eq!(result.papers_fetched, 10);
@@ -2474,5 +3092,26 @@ mod tests {
         assert_eq!(result.papers_saved, 5);
         assert_eq!(result.papers_filtered, 3);
         assert_eq!(result.errors.len(), 1);
+        assert_eq!(result.skip_summary(), "1 duplicates, 1 cached");
+    }
+
+    #[test]
+    fn test_skip_summary_lists_pre_filtered_papers() {
+        let mut result = FetchResult {
+            papers_fetched: 4,
+            papers_analyzed: 0,
+            papers_saved: 0,
+            papers_filtered: 0,
+            papers_cache_hits: 0,
+            llm_calls_saved: 0,
+            papers_duplicates: 0,
+            errors: vec![],
+            saved_papers: vec![],
+            budget_exhausted: None,
+        };
+        assert_eq!(result.skip_summary(), "");
+
+        result.llm_calls_saved = 2;
+        assert_eq!(result.skip_summary(), "2 pre-filtered");
     }
 }
@@ -135,9 +135,11 @@ mod tests {
                     title: format!("Title {}", i),
                     summary: "Summary".to_string(),
                     published: chrono::Utc::now().to_rfc3339(),
+                    updated: chrono::Utc::now().to_rfc3339(),
                     categories: vec![],
                     authors: vec![],
                     links: vec![],
+                    base_url: None,
                 },
             };
 
@@ -165,7 +167,7 @@ mod tests {
         assert_eq!(queue.available_permits().await, 2);
 
         // Acquire first permit
-        let _permit1 = queue.semaphore.acquire().await.unwrap();
+        let _permit1 = queue.semaphore.clone().acquire_owned().await.unwrap();
         assert_eq!(queue.available_permits().await, 1);
 
         // Acquire second permit
@@ -1,6 +1,7 @@
 //! ArXiv HTML parser for extracting paper content
 //! Uses LaTeXML-generated HTML from arxiv.org/html/{id}
 
+use crate::tokens::estimate_tokens;
 use select::document::Document;
 use select::node::Node;
 use select::predicate::{Class, Name, Predicate};
@@ -39,6 +40,7 @@ pub struct Section {
     pub level: u8,           // 1-6 for h1-h6
     pub anchor: String,      // e.g., "S1", "S2"
     pub html_content: String, // Original HTML (preserves structure)
+    pub markdown: String,     // Content converted to Markdown (sent to the LLM)
 }
 
 /// Extracted content from ArXiv HTML
@@ -117,7 +119,7 @@ pub fn extract_sections_by_name(
 
         // Find the parent section and extract its HTML content
         // Manual traversal since select library doesn't have find_parent with predicate
-        let html_content = {
+        let (html_content, markdown) = {
             let mut current = heading.parent();
             let mut section_node = None;
 
@@ -134,9 +136,10 @@ pub fn extract_sections_by_name(
             }
 
             if let Some(section) = section_node {
-                extract_section_html(&section, &heading)
+                let children = section_children(&section, &heading);
+                (extract_section_html(&children), nodes_to_markdown(&children))
             } else {
-                String::new()
+                (String::new(), String::new())
             }
         };
 
@@ -145,6 +148,7 @@ pub fn extract_sections_by_name(
             level,
             anchor,
             html_content,
+            markdown,
         });
     }
 
@@ -161,18 +165,13 @@ pub fn extract_sections_by_name(
     } else {
         sections
             .into_iter()
-            .filter(|s| {
-                section_names
-                    .iter()
-                    .any(|name| s.title.to_lowercase().contains(&name.to_lowercase()))
-            })
+            .filter(|s| matches_section_name(&s.title, section_names))
             .collect()
     };
 
-    // Calculate total content length
-    let total_content: usize = filtered.iter().map(|s| s.html_content.len()).sum::<usize>();
-    let abstract_len = abstract_text.as_ref().map_or(0, |s| s.len());
-    let estimated_tokens = (total_content + abstract_len) / 4;
+    // Count tokens of the content sent for analysis
+    let estimated_tokens = filtered.iter().map(|s| estimate_tokens(&s.markdown)).sum::<usize>()
+        + abstract_text.as_deref().map_or(0, estimate_tokens);
 
     Ok(ExtractedContent {
         source: ContentSource::Html,
@@ -206,7 +205,7 @@ fn find_document_root(document: &Document) -> Node<'_> {
         .expect("Could not find any valid HTML element in document")
 }
 
-/// Extract abstract section
+/// Extract abstract section as Markdown
 fn extract_abstract(document: &Document) -> Option<String> {
     let abstract_node = document
         .find(Class("ltx_abstract"))
@@ -214,26 +213,19 @@ fn extract_abstract(document: &Document) -> Option<String> {
         .next()?;
 
     // Extract paragraphs from abstract
-    let mut content = String::new();
-
-    for para in abstract_node.find(Class("ltx_p")) {
-        let text = para.text().trim().to_string();
-        if !text.is_empty() {
-            content.push_str(&text);
-            content.push_str("\n\n");
-        }
-    }
+    let paragraphs: Vec<Node> = abstract_node.find(Class("ltx_p")).collect();
+    let content = nodes_to_markdown(&paragraphs);
 
     if content.trim().is_empty() {
         None
     } else {
-        Some(content.trim().to_string())
+        Some(content)
     }
 }
 
-/// Extract HTML content from a section (after the heading)
-fn extract_section_html(section_node: &Node, heading: &Node) -> String {
-    let mut html_parts = Vec::new();
+/// Children of a section node between its heading and the first nested section
+fn section_children<'a>(section_node: &Node<'a>, heading: &Node<'a>) -> Vec<Node<'a>> {
+    let mut children = Vec::new();
     let mut started = false;
 
     for child in section_node.children() {
@@ -259,14 +251,304 @@ fn extract_section_html(section_node: &Node, heading: &Node) -> String {
             break;
         }
 
+        children.push(child);
+    }
+
+    children
+}
+
+/// Extract HTML content from section children
+fn extract_section_html(children: &[Node]) -> String {
+    let html_parts: Vec<String> = children
+        .iter()
         // Keep the HTML as-is (preserves structure for LLM)
-        if !name.is_empty() {
-            // Include the element with its tags
-            html_parts.push(child.html());
+        .filter(|child| child.name().is_some())
+        .map(|child| child.html())
+        .collect();
+
+    html_parts.join("").trim().to_string()
+}
+
+/// Markdown of a sequence of sibling LaTeXML nodes
+///
+/// Math keeps its TeX source from `alttext`, tables become pipe tables, lists
+/// keep their nesting, figures are reduced to their captions and citations to
+/// their visible text. Images, scripts and navigation are dropped.
+fn nodes_to_markdown(nodes: &[Node]) -> String {
+    let mut blocks = Vec::new();
+    render_blocks(nodes.iter().copied(), &mut blocks);
+    blocks.join("\n\n")
+}
+
+/// Elements dropped with their content
+fn is_skipped(node: &Node) -> bool {
+    let class = node.attr("class").unwrap_or("");
+    matches!(
+        node.name(),
+        Some("script" | "style" | "img" | "svg" | "button" | "nav" | "head" | "annotation" | "annotation-xml")
+    ) || class.contains("ltx_note_mark")
+        || class.contains("ltx_tag_item")
+        || class.contains("ltx_tag_note")
+        || class.contains("ltx_page_navbar")
+}
+
+fn heading_level(node: &Node) -> Option<usize> {
+    match node.name()? {
+        "h1" => Some(1),
+        "h2" => Some(2),
+        "h3" => Some(3),
+        "h4" => Some(4),
+        "h5" => Some(5),
+        "h6" => Some(6),
+        _ => None,
+    }
+}
+
+/// Elements rendered as blocks of their own
+fn is_block(node: &Node) -> bool {
+    if node.name() == Some("math") {
+        return node.attr("display") == Some("block");
+    }
+    heading_level(node).is_some()
+        || matches!(
+            node.name(),
+            Some(
+                "p" | "div" | "section" | "article" | "figure" | "figcaption" | "table" | "ul" | "ol"
+                    | "li" | "blockquote" | "pre" | "dl" | "dd" | "dt" | "main"
+            )
+        )
+}
+
+/// Render nodes, grouping runs of inline nodes into paragraphs
+fn render_blocks<'a>(nodes: impl Iterator<Item = Node<'a>>, blocks: &mut Vec<String>) {
+    let mut inline = String::new();
+    for node in nodes {
+        if is_skipped(&node) {
+            continue;
+        }
+        if is_block(&node) {
+            push_paragraph(&mut inline, blocks);
+            render_block(&node, blocks);
+        } else {
+            inline.push_str(&render_inline(&node));
         }
     }
+    push_paragraph(&mut inline, blocks);
+}
 
-    html_parts.join("").trim().to_string()
+fn push_paragraph(inline: &mut String, blocks: &mut Vec<String>) {
+    let text = collapse_whitespace(inline);
+    if !text.is_empty() {
+        blocks.push(text);
+    }
+    inline.clear();
+}
+
+fn render_block(node: &Node, blocks: &mut Vec<String>) {
+    let class = node.attr("class").unwrap_or("");
+
+    if let Some(level) = heading_level(node) {
+        // Headings inside content never become section breaks (`##`)
+        let title = collapse_whitespace(&render_children_inline(node));
+        if !title.is_empty() {
+            blocks.push(format!("{} {}", "#".repeat(level.max(3)), title));
+        }
+        return;
+    }
+
+    match node.name() {
+        Some("math") => blocks.push(format!("$$\n{}\n$$", math_tex(node))),
+        Some("table") if class.contains("ltx_equation") => render_equations(node, blocks),
+        Some("table") => {
+            if let Some(table) = render_table(node) {
+                blocks.push(table);
+            }
+        }
+        Some("figure") => render_figure(node, blocks),
+        Some("figcaption") => {
+            let caption = render_caption(node);
+            if !caption.is_empty() {
+                blocks.push(caption);
+            }
+        }
+        Some("ul" | "ol") => {
+            let list = render_list(node);
+            if !list.is_empty() {
+                blocks.push(list);
+            }
+        }
+        Some("pre") => blocks.push(format!("```\n{}\n```", node.text().trim_end())),
+        _ if class.contains("ltx_listing") => blocks.push(format!("```\n{}\n```", node.text().trim())),
+        _ => render_blocks(node.children(), blocks),
+    }
+}
+
+/// TeX source of a math element
+fn math_tex(node: &Node) -> String {
+    node.attr("alttext")
+        .map(str::to_string)
+        .or_else(|| {
+            node.find(Name("annotation"))
+                .find(|a| a.attr("encoding") == Some("application/x-tex"))
+                .map(|a| a.text())
+        })
+        .unwrap_or_else(|| node.text())
+        .trim()
+        .to_string()
+}
+
+/// Numbered equations: one display block per row, with its number as `\tag`
+fn render_equations(node: &Node, blocks: &mut Vec<String>) {
+    for row in node.find(Name("tr")) {
+        let tex: Vec<String> = row.find(Name("math")).map(|math| math_tex(&math)).collect();
+        if tex.is_empty() {
+            continue;
+        }
+        let tag = row
+            .find(Class("ltx_tag_equation"))
+            .next()
+            .map(|tag| tag.text().trim().trim_start_matches('(').trim_end_matches(')').to_string())
+            .filter(|tag| !tag.is_empty());
+        let tag = tag.map(|tag| format!(" \\tag{{{}}}", tag)).unwrap_or_default();
+        blocks.push(format!("$$\n{}{}\n$$", tex.join(" "), tag));
+    }
+}
+
+/// Pipe table; the first row is used as the header
+fn render_table(node: &Node) -> Option<String> {
+    let rows: Vec<Vec<String>> = node
+        .find(Name("tr"))
+        .map(|row| {
+            row.children()
+                .filter(|cell| matches!(cell.name(), Some("td" | "th")))
+                .map(|cell| collapse_whitespace(&render_children_inline(&cell)).replace('|', "\\|"))
+                .collect::<Vec<_>>()
+        })
+        .filter(|cells| !cells.is_empty())
+        .collect();
+
+    let columns = rows.iter().map(Vec::len).max()?;
+    let line = |cells: &[String]| {
+        let mut padded = cells.to_vec();
+        padded.resize(columns, String::new());
+        format!("| {} |", padded.join(" | "))
+    };
+
+    let mut lines = vec![line(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+    lines.extend(rows[1..].iter().map(|row| line(row)));
+    Some(lines.join("\n"))
+}
+
+/// Figures and table floats: the caption first, then the content (tables,
+/// subfigures); images are dropped
+fn render_figure(node: &Node, blocks: &mut Vec<String>) {
+    let (captions, content): (Vec<Node>, Vec<Node>) =
+        node.children().partition(|child| child.name() == Some("figcaption"));
+
+    for caption in &captions {
+        let caption = render_caption(caption);
+        if !caption.is_empty() {
+            blocks.push(caption);
+        }
+    }
+    render_blocks(content.into_iter(), blocks);
+}
+
+/// "Figure 1: text" as "**Figure 1:** text"
+fn render_caption(node: &Node) -> String {
+    let tag = node
+        .find(Class("ltx_tag"))
+        .next()
+        .map(|tag| collapse_whitespace(&tag.text()))
+        .unwrap_or_default();
+    let text = collapse_whitespace(&render_children_inline(node));
+
+    match text.strip_prefix(&tag) {
+        Some(rest) if !tag.is_empty() => format!("**{}** {}", tag, rest.trim()),
+        _ => text,
+    }
+}
+
+/// Bulleted or numbered list; nested lists are indented
+fn render_list(node: &Node) -> String {
+    let ordered = node.name() == Some("ol");
+    let mut lines = Vec::new();
+
+    for (i, item) in node.children().filter(|child| child.name() == Some("li")).enumerate() {
+        let mut blocks = Vec::new();
+        render_blocks(item.children(), &mut blocks);
+        if blocks.is_empty() {
+            continue;
+        }
+
+        let marker = if ordered { format!("{}.", i + 1) } else { "-".to_string() };
+        let indent = " ".repeat(marker.len() + 1);
+        let body = blocks.join("\n").replace('\n', &format!("\n{}", indent));
+        lines.push(format!("{} {}", marker, body));
+    }
+
+    lines.join("\n")
+}
+
+fn render_children_inline(node: &Node) -> String {
+    node.children().map(|child| render_inline(&child)).collect()
+}
+
+/// Inline Markdown of a node (whitespace is collapsed by the caller)
+fn render_inline(node: &Node) -> String {
+    if let Some(text) = node.as_text() {
+        return text.to_string();
+    }
+    if is_skipped(node) {
+        return String::new();
+    }
+
+    let class = node.attr("class").unwrap_or("");
+    let wrap = |marker: &str| {
+        let inner = collapse_whitespace(&render_children_inline(node));
+        if inner.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}{}", marker, inner, marker)
+        }
+    };
+
+    match node.name() {
+        Some("math") if node.attr("display") == Some("block") => format!(" $${}$$ ", math_tex(node)),
+        Some("math") => format!("${}$", math_tex(node)),
+        Some("br") => " ".to_string(),
+        Some("em" | "i") => wrap("*"),
+        Some("strong" | "b") => wrap("**"),
+        Some("code" | "tt") => wrap("`"),
+        Some("a") => {
+            let text = collapse_whitespace(&render_children_inline(node));
+            match node.attr("href") {
+                // Links to the paper itself (citations, references) keep their text
+                Some(href) if href.starts_with("http") && text != href && !text.is_empty() => {
+                    format!("[{}]({})", text, href)
+                }
+                Some(href) if href.starts_with("http") && text.is_empty() => href.to_string(),
+                _ => text,
+            }
+        }
+        _ if class.contains("ltx_role_footnote") => {
+            let note = node
+                .find(Class("ltx_note_content"))
+                .next()
+                .map(|content| collapse_whitespace(&render_children_inline(&content)))
+                .unwrap_or_default();
+            if note.is_empty() { String::new() } else { format!(" [Footnote: {}]", note) }
+        }
+        _ if class.contains("ltx_font_bold") => wrap("**"),
+        _ if class.contains("ltx_font_italic") => wrap("*"),
+        _ if class.contains("ltx_font_typewriter") => wrap("`"),
+        _ => render_children_inline(node),
+    }
+}
+
+/// Collapse runs of whitespace into single spaces
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
 /// Check if heading should be skipped (in nav, etc.)
@@ -303,7 +585,7 @@ fn should_skip_heading(heading: &Node) -> bool {
 }
 
 /// Check if section should be skipped based on title
-fn should_skip_section(title: &str) -> bool {
+pub(crate) fn should_skip_section(title: &str) -> bool {
     let title_lower = title.to_lowercase();
 
     SKIP_SECTIONS.iter().any(|&skip| {
@@ -311,6 +593,12 @@ fn should_skip_section(title: &str) -> bool {
     })
 }
 
+/// Check if a section title contains any of the requested names (case-insensitive)
+pub(crate) fn matches_section_name(title: &str, section_names: &[&str]) -> bool {
+    let title_lower = title.to_lowercase();
+    section_names.iter().any(|name| title_lower.contains(&name.to_lowercase()))
+}
+
 /// Clean title by removing number prefix
 /// "1 Introduction" -> "Introduction"
 fn clean_title(full_title: &str) -> String {
@@ -358,6 +646,16 @@ pub fn extract_available_sections(html: &str) -> Vec<String> {
 mod tests {
     use super::*;
 
+    /// Markdown of the body of an HTML fragment
+    fn html_to_markdown(html: &str) -> String {
+        let document = Document::from(html);
+        let roots: Vec<Node> = document.find(Name("body")).take(1).collect();
+        match roots.first() {
+            Some(body) => nodes_to_markdown(&body.children().collect::<Vec<_>>()),
+            None => String::new(),
+        }
+    }
+
     #[test]
     fn test_clean_title() {
         assert_eq!(clean_title("1 Introduction"), "Introduction");
@@ -373,4 +671,87 @@ mod tests {
         assert!(!should_skip_section("Introduction"));
         assert!(!should_skip_section("Method"));
     }
+
+    #[test]
+    fn test_sections_are_converted_to_markdown() {
+        let html = include_str!("../tests/fixtures/html/latexml_features.html");
+        let extracted = extract_sections_by_name(html, &[]).unwrap();
+
+        assert_eq!(extracted.r#abstract.as_deref(), Some("We route each token to $k$ of $E$ experts."));
+        let titles: Vec<&str> = extracted.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Introduction", "Method"]);
+
+        let intro = &extracted.sections[0].markdown;
+        assert!(intro.contains("Sparse layers (Shazeer et al., 2017) scale capacity with a router $g(x)=\\mathrm{softmax}(Wx)$."));
+        assert!(intro.contains("[Footnote: Code: https://github.com/example/moe-router]"));
+        assert!(intro.contains("- a **balancing loss** for the router, and\n- an evaluation on *three* benchmarks."));
+        assert!(intro.contains("$$\n\\mathcal{L}_{bal}=E\\sum_{i=1}^{E}f_{i}P_{i} \\tag{1}\n$$"));
+        assert!(intro.contains("**Figure 1:** Expert load with and without the balancing loss."));
+        assert!(intro.contains(
+            "**Table 1:** Perplexity and training cost.\n\n\
+             | Router | PPL | Cost |\n\
+             | --- | --- | --- |\n\
+             | Top-$k$ | 12.1 | 1.00 |\n\
+             | Ours | **12.0** | 0.70 |"
+        ));
+        assert!(!intro.contains('<'));
+        assert!(!intro.contains("Refer to caption"));
+
+        let method = &extracted.sections[1].markdown;
+        assert!(method.ends_with("1. warm up the router with uniform assignment;\n2. add the balancing loss."));
+
+        // Markdown is much smaller than the LaTeXML markup it replaces
+        let html_len: usize = extracted.sections.iter().map(|s| s.html_content.len()).sum();
+        let markdown_len: usize = extracted.sections.iter().map(|s| s.markdown.len()).sum();
+        assert!(markdown_len * 3 < html_len);
+    }
+
+    /// Runs over every page in tests/fixtures/html, so a saved arXiv page
+    /// dropped there is checked without further changes
+    #[test]
+    fn test_fixture_pages_convert_cleanly() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/html");
+        let mut pages = 0;
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("html") {
+                continue;
+            }
+            pages += 1;
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let html = std::fs::read_to_string(&path).unwrap();
+            let extracted = extract_sections_by_name(&html, &[]).unwrap();
+
+            assert!(extracted.r#abstract.is_some(), "{}: no abstract", name);
+            assert!(!extracted.sections.is_empty(), "{}: no sections", name);
+            assert!(!extracted.available_sections.is_empty(), "{}: no section list", name);
+            for section in &extracted.sections {
+                let markdown = &section.markdown;
+                assert!(!markdown.trim().is_empty(), "{}: '{}' is empty", name, section.title);
+                assert!(!markdown.contains("</"), "{}: markup left in '{}'", name, section.title);
+                assert!(!markdown.contains("Refer to caption"), "{}: alt text in '{}'", name, section.title);
+                assert!(markdown.len() <= section.html_content.len(), "{}: '{}' grew", name, section.title);
+            }
+        }
+        assert!(pages >= 2);
+    }
+
+    #[test]
+    fn test_html_to_markdown() {
+        let html = r#"<p>See <a href="https://arxiv.org/abs/2401.00001">the paper</a>.</p>
+            <ul><li>outer<ul><li>inner</li></ul></li></ul>
+            <h4 class="ltx_title ltx_title_paragraph">Setup.</h4><p>Done.</p>"#;
+        assert_eq!(
+            html_to_markdown(html),
+            "See [the paper](https://arxiv.org/abs/2401.00001).\n\n- outer\n  - inner\n\n#### Setup.\n\nDone."
+        );
+
+        let page = include_str!("../tests/fixtures/html/2401.00001.html");
+        let extracted = extract_sections_by_name(page, &["Conclusion"]).unwrap();
+        assert_eq!(
+            extracted.sections[0].markdown,
+            "Load-balanced routing cuts training cost by 30% with no loss in perplexity. \
+             Code is available at https://github.com/example/moe-router."
+        );
+    }
 }
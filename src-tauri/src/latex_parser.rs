@@ -1,105 +1,188 @@
-//! LaTeX parser for extracting specific sections from academic papers
-//! Used for Standard mode analysis (Introduction + Conclusion only)
+//! LaTeX parser for academic papers
+//! Builds a section tree like `html_parser` (title, level, label, body) and
+//! collects the abstract. Figures and tables are reduced to their captions
+//! in the section bodies; references are parsed by `bibliography`.
 
+use crate::html_parser::should_skip_section;
+use crate::latex_project::read_group;
 use regex::Regex;
 
-/// Extract Introduction and Conclusion sections from LaTeX content
-/// This is used for Standard mode analysis where we only need these key sections
-pub fn extract_intro_conclusion(latex_content: &str) -> String {
-    let mut extracted = String::new();
+lazy_static::lazy_static! {
+    static ref HEADING_RE: Regex = Regex::new(
+        r"\\(chapter|section|subsection|subsubsection|paragraph|subparagraph)\*?\s*(?:\[[^\]]*\]\s*)?\{"
+    ).unwrap();
+    static ref MAIN_END_RE: Regex = Regex::new(
+        r"\\appendix\b|\\begin\{thebibliography\}|\\bibliography\{|\\printbibliography\b|\\end\{document\}"
+    ).unwrap();
+    static ref FLOAT_RE: Regex = Regex::new(r"\\begin\{(figure|table|wrapfigure|wraptable)(\*?)\}").unwrap();
+    static ref TABULAR_RE: Regex = Regex::new(r"(?s)\\begin\{(tabular[x*]?|tabulary)\}.*?\\end\{(tabular[x*]?|tabulary)\}").unwrap();
+    static ref LABEL_RE: Regex = Regex::new(r"\\label\s*\{([^}]*)\}").unwrap();
+    static ref COMMAND_GROUP_RE: Regex = Regex::new(r"\\[a-zA-Z]+\*?\s*(?:\[[^\]]*\])?\{([^{}]*)\}").unwrap();
+    static ref COMMAND_RE: Regex = Regex::new(r"\\[a-zA-Z]+\*?").unwrap();
+    static ref ESCAPED_RE: Regex = Regex::new(r"\\([&%#_{}])").unwrap();
+    static ref BLANK_LINES_RE: Regex = Regex::new(r"\n\s*\n\s*\n").unwrap();
+}
 
-    // Patterns for Introduction section (case-insensitive, with/without asterisk)
-    let intro_patterns = [
-        r"(?i)\\section\*?\{Introduction\}",
-        r"(?i)\\section\*?\{INTRODUCTION\}",
-    ];
+/// Section of a LaTeX document, without its subsections
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub title: String,
+    pub level: u8,             // 1 for \chapter, 2 for \section, ... 6 for \subparagraph
+    pub label: Option<String>, // e.g., "sec:intro"
+    pub body: String,          // LaTeX up to the next heading, floats reduced to captions
+}
 
-    // Patterns for Conclusion section
-    let conclusion_patterns = [
-        r"(?i)\\section\*?\{Conclusion\}",
-        r"(?i)\\section\*?\{Conclusions\}",
-        r"(?i)\\section\*?\{CONCLUSION\}",
-    ];
+/// Parsed LaTeX document
+#[derive(Debug, Clone, Default)]
+pub struct LatexDocument {
+    pub r#abstract: Option<String>,
+    pub sections: Vec<Section>,   // Main content sections, in document order
+}
 
-    // Extract Introduction
-    for pattern in &intro_patterns {
-        if let Ok(re) = Regex::new(pattern) {
-            if let Some(mat) = re.find(latex_content) {
-                let start = mat.start();
-                if let Some(end) = find_next_section(latex_content, start) {
-                    if start + end <= latex_content.len() {
-                        extracted.push_str(&latex_content[start..start + end]);
-                        break;
-                    }
-                }
-            }
+/// Parse a LaTeX document (ideally loaded with `latex_project::load_project`)
+///
+/// Sections come from `\chapter` down to `\subparagraph`. Sections after
+/// `\appendix` or the bibliography, and those `html_parser` skips
+/// (references, acknowledgments, ...), are left out.
+pub fn parse_document(latex: &str) -> LatexDocument {
+    let body_start = latex.find("\\begin{document}").map_or(0, |pos| pos + "\\begin{document}".len());
+    let body = &latex[body_start..];
+    let main_end = MAIN_END_RE.find(body).map_or(body.len(), |m| m.start());
+
+    let mut document = LatexDocument {
+        r#abstract: extract_abstract(latex),
+        ..Default::default()
+    };
+    let main = render_floats(&body[..main_end]);
+
+    let headings: Vec<(usize, usize, u8, &str)> = HEADING_RE
+        .captures_iter(&main)
+        .filter_map(|caps| {
+            let heading = caps.get(0)?;
+            let (title, end) = read_group(&main, heading.end() - 1)?;
+            Some((heading.start(), end, heading_level(&caps[1]), title))
+        })
+        .collect();
+
+    for (i, &(_, end, level, raw_title)) in headings.iter().enumerate() {
+        let next = headings.get(i + 1).map_or(main.len(), |(start, ..)| *start);
+        let mut text = main[end..next.max(end)].trim();
+
+        let mut label = LABEL_RE.captures(raw_title).map(|caps| caps[1].trim().to_string());
+        if let Some(caps) = LABEL_RE.captures(text).filter(|caps| caps.get(0).is_some_and(|m| m.start() == 0)) {
+            label = label.or_else(|| Some(caps[1].trim().to_string()));
+            text = text[caps.get(0).map_or(0, |m| m.end())..].trim_start();
         }
-    }
 
-    // Extract Conclusion
-    for pattern in &conclusion_patterns {
-        if let Ok(re) = Regex::new(pattern) {
-            if let Some(mat) = re.find(latex_content) {
-                let start = mat.start();
-                if let Some(end) = find_next_section(latex_content, start) {
-                    if start + end <= latex_content.len() {
-                        extracted.push_str(&latex_content[start..start + end]);
-                    } else {
-                        // No next section, take everything till end
-                        extracted.push_str(&latex_content[start..]);
-                    }
-                    break;
-                }
-            }
+        let title = plain_text(raw_title);
+        if title.is_empty() || should_skip_section(&title) {
+            continue;
         }
+        document.sections.push(Section { title, level, label, body: tidy(text) });
     }
 
-    // If extraction failed or is too short, fallback to first 15000 chars
-    if extracted.len() < 500 {
-        eprintln!("[latex_parser] Failed to extract intro/conclusion, using first 15000 chars");
-        return latex_content.chars().take(15000).collect();
+    document
+}
+
+/// Heading level, matching the HTML heading LaTeXML generates
+fn heading_level(command: &str) -> u8 {
+    match command {
+        "chapter" => 1,
+        "section" => 2,
+        "subsection" => 3,
+        "subsubsection" => 4,
+        "paragraph" => 5,
+        _ => 6,
     }
+}
 
-    // Limit to 15000 characters to avoid token limits
-    extracted.chars().take(15000).collect()
+/// Abstract from `\begin{abstract}` or `\abstract{...}`
+fn extract_abstract(latex: &str) -> Option<String> {
+    let text = if let Some((text, _)) = environment_body(latex, "abstract", 0) {
+        text
+    } else {
+        let pos = latex.find("\\abstract{")?;
+        read_group(latex, pos + "\\abstract".len())?.0
+    };
+    let text = tidy(text);
+    (!text.is_empty()).then_some(text)
 }
 
-/// Find the next section marker in LaTeX content
-/// Searches from the given start position and returns the offset from start
-fn find_next_section(full_content: &str, start_pos: usize) -> Option<usize> {
-    if start_pos >= full_content.len() {
-        return None;
+/// Text of the first `name` environment at or after `from`, and the position
+/// past its `\end`
+fn environment_body<'a>(latex: &'a str, name: &str, from: usize) -> Option<(&'a str, usize)> {
+    let begin = format!("\\begin{{{}}}", name);
+    let end = format!("\\end{{{}}}", name);
+    let start = from + latex[from..].find(&begin)? + begin.len();
+    let stop = start + latex[start..].find(&end)?;
+    Some((&latex[start..stop], stop + end.len()))
+}
+
+/// Replace figure and table environments with their captions, numbered like
+/// LaTeX numbers them. Tables keep their tabular.
+fn render_floats(latex: &str) -> String {
+    let mut out = String::with_capacity(latex.len());
+    let mut pos = 0;
+    let (mut figures, mut tables) = (0, 0);
+
+    while let Some(caps) = FLOAT_RE.captures_at(latex, pos) {
+        let Some(begin) = caps.get(0) else { break };
+        let name = format!("{}{}", &caps[1], &caps[2]);
+        let Some((inner, end)) = environment_body(latex, &name, begin.start()) else {
+            break;
+        };
+        out.push_str(&latex[pos..begin.start()]);
+        pos = end;
+
+        let caption = last_caption(inner).map(|caption| plain_text(&caption)).unwrap_or_default();
+        if caps[1].ends_with("table") {
+            tables += 1;
+            let tabular = TABULAR_RE.find(inner).map(|m| m.as_str().trim()).unwrap_or("");
+            out.push_str(&format!("\n**Table {}:** {}\n\n{}\n", tables, caption, tabular));
+        } else {
+            figures += 1;
+            out.push_str(&format!("\n**Figure {}:** {}\n", figures, caption));
+        }
     }
 
-    let remaining = &full_content[start_pos..];
-
-    let patterns = [
-        r"\\section\{", r"\\section\*\{",
-        r"\\subsection\{", r"\\subsection\*\{",
-        r"\\subsubsection\{", r"\\subsubsection\*\{",
-        r"\\section\{References", r"\\section\*\{References",
-        r"\\section\{Bibliography", r"\\section\*\{Bibliography",
-        r"\\section\{ACKNOWLEDGMENTS", r"\\section\*\{ACKNOWLEDGMENTS",
-        r"\\bibliography", r"\\bibliographystyle",
-        r"\\appendix",
-        r"\\end\{document\}",
-    ];
+    out.push_str(&latex[pos..]);
+    out
+}
 
-    let mut earliest_pos = None;
+/// The last `\caption` of a float; with subfigures that is the main caption
+fn last_caption(latex: &str) -> Option<String> {
+    let pos = latex.rfind("\\caption")?;
+    let mut after = pos + "\\caption".len();
+    let rest = &latex[after..];
+    if let Some(short) = rest.trim_start().strip_prefix('[') {
+        after = latex.len() - short.len() + short.find(']')? + 1;
+    }
+    read_group(latex, after).map(|(caption, _)| caption.to_string())
+}
 
-    for pattern in &patterns {
-        if let Ok(re) = Regex::new(pattern) {
-            if let Some(mat) = re.find(remaining) {
-                match earliest_pos {
-                    None => earliest_pos = Some(mat.start()),
-                    Some(current) if mat.start() < current => earliest_pos = Some(mat.start()),
-                    _ => {}
-                }
-            }
+/// Readable text of a title or caption: commands dropped, their arguments kept
+pub fn plain_text(latex: &str) -> String {
+    let mut text = LABEL_RE.replace_all(latex, "").to_string();
+    // Unwrap innermost groups first, e.g. \textbf{\emph{x}}
+    for _ in 0..4 {
+        let unwrapped = COMMAND_GROUP_RE.replace_all(&text, "$1").to_string();
+        if unwrapped == text {
+            break;
         }
+        text = unwrapped;
     }
+    let text = COMMAND_RE.replace_all(&text, "");
+    let text = ESCAPED_RE.replace_all(&text, "$1");
+    text.replace(['{', '}'], "")
+        .replace('~', " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-    earliest_pos
+/// Trim a body and collapse runs of blank lines
+fn tidy(latex: &str) -> String {
+    BLANK_LINES_RE.replace_all(latex.trim(), "\n\n").to_string()
 }
 
 /// Clean LaTeX content by removing comments, citations, and formatting commands
@@ -147,9 +230,7 @@ pub fn clean_latex(latex_content: &str) -> String {
     }
 
     // Clean up excessive whitespace
-    if let Ok(re) = Regex::new(r"\n\s*\n\s*\n") {
-        cleaned = re.replace_all(&cleaned, "\n\n").to_string();
-    }
+    cleaned = BLANK_LINES_RE.replace_all(&cleaned, "\n\n").to_string();
 
     cleaned
 }
@@ -158,21 +239,6 @@ pub fn clean_latex(latex_content: &str) -> String {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_extract_intro_conclusion() {
-        let latex = r"\section{Introduction}
-This is the introduction content with some text.
-\section{Method}
-Some method content here.
-\section{Conclusion}
-This is the conclusion.";
-
-        let extracted = extract_intro_conclusion(latex);
-        assert!(extracted.contains("Introduction"));
-        assert!(extracted.contains("Conclusion"));
-        assert!(!extracted.contains("Method"));
-    }
-
     #[test]
     fn test_clean_latex() {
         let latex = r"This is \textbf{bold} text with \cite{ref123}.";
@@ -183,9 +249,44 @@ This is the conclusion.";
     }
 
     #[test]
-    fn test_fallback_to_first_chars() {
-        let latex = "Some random content without sections";
-        let extracted = extract_intro_conclusion(latex);
-        assert!(extracted.contains("Some random content"));
+    fn test_parse_document() {
+        let latex = include_str!("../tests/fixtures/latex/routing.tex");
+        let document = parse_document(latex);
+
+        assert_eq!(document.r#abstract.as_deref(), Some("We route each token to $k$ of $E$ experts."));
+
+        let outline: Vec<(u8, &str, Option<&str>)> = document
+            .sections
+            .iter()
+            .map(|s| (s.level, s.title.as_str(), s.label.as_deref()))
+            .collect();
+        assert_eq!(
+            outline,
+            vec![
+                (2, "Introduction", Some("sec:intro")),
+                (2, "The Balanced Router", Some("sec:method")),
+                (3, "Warm-up", None),
+                (2, "Conclusion", None),
+            ]
+        );
+
+        let intro = &document.sections[0].body;
+        assert!(intro.starts_with("Sparse layers~\\citep{shazeer2017} scale capacity."));
+        assert!(intro.contains("**Figure 1:** Expert load with and without the balancing loss."));
+        assert!(intro.contains("**Table 1:** Perplexity & training cost.\n\n\\begin{tabular}{lcc}"));
+        assert!(!intro.contains("includegraphics"));
+        assert!(!document.sections[1].body.contains("Warm-up"));
+        // Appendix and bibliography are not sections
+        assert!(document.sections.iter().all(|s| s.title != "Proofs"));
+        assert!(!document.sections[3].body.contains("bibitem"));
+
+        // Display equations keep their TeX in the body
+        assert!(document.sections[1].body.contains("\\mathcal{L}_{bal} = E \\sum_{i=1}^{E} f_i P_i"));
+    }
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(plain_text("The \\textsc{Foo}~Model\\label{sec:foo}"), "The Foo Model");
+        assert_eq!(plain_text("\\textbf{\\emph{Fast}} \\& Small"), "Fast & Small");
     }
 }
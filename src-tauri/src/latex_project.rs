@@ -0,0 +1,477 @@
+//! LaTeX project loader for arXiv e-prints
+//! Finds the root file of a source tree, inlines `\input`/`\include` files and
+//! expands simple `\newcommand` macros, so multi-file papers are analyzed as
+//! one document
+
+use flate2::read::GzDecoder;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tar::Archive;
+
+/// Deepest chain of nested inputs that is followed
+const MAX_INPUT_DEPTH: usize = 16;
+
+/// Expansion passes, enough for macros defined in terms of other macros
+const MAX_EXPANSION_PASSES: usize = 4;
+
+/// File names commonly used for the root file
+const ROOT_NAMES: &[&str] = &["main", "paper", "ms", "article", "manuscript"];
+
+lazy_static::lazy_static! {
+    static ref INPUT_RE: Regex = Regex::new(r"\\(?:input|include|subfile)\s*\{([^}]+)\}").unwrap();
+    static ref DEFINITION_RE: Regex = Regex::new(r"\\(?:(?:re)?newcommand|providecommand)\*?|\\def\b").unwrap();
+}
+
+/// Unpack an arXiv e-print into `dir`
+///
+/// E-prints are a gzipped tarball, a single gzipped `.tex` file, or (rarely)
+/// an uncompressed tarball. A single file is saved as `{name}.tex`.
+pub fn unpack_eprint(bytes: &[u8], dir: &Path, name: &str) -> io::Result<()> {
+    let data = if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut data = Vec::new();
+        GzDecoder::new(bytes).read_to_end(&mut data)?;
+        data
+    } else {
+        bytes.to_vec()
+    };
+
+    fs::create_dir_all(dir)?;
+    if is_tar(&data) {
+        Archive::new(data.as_slice()).unpack(dir)
+    } else if data.starts_with(b"%PDF") {
+        Err(io::Error::new(io::ErrorKind::InvalidData, "e-print is a PDF, no LaTeX source available"))
+    } else {
+        fs::write(dir.join(format!("{}.tex", name.replace('/', "_"))), data)
+    }
+}
+
+/// Tar archives carry "ustar" at offset 257 of their first header
+fn is_tar(data: &[u8]) -> bool {
+    data.get(257..262) == Some(b"ustar".as_slice())
+}
+
+/// All files with `extension` below `dir`, sorted by path
+pub(crate) fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if path.is_dir() && !hidden {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == extension) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+pub(crate) fn read_lossy(path: &Path) -> io::Result<String> {
+    Ok(String::from_utf8_lossy(&fs::read(path)?).into_owned())
+}
+
+/// Find the root file of a LaTeX project
+///
+/// The root declares `\documentclass` and contains `\begin{document}`. With
+/// several candidates a common root name wins, then the shallowest and
+/// largest file. Without any, `main.tex`/`paper.tex` or the first `.tex`
+/// file is used.
+pub fn find_root_file(dir: &Path) -> Option<PathBuf> {
+    let files = files_with_extension(dir, "tex");
+    let is_root_name = |path: &Path| {
+        path.file_stem()
+            .map(|stem| ROOT_NAMES.contains(&stem.to_string_lossy().to_lowercase().as_str()))
+            .unwrap_or(false)
+    };
+
+    let roots: Vec<(&PathBuf, usize)> = files
+        .iter()
+        .filter_map(|path| {
+            let source = strip_comments(&read_lossy(path).ok()?);
+            (source.contains("\\documentclass") && source.contains("\\begin{document}"))
+                .then_some((path, source.len()))
+        })
+        .collect();
+
+    let best = roots.iter().min_by_key(|(path, len)| {
+        (!is_root_name(path), path.components().count(), std::cmp::Reverse(*len))
+    });
+    if let Some((path, _)) = best {
+        return Some((*path).clone());
+    }
+
+    files
+        .iter()
+        .find(|path| is_root_name(path))
+        .or_else(|| files.first())
+        .cloned()
+}
+
+/// Load a LaTeX project as one document
+///
+/// `path` is the root file or the project directory. Comments are removed,
+/// inputs are inlined recursively (each file at most once per chain, so
+/// cycles end) and simple macros are expanded.
+pub fn load_project(path: &Path) -> io::Result<String> {
+    let root = if path.is_dir() {
+        find_root_file(path).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("No .tex file in {}", path.display()))
+        })?
+    } else {
+        path.to_path_buf()
+    };
+
+    let project_dir = root.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let mut chain = vec![fs::canonicalize(&root)?];
+    let source = strip_comments(&read_lossy(&root)?);
+    let flattened = inline_inputs(&source, &project_dir, &project_dir, &mut chain);
+
+    eprintln!("[load_project] Loaded {} ({} bytes after inlining inputs)", root.display(), flattened.len());
+    Ok(expand_macros(&flattened))
+}
+
+/// Replace `\input`, `\include` and `\subfile` with the files they name
+///
+/// Paths are relative to the project root, as LaTeX resolves them, or else
+/// to the including file. Missing files and cycles leave the command as is.
+fn inline_inputs(source: &str, project_dir: &Path, file_dir: &Path, chain: &mut Vec<PathBuf>) -> String {
+    INPUT_RE
+        .replace_all(source, |caps: &regex::Captures| {
+            let name = caps[1].trim();
+            let Some(path) = resolve_input(name, project_dir, file_dir) else {
+                eprintln!("[load_project] Input not found: {}", name);
+                return caps[0].to_string();
+            };
+            let Ok(canonical) = fs::canonicalize(&path) else {
+                return caps[0].to_string();
+            };
+            if chain.contains(&canonical) || chain.len() > MAX_INPUT_DEPTH {
+                eprintln!("[load_project] Skipping recursive input: {}", name);
+                return String::new();
+            }
+            let Ok(content) = read_lossy(&path) else {
+                return caps[0].to_string();
+            };
+
+            chain.push(canonical);
+            let dir = path.parent().unwrap_or(project_dir).to_path_buf();
+            let inlined = inline_inputs(&strip_comments(&content), project_dir, &dir, chain);
+            chain.pop();
+            inlined
+        })
+        .into_owned()
+}
+
+fn resolve_input(name: &str, project_dir: &Path, file_dir: &Path) -> Option<PathBuf> {
+    let with_ext = if name.ends_with(".tex") { name.to_string() } else { format!("{}.tex", name) };
+    [project_dir, file_dir]
+        .iter()
+        .flat_map(|dir| [dir.join(&with_ext), dir.join(name)])
+        .find(|path| path.is_file())
+}
+
+/// Remove `%` comments; lines that only hold a comment are dropped
+pub fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    for line in source.lines() {
+        let mut escaped = false;
+        let mut end = line.len();
+        for (i, c) in line.char_indices() {
+            match c {
+                '\\' => escaped = !escaped,
+                '%' if !escaped => {
+                    end = i;
+                    break;
+                }
+                _ => escaped = false,
+            }
+        }
+        if end < line.len() && line[..end].trim().is_empty() {
+            continue;
+        }
+        out.push_str(&line[..end]);
+        out.push('\n');
+    }
+    out
+}
+
+/// A macro without optional arguments, e.g. `\newcommand{\R}{\mathbb{R}}`
+#[derive(Debug, Clone)]
+struct Macro {
+    params: usize,
+    body: String,
+}
+
+/// Expand simple macros and remove their definitions
+///
+/// Handles `\newcommand`, `\renewcommand`, `\providecommand` and `\def`
+/// with up to nine mandatory arguments. Macros with optional arguments or
+/// delimited parameters are left alone.
+pub fn expand_macros(source: &str) -> String {
+    let mut macros = HashMap::new();
+    let mut text = String::with_capacity(source.len());
+    let mut last = 0;
+
+    for found in DEFINITION_RE.find_iter(source) {
+        if found.start() < last {
+            continue;
+        }
+        let Some((name, definition, end)) = parse_definition(source, found.end()) else {
+            continue;
+        };
+        // Recursive macros would never finish expanding
+        if definition.body.contains(&format!("\\{}", name)) {
+            continue;
+        }
+        text.push_str(&source[last..found.start()]);
+        last = end;
+        macros.insert(name, definition);
+    }
+    text.push_str(&source[last..]);
+
+    if macros.is_empty() {
+        return text;
+    }
+    for _ in 0..MAX_EXPANSION_PASSES {
+        let expanded = expand_once(&text, &macros);
+        if expanded == text {
+            break;
+        }
+        text = expanded;
+    }
+    text
+}
+
+/// Parse `{\name}[n]{body}` or `\name{body}` after a definition command
+fn parse_definition(source: &str, pos: usize) -> Option<(String, Macro, usize)> {
+    let rest = &source[pos..];
+    let trimmed = rest.trim_start();
+    let mut pos = pos + rest.len() - trimmed.len();
+
+    let name = if trimmed.starts_with('{') {
+        let (inner, end) = read_group(source, pos)?;
+        pos = end;
+        command_name(inner.trim())?
+    } else {
+        let name = command_name(trimmed)?;
+        pos += name.len() + 1;
+        name
+    };
+
+    let mut params = 0;
+    let rest = source[pos..].trim_start();
+    if let Some(after) = rest.strip_prefix('[') {
+        let close = after.find(']')?;
+        params = after[..close].trim().parse().ok().filter(|n| *n <= 9)?;
+        pos = source.len() - after.len() + close + 1;
+        // An optional argument with a default value
+        if source[pos..].trim_start().starts_with('[') {
+            return None;
+        }
+    }
+
+    let (body, end) = read_group(source, pos)?;
+    Some((name, Macro { params, body: body.to_string() }, end))
+}
+
+/// Name of the control sequence at the start of `text`, without the backslash
+fn command_name(text: &str) -> Option<String> {
+    let name: String = text.strip_prefix('\\')?.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Contents of the brace group starting at `pos` (after whitespace), and the
+/// position just past its closing brace
+pub(crate) fn read_group(source: &str, pos: usize) -> Option<(&str, usize)> {
+    let rest = &source[pos..];
+    let start = pos + rest.len() - rest.trim_start().len();
+    if !source[start..].starts_with('{') {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in source[start..].char_indices() {
+        match c {
+            '\\' => {
+                escaped = !escaped;
+                continue;
+            }
+            '{' if !escaped => depth += 1,
+            '}' if !escaped => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((&source[start + 1..start + i], start + i + 1));
+                }
+            }
+            _ => {}
+        }
+        escaped = false;
+    }
+    None
+}
+
+/// One left-to-right pass replacing macro uses with their bodies
+fn expand_once(text: &str, macros: &HashMap<String, Macro>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+
+    while let Some(offset) = text[pos..].find('\\') {
+        let start = pos + offset;
+        out.push_str(&text[pos..start]);
+
+        let name: String = text[start + 1..].chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+        let Some(definition) = macros.get(&name).filter(|_| !name.is_empty()) else {
+            // Keep escaped characters such as `\\` or `\%` together
+            let len = 1 + name.len().max(text[start + 1..].chars().next().map_or(0, char::len_utf8));
+            out.push_str(&text[start..start + len]);
+            pos = start + len;
+            continue;
+        };
+
+        let mut end = start + 1 + name.len();
+        let mut args = Vec::with_capacity(definition.params);
+        for _ in 0..definition.params {
+            match read_argument(text, end) {
+                Some((arg, next)) => {
+                    args.push(arg);
+                    end = next;
+                }
+                None => break,
+            }
+        }
+        if args.len() < definition.params {
+            // Not enough arguments: leave the use untouched
+            out.push_str(&text[start..end]);
+            pos = end;
+            continue;
+        }
+        // `\name{}` is a common way to end a macro without arguments
+        if definition.params == 0 && text[end..].starts_with("{}") {
+            end += 2;
+        }
+
+        let mut body = definition.body.clone();
+        for (i, arg) in args.iter().enumerate() {
+            body = body.replace(&format!("#{}", i + 1), arg);
+        }
+        out.push_str(&body);
+        pos = end;
+    }
+
+    out.push_str(&text[pos..]);
+    out
+}
+
+/// A braced argument or a single character argument
+fn read_argument(text: &str, pos: usize) -> Option<(&str, usize)> {
+    if let Some(group) = read_group(text, pos) {
+        return Some(group);
+    }
+    let rest = &text[pos..];
+    let start = pos + rest.len() - rest.trim_start().len();
+    let c = text[start..].chars().next()?;
+    Some((&text[start..start + c.len_utf8()], start + c.len_utf8()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scratch directory removed when dropped
+    struct ScratchDir(PathBuf);
+
+    impl ScratchDir {
+        fn new(files: &[(&str, &str)]) -> Self {
+            let dir = std::env::temp_dir().join(format!("paperfuse-latex-{}", uuid::Uuid::new_v4()));
+            for (name, content) in files {
+                let path = dir.join(name);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, content).unwrap();
+            }
+            Self(dir)
+        }
+    }
+
+    impl Drop for ScratchDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_root_is_found_by_documentclass() {
+        let dir = ScratchDir::new(&[
+            ("appendix.tex", "\\section{Proofs}"),
+            ("src/neurips.tex", "\\documentclass{article}\n\\begin{document}\n\\input{sections/intro}\n\\end{document}"),
+            ("sections/intro.tex", "\\section{Introduction}"),
+        ]);
+        assert_eq!(find_root_file(&dir.0), Some(dir.0.join("src/neurips.tex")));
+    }
+
+    #[test]
+    fn test_inputs_are_inlined_recursively() {
+        let dir = ScratchDir::new(&[
+            ("main.tex", "\\documentclass{article}\n\\begin{document}\n\\input{sections/intro}\n\\include{conclusion.tex}\n\\end{document}\n"),
+            ("sections/intro.tex", "\\section{Introduction}\nIntro text. % a remark\n\\input{sections/setup}\n"),
+            ("sections/setup.tex", "% only a comment\nSetup text.\n\\input{main}\n"),
+            ("conclusion.tex", "\\section{Conclusion}\nDone at 100\\%.\n"),
+        ]);
+
+        let project = load_project(&dir.0).unwrap();
+        assert!(project.contains("\\section{Introduction}\nIntro text. \nSetup text.\n"));
+        assert!(project.contains("\\section{Conclusion}\nDone at 100\\%."));
+        assert!(!project.contains("\\input"));
+        assert!(!project.contains("remark"));
+        // The cycle back to main.tex is cut after one round
+        assert_eq!(project.matches("\\documentclass").count(), 1);
+    }
+
+    #[test]
+    fn test_simple_macros_are_expanded() {
+        let source = "\\newcommand{\\R}{\\mathbb{R}}\n\
+            \\newcommand\\method{RouteNet}\n\
+            \\renewcommand{\\norm}[1]{\\lVert #1 \\rVert}\n\
+            \\newcommand{\\opt}[2][x]{#1#2}\n\
+            \\def\\loss{\\mathcal{L}_{\\method}}\n\
+            We present \\method{}, minimizing $\\loss$ over $\\R^d$ with $\\norm{w}$ and \\opt{a}, 100\\%.";
+
+        let expanded = expand_macros(source);
+        assert!(expanded.ends_with(
+            "We present RouteNet, minimizing $\\mathcal{L}_{RouteNet}$ over $\\mathbb{R}^d$ with $\\lVert w \\rVert$ and \\opt{a}, 100\\%."
+        ));
+        assert!(!expanded.contains("\\newcommand{\\R}"));
+        // Macros with optional arguments keep their definition
+        assert!(expanded.contains("\\newcommand{\\opt}[2][x]{#1#2}"));
+        // \Rbar is not \R
+        assert_eq!(expand_macros("\\newcommand{\\R}{X}\\Rbar \\R"), "\\Rbar X");
+    }
+
+    #[test]
+    fn test_unpack_single_file_eprint() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"\\documentclass{article}\\begin{document}Hi\\end{document}").unwrap();
+        let bytes = encoder.finish().unwrap();
+
+        let dir = ScratchDir::new(&[]);
+        unpack_eprint(&bytes, &dir.0, "2401.00001").unwrap();
+        let root = find_root_file(&dir.0).unwrap();
+        assert_eq!(root, dir.0.join("2401.00001.tex"));
+        assert!(load_project(&root).unwrap().contains("Hi"));
+
+        assert!(unpack_eprint(b"%PDF-1.5", &dir.0, "2401.00002").is_err());
+    }
+}
@@ -6,12 +6,23 @@ mod models;
 mod database;
 mod commands;
 mod arxiv;
+mod authors;
 mod llm;
 mod llm_cache;
+mod budget;
+mod tokens;
 mod retry;
 mod fetch;
 mod scheduler;
 mod latex_parser;
+mod latex_project;
+mod pdf_parser;
+mod bibliography;
+mod embeddings;
+mod recommend;
+mod feedback;
+mod prefilter;
+mod rules;
 mod html_parser;
 mod analysis;
 mod logging;
@@ -19,21 +30,46 @@ mod logging;
 // Re-export specific types instead of glob to avoid ambiguity
 pub use models::{
     Paper, ArxivPaper, AuthorInfo, KeyFormula, Algorithm, FlowDiagram,
-    Settings, LLMProvider, ScheduleFrequency, TopicConfig,
+    RelatedPaper, PaperRelationship, FailedBlock, Recommendation, SearchHit,
+    Settings, BudgetConfig, EmbeddingBackend, EmbeddingConfig, LLMProvider, PrefilterConfig, RelevanceFeedbackConfig, ScheduleFrequency, TopicConfig, WatchlistConfig, WatchlistMode,
     FetchOptions, FetchResult, FetchStatus, FetchStatusState,
     ScheduleStatus, ScheduleRun, ScheduleRunStatus,
     Collection, CollectionWithPaperCount, CreateCollection, UpdateCollection,
+    CustomBlock, CustomBlockInput, PaperAnalysisBlock,
+    AnalysisRun, NewAnalysisRun, AnalysisRunDiff, FieldChange, FieldChangeKind,
+    LlmUsage, ModelPrice, TokenUsage, UsageSummary,
+    Citation, CitationGraph, CitationNode, CitationEdge,
+    RelevanceFeedback, RelevanceMetrics, RelevanceReport,
     compute_topics_hash,
 };
 
+// Re-export the fetch pipeline and repositories (used by integration tests)
+pub use database::{
+    create_schema, PaperRepository, SettingsRepository, ClassificationCacheRepository,
+    CollectionRepository, CustomBlockRepository, AnalysisBlockRepository, AnalysisRunRepository,
+    LlmUsageRepository, CitationRepository, EmbeddingRepository, InteractionRepository, FeedbackRepository,
+    AuthorRepository, FetchHistoryRepository,
+};
+pub use fetch::{FetchManager, FetchError};
+// `FetchResult` is taken by the frontend-facing model
+pub use fetch::FetchResult as FetchRunResult;
+pub use budget::{BudgetStage, BudgetStatus};
+pub use llm::{LlmClient, UsageContext};
+pub use analysis::translate::translate_analysis;
+pub use embeddings::{hybrid_search, refresh_embeddings, update_embeddings, Embedder};
+pub use recommend::{recommend, similar_papers};
+pub use feedback::relevance_report;
+
 // Re-export commands
 pub use commands::{
-    get_papers, get_paper_by_id, search_papers, get_papers_by_tag,
-    get_paper_count, save_paper, delete_paper, batch_delete_papers, get_tags_with_counts,
+    get_papers, get_paper_by_id, search_papers, get_papers_by_tag, get_papers_by_author,
+    get_paper_count, save_paper, delete_paper, semantic_search, batch_delete_papers, get_tags_with_counts,
     get_spam_papers, get_spam_paper_count, toggle_paper_spam,
     download_paper_pdf, get_pdf_path, open_local_file,
-    analyze_paper, batch_analyze_papers,
-    get_settings, save_settings, get_setting, set_setting,
+    analyze_paper, batch_analyze_papers, get_paper_analysis_blocks, get_analysis_block_history,
+    get_analysis_runs, restore_analysis_run, diff_analysis_runs,
+    get_supported_languages, get_paper_analysis_languages, get_paper_in_language, translate_paper_analysis,
+    get_settings, save_settings, get_setting, set_setting, validate_topic_rule,
     get_cache_stats, clear_cache,
     start_fetch, get_fetch_status, is_fetching, cancel_fetch,
     get_fetch_history, delete_fetch_history_entry,
@@ -43,11 +79,20 @@ pub use commands::{
     delete_collection, add_paper_to_collection, remove_paper_from_collection,
     get_collection_papers, get_paper_collections,
     get_platform_info,
+    get_usage_by_day, get_usage_by_topic, get_usage_by_fetch, get_paper_usage,
+    get_model_prices, set_model_price, delete_model_price, get_budget_status,
+    get_cited_papers, get_citing_papers, get_paper_citations, extract_paper_citations,
+    export_citation_graph,
+    get_recommendations, get_similar_papers, record_paper_opened,
+    set_relevance_feedback, get_relevance_feedback, get_relevance_report,
     FetchManagerState, SchedulerState,
 };
 
 // Re-export analysis config commands
-pub use commands::settings::{get_available_blocks, get_analysis_config, save_analysis_config};
+pub use commands::settings::{
+    get_available_blocks, get_analysis_config, save_analysis_config,
+    get_custom_blocks, create_custom_block, update_custom_block, delete_custom_block,
+};
 
 /// Application state
 pub struct AppState {
@@ -75,6 +120,11 @@ pub fn run() {
                 // This is safe to run multiple times as it ignores existing tables/columns
                 database::create_schema(&pool).await.expect("Failed to create schema");
 
+                // Make user-defined analysis blocks available to every command
+                if let Err(e) = analysis::registry::sync_custom_blocks(&pool).await {
+                    eprintln!("Failed to load custom analysis blocks: {}", e);
+                }
+
                 pool
             });
 
@@ -90,6 +140,9 @@ pub fn run() {
             // Initialize scheduler state
             app.manage(commands::SchedulerState::new());
 
+            // Embed papers saved before embeddings existed or with another model
+            embeddings::spawn_refresh(pool);
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -99,7 +152,9 @@ pub fn run() {
             get_papers,
             get_paper_by_id,
             search_papers,
+            semantic_search,
             get_papers_by_tag,
+            get_papers_by_author,
             get_paper_count,
             save_paper,
             delete_paper,
@@ -116,16 +171,30 @@ pub fn run() {
             // Analysis commands
             analyze_paper,
             batch_analyze_papers,
+            get_paper_analysis_blocks,
+            get_analysis_block_history,
+            get_analysis_runs,
+            restore_analysis_run,
+            diff_analysis_runs,
+            get_supported_languages,
+            get_paper_analysis_languages,
+            get_paper_in_language,
+            translate_paper_analysis,
             // Settings commands
             get_settings,
             save_settings,
             get_setting,
             set_setting,
+            validate_topic_rule,
             get_cache_stats,
             clear_cache,
             get_available_blocks,
             get_analysis_config,
             save_analysis_config,
+            get_custom_blocks,
+            create_custom_block,
+            update_custom_block,
+            delete_custom_block,
             // Fetch commands
             start_fetch,
             get_fetch_status,
@@ -149,6 +218,29 @@ pub fn run() {
             remove_paper_from_collection,
             get_collection_papers,
             get_paper_collections,
+            // Usage commands
+            get_usage_by_day,
+            get_usage_by_topic,
+            get_usage_by_fetch,
+            get_paper_usage,
+            get_model_prices,
+            set_model_price,
+            delete_model_price,
+            get_budget_status,
+            // Citation commands
+            get_cited_papers,
+            get_citing_papers,
+            get_paper_citations,
+            extract_paper_citations,
+            export_citation_graph,
+            // Recommendation commands
+            get_recommendations,
+            get_similar_papers,
+            record_paper_opened,
+            // Relevance feedback commands
+            set_relevance_feedback,
+            get_relevance_feedback,
+            get_relevance_report,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,162 @@
+//! Pluggable LLM backends
+//!
+//! Each provider implements [`LlmBackend`] in its own file and is registered
+//! in [`BackendRegistry::new`]. `LlmClient`, the retry executor, the fetch
+//! pipeline and the Tauri commands only talk to providers through this trait.
+
+use super::LlmError;
+use crate::models::{LLMProvider, Settings, TokenUsage};
+use crate::retry::classifier::ErrorClassifier;
+use reqwest::{Client, RequestBuilder};
+use std::sync::Arc;
+
+/// Provider-specific values resolved from `Settings`
+#[derive(Debug, Clone, Default)]
+pub struct ProviderConfig {
+    /// API key to send, or `None` if the provider needs one and none is configured
+    pub api_key: Option<String>,
+    pub quick_model: Option<String>,
+    pub deep_model: Option<String>,
+    pub base_url: Option<String>,
+}
+
+/// A single chat completion call
+#[derive(Debug, Clone)]
+pub struct ChatRequest<'a> {
+    pub model: &'a str,
+    pub prompt: &'a str,
+    pub max_tokens: i32,
+    pub temperature: f32,
+    pub api_key: &'a str,
+    pub base_url: Option<&'a str>,
+    /// JSON Schema the reply must follow (only set for backends with structured output)
+    pub response_schema: Option<&'a serde_json::Value>,
+}
+
+/// An LLM provider: how to build requests, read responses and classify errors
+pub trait LlmBackend: Send + Sync {
+    /// Provider this backend serves
+    fn provider(&self) -> LLMProvider;
+
+    /// Human-readable name used in logs and error messages
+    fn display_name(&self) -> &'static str;
+
+    /// Default (quick, deep) models when none are configured
+    fn default_models(&self) -> (&'static str, &'static str);
+
+    /// Whether requests fail without an API key
+    fn requires_api_key(&self) -> bool {
+        true
+    }
+
+    /// Whether `build_request` can enforce `ChatRequest::response_schema`
+    /// natively (structured output or a forced tool call)
+    fn supports_structured_output(&self) -> bool {
+        false
+    }
+
+    /// Resolve API key, models and endpoint for this provider from settings
+    fn provider_config(&self, settings: &Settings) -> ProviderConfig;
+
+    /// Check that settings hold what this provider needs to make requests
+    ///
+    /// Returns a message for the user when something is missing.
+    fn validate_config(&self, settings: &Settings) -> Result<(), String> {
+        let api_key = self.provider_config(settings).api_key.unwrap_or_default();
+        if self.requires_api_key() && api_key.trim().is_empty() {
+            return Err("API key is not configured. Please add your API key in the settings.".to_string());
+        }
+        Ok(())
+    }
+
+    /// Build the HTTP request for a chat completion
+    fn build_request(&self, client: &Client, request: &ChatRequest<'_>) -> RequestBuilder;
+
+    /// Extract the message text from a successful response body
+    fn extract_content(&self, response_text: &str) -> Result<String, LlmError>;
+
+    /// Token usage reported in a successful response body, if any
+    fn extract_usage(&self, _response_text: &str) -> Option<TokenUsage> {
+        None
+    }
+
+    /// Error classifier used by the retry executor
+    fn classifier(&self) -> Box<dyn ErrorClassifier>;
+}
+
+/// Registry of available LLM backends
+pub struct BackendRegistry {
+    backends: Vec<Arc<dyn LlmBackend>>,
+}
+
+impl BackendRegistry {
+    /// Create a new registry with all built-in backends registered
+    pub fn new() -> Self {
+        Self {
+            backends: vec![
+                Arc::new(super::glm::GlmBackend),
+                Arc::new(super::claude::ClaudeBackend),
+                Arc::new(super::openai::OpenAiBackend),
+            ],
+        }
+    }
+
+    /// Get the backend for a provider
+    pub fn get(&self, provider: &LLMProvider) -> Option<Arc<dyn LlmBackend>> {
+        self.backends
+            .iter()
+            .find(|b| &b.provider() == provider)
+            .cloned()
+    }
+
+    /// Get all registered backends
+    #[allow(dead_code)]
+    pub fn get_all(&self) -> Vec<Arc<dyn LlmBackend>> {
+        self.backends.clone()
+    }
+}
+
+// Global lazy instance
+lazy_static::lazy_static! {
+    /// Global LLM backend registry
+    pub static ref BACKENDS: BackendRegistry = BackendRegistry::new();
+}
+
+/// Look up the backend for a provider
+pub fn get_backend(provider: &LLMProvider) -> Result<Arc<dyn LlmBackend>, LlmError> {
+    BACKENDS
+        .get(provider)
+        .ok_or_else(|| LlmError::UnsupportedProvider(format!("{:?}", provider)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_provider_has_backend() {
+        for provider in [LLMProvider::Glm, LLMProvider::Claude, LLMProvider::OpenAiCompatible] {
+            let backend = get_backend(&provider).unwrap();
+            assert_eq!(backend.provider(), provider);
+        }
+    }
+
+    #[test]
+    fn test_provider_config_from_settings() {
+        let mut settings = Settings::default();
+        settings.claude_api_key = Some("sk-ant".to_string());
+        settings.openai_base_url = Some("http://localhost:8000/v1".to_string());
+
+        let glm = get_backend(&LLMProvider::Glm).unwrap().provider_config(&settings);
+        assert!(glm.api_key.is_none());
+        assert_eq!(glm.quick_model.as_deref(), Some("glm-4.5-air"));
+
+        let claude = get_backend(&LLMProvider::Claude).unwrap().provider_config(&settings);
+        assert_eq!(claude.api_key.as_deref(), Some("sk-ant"));
+
+        // The key is optional for OpenAI-compatible gateways
+        let openai = get_backend(&LLMProvider::OpenAiCompatible).unwrap().provider_config(&settings);
+        assert_eq!(openai.api_key.as_deref(), Some(""));
+        assert_eq!(openai.base_url.as_deref(), Some("http://localhost:8000/v1"));
+    }
+}
@@ -0,0 +1,178 @@
+//! Claude (Anthropic) backend
+
+use super::backend::{ChatRequest, LlmBackend, ProviderConfig};
+use super::{ChatMessage, LlmError};
+use crate::models::{LLMProvider, Settings, TokenUsage};
+use crate::retry::classifier::{ClaudeErrorClassifier, ErrorClassifier};
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const ANTHROPIC_ENDPOINT: &str = "https://api.anthropic.com/v1/messages";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Tool the model is forced to call when a reply schema is given
+const ANALYSIS_TOOL_NAME: &str = "record_analysis";
+
+/// Anthropic API request
+#[derive(Debug, Serialize)]
+struct AnthropicRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    max_tokens: i32,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<Value>,
+}
+
+/// Anthropic API response
+#[derive(Debug, Deserialize)]
+struct AnthropicResponse {
+    content: Vec<AnthropicContent>,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+/// Token counts reported by the Messages API
+#[derive(Debug, Deserialize)]
+struct AnthropicUsage {
+    input_tokens: i64,
+    output_tokens: i64,
+}
+
+/// A content block: `text`, or `tool_use` carrying the structured `input`
+#[derive(Debug, Deserialize)]
+struct AnthropicContent {
+    #[serde(rename = "type", default)]
+    content_type: String,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    input: Option<Value>,
+}
+
+/// Backend for the Anthropic Messages API
+pub struct ClaudeBackend;
+
+impl LlmBackend for ClaudeBackend {
+    fn provider(&self) -> LLMProvider {
+        LLMProvider::Claude
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Claude"
+    }
+
+    fn default_models(&self) -> (&'static str, &'static str) {
+        ("claude-3-5-haiku-20241022", "claude-3-5-sonnet-20241022")
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
+
+    fn provider_config(&self, settings: &Settings) -> ProviderConfig {
+        ProviderConfig {
+            api_key: settings.claude_api_key.clone(),
+            quick_model: settings.claude_quick_model.clone(),
+            deep_model: settings.claude_deep_model.clone(),
+            base_url: None,
+        }
+    }
+
+    fn build_request(&self, client: &Client, request: &ChatRequest<'_>) -> RequestBuilder {
+        let body = AnthropicRequest {
+            model: request.model.to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: request.prompt.to_string(),
+            }],
+            max_tokens: request.max_tokens,
+            temperature: request.temperature,
+            tools: request.response_schema.map(|schema| {
+                vec![json!({
+                    "name": ANALYSIS_TOOL_NAME,
+                    "description": "Record the paper analysis",
+                    "input_schema": schema,
+                })]
+            }),
+            tool_choice: request
+                .response_schema
+                .map(|_| json!({ "type": "tool", "name": ANALYSIS_TOOL_NAME })),
+        };
+
+        client
+            .post(ANTHROPIC_ENDPOINT)
+            .header("x-api-key", request.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header("content-type", "application/json")
+            .json(&body)
+    }
+
+    fn extract_content(&self, response_text: &str) -> Result<String, LlmError> {
+        let anthropic_response: AnthropicResponse = serde_json::from_str(response_text)
+            .map_err(|e| LlmError::ParseError(format!(
+                "Failed to parse Claude response as JSON: {}. Raw response: {}",
+                e, response_text
+            )))?;
+
+        // A forced tool call carries the structured reply as its input
+        if let Some(input) = anthropic_response
+            .content
+            .iter()
+            .find(|c| c.content_type == "tool_use")
+            .and_then(|c| c.input.as_ref())
+        {
+            return Ok(input.to_string());
+        }
+
+        anthropic_response
+            .content
+            .iter()
+            .find_map(|c| c.text.clone())
+            .ok_or_else(|| LlmError::ApiError("No response content from Claude".to_string()))
+    }
+
+    fn extract_usage(&self, response_text: &str) -> Option<TokenUsage> {
+        let usage = serde_json::from_str::<AnthropicResponse>(response_text).ok()?.usage?;
+        Some(TokenUsage {
+            prompt_tokens: usage.input_tokens,
+            completion_tokens: usage.output_tokens,
+        })
+    }
+
+    fn classifier(&self) -> Box<dyn ErrorClassifier> {
+        Box::new(ClaudeErrorClassifier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_text_and_tool_use() {
+        let backend = ClaudeBackend;
+
+        let text = r#"{"content":[{"type":"text","text":"hello"}]}"#;
+        assert_eq!(backend.extract_content(text).unwrap(), "hello");
+
+        let tool_use = r#"{"content":[
+            {"type":"text","text":"Recording the analysis"},
+            {"type":"tool_use","id":"toolu_1","name":"record_analysis","input":{"ai_summary":"s"}}
+        ]}"#;
+        assert_eq!(backend.extract_content(tool_use).unwrap(), r#"{"ai_summary":"s"}"#);
+    }
+
+    #[test]
+    fn test_extract_usage() {
+        let backend = ClaudeBackend;
+        let with_usage = r#"{"content":[],"usage":{"input_tokens":900,"output_tokens":80}}"#;
+        assert_eq!(
+            backend.extract_usage(with_usage),
+            Some(TokenUsage { prompt_tokens: 900, completion_tokens: 80 })
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! GLM (ZhipuAI) backend
+
+use super::backend::{ChatRequest, LlmBackend, ProviderConfig};
+use super::openai::{ChatCompletionRequest, ChatCompletionResponse};
+use super::LlmError;
+use crate::models::{LLMProvider, Settings, TokenUsage};
+use crate::retry::classifier::{ErrorClassifier, GlmErrorClassifier};
+use reqwest::{Client, RequestBuilder};
+
+const GLM_ENDPOINT: &str = "https://open.bigmodel.cn/api/paas/v4/chat/completions";
+
+/// Backend for the ZhipuAI GLM API (OpenAI-style chat completions)
+pub struct GlmBackend;
+
+impl LlmBackend for GlmBackend {
+    fn provider(&self) -> LLMProvider {
+        LLMProvider::Glm
+    }
+
+    fn display_name(&self) -> &'static str {
+        "GLM"
+    }
+
+    fn default_models(&self) -> (&'static str, &'static str) {
+        ("glm-4-flash", "glm-4-plus")
+    }
+
+    fn provider_config(&self, settings: &Settings) -> ProviderConfig {
+        ProviderConfig {
+            api_key: settings.glm_api_key.clone(),
+            quick_model: settings.glm_quick_model.clone(),
+            deep_model: settings.glm_deep_model.clone(),
+            base_url: None,
+        }
+    }
+
+    fn build_request(&self, client: &Client, request: &ChatRequest<'_>) -> RequestBuilder {
+        client
+            .post(GLM_ENDPOINT)
+            .header("Authorization", format!("Bearer {}", request.api_key))
+            .json(&ChatCompletionRequest::from_chat(request))
+    }
+
+    fn extract_content(&self, response_text: &str) -> Result<String, LlmError> {
+        // Check if response is empty
+        if response_text.trim().is_empty() {
+            return Err(LlmError::ApiError(
+                "GLM API returned empty response. Check your API key balance and quota.".to_string()
+            ));
+        }
+
+        // Try to parse as JSON
+        let glm_response: ChatCompletionResponse = serde_json::from_str(response_text)
+            .map_err(|e| LlmError::ApiError(format!(
+                "Failed to parse GLM response as JSON: {}. Raw response: {}",
+                e, response_text
+            )))?;
+
+        println!("[LLM GlmBackend] Received response with {} choices", glm_response.choices.len());
+
+        if glm_response.choices.is_empty() {
+            return Err(LlmError::ApiError(format!(
+                "GLM API returned no choices. Raw response: {}",
+                response_text
+            )));
+        }
+
+        let content = glm_response.choices[0].message.content.trim();
+        if content.is_empty() {
+            return Err(LlmError::ApiError(
+                "GLM API returned empty message content. The model may have refused to respond or encountered an error.".to_string()
+            ));
+        }
+
+        println!("[LLM GlmBackend] Response content: {}", content);
+        Ok(content.to_string())
+    }
+
+    fn extract_usage(&self, response_text: &str) -> Option<TokenUsage> {
+        ChatCompletionResponse::usage_from(response_text)
+    }
+
+    fn classifier(&self) -> Box<dyn ErrorClassifier> {
+        Box::new(GlmErrorClassifier)
+    }
+}
@@ -0,0 +1,262 @@
+//! OpenAI-compatible backend (vLLM, llama.cpp server, Ollama, OpenAI itself)
+
+use super::backend::{ChatRequest, LlmBackend, ProviderConfig};
+use super::{ChatMessage, LlmError};
+use crate::models::{LLMProvider, Settings, TokenUsage};
+use crate::retry::classifier::{ErrorClassifier, OpenAiErrorClassifier};
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Name of the structured output schema sent with analysis requests
+pub(crate) const STRUCTURED_OUTPUT_NAME: &str = "paper_analysis";
+
+/// Default base URL for the OpenAI-compatible provider
+pub const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// Chat completions request (also used by GLM, which speaks the same format)
+#[derive(Debug, Serialize)]
+pub(crate) struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub temperature: f32,
+    pub max_tokens: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<Value>,
+}
+
+impl ChatCompletionRequest {
+    pub fn from_chat(request: &ChatRequest<'_>) -> Self {
+        Self {
+            model: request.model.to_string(),
+            messages: vec![ChatMessage {
+                role: "user".to_string(),
+                content: request.prompt.to_string(),
+            }],
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            response_format: None,
+        }
+    }
+}
+
+/// `response_format` asking for a reply that follows `schema`
+fn json_schema_format(schema: &Value) -> Value {
+    json!({
+        "type": "json_schema",
+        "json_schema": {
+            "name": STRUCTURED_OUTPUT_NAME,
+            "strict": true,
+            "schema": schema,
+        }
+    })
+}
+
+/// Chat completions response
+#[derive(Debug, Deserialize)]
+pub(crate) struct ChatCompletionResponse {
+    pub choices: Vec<ChatCompletionChoice>,
+    #[serde(default)]
+    pub usage: Option<ChatCompletionUsage>,
+}
+
+/// Token counts reported with a chat completion
+#[derive(Debug, Deserialize)]
+pub(crate) struct ChatCompletionUsage {
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+}
+
+impl ChatCompletionResponse {
+    /// Usage reported in a raw response body
+    pub fn usage_from(response_text: &str) -> Option<TokenUsage> {
+        let usage = serde_json::from_str::<Self>(response_text).ok()?.usage?;
+        Some(TokenUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ChatCompletionChoice {
+    pub message: ChatMessage,
+}
+
+/// Chat completions endpoint for a base URL (`None` or empty uses the default)
+pub fn chat_completions_endpoint(base_url: Option<&str>) -> String {
+    let base_url = base_url
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .unwrap_or(DEFAULT_OPENAI_BASE_URL);
+    format!("{}/chat/completions", base_url.trim_end_matches('/'))
+}
+
+/// Backend for any server speaking the OpenAI chat completions API
+pub struct OpenAiBackend;
+
+impl LlmBackend for OpenAiBackend {
+    fn provider(&self) -> LLMProvider {
+        LLMProvider::OpenAiCompatible
+    }
+
+    fn display_name(&self) -> &'static str {
+        "OpenAI-compatible"
+    }
+
+    fn default_models(&self) -> (&'static str, &'static str) {
+        ("gpt-4o-mini", "gpt-4o")
+    }
+
+    // Self-hosted gateways usually run without auth
+    fn requires_api_key(&self) -> bool {
+        false
+    }
+
+    fn supports_structured_output(&self) -> bool {
+        true
+    }
+
+    fn provider_config(&self, settings: &Settings) -> ProviderConfig {
+        ProviderConfig {
+            api_key: Some(settings.openai_api_key.clone().unwrap_or_default()),
+            quick_model: settings.openai_quick_model.clone(),
+            deep_model: settings.openai_deep_model.clone(),
+            base_url: settings.openai_base_url.clone(),
+        }
+    }
+
+    // The key is optional, but there is no sensible default gateway to fall back to
+    fn validate_config(&self, settings: &Settings) -> Result<(), String> {
+        if settings.openai_base_url.as_deref().unwrap_or("").trim().is_empty() {
+            return Err("Base URL is not configured. Please add your OpenAI-compatible endpoint in the settings.".to_string());
+        }
+        Ok(())
+    }
+
+    fn build_request(&self, client: &Client, request: &ChatRequest<'_>) -> RequestBuilder {
+        let mut body = ChatCompletionRequest::from_chat(request);
+        body.response_format = request.response_schema.map(json_schema_format);
+
+        let builder = client
+            .post(chat_completions_endpoint(request.base_url))
+            .json(&body);
+
+        // Only send credentials when a key is configured
+        if request.api_key.is_empty() {
+            builder
+        } else {
+            builder.header("Authorization", format!("Bearer {}", request.api_key))
+        }
+    }
+
+    fn extract_content(&self, response_text: &str) -> Result<String, LlmError> {
+        let response: ChatCompletionResponse = serde_json::from_str(response_text)
+            .map_err(|e| LlmError::ParseError(format!(
+                "Failed to parse OpenAI-compatible response as JSON: {}. Raw response: {}",
+                e, response_text
+            )))?;
+
+        let content = response
+            .choices
+            .first()
+            .map(|c| c.message.content.trim().to_string())
+            .unwrap_or_default();
+
+        if content.is_empty() {
+            return Err(LlmError::ApiError(format!(
+                "OpenAI-compatible API returned no message content. Raw response: {}",
+                response_text
+            )));
+        }
+
+        Ok(content)
+    }
+
+    fn extract_usage(&self, response_text: &str) -> Option<TokenUsage> {
+        ChatCompletionResponse::usage_from(response_text)
+    }
+
+    fn classifier(&self) -> Box<dyn ErrorClassifier> {
+        Box::new(OpenAiErrorClassifier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_completions_endpoint() {
+        assert_eq!(chat_completions_endpoint(None), "https://api.openai.com/v1/chat/completions");
+        assert_eq!(
+            chat_completions_endpoint(Some("http://localhost:11434/v1/")),
+            "http://localhost:11434/v1/chat/completions"
+        );
+        // Empty base URL falls back to the default endpoint
+        assert_eq!(chat_completions_endpoint(Some("  ")), "https://api.openai.com/v1/chat/completions");
+    }
+
+    #[test]
+    fn test_validate_config_requires_base_url() {
+        let backend = OpenAiBackend;
+        let mut settings = Settings::default();
+        assert!(backend.validate_config(&settings).unwrap_err().contains("Base URL"));
+
+        settings.openai_base_url = Some(" ".to_string());
+        assert!(backend.validate_config(&settings).is_err());
+
+        // No API key needed
+        settings.openai_base_url = Some("http://localhost:11434/v1".to_string());
+        assert!(backend.validate_config(&settings).is_ok());
+    }
+
+    #[test]
+    fn test_extract_content() {
+        let backend = OpenAiBackend;
+        let body = r#"{"choices":[{"message":{"role":"assistant","content":" hi "}}]}"#;
+        assert_eq!(backend.extract_content(body).unwrap(), "hi");
+
+        let empty = r#"{"choices":[]}"#;
+        assert!(matches!(backend.extract_content(empty), Err(LlmError::ApiError(_))));
+        assert!(matches!(backend.extract_content("not json"), Err(LlmError::ParseError(_))));
+    }
+
+    #[test]
+    fn test_extract_usage() {
+        let backend = OpenAiBackend;
+        let body = r#"{"choices":[],"usage":{"prompt_tokens":120,"completion_tokens":30,"total_tokens":150}}"#;
+        assert_eq!(
+            backend.extract_usage(body),
+            Some(TokenUsage { prompt_tokens: 120, completion_tokens: 30 })
+        );
+
+        // Some local servers report no usage
+        assert_eq!(backend.extract_usage(r#"{"choices":[]}"#), None);
+    }
+
+    #[test]
+    fn test_response_format_only_with_schema() {
+        let schema = json!({ "type": "object" });
+        let mut request = ChatRequest {
+            model: "m",
+            prompt: "p",
+            max_tokens: 10,
+            temperature: 0.0,
+            api_key: "",
+            base_url: None,
+            response_schema: None,
+        };
+
+        let client = Client::new();
+        let plain = OpenAiBackend.build_request(&client, &request).build().unwrap();
+        let body: Value = serde_json::from_slice(plain.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert!(body.get("response_format").is_none());
+
+        request.response_schema = Some(&schema);
+        let structured = OpenAiBackend.build_request(&client, &request).build().unwrap();
+        let body: Value = serde_json::from_slice(structured.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body["response_format"]["type"], "json_schema");
+        assert_eq!(body["response_format"]["json_schema"]["schema"], schema);
+    }
+}
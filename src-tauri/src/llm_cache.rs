@@ -165,7 +165,7 @@ impl LlmCache {
     }
 
     /// Simple hash of prompt for validation
-    fn hash_prompt(prompt: &str) -> String {
+    pub fn hash_prompt(prompt: &str) -> String {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
 
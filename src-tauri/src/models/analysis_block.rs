@@ -0,0 +1,77 @@
+use crate::analysis::{
+    AnalysisBlockConfig, AnalysisDepth, BlockCategory, BlockRunMode, CustomBlockSpec, I18nText, OutputSchema,
+};
+use serde::{Deserialize, Serialize};
+
+/// Default order of custom blocks: after every built-in block
+pub const DEFAULT_CUSTOM_BLOCK_ORDER: usize = 100;
+
+/// A user-defined analysis block as stored in the database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomBlock {
+    pub id: String,
+    pub name: I18nText,
+    pub description: I18nText,
+    pub instructions: String,
+    pub json_schema: serde_json::Value,
+    pub default_enabled: bool,
+    pub default_mode: BlockRunMode,
+    pub order: usize,
+    pub depends_on: Option<Vec<String>>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl CustomBlock {
+    /// Block definition used by the registry and the prompt builder
+    pub fn to_block_config(&self) -> AnalysisBlockConfig {
+        AnalysisBlockConfig {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            category: BlockCategory::Custom,
+            default_enabled: self.default_enabled,
+            supported_modes: vec![AnalysisDepth::Standard, AnalysisDepth::Full],
+            default_mode: self.default_mode,
+            order: self.order,
+            depends_on: self.depends_on.clone(),
+            output_schema: OutputSchema::Custom,
+            custom: Some(CustomBlockSpec {
+                instructions: self.instructions.clone(),
+                json_schema: self.json_schema.clone(),
+            }),
+        }
+    }
+}
+
+/// Fields of a custom block supplied when creating or updating it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomBlockInput {
+    pub id: String,
+    pub name: I18nText,
+    pub description: Option<I18nText>,
+    pub instructions: String,
+    pub json_schema: serde_json::Value,
+    pub default_enabled: Option<bool>,
+    pub default_mode: Option<BlockRunMode>,
+    pub order: Option<usize>,
+    pub depends_on: Option<Vec<String>>,
+}
+
+/// One block output of an analysis run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaperAnalysisBlock {
+    pub id: i64,
+    pub paper_id: String,
+    pub block_id: String,
+    /// Analysis run that produced this output
+    pub run_id: Option<String>,
+    pub depth: String,
+    pub language: String,
+    pub model: Option<String>,
+    pub json: serde_json::Value,
+    pub created_at: String,
+}
@@ -0,0 +1,65 @@
+use crate::models::FailedBlock;
+use serde::{Deserialize, Serialize};
+
+/// One analysis of a paper: who produced it, from which prompt, and what came back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisRun {
+    pub id: String,
+    pub paper_id: String,
+    pub provider: String,
+    pub model: String,
+    pub depth: String,
+    pub language: String,
+    pub prompt_hash: String,
+    /// Every accepted reply field, e.g. `{"ai_summary": "...", "novelty_score": 7}`
+    pub result: serde_json::Value,
+    pub failed_blocks: Option<Vec<FailedBlock>>,
+    /// Set when this run re-applied the output of an earlier run
+    pub restored_from: Option<String>,
+    /// Language this run was translated from, for runs made by translation
+    pub translated_from: Option<String>,
+    pub created_at: String,
+}
+
+/// Metadata of a run about to be recorded
+#[derive(Debug, Clone)]
+pub struct NewAnalysisRun {
+    pub paper_id: String,
+    pub provider: String,
+    pub model: String,
+    pub depth: String,
+    pub language: String,
+    pub prompt_hash: String,
+    pub restored_from: Option<String>,
+    pub translated_from: Option<String>,
+}
+
+/// How a reply field differs between two runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One reply field that differs between two runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    pub field: String,
+    pub kind: FieldChangeKind,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+/// Field-by-field comparison of two runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisRunDiff {
+    pub from: AnalysisRun,
+    pub to: AnalysisRun,
+    /// Changed fields, ordered by field name; unchanged fields are left out
+    pub changes: Vec<FieldChange>,
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+/// One reference of a paper
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Citation {
+    pub id: i64,
+    pub citing_id: String,
+    pub cited_arxiv_id: Option<String>,
+    pub cited_title: Option<String>,
+    pub doi: Option<String>,
+}
+
+/// Library paper in the citation graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationNode {
+    pub id: String,
+    pub arxiv_id: String,
+    pub title: String,
+    pub published_date: String,
+}
+
+/// `source` cites `target`, both library paper IDs
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationEdge {
+    pub source: String,
+    pub target: String,
+}
+
+/// Citations between the papers of the library
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CitationGraph {
+    pub nodes: Vec<CitationNode>,
+    pub edges: Vec<CitationEdge>,
+}
+
+impl CitationGraph {
+    /// Graphviz DOT source of the graph, papers labeled with their titles
+    pub fn to_dot(&self) -> String {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let mut dot = String::from("digraph citations {\n    node [shape=box];\n");
+        for node in &self.nodes {
+            dot.push_str(&format!("    {} [label={}];\n", quote(&node.id), quote(&node.title)));
+        }
+        for edge in &self.edges {
+            dot.push_str(&format!("    {} -> {};\n", quote(&edge.source), quote(&edge.target)));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dot_escapes_labels() {
+        let graph = CitationGraph {
+            nodes: vec![
+                CitationNode {
+                    id: "2401.00001".to_string(),
+                    arxiv_id: "2401.00001".to_string(),
+                    title: "The \"Balanced\" Router".to_string(),
+                    published_date: "2024-01-01".to_string(),
+                },
+                CitationNode {
+                    id: "1701.06538".to_string(),
+                    arxiv_id: "1701.06538".to_string(),
+                    title: "Outrageously Large Neural Networks".to_string(),
+                    published_date: "2017-01-23".to_string(),
+                },
+            ],
+            edges: vec![CitationEdge { source: "2401.00001".to_string(), target: "1701.06538".to_string() }],
+        };
+
+        assert_eq!(graph.to_dot(), "digraph citations {
+    node [shape=box];
+    \"2401.00001\" [label=\"The \\\"Balanced\\\" Router\"];
+    \"1701.06538\" [label=\"Outrageously Large Neural Networks\"];
+    \"2401.00001\" -> \"1701.06538\";
+}
+");
+    }
+}
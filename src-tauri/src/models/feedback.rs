@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+/// Thumbs-up or down on whether a paper belongs to a topic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelevanceFeedback {
+    pub paper_id: String,
+    /// Topic key
+    pub topic: String,
+    pub relevant: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Judged paper shown to the relevance model as an example
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelevanceExample {
+    pub topic: String,
+    pub title: String,
+    pub summary: String,
+    pub relevant: bool,
+}
+
+/// How well past relevance decisions agree with the feedback, for one topic
+/// or (`topic` None) all of them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelevanceMetrics {
+    pub topic: Option<String>,
+    /// Feedback with a recorded relevance decision
+    pub feedback_count: usize,
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+    pub true_negatives: usize,
+    /// Share of accepted papers that got a thumbs-up, `None` when nothing was accepted
+    pub precision: Option<f64>,
+    /// Share of thumbs-up papers that were accepted, `None` without thumbs-up
+    pub recall: Option<f64>,
+    /// Threshold that best separates this topic's feedback, when there is enough
+    pub calibrated_threshold: Option<i32>,
+}
+
+/// Precision and recall of past relevance decisions against the feedback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelevanceReport {
+    /// Threshold the decisions were measured at
+    pub min_relevance: i32,
+    pub overall: RelevanceMetrics,
+    pub topics: Vec<RelevanceMetrics>,
+    /// Feedback on papers without a recorded relevance score
+    pub without_decision: usize,
+}
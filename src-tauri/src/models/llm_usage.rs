@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+/// Token counts of one LLM call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+}
+
+/// One recorded LLM call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmUsage {
+    pub id: i64,
+    pub provider: String,
+    pub model: String,
+    /// "relevance", "standard", "full", ...
+    pub analysis_type: String,
+    pub paper_id: Option<String>,
+    pub fetch_id: Option<String>,
+    /// Topic keys of the paper at the time of the call
+    pub topics: Vec<String>,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    /// True when the provider reported no usage and the counts were estimated
+    pub estimated: bool,
+    pub created_at: String,
+}
+
+/// A call about to be recorded
+#[derive(Debug, Clone)]
+pub struct NewLlmUsage {
+    pub provider: String,
+    pub model: String,
+    pub analysis_type: String,
+    pub paper_id: Option<String>,
+    pub fetch_id: Option<String>,
+    pub topics: Vec<String>,
+    pub usage: TokenUsage,
+    pub estimated: bool,
+}
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPrice {
+    pub model: String,
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+/// Token usage and spend of a group of calls (a day, a topic or a fetch)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummary {
+    /// Day (YYYY-MM-DD), topic key or fetch ID; `None` groups calls without one
+    pub key: Option<String>,
+    pub calls: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    /// Spend in USD of the calls whose model has a price
+    pub cost: f64,
+    /// Calls whose model has no price configured, so `cost` leaves them out
+    pub unpriced_calls: i64,
+    pub first_call_at: String,
+    pub last_call_at: String,
+}
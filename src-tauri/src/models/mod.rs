@@ -1,12 +1,22 @@
 pub mod paper;
 pub mod settings;
 pub mod collection;
+pub mod analysis_block;
+pub mod analysis_run;
+pub mod llm_usage;
+pub mod citation;
+pub mod feedback;
 
-pub use paper::{Paper, ArxivPaper, AuthorInfo, KeyFormula, Algorithm, FlowDiagram, RelatedPaper, PaperRelationship};
+pub use paper::{Paper, ArxivPaper, AuthorInfo, KeyFormula, Algorithm, FlowDiagram, RelatedPaper, PaperRelationship, FailedBlock, Recommendation, SearchHit};
 pub use settings::{
-    Settings, LLMProvider, ScheduleFrequency, TopicConfig,
+    Settings, BudgetConfig, EmbeddingBackend, EmbeddingConfig, LLMProvider, PrefilterConfig, RelevanceFeedbackConfig, ScheduleFrequency, TopicConfig, WatchlistConfig, WatchlistMode,
     FetchOptions, FetchResult, FetchStatus, FetchStatusState,
     ScheduleStatus, ScheduleRun, ScheduleRunStatus,
     compute_topics_hash,
 };
 pub use collection::{Collection, CollectionWithPaperCount, CreateCollection, UpdateCollection};
+pub use analysis_block::{CustomBlock, CustomBlockInput, PaperAnalysisBlock};
+pub use analysis_run::{AnalysisRun, AnalysisRunDiff, FieldChange, FieldChangeKind, NewAnalysisRun};
+pub use llm_usage::{LlmUsage, ModelPrice, NewLlmUsage, TokenUsage, UsageSummary};
+pub use citation::{Citation, CitationEdge, CitationGraph, CitationNode};
+pub use feedback::{RelevanceExample, RelevanceFeedback, RelevanceMetrics, RelevanceReport};
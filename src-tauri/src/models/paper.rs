@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use crate::models::PaperAnalysisBlock;
 
 /// Author information with affiliation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,9 +50,41 @@ pub struct Paper {
     pub related_papers: Option<Vec<RelatedPaper>>,  // Related works identified during analysis
 
     // HTML parsing fields
-    pub content_source: Option<String>,     // 'html', 'latex', 'abstract'
+    pub content_source: Option<String>,     // 'html', 'latex', 'pdf', 'abstract'
     pub estimated_tokens: Option<i32>,      // Estimated token count of analyzed content
     pub available_sections: Option<Vec<String>>,  // Sections available in the source
+
+    // Partial analysis
+    pub failed_blocks: Option<Vec<FailedBlock>>,  // Blocks whose output failed validation
+
+    // Latest output of each analysis block, from paper_analysis_blocks.
+    // Loaded by PaperRepository::get_by_id; not a papers column.
+    #[serde(default)]
+    pub analysis_blocks: Option<Vec<PaperAnalysisBlock>>,
+}
+
+/// Paper found by semantic or hybrid search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub paper: Paper,
+    /// Blended score, 0.0-1.0
+    pub score: f64,
+    /// BM25 relative to the best keyword match, `None` without keyword match
+    pub keyword_score: Option<f64>,
+    /// Cosine similarity relative to the most similar paper
+    pub semantic_score: Option<f64>,
+}
+
+/// Paper suggested by the recommender
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recommendation {
+    pub paper: Paper,
+    /// Higher is better; comparable only within one list
+    pub score: f64,
+    /// Kept paper this one is most similar to, to explain the suggestion
+    pub similar_to: Option<String>,
 }
 
 /// Related paper reference
@@ -78,6 +111,14 @@ pub enum PaperRelationship {
     SimilarTo,
 }
 
+/// Analysis block whose output failed schema validation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedBlock {
+    pub block_id: String,
+    pub error: String,
+}
+
 /// Key formula from a paper
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyFormula {
@@ -160,6 +201,8 @@ impl Paper {
             content_source: None,
             estimated_tokens: None,
             available_sections: None,
+            failed_blocks: None,
+            analysis_blocks: None,
         }
     }
 
@@ -178,7 +221,10 @@ mod tests {
         let arxiv = ArxivPaper {
             id: "2312.12345".to_string(),
             title: "Test Paper".to_string(),
-            authors: vec!["Author 1".to_string(), "Author 2".to_string()],
+            authors: vec![
+                AuthorInfo { name: "Author 1".to_string(), affiliation: None },
+                AuthorInfo { name: "Author 2".to_string(), affiliation: None },
+            ],
             summary: "This is a test abstract.".to_string(),
             published: Utc::now(),
             updated: Utc::now(),
@@ -239,6 +285,8 @@ mod tests {
             content_source: None,
             estimated_tokens: None,
             available_sections: None,
+            failed_blocks: None,
+            analysis_blocks: None,
         };
 
         paper.touch();
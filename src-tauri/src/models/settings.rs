@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use crate::analysis;
 
 /// Retry configuration for LLM API calls
@@ -53,6 +54,160 @@ impl Default for RetryConfig {
     }
 }
 
+/// Spending limits for LLM calls, measured from recorded token usage
+///
+/// Limits left unset are not enforced. Cost limits are in USD and only count
+/// calls of models with a configured price.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BudgetConfig {
+    pub daily_token_limit: Option<i64>,
+    pub monthly_token_limit: Option<i64>,
+    pub daily_cost_limit: Option<f64>,
+    pub monthly_cost_limit: Option<f64>,
+
+    /// Share of a limit (0.0-1.0) after which deep analysis stops and only
+    /// relevance scoring continues (default: 0.8)
+    pub deep_analysis_cutoff: f64,
+}
+
+impl Default for BudgetConfig {
+    fn default() -> Self {
+        Self {
+            daily_token_limit: None,
+            monthly_token_limit: None,
+            daily_cost_limit: None,
+            monthly_cost_limit: None,
+            deep_analysis_cutoff: 0.8,
+        }
+    }
+}
+
+/// Which embedding model vectors come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingBackend {
+    /// Built-in hashed word and subword model, runs on the CPU without downloads
+    Local,
+    /// Any server speaking the OpenAI embeddings API (OpenAI, GLM, Ollama, ...)
+    Api,
+}
+
+/// Paper embeddings for semantic search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EmbeddingConfig {
+    pub backend: EmbeddingBackend,
+    /// Model of the embeddings API (default: "text-embedding-3-small")
+    pub model: Option<String>,
+    /// Base URL of the embeddings API (default: the OpenAI-compatible base URL)
+    pub base_url: Option<String>,
+    /// API key of the embeddings API (default: the OpenAI-compatible API key)
+    pub api_key: Option<String>,
+    /// Weight of cosine similarity against BM25 in hybrid search, 0.0-1.0
+    /// (default: 0.5)
+    pub semantic_weight: f64,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            backend: EmbeddingBackend::Local,
+            model: None,
+            base_url: None,
+            api_key: None,
+            semantic_weight: 0.5,
+        }
+    }
+}
+
+/// How thumbs-up/down on papers feed back into relevance analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RelevanceFeedbackConfig {
+    /// Judged papers shown to the relevance model as examples, 0 to disable
+    /// (default: 4)
+    pub few_shot_examples: usize,
+    /// Replace the minimum relevance of topics with enough feedback by the
+    /// threshold that best separates their thumbs-up from their thumbs-down
+    /// (default: disabled)
+    pub calibrate_thresholds: bool,
+}
+
+impl Default for RelevanceFeedbackConfig {
+    fn default() -> Self {
+        Self {
+            few_shot_examples: 4,
+            calibrate_thresholds: false,
+        }
+    }
+}
+
+/// Local pre-scoring of fetched papers from the topics' keywords, negative
+/// keywords, allowed authors and category weights
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PrefilterConfig {
+    /// Decide clear-cut papers without the LLM (default: disabled)
+    pub enabled: bool,
+    /// Papers scoring below this for every topic are dropped (default: 5)
+    pub floor: f64,
+    /// Papers scoring at least this for a topic are accepted (default: 80)
+    pub ceiling: f64,
+}
+
+impl Default for PrefilterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            floor: 5.0,
+            ceiling: 80.0,
+        }
+    }
+}
+
+/// What a watchlist hit does to a paper in the fetch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchlistMode {
+    /// Keep the paper whatever its relevance score
+    #[default]
+    Bypass,
+    /// Add the boost to its relevance score
+    Boost,
+}
+
+/// Authors and institutions followed in the fetch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatchlistConfig {
+    /// Author names, matched ignoring diacritics and allowing initials
+    pub authors: Vec<String>,
+    /// Institutions matched as phrases in author affiliations
+    pub affiliations: Vec<String>,
+    /// What a hit does (default: bypass the relevance threshold)
+    pub mode: WatchlistMode,
+    /// Points added to the relevance score in boost mode (default: 20)
+    pub boost: i32,
+}
+
+impl Default for WatchlistConfig {
+    fn default() -> Self {
+        Self {
+            authors: Vec::new(),
+            affiliations: Vec::new(),
+            mode: WatchlistMode::Bypass,
+            boost: 20,
+        }
+    }
+}
+
+impl WatchlistConfig {
+    pub fn is_empty(&self) -> bool {
+        self.authors.is_empty() && self.affiliations.is_empty()
+    }
+}
+
 /// Application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -64,6 +219,16 @@ pub struct Settings {
     pub glm_deep_model: Option<String>,
     pub claude_quick_model: Option<String>,
     pub claude_deep_model: Option<String>,
+    /// API key for the OpenAI-compatible provider (optional - local gateways often need none)
+    #[serde(default)]
+    pub openai_api_key: Option<String>,
+    /// Base URL of the OpenAI-compatible endpoint, e.g. "http://localhost:8000/v1"
+    #[serde(default)]
+    pub openai_base_url: Option<String>,
+    #[serde(default)]
+    pub openai_quick_model: Option<String>,
+    #[serde(default)]
+    pub openai_deep_model: Option<String>,
     pub topics: Vec<TopicConfig>,
     pub schedule_enabled: bool,
     pub schedule_frequency: ScheduleFrequency,
@@ -88,6 +253,37 @@ pub struct Settings {
     /// Modular analysis configuration (enabled blocks and modes)
     #[serde(default)]
     pub analysis_config: Option<analysis::UserAnalysisConfig>,
+    /// Send the analysis JSON schema as native structured output where the
+    /// provider supports it (default: enabled)
+    #[serde(default)]
+    pub structured_output: Option<bool>,
+    /// How analysis blocks are sent: "combined" (one prompt) or "per_block"
+    /// (one call per block, scheduled by dependencies) (default: "combined")
+    #[serde(default)]
+    pub block_execution_mode: Option<String>,
+    /// Daily and monthly spending limits (default: none)
+    #[serde(default)]
+    pub budget: Option<BudgetConfig>,
+    /// Context window in tokens, overriding the one known for the model
+    /// (for self-hosted models; default: known per model)
+    #[serde(default)]
+    pub context_window: Option<usize>,
+    /// Summarize groups of sections first when a paper is too long for a
+    /// full analysis in one prompt, instead of leaving sections out (default: enabled)
+    #[serde(default)]
+    pub chunked_analysis: Option<bool>,
+    /// Embedding model for semantic search (default: the local model)
+    #[serde(default)]
+    pub embeddings: Option<EmbeddingConfig>,
+    /// Use of relevance feedback in the fetch (default: examples, no calibration)
+    #[serde(default)]
+    pub relevance_feedback: Option<RelevanceFeedbackConfig>,
+    /// Local pre-filter ahead of the relevance model (default: disabled)
+    #[serde(default)]
+    pub prefilter: Option<PrefilterConfig>,
+    /// Followed authors and affiliations (default: none)
+    #[serde(default)]
+    pub watchlist: Option<WatchlistConfig>,
 }
 
 /// LLM provider
@@ -96,6 +292,20 @@ pub struct Settings {
 pub enum LLMProvider {
     Glm,
     Claude,
+    /// Any server speaking the OpenAI chat completions API (vLLM, llama.cpp server, Ollama, ...)
+    #[serde(rename = "openai")]
+    OpenAiCompatible,
+}
+
+impl LLMProvider {
+    /// Serialized name, as stored in settings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LLMProvider::Glm => "glm",
+            LLMProvider::Claude => "claude",
+            LLMProvider::OpenAiCompatible => "openai",
+        }
+    }
 }
 
 /// Schedule frequency
@@ -164,6 +374,20 @@ pub struct TopicConfig {
     pub quick_score_threshold: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub keywords: Option<Vec<String>>,
+    /// Words that rule a paper out of this topic in the local pre-filter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub negative_keywords: Option<Vec<String>>,
+    /// Authors whose papers the local pre-filter accepts for this topic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_authors: Option<Vec<String>>,
+    /// Multiplier of the local pre-filter score per arXiv category, e.g.
+    /// `{"cs.CL": 1.5, "cs.CV": 0.5}` (categories not listed: 1.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category_weights: Option<HashMap<String, f64>>,
+    /// Boolean rule papers must satisfy to belong to this topic, e.g.
+    /// `(RLHF OR DPO) AND NOT robotics` (see `rules`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
 }
 
 /// Fetch options
@@ -173,6 +397,9 @@ pub struct FetchOptions {
     pub llm_provider: LLMProvider,
     pub quick_model: Option<String>,
     pub deep_model: Option<String>,
+    /// Base URL for the OpenAI-compatible provider (ignored by other providers)
+    #[serde(default)]
+    pub base_url: Option<String>,
     pub categories: Vec<String>,
     pub max_papers: i32,
     pub days_back: Option<i32>,
@@ -191,7 +418,7 @@ pub struct FetchOptions {
     /// Maximum concurrent analyses in async mode (1-5, default: 1)
     #[serde(default)]
     pub max_concurrent: Option<usize>,
-    /// Response language: "en" or "zh" (default: "en")
+    /// Response language code, e.g. "en", "zh", "ja" (default: "en")
     #[serde(default = "default_language")]
     pub language: Option<String>,
     /// Fetch by ID mode: fetch specific papers by arXiv ID instead of category search
@@ -200,6 +427,28 @@ pub struct FetchOptions {
     /// List of arXiv IDs for fetch-by-ID mode
     #[serde(skip_serializing_if = "Option::is_none")]
     pub arxiv_ids: Option<Vec<String>>,
+    /// Minimum relevance per topic key, overriding `min_relevance` for papers
+    /// suggested for that topic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topic_thresholds: Option<HashMap<String, i32>>,
+    /// Followed authors and affiliations, from the settings when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watchlist: Option<WatchlistConfig>,
+}
+
+impl FetchOptions {
+    /// Minimum relevance of a paper suggested for `topics`: the lowest
+    /// threshold among them, since passing for one topic is enough
+    pub fn min_relevance_for(&self, topics: &[String]) -> i32 {
+        let Some(thresholds) = &self.topic_thresholds else {
+            return self.min_relevance;
+        };
+        topics
+            .iter()
+            .map(|topic| thresholds.get(topic).copied().unwrap_or(self.min_relevance))
+            .min()
+            .unwrap_or(self.min_relevance)
+    }
 }
 
 /// Default value for deep_analysis_threshold (70/100 on 0-100 scale)
@@ -245,6 +494,9 @@ pub struct FetchStatus {
     /// Number of papers that used cached relevance results
     #[serde(default)]
     pub papers_cache_hits: usize,
+    /// Number of papers the local pre-filter decided without the LLM
+    #[serde(default)]
+    pub llm_calls_saved: usize,
     #[serde(default)]
     pub errors: Vec<String>,
     /// Async mode specific fields (only used when async_mode is true)
@@ -314,6 +566,10 @@ impl Default for Settings {
             glm_deep_model: Some("glm-4.7".to_string()),
             claude_quick_model: Some("claude-haiku".to_string()),
             claude_deep_model: Some("claude-sonnet".to_string()),
+            openai_api_key: None,
+            openai_base_url: None,
+            openai_quick_model: None,
+            openai_deep_model: None,
             topics: vec![],
             schedule_enabled: false,
             schedule_frequency: ScheduleFrequency::Daily,
@@ -327,6 +583,15 @@ impl Default for Settings {
             async_analysis_mode: Some("sync".to_string()),
             max_concurrent_analyses: Some(1),
             analysis_config: Some(analysis::UserAnalysisConfig::default()),
+            structured_output: None,
+            block_execution_mode: None,
+            budget: None,
+            context_window: None,
+            chunked_analysis: None,
+            embeddings: None,
+            relevance_feedback: None,
+            prefilter: None,
+            watchlist: None,
         }
     }
 }
@@ -361,11 +626,16 @@ mod tests {
                 label: "Reinforcement Learning".to_string(),
                 description: "RL".to_string(),
                 color: "bg-purple".to_string(),
+                enabled: true,
                 arxiv_categories: Some(vec!["cs.AI".to_string()]),
                 max_papers_per_day: Some(10),
                 deep_analysis_count: None,
                 quick_score_threshold: None,
                 keywords: Some(vec!["reinforcement".to_string()]),
+                negative_keywords: None,
+                allowed_authors: None,
+                category_weights: None,
+                rule: None,
             },
         ];
 
@@ -375,11 +645,16 @@ mod tests {
                 label: "Reinforcement Learning".to_string(),
                 description: "RL".to_string(),
                 color: "bg-purple".to_string(),
+                enabled: true,
                 arxiv_categories: Some(vec!["cs.AI".to_string()]),
                 max_papers_per_day: Some(10),
                 deep_analysis_count: None,
                 quick_score_threshold: None,
                 keywords: Some(vec!["reinforcement".to_string()]),
+                negative_keywords: None,
+                allowed_authors: None,
+                category_weights: None,
+                rule: None,
             },
         ];
 
@@ -415,7 +690,13 @@ mod tests {
             papers_filtered: 0,
             papers_duplicates: 0,
             papers_cache_hits: 0,
+            llm_calls_saved: 0,
             errors: vec![],
+            queue_size: 0,
+            active_tasks: 0,
+            completed_tasks: 0,
+            failed_tasks: 0,
+            async_mode: false,
         };
 
         let json = serde_json::to_string(&status).unwrap();
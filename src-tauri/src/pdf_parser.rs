@@ -0,0 +1,1118 @@
+//! PDF text extraction for papers without HTML or LaTeX source
+//! A small pure-Rust reader: decodes page content streams, maps glyph codes
+//! through ToUnicode CMaps or the font encoding, and detects section
+//! headings heuristically
+
+use crate::html_parser::should_skip_section;
+use flate2::read::ZlibDecoder;
+use regex::bytes::Regex as BytesRegex;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PdfParseError {
+    #[error("Not a PDF file")]
+    NotPdf,
+
+    #[error("PDF is encrypted")]
+    Encrypted,
+
+    #[error("No text found in PDF")]
+    NoText,
+
+    #[error("PDF content is too large to decode")]
+    TooLarge,
+}
+
+lazy_static::lazy_static! {
+    static ref OBJ_RE: BytesRegex = BytesRegex::new(r"(?-u)\b(\d+)\s+\d+\s+obj\b").unwrap();
+    static ref REF_RE: BytesRegex = BytesRegex::new(r"(?-u)(\d+)\s+\d+\s+R\b").unwrap();
+    static ref FONT_REF_RE: BytesRegex = BytesRegex::new(r"(?-u)/([^\s/<>\[\]()]+)\s*(\d+)\s+\d+\s+R\b").unwrap();
+    static ref HEX_RE: BytesRegex = BytesRegex::new(r"(?-u)<([0-9A-Fa-f\s]*)>").unwrap();
+    static ref HYPHEN_RE: Regex = Regex::new(r"(\p{L})-\n(\p{Ll})").unwrap();
+    static ref NUMBERED_HEADING_RE: Regex = Regex::new(r"^((?:\d+|[IVX]+)(?:\.\d+)*)\.?\s+(\p{Lu}.*)$").unwrap();
+    static ref ABSTRACT_RE: Regex = Regex::new(r"^(?i:abstract)\s*(?:[.:\u{2014}\u{2013}-]\s*(.*))?$").unwrap();
+}
+
+/// Unnumbered headings recognized on a line of their own
+const UNNUMBERED_HEADINGS: &[&str] = &[
+    "Introduction",
+    "Related Work",
+    "Background",
+    "Method",
+    "Methods",
+    "Methodology",
+    "Experiments",
+    "Results",
+    "Discussion",
+    "Limitations",
+    "Conclusion",
+    "Conclusions",
+    "Acknowledgments",
+    "Acknowledgements",
+    "References",
+    "Bibliography",
+];
+
+/// Headings after which the rest of the paper is back matter
+const BACK_MATTER: &[&str] = &["References", "Bibliography", "Appendix"];
+
+/// Decoded size limit of one stream, so a compressed bomb cannot exhaust memory
+const MAX_DECODED_STREAM: usize = 32 << 20;
+/// Decoded size limit of all streams of a file
+const MAX_DECODED_TOTAL: usize = 128 << 20;
+
+/// Kern in thousandths of an em from which a `TJ` gap counts as a space
+const WORD_SPACE_KERN: f32 = 200.0;
+
+/// Longest line taken for a heading, in words
+const MAX_HEADING_WORDS: usize = 12;
+
+/// Section detected in the extracted text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub title: String,
+    pub level: u8, // 2 for "1 Introduction", 3 for "1.1 Setup", ...
+    pub body: String,
+}
+
+/// Text of a PDF split into sections
+#[derive(Debug, Clone, Default)]
+pub struct PdfDocument {
+    pub r#abstract: Option<String>,
+    pub sections: Vec<Section>,
+    pub text: String, // Whole extracted text
+}
+
+/// Extract the text of a PDF and split it into sections
+pub fn parse_document(bytes: &[u8]) -> Result<PdfDocument, PdfParseError> {
+    let text = extract_text(bytes)?;
+    let (r#abstract, sections) = detect_sections(&text);
+    Ok(PdfDocument { r#abstract, sections, text })
+}
+
+/// Extract the text of all pages, one line per text line, pages separated by
+/// blank lines
+pub fn extract_text(bytes: &[u8]) -> Result<String, PdfParseError> {
+    if !bytes.starts_with(b"%PDF") {
+        return Err(PdfParseError::NotPdf);
+    }
+    let objects = parse_objects(bytes)?;
+    if let Some(trailer) = bytes.windows(7).rposition(|w| w == b"trailer") {
+        if contains_key(&bytes[trailer..], "Encrypt") {
+            return Err(PdfParseError::Encrypted);
+        }
+    }
+    if objects.values().any(|obj| contains_key(&obj.dict, "Encrypt") && contains_key(&obj.dict, "Root")) {
+        return Err(PdfParseError::Encrypted);
+    }
+
+    let mut fonts: HashMap<u32, Font> = HashMap::new();
+    let mut pages = Vec::new();
+    for page in page_ids(&objects) {
+        let Some(page_obj) = objects.get(&page) else { continue };
+
+        let mut page_fonts = HashMap::new();
+        if let Some(resources) = inherited(&objects, page, "Resources") {
+            let resources = resolve(&objects, resources);
+            if let Some(font_dict) = dict_value(resources, "Font") {
+                for caps in FONT_REF_RE.captures_iter(resolve(&objects, font_dict)) {
+                    let name = String::from_utf8_lossy(&caps[1]).into_owned();
+                    let Some(id) = parse_u32(&caps[2]) else { continue };
+                    let font = fonts.entry(id).or_insert_with(|| Font::load(&objects, id)).clone();
+                    page_fonts.insert(name, font);
+                }
+            }
+        }
+
+        let mut content = Vec::new();
+        if let Some(contents) = dict_value(&page_obj.dict, "Contents") {
+            for caps in REF_RE.captures_iter(contents) {
+                if let Some(stream) = parse_u32(&caps[1]).and_then(|id| objects.get(&id)?.stream.as_ref()) {
+                    content.extend_from_slice(stream);
+                    content.push(b'\n');
+                }
+            }
+        }
+        pages.push(render_page(&content, &page_fonts));
+    }
+
+    let text = clean_text(&pages.join("\n\n"));
+    if text.trim().is_empty() {
+        return Err(PdfParseError::NoText);
+    }
+    Ok(text)
+}
+
+/// Object dictionary (everything before `stream`) and decoded stream
+#[derive(Debug, Clone, Default)]
+struct PdfObject {
+    dict: Vec<u8>,
+    stream: Option<Vec<u8>>,
+}
+
+/// All objects of the file, including those packed in object streams
+fn parse_objects(bytes: &[u8]) -> Result<HashMap<u32, PdfObject>, PdfParseError> {
+    let mut objects = HashMap::new();
+    let mut pos = 0;
+    let mut decoded_total = 0;
+
+    while let Some(caps) = OBJ_RE.captures_at(bytes, pos) {
+        let (Some(header), Some(id)) = (caps.get(0), parse_u32(&caps[1])) else { break };
+        let start = header.end();
+        let end_obj = find(bytes, b"endobj", start).unwrap_or(bytes.len());
+        let stream_kw = find(bytes, b"stream", start).filter(|&s| s < end_obj);
+
+        let Some(stream_kw) = stream_kw else {
+            objects.insert(id, PdfObject { dict: bytes[start..end_obj].to_vec(), stream: None });
+            pos = end_obj;
+            continue;
+        };
+
+        let dict = &bytes[start..stream_kw];
+        let mut data_start = stream_kw + b"stream".len();
+        if bytes.get(data_start) == Some(&b'\r') {
+            data_start += 1;
+        }
+        if bytes.get(data_start) == Some(&b'\n') {
+            data_start += 1;
+        }
+        // Trust a direct /Length, binary data may contain "endstream"
+        let data_end = dict_value(dict, "Length")
+            .filter(|value| REF_RE.find(value).is_none())
+            .and_then(|value| parse_u32(value.trim_ascii()))
+            .map(|len| data_start + len as usize)
+            .filter(|&end| end <= bytes.len() && bytes[end..].trim_ascii_start().starts_with(b"endstream"))
+            .or_else(|| find(bytes, b"endstream", data_start))
+            .unwrap_or(bytes.len());
+
+        let stream = decode_stream(dict, &bytes[data_start..data_end])?;
+        decoded_total += stream.as_ref().map_or(0, Vec::len);
+        if decoded_total > MAX_DECODED_TOTAL {
+            return Err(PdfParseError::TooLarge);
+        }
+        objects.insert(id, PdfObject { dict: dict.to_vec(), stream });
+        pos = find(bytes, b"endobj", data_end).map_or(bytes.len(), |p| p + b"endobj".len());
+    }
+
+    let packed: Vec<(u32, PdfObject)> = objects
+        .values()
+        .filter(|obj| name_value(&obj.dict, "Type").as_deref() == Some("ObjStm"))
+        .flat_map(|obj| unpack_object_stream(obj))
+        .collect();
+    for (id, obj) in packed {
+        objects.entry(id).or_insert(obj);
+    }
+    Ok(objects)
+}
+
+/// Objects stored in an object stream (`/Type /ObjStm`)
+fn unpack_object_stream(obj: &PdfObject) -> Vec<(u32, PdfObject)> {
+    let (Some(data), Some(count), Some(first)) = (
+        obj.stream.as_ref(),
+        dict_value(&obj.dict, "N").and_then(|v| parse_u32(v.trim_ascii())),
+        dict_value(&obj.dict, "First").and_then(|v| parse_u32(v.trim_ascii())),
+    ) else {
+        return Vec::new();
+    };
+    let first = first as usize;
+    let Some(header) = data.get(..first) else { return Vec::new() };
+
+    let numbers: Vec<u32> = header
+        .split(|b| b.is_ascii_whitespace())
+        .filter_map(parse_u32)
+        .collect();
+    let entries: Vec<(u32, usize)> = numbers
+        .chunks_exact(2)
+        .take(count as usize)
+        .map(|pair| (pair[0], first + pair[1] as usize))
+        .collect();
+
+    entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &(id, start))| {
+            let end = entries.get(i + 1).map_or(data.len(), |&(_, next)| next);
+            let dict = data.get(start..end.max(start))?.to_vec();
+            Some((id, PdfObject { dict, stream: None }))
+        })
+        .collect()
+}
+
+/// Decode a stream; only FlateDecode and unfiltered streams carry text
+fn decode_stream(dict: &[u8], data: &[u8]) -> Result<Option<Vec<u8>>, PdfParseError> {
+    let Some(filter) = dict_value(dict, "Filter") else {
+        return Ok(Some(data.to_vec()));
+    };
+    let filter = String::from_utf8_lossy(filter);
+    let flate = filter
+        .split('/')
+        .skip(1)
+        .map(|name| name.trim_matches(|c: char| c.is_whitespace() || c == ']'))
+        .all(|name| name == "FlateDecode" || name == "Fl");
+    if !flate {
+        return Ok(None);
+    }
+
+    let mut decoded = Vec::new();
+    // Truncated streams still give their readable prefix
+    let _ = ZlibDecoder::new(data)
+        .take(MAX_DECODED_STREAM as u64 + 1)
+        .read_to_end(&mut decoded);
+    if decoded.len() > MAX_DECODED_STREAM {
+        return Err(PdfParseError::TooLarge);
+    }
+    Ok((!decoded.is_empty()).then_some(decoded))
+}
+
+/// Page object ids in reading order, following `/Pages` from the catalog
+fn page_ids(objects: &HashMap<u32, PdfObject>) -> Vec<u32> {
+    let root = objects
+        .values()
+        .find(|obj| name_value(&obj.dict, "Type").as_deref() == Some("Catalog"))
+        .and_then(|catalog| dict_value(&catalog.dict, "Pages"))
+        .and_then(reference);
+
+    let mut pages = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending: Vec<u32> = root.into_iter().collect();
+    while let Some(id) = pending.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let Some(obj) = objects.get(&id) else { continue };
+        if let Some(kids) = dict_value(&obj.dict, "Kids") {
+            let kids: Vec<u32> = REF_RE.captures_iter(kids).filter_map(|caps| parse_u32(&caps[1])).collect();
+            pending.extend(kids.into_iter().rev());
+        } else if name_value(&obj.dict, "Type").as_deref() == Some("Page") {
+            pages.push(id);
+        }
+    }
+
+    if pages.is_empty() {
+        // No usable page tree: take page objects in object order
+        pages = objects
+            .iter()
+            .filter(|(_, obj)| name_value(&obj.dict, "Type").as_deref() == Some("Page"))
+            .map(|(id, _)| *id)
+            .collect();
+        pages.sort_unstable();
+    }
+    pages
+}
+
+/// A page attribute, inherited from the parent page tree nodes if missing
+fn inherited<'a>(objects: &'a HashMap<u32, PdfObject>, page: u32, key: &str) -> Option<&'a [u8]> {
+    let mut id = page;
+    for _ in 0..32 {
+        let obj = objects.get(&id)?;
+        if let Some(value) = dict_value(&obj.dict, key) {
+            return Some(value);
+        }
+        id = dict_value(&obj.dict, "Parent").and_then(reference)?;
+    }
+    None
+}
+
+/// Follow an indirect reference to the referenced object's dictionary
+fn resolve<'a>(objects: &'a HashMap<u32, PdfObject>, value: &'a [u8]) -> &'a [u8] {
+    match reference(value).and_then(|id| objects.get(&id)) {
+        Some(obj) => &obj.dict,
+        None => value,
+    }
+}
+
+/// Object id of a value of the form `12 0 R`
+fn reference(value: &[u8]) -> Option<u32> {
+    let caps = REF_RE.captures(value)?;
+    (caps.get(0)?.start() == value.len() - value.trim_ascii_start().len())
+        .then(|| parse_u32(&caps[1]))
+        .flatten()
+}
+
+fn parse_u32(bytes: &[u8]) -> Option<u32> {
+    std::str::from_utf8(bytes).ok()?.parse().ok()
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack.get(from..)?.windows(needle.len()).position(|w| w == needle).map(|p| p + from)
+}
+
+/// True when `/key` appears as a key in `dict`
+fn contains_key(dict: &[u8], key: &str) -> bool {
+    key_end(dict, key, 0).is_some()
+}
+
+/// Position just past `/key` (not a prefix of a longer name)
+fn key_end(dict: &[u8], key: &str, from: usize) -> Option<usize> {
+    let needle = format!("/{}", key);
+    let mut pos = from;
+    while let Some(start) = find(dict, needle.as_bytes(), pos) {
+        let end = start + needle.len();
+        if dict.get(end).is_none_or(|b| !b.is_ascii_alphanumeric()) {
+            return Some(end);
+        }
+        pos = end;
+    }
+    None
+}
+
+/// Raw value of `/key` in a dictionary: a nested dictionary or array, a
+/// reference, a name, a string or a number
+fn dict_value<'a>(dict: &'a [u8], key: &str) -> Option<&'a [u8]> {
+    let start = key_end(dict, key, 0)?;
+    let rest = &dict[start..];
+    let offset = rest.len() - rest.trim_ascii_start().len();
+    let rest = &rest[offset..];
+
+    let len = if rest.starts_with(b"<<") {
+        balanced(rest, b"<<", b">>")?
+    } else if rest.starts_with(b"[") {
+        balanced(rest, b"[", b"]")?
+    } else if rest.starts_with(b"(") {
+        balanced(rest, b"(", b")")?
+    } else if let Some(found) = REF_RE.find(rest).filter(|m| m.start() == 0) {
+        found.end()
+    } else {
+        let body = rest.get(1..)?;
+        1 + body.iter().position(|b| b.is_ascii_whitespace() || b"/<>[]()".contains(b)).unwrap_or(body.len())
+    };
+    Some(&rest[..len])
+}
+
+/// Length of the group opened at the start of `data`
+fn balanced(data: &[u8], open: &[u8], close: &[u8]) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] == b'\\' && open == b"(" {
+            i += 2;
+            continue;
+        }
+        if data[i..].starts_with(open) {
+            depth += 1;
+            i += open.len();
+        } else if data[i..].starts_with(close) {
+            depth -= 1;
+            i += close.len();
+            if depth == 0 {
+                return Some(i);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Value of `/key` when it is a name, without the slash
+fn name_value(dict: &[u8], key: &str) -> Option<String> {
+    let value = dict_value(dict, key)?;
+    value.strip_prefix(b"/").map(|name| String::from_utf8_lossy(name).into_owned())
+}
+
+/// Maps glyph codes of a font to text
+#[derive(Debug, Clone, Default)]
+struct Font {
+    to_unicode: HashMap<u32, String>,
+    code_bytes: usize,
+    differences: HashMap<u8, String>,
+}
+
+impl Font {
+    fn load(objects: &HashMap<u32, PdfObject>, id: u32) -> Font {
+        let Some(obj) = objects.get(&id) else { return Font { code_bytes: 1, ..Default::default() } };
+        let composite = name_value(&obj.dict, "Subtype").as_deref() == Some("Type0");
+        let mut font = Font { code_bytes: if composite { 2 } else { 1 }, ..Default::default() };
+
+        if let Some(cmap) = dict_value(&obj.dict, "ToUnicode")
+            .and_then(reference)
+            .and_then(|id| objects.get(&id)?.stream.as_ref())
+        {
+            let (map, code_bytes) = parse_cmap(cmap);
+            font.to_unicode = map;
+            if code_bytes > 0 {
+                font.code_bytes = code_bytes;
+            }
+        }
+
+        if let Some(encoding) = dict_value(&obj.dict, "Encoding") {
+            if let Some(differences) = dict_value(resolve(objects, encoding), "Differences") {
+                font.differences = parse_differences(differences);
+            }
+        }
+        font
+    }
+
+    fn decode(&self, bytes: &[u8]) -> String {
+        let mut text = String::new();
+        for code in bytes.chunks(self.code_bytes.max(1)) {
+            let value = code.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32);
+            if let Some(mapped) = self.to_unicode.get(&value) {
+                text.push_str(mapped);
+            } else if code.len() == 1 {
+                let byte = code[0];
+                match self.differences.get(&byte).and_then(|name| glyph_text(name)) {
+                    Some(glyph) => text.push_str(&glyph),
+                    None => text.push_str(byte_text(byte)),
+                }
+            }
+        }
+        text
+    }
+}
+
+/// Code-to-text map of a ToUnicode CMap and the code width in bytes
+fn parse_cmap(cmap: &[u8]) -> (HashMap<u32, String>, usize) {
+    let text = String::from_utf8_lossy(cmap);
+    let mut map = HashMap::new();
+    let mut code_bytes = 0;
+
+    let hex = |s: &str| -> Vec<u8> {
+        let digits: Vec<u8> = s.bytes().filter(u8::is_ascii_hexdigit).collect();
+        digits
+            .chunks(2)
+            .filter_map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+            .collect()
+    };
+    let code = |bytes: &[u8]| bytes.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32);
+    let unicode = |bytes: &[u8]| {
+        let units: Vec<u16> = bytes.chunks(2).map(|p| ((p[0] as u16) << 8) | *p.get(1).unwrap_or(&0) as u16).collect();
+        String::from_utf16_lossy(&units)
+    };
+
+    for block in text.split("beginbfchar").skip(1) {
+        let block = block.split("endbfchar").next().unwrap_or("");
+        let tokens: Vec<&str> = block.split('<').skip(1).filter_map(|t| t.split('>').next()).collect();
+        for pair in tokens.chunks_exact(2) {
+            let src = hex(pair[0]);
+            code_bytes = code_bytes.max(src.len());
+            map.insert(code(&src), unicode(&hex(pair[1])));
+        }
+    }
+
+    for block in text.split("beginbfrange").skip(1) {
+        let block = block.split("endbfrange").next().unwrap_or("");
+        for line in block.lines() {
+            let tokens: Vec<&str> = line.split('<').skip(1).filter_map(|t| t.split('>').next()).collect();
+            if tokens.len() < 3 {
+                continue;
+            }
+            let (lo, hi) = (hex(tokens[0]), hex(tokens[1]));
+            code_bytes = code_bytes.max(lo.len());
+            let (lo, hi) = (code(&lo), code(&hi));
+            if hi < lo || hi - lo > 0xFFFF {
+                continue;
+            }
+            if line.contains('[') {
+                // One destination per code
+                for (offset, dst) in tokens[2..].iter().enumerate() {
+                    map.insert(lo + offset as u32, unicode(&hex(dst)));
+                }
+            } else {
+                let dst = hex(tokens[2]);
+                let Some((&last, prefix)) = dst.split_last() else { continue };
+                for offset in 0..=(hi - lo) {
+                    let mut bytes = prefix.to_vec();
+                    let (value, overflow) = last.overflowing_add(offset as u8);
+                    if overflow || offset > 0xFF {
+                        break;
+                    }
+                    bytes.push(value);
+                    map.insert(lo + offset, unicode(&bytes));
+                }
+            }
+        }
+    }
+
+    (map, code_bytes)
+}
+
+/// `/Differences [ 11 /ff /fi 39 /quoteright ]` as code to glyph name
+fn parse_differences(array: &[u8]) -> HashMap<u8, String> {
+    let mut differences = HashMap::new();
+    let mut code: u32 = 0;
+    let text = String::from_utf8_lossy(array);
+    for token in text.trim_matches(|c| c == '[' || c == ']').replace('/', " /").split_whitespace() {
+        if let Some(name) = token.strip_prefix('/') {
+            if let Ok(byte) = u8::try_from(code) {
+                differences.insert(byte, name.to_string());
+            }
+            code += 1;
+        } else if let Ok(number) = token.parse() {
+            code = number;
+        }
+    }
+    differences
+}
+
+/// Text of a glyph name from the Adobe Glyph List subset papers use
+fn glyph_text(name: &str) -> Option<String> {
+    if name.len() == 1 && name.is_ascii() {
+        return Some(name.to_string());
+    }
+    if let Some(hex) = name.strip_prefix("uni").filter(|h| h.len() == 4) {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32).map(String::from);
+    }
+    let text = match name {
+        "space" => " ",
+        "zero" => "0",
+        "one" => "1",
+        "two" => "2",
+        "three" => "3",
+        "four" => "4",
+        "five" => "5",
+        "six" => "6",
+        "seven" => "7",
+        "eight" => "8",
+        "nine" => "9",
+        "period" => ".",
+        "comma" => ",",
+        "colon" => ":",
+        "semicolon" => ";",
+        "hyphen" | "minus" => "-",
+        "endash" => "\u{2013}",
+        "emdash" => "\u{2014}",
+        "parenleft" => "(",
+        "parenright" => ")",
+        "bracketleft" => "[",
+        "bracketright" => "]",
+        "slash" => "/",
+        "question" => "?",
+        "exclam" => "!",
+        "percent" => "%",
+        "ampersand" => "&",
+        "plus" => "+",
+        "equal" => "=",
+        "quoteright" | "quotesingle" => "'",
+        "quoteleft" => "\u{2018}",
+        "quotedblleft" => "\u{201C}",
+        "quotedblright" => "\u{201D}",
+        "quotedbl" => "\"",
+        "ff" => "ff",
+        "fi" => "fi",
+        "fl" => "fl",
+        "ffi" => "ffi",
+        "ffl" => "ffl",
+        "dotlessi" => "i",
+        "bullet" => "\u{2022}",
+        _ => return None,
+    };
+    Some(text.to_string())
+}
+
+/// Text of a byte in fonts without a map: ASCII, plus the TeX ligature slots
+fn byte_text(byte: u8) -> &'static str {
+    const ASCII: &str = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+    match byte {
+        0x20..=0x7E => &ASCII[(byte - 0x20) as usize..(byte - 0x1F) as usize],
+        0x0B => "ff",
+        0x0C => "fi",
+        0x0D => "fl",
+        0x0E => "ffi",
+        0x0F => "ffl",
+        _ => "",
+    }
+}
+
+/// Token of a content stream
+#[derive(Debug, Clone)]
+enum Token {
+    Number(f32),
+    Str(Vec<u8>),
+    Name(String),
+    Array(Vec<Token>),
+    Op(String),
+}
+
+/// Tokenize a content stream; inline images and dictionaries are skipped
+fn tokenize(data: &[u8]) -> Vec<Token> {
+    let mut stack: Vec<Vec<Token>> = vec![Vec::new()];
+    let mut i = 0;
+
+    while i < data.len() {
+        let b = data[i];
+        match b {
+            b'%' => {
+                while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            b'(' => {
+                let (bytes, end) = literal_string(data, i);
+                push(&mut stack, Token::Str(bytes));
+                i = end;
+            }
+            b'<' if data.get(i + 1) == Some(&b'<') => i += 2,
+            b'>' if data.get(i + 1) == Some(&b'>') => i += 2,
+            b'<' => {
+                let end = find(data, b">", i).unwrap_or(data.len());
+                let caps = HEX_RE.captures(&data[i..(end + 1).min(data.len())]);
+                let digits: Vec<u8> = caps
+                    .map(|c| c[1].iter().copied().filter(u8::is_ascii_hexdigit).collect())
+                    .unwrap_or_default();
+                let bytes = digits
+                    .chunks(2)
+                    .filter_map(|pair| {
+                        let pair = if pair.len() == 1 { vec![pair[0], b'0'] } else { pair.to_vec() };
+                        u8::from_str_radix(std::str::from_utf8(&pair).ok()?, 16).ok()
+                    })
+                    .collect();
+                push(&mut stack, Token::Str(bytes));
+                i = end + 1;
+            }
+            b'[' => {
+                stack.push(Vec::new());
+                i += 1;
+            }
+            b']' => {
+                if stack.len() > 1 {
+                    let array = stack.pop().unwrap_or_default();
+                    push(&mut stack, Token::Array(array));
+                }
+                i += 1;
+            }
+            b'/' => {
+                let end = token_end(data, i + 1);
+                push(&mut stack, Token::Name(String::from_utf8_lossy(&data[i + 1..end]).into_owned()));
+                i = end;
+            }
+            _ if b.is_ascii_whitespace() => i += 1,
+            _ => {
+                let end = token_end(data, i).max(i + 1);
+                let word = String::from_utf8_lossy(&data[i..end]).into_owned();
+                i = end;
+                if let Ok(number) = word.parse::<f32>() {
+                    push(&mut stack, Token::Number(number));
+                } else if word == "ID" {
+                    // Inline image data runs to "EI"
+                    i = find(data, b"EI", i).map_or(data.len(), |p| p + 2);
+                } else {
+                    push(&mut stack, Token::Op(word));
+                }
+            }
+        }
+    }
+
+    stack.into_iter().flatten().collect()
+}
+
+fn push(stack: &mut [Vec<Token>], token: Token) {
+    if let Some(top) = stack.last_mut() {
+        top.push(token);
+    }
+}
+
+fn token_end(data: &[u8], from: usize) -> usize {
+    data[from..]
+        .iter()
+        .position(|b| b.is_ascii_whitespace() || b"/<>[]()%".contains(b))
+        .map_or(data.len(), |p| from + p)
+}
+
+/// Bytes of the literal string starting at `start`, and the position past it
+fn literal_string(data: &[u8], start: usize) -> (Vec<u8>, usize) {
+    let mut bytes = Vec::new();
+    let mut depth = 0;
+    let mut i = start;
+
+    while i < data.len() {
+        let b = data[i];
+        match b {
+            b'(' => {
+                if depth > 0 {
+                    bytes.push(b);
+                }
+                depth += 1;
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return (bytes, i + 1);
+                }
+                bytes.push(b);
+            }
+            b'\\' => {
+                i += 1;
+                let Some(&next) = data.get(i) else { break };
+                match next {
+                    b'n' => bytes.push(b'\n'),
+                    b'r' => bytes.push(b'\r'),
+                    b't' => bytes.push(b'\t'),
+                    b'b' => bytes.push(0x08),
+                    b'f' => bytes.push(0x0C),
+                    b'0'..=b'7' => {
+                        let digits: Vec<u8> = data[i..].iter().take(3).take_while(|d| (b'0'..=b'7').contains(d)).copied().collect();
+                        let value = digits.iter().fold(0u32, |acc, d| acc * 8 + (d - b'0') as u32);
+                        bytes.push(value as u8);
+                        i += digits.len() - 1;
+                    }
+                    b'\r' | b'\n' => {}
+                    _ => bytes.push(next),
+                }
+            }
+            _ => bytes.push(b),
+        }
+        i += 1;
+    }
+    (bytes, data.len())
+}
+
+/// Text of one page, with line breaks where the baseline moves
+fn render_page(content: &[u8], fonts: &HashMap<String, Font>) -> String {
+    let fallback = Font { code_bytes: 1, ..Default::default() };
+    let mut out = String::new();
+    let mut operands: Vec<Token> = Vec::new();
+    let mut font = &fallback;
+    let mut font_size: f32 = 10.0;
+    let mut scale: f32 = 1.0; // vertical scale of the text matrix
+    let mut line_y: f32 = 0.0;
+    let mut emitted_y: Option<f32> = None;
+
+    for token in tokenize(content) {
+        let Token::Op(op) = token else {
+            operands.push(token);
+            continue;
+        };
+        let n = operands.len();
+        match op.as_str() {
+            "BT" => {
+                line_y = 0.0;
+                scale = 1.0;
+            }
+            "Tf" => {
+                if let Some(Token::Name(name)) = operands.get(n.wrapping_sub(2)) {
+                    font = fonts.get(name).unwrap_or(&fallback);
+                }
+                font_size = number(operands.last()).abs().max(1.0);
+            }
+            "Tm" if n >= 6 => {
+                scale = number(operands.get(n - 3)).abs().max(f32::EPSILON);
+                let y = number(operands.get(n - 1));
+                if emitted_y.is_some_and(|last| (y - last).abs() <= font_size * scale * 0.5) && !out.ends_with([' ', '\n']) {
+                    out.push(' ');
+                }
+                line_y = y;
+            }
+            "Td" | "TD" if n >= 2 => {
+                let (tx, ty) = (number(operands.get(n - 2)), number(operands.get(n - 1)));
+                line_y += ty * scale;
+                if ty == 0.0 && tx > 0.0 && !out.ends_with([' ', '\n']) {
+                    out.push(' ');
+                }
+            }
+            "T*" => line_y -= font_size * scale * 1.2,
+            "Tj" | "'" | "\"" => {
+                if op != "Tj" {
+                    line_y -= font_size * scale * 1.2;
+                }
+                if let Some(Token::Str(bytes)) = operands.last() {
+                    show(&mut out, &font.decode(bytes), line_y, font_size * scale, &mut emitted_y);
+                }
+            }
+            "TJ" => {
+                if let Some(Token::Array(items)) = operands.last() {
+                    let mut text = String::new();
+                    for item in items {
+                        match item {
+                            Token::Str(bytes) => text.push_str(&font.decode(bytes)),
+                            // A kern wider than a fifth of an em is a word space
+                            Token::Number(kern) if *kern <= -WORD_SPACE_KERN && !text.ends_with(' ') => text.push(' '),
+                            _ => {}
+                        }
+                    }
+                    show(&mut out, &text, line_y, font_size * scale, &mut emitted_y);
+                }
+            }
+            _ => {}
+        }
+        operands.clear();
+    }
+
+    out
+}
+
+fn number(token: Option<&Token>) -> f32 {
+    match token {
+        Some(Token::Number(n)) => *n,
+        _ => 0.0,
+    }
+}
+
+/// Append shown text, starting a new line if the baseline moved by more
+/// than half the font size
+fn show(out: &mut String, text: &str, y: f32, size: f32, emitted_y: &mut Option<f32>) {
+    if let Some(last) = *emitted_y {
+        if (y - last).abs() > size * 0.5 && !out.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    *emitted_y = Some(y);
+    out.push_str(text);
+}
+
+/// Normalize extracted text: trim lines, drop page numbers, join words
+/// hyphenated across lines
+fn clean_text(text: &str) -> String {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.chars().all(|c| c.is_ascii_digit()) || line.is_empty())
+        .collect();
+    let joined = lines.join("\n");
+    let joined = HYPHEN_RE.replace_all(&joined, "$1$2");
+    let mut out = String::with_capacity(joined.len());
+    let mut blank = 0;
+    for line in joined.lines() {
+        blank = if line.is_empty() { blank + 1 } else { 0 };
+        if blank <= 1 {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Heading found on a line
+struct Heading {
+    title: String,
+    level: u8,
+    /// Text after the heading on the same line ("Abstract. We ...")
+    rest: Option<String>,
+}
+
+/// Split text into the abstract and sections
+///
+/// Headings are numbered lines ("2 Method", "2.1 Setup", "III. RESULTS")
+/// whose numbers follow each other, or common section names on a line of
+/// their own. Everything from the references or appendix on is left out.
+pub fn detect_sections(text: &str) -> (Option<String>, Vec<Section>) {
+    let mut r#abstract: Option<String> = None;
+    let mut sections: Vec<Section> = Vec::new();
+    let mut current: Option<Section> = None;
+    let mut in_abstract = false;
+    let mut top_number = 0;
+
+    for line in text.lines() {
+        if r#abstract.is_none() && sections.is_empty() && current.is_none() {
+            if let Some(caps) = ABSTRACT_RE.captures(line.trim()) {
+                in_abstract = true;
+                r#abstract = Some(caps.get(1).map_or(String::new(), |m| m.as_str().to_string()));
+                continue;
+            }
+        }
+
+        if let Some(heading) = detect_heading(line, &mut top_number) {
+            in_abstract = false;
+            finish_section(current.take(), &mut sections);
+            if BACK_MATTER.iter().any(|name| heading.title.eq_ignore_ascii_case(name) || heading.title.starts_with(name)) {
+                break;
+            }
+            current = Some(Section { title: heading.title, level: heading.level, body: heading.rest.unwrap_or_default() });
+            continue;
+        }
+
+        let target = if in_abstract {
+            r#abstract.as_mut()
+        } else {
+            current.as_mut().map(|section| &mut section.body)
+        };
+        if let Some(target) = target {
+            if !target.is_empty() {
+                target.push('\n');
+            }
+            target.push_str(line);
+        }
+    }
+    finish_section(current, &mut sections);
+
+    let r#abstract = r#abstract.map(|text| text.trim().to_string()).filter(|text| !text.is_empty());
+    (r#abstract, sections)
+}
+
+fn finish_section(section: Option<Section>, sections: &mut Vec<Section>) {
+    if let Some(mut section) = section {
+        section.body = section.body.trim().to_string();
+        if !should_skip_section(&section.title) {
+            sections.push(section);
+        }
+    }
+}
+
+/// Heading on `line`, tracking the last top-level section number
+fn detect_heading(line: &str, top_number: &mut u32) -> Option<Heading> {
+    let line = line.trim();
+    if line.is_empty() || line.split_whitespace().count() > MAX_HEADING_WORDS + 1 {
+        return None;
+    }
+
+    if let Some(caps) = NUMBERED_HEADING_RE.captures(line) {
+        let number = &caps[1];
+        let title = caps[2].trim().trim_end_matches(':').to_string();
+        let looks_like_title = !title.ends_with(['.', ',', ';'])
+            && !title.contains(['=', '(', '<', '>'])
+            // Table of contents entries end in dot leaders and a page number
+            && !title.contains("..")
+            && !title.contains("::")
+            && !title.contains(". .")
+            && title.chars().filter(|c| c.is_alphabetic()).count() >= 3;
+        if looks_like_title {
+            let mut parts = number.split('.');
+            let top = parts.next().and_then(section_number)?;
+            let depth = parts.count() as u8;
+            // Top-level numbers must count up, subsections stay in their section
+            let follows = if depth == 0 { top == *top_number + 1 } else { top == *top_number };
+            if follows {
+                *top_number = top;
+                return Some(Heading { title: title_case(&title), level: 2 + depth.min(4), rest: None });
+            }
+        }
+    }
+
+    let name = line.trim_end_matches([':', '.']);
+    UNNUMBERED_HEADINGS
+        .iter()
+        .chain(BACK_MATTER)
+        .find(|heading| name.eq_ignore_ascii_case(heading))
+        .map(|heading| Heading { title: heading.to_string(), level: 2, rest: None })
+}
+
+/// Arabic or Roman section number
+fn section_number(text: &str) -> Option<u32> {
+    if let Ok(number) = text.parse() {
+        return Some(number);
+    }
+    let value = |c| match c {
+        'I' => Some(1),
+        'V' => Some(5),
+        'X' => Some(10),
+        _ => None,
+    };
+    let digits: Vec<u32> = text.chars().map(value).collect::<Option<_>>()?;
+    Some(digits.iter().enumerate().fold(0, |acc, (i, &d)| {
+        if digits.get(i + 1).is_some_and(|&next| next > d) { acc - d as i64 } else { acc + d as i64 }
+    }) as u32)
+}
+
+/// "RELATED WORK" becomes "Related Work"; mixed-case titles are kept
+fn title_case(title: &str) -> String {
+    if title.chars().any(|c| c.is_lowercase()) {
+        return title.to_string();
+    }
+    title
+        .split_whitespace()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| first.to_string() + &chars.as_str().to_lowercase())
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    /// A two-page PDF: page 1 uses a Type0 font with a ToUnicode CMap and
+    /// packs its font in an object stream, page 2 a Type1 font with a
+    /// Differences encoding
+    fn sample_pdf() -> Vec<u8> {
+        let compress = |data: &[u8]| {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        };
+        let stream = |pdf: &mut Vec<u8>, id: u32, dict: &str, data: &[u8]| {
+            let data = compress(data);
+            pdf.extend(format!("{} 0 obj\n<< {} /Filter /FlateDecode /Length {} >>\nstream\n", id, dict, data.len()).bytes());
+            pdf.extend(data);
+            pdf.extend(b"\nendstream\nendobj\n");
+        };
+
+        let page1 = b"BT /F1 12 Tf 72 720 Td <000100020003> Tj ET\n\
+            BT /F1 10 Tf 72 700 Td [<0004> -300 <0005>] TJ ET";
+        let page2 = b"BT /F2 10 Tf 1 0 0 1 72 720 Tm (2 Method) Tj 0 -12 Td [(W) 20 (e \x0crst train.)] TJ\n\
+            0 -12 Td (Con\\(clusion\\)s) Tj T* (3 Conclusion) Tj T* (It works.) Tj ET";
+        let cmap = b"/CIDInit /ProcSet findresource begin\n1 begincodespacerange <0000> <FFFF> endcodespacerange\n\
+            3 beginbfchar\n<0001> <0031>\n<0002> <0020>\n<0003> <0049006E00740072006F>\nendbfchar\n\
+            1 beginbfrange\n<0004> <0005> [<0054006F> <0069006E0074>]\nendbfrange\nendcmap";
+
+        let mut pdf = b"%PDF-1.5\n".to_vec();
+        pdf.extend(b"1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n");
+        pdf.extend(b"2 0 obj\n<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /Resources << /Font << /F1 5 0 R /F2 6 0 R >> >> >>\nendobj\n");
+        pdf.extend(b"3 0 obj\n<< /Type /Page /Parent 2 0 R /Contents 7 0 R >>\nendobj\n");
+        pdf.extend(b"4 0 obj\n<< /Type /Page /Parent 2 0 R /Contents [8 0 R] >>\nendobj\n");
+        let packed = b"5 0 << /Type /Font /Subtype /Type0 /BaseFont /Sans /ToUnicode 9 0 R >>";
+        let first = packed.iter().position(|&b| b == b'<').unwrap();
+        stream(&mut pdf, 10, &format!("/Type /ObjStm /N 1 /First {}", first), packed);
+        pdf.extend(b"6 0 obj\n<< /Type /Font /Subtype /Type1 /BaseFont /CMR10 /Encoding << /Differences [12 /fi] >> >>\nendobj\n");
+        stream(&mut pdf, 7, "", page1);
+        stream(&mut pdf, 8, "", page2);
+        stream(&mut pdf, 9, "", cmap);
+        pdf.extend(b"trailer\n<< /Root 1 0 R >>\n%%EOF\n");
+        pdf
+    }
+
+    #[test]
+    fn test_extract_text() {
+        let text = extract_text(&sample_pdf()).unwrap();
+        assert_eq!(
+            text,
+            "1 Intro\nTo int\n\n2 Method\nWe first train.\nCon(clusion)s\n3 Conclusion\nIt works."
+        );
+    }
+
+    #[test]
+    fn test_rejects_decompression_bomb() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&vec![0u8; MAX_DECODED_STREAM + 1]).unwrap();
+        let bomb = encoder.finish().unwrap();
+        assert!(matches!(decode_stream(b"/Filter /FlateDecode", &bomb), Err(PdfParseError::TooLarge)));
+
+        let mut pdf = b"%PDF-1.5\n1 0 obj\n<< /Filter /FlateDecode >>\nstream\n".to_vec();
+        pdf.extend(bomb);
+        pdf.extend(b"\nendstream\nendobj\n");
+        assert!(matches!(extract_text(&pdf), Err(PdfParseError::TooLarge)));
+    }
+
+    #[test]
+    fn test_rejects_non_pdf() {
+        assert!(matches!(extract_text(b"<html></html>"), Err(PdfParseError::NotPdf)));
+        assert!(matches!(
+            extract_text(b"%PDF-1.4\ntrailer\n<< /Root 1 0 R /Encrypt 2 0 R >>"),
+            Err(PdfParseError::Encrypted)
+        ));
+    }
+
+    #[test]
+    fn test_detect_sections() {
+        let text = "Sparse Routing\nAnn Author\nAbstract\nWe route tokens.\n\
+            I. INTRODUCTION\nExperts are sparse.\n\
+            2 Results were strong.\n\
+            II. METHOD\nA router picks k\nexperts.\n\
+            2.1 Load Balancing\nAn auxiliary loss.\n\
+            4 Unrelated Table Row\n\
+            Conclusion\nIt works.\n\
+            References\n[1] Shazeer. 2017.\n\
+            A Proofs\nOmitted.";
+        let (r#abstract, sections) = detect_sections(text);
+
+        assert_eq!(r#abstract.as_deref(), Some("We route tokens."));
+        let outline: Vec<(u8, &str)> = sections.iter().map(|s| (s.level, s.title.as_str())).collect();
+        assert_eq!(outline, vec![(2, "Introduction"), (2, "Method"), (3, "Load Balancing"), (2, "Conclusion")]);
+        assert_eq!(sections[0].body, "Experts are sparse.\n2 Results were strong.");
+        assert_eq!(sections[2].body, "An auxiliary loss.\n4 Unrelated Table Row");
+        assert_eq!(sections[3].body, "It works.");
+    }
+
+    #[test]
+    fn test_inline_abstract() {
+        let (r#abstract, _) = detect_sections("Title\nAbstract\u{2014}We route tokens.\n1 Introduction\nText.");
+        assert_eq!(r#abstract.as_deref(), Some("We route tokens."));
+    }
+}
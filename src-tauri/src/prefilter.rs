@@ -0,0 +1,345 @@
+//! Local pre-filter ahead of the LLM relevance call
+//!
+//! Each enabled topic scores a paper 0-100 from its title and abstract:
+//! BM25 of the topic's keywords (IDF over the papers of the fetch), scaled
+//! by the topic's category weights. A negative keyword scores 0 and an
+//! allowed author 100. Papers whose best score is below the floor are
+//! dropped and papers reaching the ceiling accepted, both without calling
+//! the LLM. Topics without keywords or authors never decide, so a paper is
+//! only dropped when every topic could judge it.
+
+use crate::arxiv::ArxivEntry;
+use crate::authors::PersonName;
+use crate::llm::RelevanceResult;
+use crate::models::{PrefilterConfig, TopicConfig};
+use std::collections::{HashMap, HashSet};
+
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+/// BM25 that maps to a score of 50
+const HALF_SCORE_BM25: f64 = 5.0;
+/// Keyword terms this long also match longer words ("transformer" → "transformers")
+const MIN_PREFIX_LEN: usize = 4;
+
+/// Decision of the pre-filter for a paper it could judge
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrefilterVerdict {
+    /// Relevant without asking the LLM, for `topics`
+    Accept { score: i32, topics: Vec<String>, reason: String },
+    /// Not relevant to any topic
+    Reject { score: i32, reason: String },
+}
+
+impl PrefilterVerdict {
+    pub fn accepted(&self) -> bool {
+        matches!(self, PrefilterVerdict::Accept { .. })
+    }
+
+    /// The verdict in place of the LLM's relevance result
+    pub fn into_relevance(self) -> RelevanceResult {
+        let (score, reason, suggested_topics) = match self {
+            PrefilterVerdict::Accept { score, topics, reason } => (score, reason, topics),
+            PrefilterVerdict::Reject { score, reason } => (score, reason, vec![]),
+        };
+        RelevanceResult { score, reason, suggested_tags: vec![], suggested_topics }
+    }
+}
+
+/// Lowercase alphanumeric words of a text
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn term_matches(token: &str, term: &str) -> bool {
+    token == term || (term.chars().count() >= MIN_PREFIX_LEN && token.starts_with(term))
+}
+
+/// Whether `tokens` contain the words of `phrase` in order, the last word
+/// possibly as a prefix ("language model" matches "language models")
+pub fn contains_phrase(tokens: &[String], phrase: &str) -> bool {
+    let words = tokenize(phrase);
+    let Some((last, init)) = words.split_last() else {
+        return false;
+    };
+    tokens.windows(words.len()).any(|window| {
+        window[..init.len()] == *init && term_matches(&window[init.len()], last)
+    })
+}
+
+/// Title and abstract of a paper as words
+struct Document {
+    tokens: Vec<String>,
+    counts: HashMap<String, usize>,
+}
+
+impl Document {
+    fn new(entry: &ArxivEntry) -> Self {
+        let tokens = tokenize(&format!("{} {}", entry.title, entry.summary));
+        let mut counts = HashMap::new();
+        for token in &tokens {
+            *counts.entry(token.clone()).or_insert(0) += 1;
+        }
+        Self { tokens, counts }
+    }
+
+    fn term_frequency(&self, term: &str) -> usize {
+        self.counts
+            .iter()
+            .filter(|(token, _)| term_matches(token, term))
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+/// Pre-filter for the papers of one fetch
+pub struct Prefilter {
+    config: PrefilterConfig,
+    idf: HashMap<String, f64>,
+    average_length: f64,
+}
+
+impl Prefilter {
+    /// IDF of every keyword term is computed over `entries`
+    pub fn new(config: PrefilterConfig, topics: &[TopicConfig], entries: &[ArxivEntry]) -> Self {
+        let documents: Vec<Document> = entries.iter().map(Document::new).collect();
+        let terms: HashSet<String> = topics.iter().flat_map(keyword_terms).collect();
+
+        let total = documents.len() as f64;
+        let idf = terms
+            .into_iter()
+            .map(|term| {
+                let containing = documents.iter().filter(|d| d.term_frequency(&term) > 0).count() as f64;
+                let idf = (1.0 + (total - containing + 0.5) / (containing + 0.5)).ln();
+                (term, idf)
+            })
+            .collect();
+        let average_length = if documents.is_empty() {
+            1.0
+        } else {
+            documents.iter().map(|d| d.tokens.len()).sum::<usize>() as f64 / total
+        };
+
+        Self { config, idf, average_length: average_length.max(1.0) }
+    }
+
+    /// Verdict for a paper on the enabled `topics`, `None` when the LLM has
+    /// to decide
+    pub fn verdict(&self, entry: &ArxivEntry, topics: &[TopicConfig]) -> Option<PrefilterVerdict> {
+        let document = Document::new(entry);
+        let scores: Vec<(&TopicConfig, Option<(f64, String)>)> = topics
+            .iter()
+            .filter(|topic| topic.enabled)
+            .map(|topic| (topic, self.topic_score(topic, entry, &document)))
+            .collect();
+
+        let accepted: Vec<(&TopicConfig, f64, &str)> = scores
+            .iter()
+            .filter_map(|(topic, score)| {
+                let (score, reason) = score.as_ref()?;
+                (*score >= self.config.ceiling).then_some((*topic, *score, reason.as_str()))
+            })
+            .collect();
+        if let Some((_, score, reason)) = accepted.iter().max_by(|a, b| a.1.total_cmp(&b.1)) {
+            return Some(PrefilterVerdict::Accept {
+                score: score.round() as i32,
+                topics: accepted.iter().map(|(topic, _, _)| topic.key.clone()).collect(),
+                reason: format!("Pre-filter: {}", reason),
+            });
+        }
+
+        // Every topic must have judged the paper to drop it
+        if scores.is_empty() || scores.iter().any(|(_, score)| score.is_none()) {
+            return None;
+        }
+        let (_, best) = scores
+            .iter()
+            .filter_map(|(topic, score)| Some((topic, score.as_ref()?)))
+            .max_by(|a, b| a.1 .0.total_cmp(&b.1 .0))?;
+        (best.0 < self.config.floor).then(|| PrefilterVerdict::Reject {
+            score: best.0.round() as i32,
+            reason: format!("Pre-filter: {}", best.1),
+        })
+    }
+
+    /// Score of a paper for one topic with the reason for it, `None` when
+    /// the topic has nothing to judge by
+    fn topic_score(&self, topic: &TopicConfig, entry: &ArxivEntry, document: &Document) -> Option<(f64, String)> {
+        if let Some(negative) = topic
+            .negative_keywords
+            .iter()
+            .flatten()
+            .find(|keyword| contains_phrase(&document.tokens, keyword))
+        {
+            return Some((0.0, format!("negative keyword \"{}\" for {}", negative, topic.label)));
+        }
+
+        let allowed: Vec<PersonName> = topic.allowed_authors.iter().flatten().filter_map(|a| PersonName::parse(a)).collect();
+        if let Some(author) = entry
+            .authors
+            .iter()
+            .find(|a| PersonName::parse(&a.name).is_some_and(|name| allowed.iter().any(|w| w.matches(&name))))
+        {
+            return Some((100.0, format!("{} is an allowed author for {}", author.name, topic.label)));
+        }
+
+        let terms = keyword_terms(topic);
+        if terms.is_empty() {
+            return None;
+        }
+        let length_norm = 1.0 - B + B * document.tokens.len() as f64 / self.average_length;
+        let mut matched = Vec::new();
+        let mut bm25 = 0.0;
+        for term in &terms {
+            let tf = document.term_frequency(term) as f64;
+            if tf > 0.0 {
+                matched.push(term.as_str());
+                bm25 += self.idf.get(term).copied().unwrap_or(0.0) * tf * (K1 + 1.0) / (tf + K1 * length_norm);
+            }
+        }
+
+        let weight = topic
+            .category_weights
+            .as_ref()
+            .and_then(|weights| {
+                entry
+                    .categories
+                    .iter()
+                    .filter_map(|category| weights.get(&category.term).copied())
+                    .reduce(f64::max)
+            })
+            .unwrap_or(1.0);
+        let score = (100.0 * bm25 / (bm25 + HALF_SCORE_BM25) * weight).clamp(0.0, 100.0);
+
+        let reason = if matched.is_empty() {
+            format!("no keyword of {} mentioned", topic.label)
+        } else {
+            format!("{} keywords {} (local score {:.0})", topic.label, matched.join(", "), score)
+        };
+        Some((score, reason))
+    }
+}
+
+/// Distinct words of a topic's keywords
+fn keyword_terms(topic: &TopicConfig) -> Vec<String> {
+    let mut terms: Vec<String> = topic.keywords.iter().flatten().flat_map(|k| tokenize(k)).collect();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arxiv::{ArxivAuthor, ArxivCategory};
+
+    fn entry(title: &str, summary: &str, authors: &[&str], categories: &[&str]) -> ArxivEntry {
+        ArxivEntry {
+            id: title.to_string(),
+            title: title.to_string(),
+            summary: summary.to_string(),
+            published: String::new(),
+            updated: String::new(),
+            links: vec![],
+            authors: authors
+                .iter()
+                .map(|name| ArxivAuthor { name: name.to_string(), affiliation: None })
+                .collect(),
+            categories: categories
+                .iter()
+                .map(|term| ArxivCategory { term: term.to_string() })
+                .collect(),
+            base_url: None,
+        }
+    }
+
+    fn topic(keywords: &[&str]) -> TopicConfig {
+        TopicConfig {
+            key: "rl".to_string(),
+            label: "RL".to_string(),
+            description: String::new(),
+            color: String::new(),
+            enabled: true,
+            arxiv_categories: None,
+            max_papers_per_day: None,
+            deep_analysis_count: None,
+            quick_score_threshold: None,
+            keywords: Some(keywords.iter().map(|k| k.to_string()).collect()),
+            negative_keywords: None,
+            allowed_authors: None,
+            category_weights: None,
+            rule: None,
+        }
+    }
+
+    fn config() -> PrefilterConfig {
+        PrefilterConfig { enabled: true, floor: 5.0, ceiling: 40.0 }
+    }
+
+    #[test]
+    fn test_contains_phrase() {
+        let tokens = tokenize("Scaling large language models, in simulation.");
+        assert!(contains_phrase(&tokens, "language model"));
+        assert!(contains_phrase(&tokens, "Simulation"));
+        assert!(!contains_phrase(&tokens, "models language"));
+        // Short words only match whole
+        assert!(!contains_phrase(&tokens, "lar"));
+        assert!(!contains_phrase(&tokens, ""));
+    }
+
+    #[test]
+    fn test_verdicts() {
+        let entries = vec![
+            entry(
+                "Policy gradient methods",
+                "Reinforcement learning with policy gradients and reward shaping for reinforcement agents.",
+                &["Ada Lovelace"],
+                &["cs.LG"],
+            ),
+            entry("A survey of tax law", "Nothing about statistics.", &["B. Author"], &["econ.GN"]),
+            entry("Reward models", "We study rewards in a robotics setting.", &["C. Author"], &["cs.RO"]),
+        ];
+        let mut rl = topic(&["reinforcement learning", "policy gradient", "reward"]);
+        rl.negative_keywords = Some(vec!["robotic".to_string()]);
+        let topics = [rl.clone()];
+        let prefilter = Prefilter::new(config(), &topics, &entries);
+
+        match prefilter.verdict(&entries[0], &topics) {
+            Some(PrefilterVerdict::Accept { topics, reason, .. }) => {
+                assert_eq!(topics, vec!["rl"]);
+                assert!(reason.contains("policy"), "{}", reason);
+            }
+            other => panic!("expected accept, got {:?}", other),
+        }
+        assert_eq!(
+            prefilter.verdict(&entries[1], &topics),
+            Some(PrefilterVerdict::Reject { score: 0, reason: "Pre-filter: no keyword of RL mentioned".to_string() })
+        );
+        match prefilter.verdict(&entries[2], &topics) {
+            Some(PrefilterVerdict::Reject { reason, .. }) => assert!(reason.contains("negative keyword")),
+            other => panic!("expected reject, got {:?}", other),
+        }
+
+        // Allowed authors win over a missing keyword; a zero category weight
+        // silences a category
+        rl.allowed_authors = Some(vec!["b author".to_string()]);
+        rl.category_weights = Some([("cs.LG".to_string(), 0.0)].into_iter().collect());
+        let topics = [rl];
+        let prefilter = Prefilter::new(config(), &topics, &entries);
+        assert!(matches!(prefilter.verdict(&entries[1], &topics), Some(PrefilterVerdict::Accept { score: 100, .. })));
+        assert!(matches!(prefilter.verdict(&entries[0], &topics), Some(PrefilterVerdict::Reject { .. })));
+    }
+
+    #[test]
+    fn test_topics_without_keywords_leave_it_to_the_llm() {
+        let entries = vec![entry("A survey of tax law", "Nothing relevant.", &[], &[])];
+        let mut open = topic(&[]);
+        open.key = "open".to_string();
+        let topics = [topic(&["reinforcement"]), open];
+        let prefilter = Prefilter::new(config(), &topics, &entries);
+        assert_eq!(prefilter.verdict(&entries[0], &topics), None);
+    }
+}
@@ -0,0 +1,282 @@
+//! "More like this" recommendations
+//!
+//! Papers the user kept (added to a collection, deep-analyzed or opened) and
+//! papers marked as spam make up a profile: the weighted sum of their
+//! embedding vectors. New papers are ranked by similarity to the kept papers
+//! minus a share of their similarity to the spam.
+
+use crate::database::interactions::PaperSignals;
+use crate::database::{EmbeddingRepository, InteractionRepository, PaperRepository};
+use crate::embeddings::{cosine_similarity, Embedder, EmbeddingError};
+use crate::models::Recommendation;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+/// Papers fetched within this many days are candidates for recommendation
+pub const DEFAULT_RECENT_DAYS: i64 = 7;
+
+const COLLECTION_WEIGHT: f32 = 3.0;
+const DEEP_ANALYSIS_WEIGHT: f32 = 1.0;
+/// Scaled by the log of the number of opens
+const OPEN_WEIGHT: f32 = 1.0;
+const SPAM_WEIGHT: f32 = -1.0;
+/// Share of the similarity to spam subtracted from a paper's score
+const SPAM_PENALTY: f64 = 0.5;
+
+/// How much a paper tells about the user's interests: positive when kept,
+/// negative when spam, 0 without any signal
+pub fn signal_weight(signals: &PaperSignals) -> f32 {
+    if signals.is_spam {
+        return SPAM_WEIGHT;
+    }
+    let mut weight = 0.0;
+    if signals.collection_count > 0 {
+        weight += COLLECTION_WEIGHT;
+    }
+    if signals.is_deep_analyzed {
+        weight += DEEP_ANALYSIS_WEIGHT;
+    }
+    if signals.open_count > 0 {
+        weight += OPEN_WEIGHT * (1.0 + signals.open_count as f32).ln();
+    }
+    weight
+}
+
+/// Score of one candidate paper
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredPaper {
+    pub paper_id: String,
+    pub score: f64,
+    pub similar_to: Option<String>,
+}
+
+/// Rank the papers fetched since `cutoff` that the user has not acted on
+///
+/// Deep-analyzed papers stay candidates, since new papers are deep-analyzed
+/// by the fetch itself, but their own vector is taken out of the profile
+/// before they are scored. Without any kept paper nothing is ranked.
+pub fn rank_candidates(
+    signals: &[PaperSignals],
+    vectors: &HashMap<String, Vec<f32>>,
+    cutoff: DateTime<Utc>,
+) -> Vec<ScoredPaper> {
+    let mut kept_sum: Vec<f32> = Vec::new();
+    let mut spam_sum: Vec<f32> = Vec::new();
+    let mut kept: Vec<(&str, &[f32])> = Vec::new();
+    for paper in signals {
+        let Some(vector) = vectors.get(&paper.paper_id) else {
+            continue;
+        };
+        let weight = signal_weight(paper);
+        if weight > 0.0 {
+            add_scaled(&mut kept_sum, vector, weight);
+            kept.push((&paper.paper_id, vector));
+        } else if weight < 0.0 {
+            add_scaled(&mut spam_sum, vector, -weight);
+        }
+    }
+    if kept.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<ScoredPaper> = signals
+        .iter()
+        .filter(|paper| is_candidate(paper, cutoff))
+        .filter_map(|paper| {
+            let vector = vectors.get(&paper.paper_id)?;
+            let mut profile = kept_sum.clone();
+            add_scaled(&mut profile, vector, -signal_weight(paper));
+
+            let mut score = cosine_similarity(vector, &profile) as f64;
+            if !spam_sum.is_empty() {
+                score -= SPAM_PENALTY * cosine_similarity(vector, &spam_sum) as f64;
+            }
+            let similar_to = kept
+                .iter()
+                .filter(|(id, _)| *id != paper.paper_id)
+                .map(|(id, kept_vector)| (*id, cosine_similarity(vector, kept_vector)))
+                .filter(|(_, similarity)| *similarity > 0.0)
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(id, _)| id.to_string());
+            Some(ScoredPaper { paper_id: paper.paper_id.clone(), score, similar_to })
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.paper_id.cmp(&b.paper_id)));
+    ranked
+}
+
+/// Fetched after `cutoff`, not spam, in no collection and never opened
+fn is_candidate(paper: &PaperSignals, cutoff: DateTime<Utc>) -> bool {
+    let recent = DateTime::parse_from_rfc3339(&paper.created_at)
+        .map(|created| created.with_timezone(&Utc) >= cutoff)
+        .unwrap_or(false);
+    recent && !paper.is_spam && paper.collection_count == 0 && paper.open_count == 0
+}
+
+fn add_scaled(sum: &mut Vec<f32>, vector: &[f32], scale: f32) {
+    if sum.is_empty() {
+        sum.resize(vector.len(), 0.0);
+    }
+    if sum.len() == vector.len() {
+        sum.iter_mut().zip(vector).for_each(|(s, v)| *s += scale * v);
+    }
+}
+
+/// Personalized ranking of the papers fetched in the last `days` days
+pub async fn recommend(
+    pool: &SqlitePool,
+    embedder: &Embedder,
+    days: i64,
+    limit: usize,
+) -> Result<Vec<Recommendation>, EmbeddingError> {
+    let vectors: HashMap<String, Vec<f32>> = EmbeddingRepository::new(pool)
+        .vectors(&embedder.model_id())
+        .await?
+        .into_iter()
+        .collect();
+    let signals = InteractionRepository::new(pool).signals().await?;
+
+    let ranked = rank_candidates(&signals, &vectors, Utc::now() - Duration::days(days));
+    load(pool, ranked.into_iter().take(limit)).await
+}
+
+/// Papers most similar to a paper, spam excluded
+///
+/// Empty until the paper has been embedded.
+pub async fn similar_papers(
+    pool: &SqlitePool,
+    embedder: &Embedder,
+    paper_id: &str,
+    limit: usize,
+) -> Result<Vec<Recommendation>, EmbeddingError> {
+    let vectors = EmbeddingRepository::new(pool).vectors(&embedder.model_id()).await?;
+    let Some((_, target)) = vectors.iter().find(|(id, _)| id == paper_id) else {
+        PaperRepository::new(pool).get_by_id(paper_id).await?;
+        return Ok(Vec::new());
+    };
+    let spam: Vec<String> = InteractionRepository::new(pool)
+        .signals()
+        .await?
+        .into_iter()
+        .filter(|paper| paper.is_spam)
+        .map(|paper| paper.paper_id)
+        .collect();
+
+    let mut ranked: Vec<ScoredPaper> = vectors
+        .iter()
+        .filter(|(id, _)| id != paper_id && !spam.contains(id))
+        .map(|(id, vector)| ScoredPaper {
+            paper_id: id.clone(),
+            score: cosine_similarity(target, vector) as f64,
+            similar_to: None,
+        })
+        .filter(|scored| scored.score > 0.0)
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.paper_id.cmp(&b.paper_id)));
+    load(pool, ranked.into_iter().take(limit)).await
+}
+
+async fn load(
+    pool: &SqlitePool,
+    ranked: impl Iterator<Item = ScoredPaper>,
+) -> Result<Vec<Recommendation>, EmbeddingError> {
+    let papers = PaperRepository::new(pool);
+    let mut recommendations = Vec::new();
+    for scored in ranked {
+        recommendations.push(Recommendation {
+            paper: papers.get_by_id(&scored.paper_id).await?,
+            score: scored.score,
+            similar_to: scored.similar_to,
+        });
+    }
+    Ok(recommendations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paper(id: &str, created_at: &str) -> PaperSignals {
+        PaperSignals {
+            paper_id: id.to_string(),
+            created_at: created_at.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_signal_weight() {
+        let mut signals = paper("a", "2024-01-01T00:00:00.000Z");
+        assert_eq!(signal_weight(&signals), 0.0);
+        signals.collection_count = 2;
+        signals.is_deep_analyzed = true;
+        assert_eq!(signal_weight(&signals), COLLECTION_WEIGHT + DEEP_ANALYSIS_WEIGHT);
+        signals.is_spam = true;
+        assert_eq!(signal_weight(&signals), SPAM_WEIGHT);
+    }
+
+    #[test]
+    fn test_rank_candidates_follows_kept_papers() {
+        let old = "2024-01-01T00:00:00.000Z";
+        let new = "2024-03-01T00:00:00.000Z";
+        let cutoff = DateTime::parse_from_rfc3339("2024-02-01T00:00:00Z").unwrap().with_timezone(&Utc);
+
+        let mut kept = paper("kept", old);
+        kept.collection_count = 1;
+        let mut spam = paper("spam", old);
+        spam.is_spam = true;
+        let mut opened = paper("opened", new);
+        opened.open_count = 3;
+        let signals = vec![
+            kept,
+            spam,
+            opened,
+            paper("like-kept", new),
+            paper("like-spam", new),
+            paper("old", old),
+        ];
+        let vectors: HashMap<String, Vec<f32>> = [
+            ("kept", vec![1.0, 0.0, 0.0]),
+            ("spam", vec![0.0, 1.0, 0.0]),
+            ("opened", vec![0.0, 0.0, 1.0]),
+            ("like-kept", vec![0.9, 0.1, 0.0]),
+            ("like-spam", vec![0.3, 0.9, 0.0]),
+            ("old", vec![1.0, 0.0, 0.0]),
+        ]
+        .into_iter()
+        .map(|(id, v)| (id.to_string(), v))
+        .collect();
+
+        let ranked = rank_candidates(&signals, &vectors, cutoff);
+        let ids: Vec<&str> = ranked.iter().map(|r| r.paper_id.as_str()).collect();
+        // Opened, spam and old papers are not candidates
+        assert_eq!(ids, vec!["like-kept", "like-spam"]);
+        assert_eq!(ranked[0].similar_to.as_deref(), Some("kept"));
+        assert!(ranked[1].score < 0.0);
+
+        // Nothing kept, nothing to learn from
+        assert!(rank_candidates(&signals[1..2], &vectors, cutoff).is_empty());
+    }
+
+    #[test]
+    fn test_deep_analyzed_candidate_is_not_its_own_profile() {
+        let new = "2024-03-01T00:00:00.000Z";
+        let cutoff = DateTime::parse_from_rfc3339("2024-02-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        let mut kept = paper("kept", new);
+        kept.collection_count = 1;
+        let mut analyzed = paper("analyzed", new);
+        analyzed.is_deep_analyzed = true;
+        let vectors: HashMap<String, Vec<f32>> = [
+            ("kept".to_string(), vec![1.0, 0.0]),
+            ("analyzed".to_string(), vec![0.0, 1.0]),
+        ]
+        .into_iter()
+        .collect();
+
+        let ranked = rank_candidates(&[kept, analyzed], &vectors, cutoff);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].score, 0.0);
+        assert_eq!(ranked[0].similar_to, None);
+    }
+}
@@ -143,6 +143,10 @@ impl ErrorClassifier for GlmErrorClassifier {
             LlmError::UnsupportedProvider(_) => {
                 RetryDecision::not_retryable("Unsupported LLM provider")
             }
+
+            LlmError::BudgetExceeded(reason) => {
+                RetryDecision::not_retryable(&format!("LLM budget exhausted: {}", reason))
+            }
         }
     }
 }
@@ -268,15 +272,131 @@ impl ErrorClassifier for ClaudeErrorClassifier {
             LlmError::UnsupportedProvider(_) => {
                 RetryDecision::not_retryable("Unsupported LLM provider")
             }
+
+            LlmError::BudgetExceeded(reason) => {
+                RetryDecision::not_retryable(&format!("LLM budget exhausted: {}", reason))
+            }
+        }
+    }
+}
+
+/// OpenAI-compatible (vLLM, llama.cpp server, Ollama, ...) error classifier
+///
+/// # Retry Policy
+/// - **Retry on**: 429 (rate limit), 500-599 (server errors), network errors, timeouts
+/// - **Don't retry on**: 400 (bad request), 401 (auth), 403 (forbidden), 404 (unknown model), parse errors
+///
+/// Self-hosted servers are often restarted or still loading a model, so
+/// connection failures are treated as transient.
+pub struct OpenAiErrorClassifier;
+
+impl ErrorClassifier for OpenAiErrorClassifier {
+    fn classify(&self, error: &LlmError) -> RetryDecision {
+        match error {
+            LlmError::RequestError(reqwest_err) => {
+                let status = reqwest_err.status();
+
+                match status {
+                    Some(s) if s.as_u16() == 429 => {
+                        RetryDecision::retryable(
+                            RetryableError::RateLimit,
+                            &format!("OpenAI-compatible rate limit exceeded (429) - {}", s.canonical_reason().unwrap_or("Too Many Requests")),
+                        )
+                    }
+                    Some(s) if s.as_u16() >= 500 => {
+                        RetryDecision::retryable(
+                            RetryableError::ServerError,
+                            &format!("OpenAI-compatible server error ({}) - {}", s.as_u16(), s.canonical_reason().unwrap_or("Internal Server Error")),
+                        )
+                    }
+                    Some(s) => {
+                        RetryDecision::not_retryable(
+                            &format!("OpenAI-compatible error ({}): {} - not retryable", s.as_u16(), s.canonical_reason().unwrap_or("Unknown")),
+                        )
+                    }
+                    None => {
+                        if reqwest_err.is_timeout() {
+                            RetryDecision::retryable(
+                                RetryableError::Timeout,
+                                &format!("OpenAI-compatible request timeout: {}", reqwest_err),
+                            )
+                        } else if reqwest_err.is_connect() {
+                            RetryDecision::retryable(
+                                RetryableError::NetworkError,
+                                &format!("OpenAI-compatible connection failed - is the server running? {}", reqwest_err),
+                            )
+                        } else {
+                            RetryDecision::retryable(
+                                RetryableError::NetworkError,
+                                &format!("OpenAI-compatible network error: {}", reqwest_err),
+                            )
+                        }
+                    }
+                }
+            }
+
+            LlmError::ApiError(msg) => {
+                let msg_lower = msg.to_lowercase();
+
+                if msg.contains("429") || msg_lower.contains("rate limit") || msg_lower.contains("too many requests") {
+                    RetryDecision::retryable(
+                        RetryableError::RateLimit,
+                        &format!("OpenAI-compatible rate limit exceeded: {}", msg),
+                    )
+                } else if msg.contains("401") || msg_lower.contains("unauthorized") || msg_lower.contains("invalid api key") {
+                    RetryDecision::not_retryable(
+                        &format!("OpenAI-compatible authentication failed - check API key: {}", msg),
+                    )
+                } else if msg.contains("404") || msg_lower.contains("model not found") {
+                    RetryDecision::not_retryable(
+                        &format!("OpenAI-compatible endpoint or model not found - check base URL and model name: {}", msg),
+                    )
+                } else if msg.contains("400") || msg_lower.contains("bad request") {
+                    RetryDecision::not_retryable(
+                        &format!("OpenAI-compatible bad request - check request format: {}", msg),
+                    )
+                } else if msg.contains("500") || msg.contains("502") || msg.contains("503") || msg.contains("504") ||
+                          msg_lower.contains("internal server error") || msg_lower.contains("service unavailable") {
+                    RetryDecision::retryable(
+                        RetryableError::ServerError,
+                        &format!("OpenAI-compatible server error: {}", msg),
+                    )
+                } else {
+                    RetryDecision::not_retryable(
+                        &format!("OpenAI-compatible API error: {}", msg),
+                    )
+                }
+            }
+
+            LlmError::ParseError(msg) => {
+                RetryDecision::not_retryable(
+                    &format!("OpenAI-compatible response parse error: {}", msg),
+                )
+            }
+
+            LlmError::NoApiKey => {
+                RetryDecision::not_retryable("No OpenAI-compatible API key configured")
+            }
+
+            LlmError::UnsupportedProvider(_) => {
+                RetryDecision::not_retryable("Unsupported LLM provider")
+            }
+
+            LlmError::BudgetExceeded(reason) => {
+                RetryDecision::not_retryable(&format!("LLM budget exhausted: {}", reason))
+            }
         }
     }
 }
 
 /// Get error classifier for the specified provider
+///
+/// Delegates to the provider's registered `LlmBackend`; unknown providers fall
+/// back to the GLM classifier's conservative policy.
 pub fn get_classifier(provider: LLMProvider) -> Box<dyn ErrorClassifier> {
-    match provider {
-        LLMProvider::Glm => Box::new(GlmErrorClassifier),
-        LLMProvider::Claude => Box::new(ClaudeErrorClassifier),
+    match crate::llm::get_backend(&provider) {
+        Ok(backend) => backend.classifier(),
+        Err(_) => Box::new(GlmErrorClassifier),
     }
 }
 
@@ -287,11 +407,12 @@ mod tests {
 
     // Helper to create a mock request error with status code
     fn create_mock_error(status: u16) -> LlmError {
-        let url = format!("https://api.example.com/{}", status);
-        let err = reqwest::Error::from(
-            reqwest::StatusCode::from_u16(status).unwrap()
-        ).with::<reqwest::Error>(reqwest::Error::from(std::io::Error::new(std::io::ErrorKind::Other, "test")));
-        LlmError::RequestError(err)
+        let response = tauri::http::Response::builder()
+            .status(StatusCode::from_u16(status).unwrap())
+            .body("")
+            .unwrap();
+        let err = reqwest::Response::from(response).error_for_status().unwrap_err();
+        LlmError::from(err)
     }
 
     #[test]
@@ -351,7 +472,19 @@ mod tests {
         let decision = classifier.classify(&error);
 
         assert!(!decision.should_retry);
-        assert!(decision.reason.contains("No API key"));
+        assert_eq!(decision.reason, "No GLM API key configured");
+    }
+
+    #[test]
+    fn test_glm_classifier_request_error_status() {
+        let classifier = GlmErrorClassifier;
+
+        let decision = classifier.classify(&create_mock_error(503));
+        assert!(decision.should_retry);
+        assert_eq!(decision.error_type, Some(RetryableError::ServerError));
+
+        let decision = classifier.classify(&create_mock_error(403));
+        assert!(!decision.should_retry);
     }
 
     #[test]
@@ -373,4 +506,24 @@ mod tests {
         assert!(decision.should_retry);
         assert_eq!(decision.error_type, Some(RetryableError::ServerError));
     }
+
+    #[test]
+    fn test_openai_classifier_503_retryable() {
+        let classifier = OpenAiErrorClassifier;
+        let error = LlmError::ApiError("OpenAI-compatible API error (503 Service Unavailable): model loading".to_string());
+        let decision = classifier.classify(&error);
+
+        assert!(decision.should_retry);
+        assert_eq!(decision.error_type, Some(RetryableError::ServerError));
+    }
+
+    #[test]
+    fn test_openai_classifier_404_not_retryable() {
+        let classifier = OpenAiErrorClassifier;
+        let error = LlmError::ApiError("OpenAI-compatible API error (404 Not Found): model not found".to_string());
+        let decision = classifier.classify(&error);
+
+        assert!(!decision.should_retry);
+        assert!(decision.reason.contains("not found"));
+    }
 }
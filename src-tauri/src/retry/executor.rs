@@ -172,23 +172,36 @@ impl RetryExecutor {
 mod tests {
     use super::*;
     use std::sync::atomic::{AtomicU32, Ordering};
-
-    // Mock operation that succeeds after N attempts
-    fn mock_operation(success_after: AtomicU32) -> impl Fn() -> Pin<Box<dyn std::future::Future<Output = Result<String, LlmError>> + Send>> {
+    use std::sync::Arc;
+
+    // Mock operation that fails with a server error until `success_after`
+    // attempts were made, counting them in `attempts`
+    fn mock_operation(
+        success_after: u32,
+        attempts: Arc<AtomicU32>,
+    ) -> impl Fn() -> Pin<Box<dyn std::future::Future<Output = Result<String, LlmError>> + Send>> {
         move || {
+            let attempts = attempts.clone();
             Box::pin(async move {
-                let attempt = success_after.fetch_add(1, Ordering::SeqCst);
-                if attempt >= success_after.load(Ordering::SeqCst) {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+                if attempt >= success_after {
                     Ok("Success".to_string())
                 } else {
-                    Err(LlmError::ApiError(format!("Attempt {}", attempt)))
+                    Err(LlmError::ApiError(format!("GLM API error: 500 internal server error (attempt {})", attempt)))
                 }
             })
         }
     }
 
-    // Note: These are basic structure tests. Full integration tests would require
-    // async test runtime and more sophisticated mocking.
+    fn fast_config(max_retries: u32) -> RetryConfig {
+        RetryConfig {
+            max_retries,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 1,
+            jitter_factor: 0.0,
+            ..Default::default()
+        }
+    }
 
     #[test]
     fn test_executor_creation() {
@@ -218,4 +231,24 @@ mod tests {
         // 1000 * 2^2 = 4000ms
         assert_eq!(delay.as_millis(), 4000);
     }
+
+    #[tokio::test]
+    async fn test_execute_retries_until_success() {
+        let executor = RetryExecutor::new(LLMProvider::Glm, fast_config(3));
+        let attempts = Arc::new(AtomicU32::new(0));
+
+        let result = executor.execute(mock_operation(2, attempts.clone()), "mock").await;
+        assert_eq!(result.unwrap(), "Success");
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_execute_gives_up_after_max_retries() {
+        let executor = RetryExecutor::new(LLMProvider::Glm, fast_config(1));
+        let attempts = Arc::new(AtomicU32::new(0));
+
+        let result = executor.execute(mock_operation(5, attempts.clone()), "mock").await;
+        assert!(matches!(result, Err(LlmError::ApiError(msg)) if msg.contains("attempt 1")));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
 }
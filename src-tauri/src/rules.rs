@@ -0,0 +1,513 @@
+//! Boolean topic rules
+//!
+//! A topic's rule is a hard requirement checked before relevance analysis,
+//! e.g. `(RLHF OR DPO) AND NOT robotics` or `NOT only_category:cs.CV`.
+//!
+//! - Terms match words or `"quoted phrases"` in the title or abstract, the
+//!   last word also as a prefix when it has 4 letters or more
+//! - `title:`, `abstract:` and `author:` restrict a term to one field;
+//!   `category:` matches any of the paper's categories and `only_category:`
+//!   papers whose every category is the given one
+//! - `AND`, `OR` and `NOT` are written in capitals, `NOT` binding tightest
+//!   and `OR` loosest; adjacent terms are joined by `AND`
+//!
+//! Papers failing the rule of every enabled topic are dropped without an
+//! LLM call, and failing a topic's rule takes that topic out of the paper's
+//! candidates. The rules a paper matched are explained in its `filter_reason`.
+
+use crate::arxiv::ArxivEntry;
+use crate::models::TopicConfig;
+use crate::prefilter::{contains_phrase, tokenize};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RuleError {
+    #[error("Unexpected \"{0}\" at position {1}")]
+    Unexpected(String, usize),
+
+    #[error("Unexpected end of rule, expected {0}")]
+    UnexpectedEnd(&'static str),
+
+    #[error("Unclosed quote at position {0}")]
+    UnclosedQuote(usize),
+
+    #[error("Unknown field \"{0}\" (expected title, abstract, author, category or only_category)")]
+    UnknownField(String),
+}
+
+/// Part of a paper a term is matched against
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    /// Title or abstract
+    Text,
+    Title,
+    Abstract,
+    Author,
+    Category,
+    OnlyCategory,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "title" => Some(Field::Title),
+            "abstract" => Some(Field::Abstract),
+            "author" => Some(Field::Author),
+            "category" => Some(Field::Category),
+            "only_category" => Some(Field::OnlyCategory),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    Term { field: Field, value: String },
+    Not(Box<Rule>),
+    And(Vec<Rule>),
+    Or(Vec<Rule>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(Field, String),
+}
+
+fn lex(rule: &str) -> Result<Vec<(Token, usize)>, RuleError> {
+    let chars: Vec<char> = rule.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let quoted = |start: usize| -> Result<(String, usize), RuleError> {
+        let end = chars[start + 1..]
+            .iter()
+            .position(|&c| c == '"')
+            .ok_or(RuleError::UnclosedQuote(start))?;
+        Ok((chars[start + 1..start + 1 + end].iter().collect(), start + end + 2))
+    };
+
+    while i < chars.len() {
+        let start = i;
+        match chars[i] {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push((Token::Open, start));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((Token::Close, start));
+                i += 1;
+            }
+            '"' => {
+                let (phrase, next) = quoted(i)?;
+                tokens.push((Token::Term(Field::Text, phrase), start));
+                i = next;
+            }
+            _ => {
+                while i < chars.len() && !chars[i].is_whitespace() && !matches!(chars[i], '(' | ')' | '"') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let token = match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => match word.split_once(':') {
+                        Some((name, value)) => {
+                            let field = Field::parse(&name.to_lowercase())
+                                .ok_or_else(|| RuleError::UnknownField(name.to_string()))?;
+                            if !value.is_empty() {
+                                Token::Term(field, value.to_string())
+                            } else if chars.get(i) == Some(&'"') {
+                                let (phrase, next) = quoted(i)?;
+                                i = next;
+                                Token::Term(field, phrase)
+                            } else {
+                                return Err(RuleError::Unexpected(word, start));
+                            }
+                        }
+                        None => Token::Term(Field::Text, word),
+                    },
+                };
+                tokens.push((token, start));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(token, _)| token)
+    }
+
+    fn unexpected(&self) -> RuleError {
+        match self.tokens.get(self.next) {
+            Some((token, position)) => {
+                let text = match token {
+                    Token::Open => "(".to_string(),
+                    Token::Close => ")".to_string(),
+                    Token::And => "AND".to_string(),
+                    Token::Or => "OR".to_string(),
+                    Token::Not => "NOT".to_string(),
+                    Token::Term(_, value) => value.clone(),
+                };
+                RuleError::Unexpected(text, *position)
+            }
+            None => RuleError::UnexpectedEnd("a term"),
+        }
+    }
+
+    fn or(&mut self) -> Result<Rule, RuleError> {
+        let mut rules = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next += 1;
+            rules.push(self.and()?);
+        }
+        Ok(if rules.len() == 1 { rules.remove(0) } else { Rule::Or(rules) })
+    }
+
+    fn and(&mut self) -> Result<Rule, RuleError> {
+        let mut rules = vec![self.not()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.next += 1,
+                Some(Token::Open | Token::Not | Token::Term(..)) => {}
+                _ => break,
+            }
+            rules.push(self.not()?);
+        }
+        Ok(if rules.len() == 1 { rules.remove(0) } else { Rule::And(rules) })
+    }
+
+    fn not(&mut self) -> Result<Rule, RuleError> {
+        if self.peek() == Some(&Token::Not) {
+            self.next += 1;
+            return Ok(Rule::Not(Box::new(self.not()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Rule, RuleError> {
+        match self.peek().cloned() {
+            Some(Token::Open) => {
+                self.next += 1;
+                let rule = self.or()?;
+                match self.peek() {
+                    Some(Token::Close) => {
+                        self.next += 1;
+                        Ok(rule)
+                    }
+                    Some(_) => Err(self.unexpected()),
+                    None => Err(RuleError::UnexpectedEnd("\")\"")),
+                }
+            }
+            Some(Token::Term(field, value)) => {
+                self.next += 1;
+                Ok(Rule::Term { field, value })
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+/// Parse a rule, `None` for a blank one
+pub fn parse(rule: &str) -> Result<Option<Rule>, RuleError> {
+    let tokens = lex(rule)?;
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+    let mut parser = Parser { tokens, next: 0 };
+    let parsed = parser.or()?;
+    if parser.next < parser.tokens.len() {
+        return Err(parser.unexpected());
+    }
+    Ok(Some(parsed))
+}
+
+/// Fields of a paper as rules see them
+pub struct RuleInput {
+    title: Vec<String>,
+    summary: Vec<String>,
+    authors: Vec<Vec<String>>,
+    categories: Vec<String>,
+}
+
+impl RuleInput {
+    pub fn new(entry: &ArxivEntry) -> Self {
+        Self {
+            title: tokenize(&entry.title),
+            summary: tokenize(&entry.summary),
+            authors: entry.authors.iter().map(|a| tokenize(&a.name)).collect(),
+            categories: entry.categories.iter().map(|c| c.term.to_lowercase()).collect(),
+        }
+    }
+}
+
+impl Rule {
+    /// Whether the paper satisfies the rule, and why
+    pub fn evaluate(&self, input: &RuleInput) -> (bool, String) {
+        match self {
+            Rule::Term { field, value } => {
+                let category = value.to_lowercase();
+                let matched = match field {
+                    Field::Text => contains_phrase(&input.title, value) || contains_phrase(&input.summary, value),
+                    Field::Title => contains_phrase(&input.title, value),
+                    Field::Abstract => contains_phrase(&input.summary, value),
+                    Field::Author => input.authors.iter().any(|name| contains_phrase(name, value)),
+                    Field::Category => input.categories.contains(&category),
+                    Field::OnlyCategory => {
+                        !input.categories.is_empty() && input.categories.iter().all(|c| *c == category)
+                    }
+                };
+                let explanation = match (field, matched) {
+                    (Field::Text, true) => format!("mentions \"{}\"", value),
+                    (Field::Text, false) => format!("does not mention \"{}\"", value),
+                    (Field::Title, true) => format!("title mentions \"{}\"", value),
+                    (Field::Title, false) => format!("title does not mention \"{}\"", value),
+                    (Field::Abstract, true) => format!("abstract mentions \"{}\"", value),
+                    (Field::Abstract, false) => format!("abstract does not mention \"{}\"", value),
+                    (Field::Author, true) => format!("has author \"{}\"", value),
+                    (Field::Author, false) => format!("has no author \"{}\"", value),
+                    (Field::Category, true) => format!("is in {}", value),
+                    (Field::Category, false) => format!("is not in {}", value),
+                    (Field::OnlyCategory, true) => format!("is only in {}", value),
+                    (Field::OnlyCategory, false) => format!("is not only in {}", value),
+                };
+                (matched, explanation)
+            }
+            Rule::Not(rule) => {
+                let (matched, explanation) = rule.evaluate(input);
+                (!matched, explanation)
+            }
+            // All parts explain a match, the first failing part a miss
+            Rule::And(rules) => combine(rules, input, false),
+            Rule::Or(rules) => combine(rules, input, true),
+        }
+    }
+}
+
+/// AND (`deciding` false) or OR (`deciding` true): the first part with the
+/// deciding outcome explains it, otherwise all parts together
+fn combine(rules: &[Rule], input: &RuleInput, deciding: bool) -> (bool, String) {
+    let mut explanations = Vec::new();
+    for rule in rules {
+        let (matched, explanation) = rule.evaluate(input);
+        if matched == deciding {
+            return (deciding, explanation);
+        }
+        explanations.push(explanation);
+    }
+    (!deciding, explanations.join(" and "))
+}
+
+/// A topic's rule applied to a paper
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleMatch {
+    pub topic: String,
+    pub label: String,
+    pub matched: bool,
+    pub explanation: String,
+}
+
+/// Rules of every enabled topic that has one
+#[derive(Debug)]
+pub struct TopicRules {
+    rules: Vec<(String, String, Rule)>,
+}
+
+impl TopicRules {
+    /// Parse the rules of all topics, keeping those of the enabled ones
+    pub fn compile(topics: &[TopicConfig]) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        for topic in topics {
+            let Some(rule) = topic.rule.as_deref() else {
+                continue;
+            };
+            let parsed = parse(rule).map_err(|e| format!("Invalid rule for topic {}: {}", topic.label, e))?;
+            if let Some(parsed) = parsed.filter(|_| topic.enabled) {
+                rules.push((topic.key.clone(), topic.label.clone(), parsed));
+            }
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn evaluate(&self, entry: &ArxivEntry) -> RuleOutcome {
+        let input = RuleInput::new(entry);
+        RuleOutcome {
+            matches: self
+                .rules
+                .iter()
+                .map(|(topic, label, rule)| {
+                    let (matched, explanation) = rule.evaluate(&input);
+                    RuleMatch { topic: topic.clone(), label: label.clone(), matched, explanation }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Rules of all topics applied to one paper
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleOutcome {
+    pub matches: Vec<RuleMatch>,
+}
+
+impl RuleOutcome {
+    /// Whether a topic is still a candidate: it has no rule or the paper matched it
+    pub fn allows(&self, topic: &str) -> bool {
+        self.matches.iter().all(|m| m.topic != topic || m.matched)
+    }
+
+    /// Topics still candidates for the paper
+    pub fn allowed_topics(&self, topics: &[TopicConfig]) -> Vec<TopicConfig> {
+        topics.iter().filter(|t| self.allows(&t.key)).cloned().collect()
+    }
+
+    /// Why the paper is dropped, when the rules leave no enabled topic
+    pub fn rejection(&self, topics: &[TopicConfig]) -> Option<String> {
+        if self.matches.is_empty() || topics.iter().any(|t| t.enabled && self.allows(&t.key)) {
+            return None;
+        }
+        Some(describe(self.matches.iter().filter(|m| !m.matched), "Failed rule"))
+    }
+
+    /// The rules the paper matched for `topics`, for its filter reason
+    pub fn reason_for(&self, topics: &[String]) -> Option<String> {
+        let matched: Vec<&RuleMatch> = self.matches.iter().filter(|m| m.matched && topics.contains(&m.topic)).collect();
+        (!matched.is_empty()).then(|| describe(matched.into_iter(), "Matched rule"))
+    }
+}
+
+fn describe<'a>(matches: impl Iterator<Item = &'a RuleMatch>, prefix: &str) -> String {
+    matches
+        .map(|m| format!("{} for {}: {}", prefix, m.label, m.explanation))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Filter reason with a note, such as the matched rules, appended
+pub fn append_reason(reason: Option<String>, note: Option<String>) -> Option<String> {
+    match (reason, note) {
+        (Some(reason), Some(note)) => Some(format!("{} ({})", reason, note)),
+        (reason, note) => reason.or(note),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arxiv::{ArxivAuthor, ArxivCategory};
+
+    fn entry(title: &str, summary: &str, categories: &[&str]) -> ArxivEntry {
+        ArxivEntry {
+            id: title.to_string(),
+            title: title.to_string(),
+            summary: summary.to_string(),
+            published: String::new(),
+            updated: String::new(),
+            links: vec![],
+            authors: vec![ArxivAuthor { name: "Ada Lovelace".to_string(), affiliation: None }],
+            categories: categories.iter().map(|term| ArxivCategory { term: term.to_string() }).collect(),
+            base_url: None,
+        }
+    }
+
+    fn eval(rule: &str, entry: &ArxivEntry) -> (bool, String) {
+        parse(rule).unwrap().unwrap().evaluate(&RuleInput::new(entry))
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let term = |value: &str| Rule::Term { field: Field::Text, value: value.to_string() };
+        assert_eq!(
+            parse("a OR b c AND NOT d").unwrap(),
+            Some(Rule::Or(vec![term("a"), Rule::And(vec![term("b"), term("c"), Rule::Not(Box::new(term("d")))])]))
+        );
+        assert_eq!(
+            parse("author:\"Ada Lovelace\" category:cs.CL").unwrap(),
+            Some(Rule::And(vec![
+                Rule::Term { field: Field::Author, value: "Ada Lovelace".to_string() },
+                Rule::Term { field: Field::Category, value: "cs.CL".to_string() },
+            ]))
+        );
+        assert_eq!(parse("  ").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("(a OR b"), Err(RuleError::UnexpectedEnd("\")\"")));
+        assert_eq!(parse("a AND"), Err(RuleError::UnexpectedEnd("a term")));
+        assert_eq!(parse("a ) b"), Err(RuleError::Unexpected(")".to_string(), 2)));
+        assert_eq!(parse("\"open"), Err(RuleError::UnclosedQuote(0)));
+        assert_eq!(parse("venue:icml"), Err(RuleError::UnknownField("venue".to_string())));
+    }
+
+    #[test]
+    fn test_evaluate_explains_outcome() {
+        let paper = entry(
+            "Direct Preference Optimization at scale",
+            "We revisit DPO for aligning language models.",
+            &["cs.CL", "cs.LG"],
+        );
+        let rule = "(RLHF OR DPO) AND NOT robotics";
+        assert_eq!(eval(rule, &paper), (true, "mentions \"DPO\" and does not mention \"robotics\"".to_string()));
+
+        let robot = entry("DPO for robotics", "Preference learning for robot arms.", &["cs.RO"]);
+        assert_eq!(eval(rule, &robot), (false, "mentions \"robotics\"".to_string()));
+        assert_eq!(eval("RLHF OR \"reward model\"", &robot).1, "does not mention \"RLHF\" and does not mention \"reward model\"");
+
+        let vision = entry("A vision paper", "Images.", &["cs.CV"]);
+        assert!(!eval("NOT only_category:cs.CV", &vision).0);
+        assert!(eval("NOT only_category:cs.CV", &paper).0);
+        assert!(eval("author:lovelace AND title:optimization", &paper).0);
+    }
+
+    #[test]
+    fn test_outcome_per_topic() {
+        let topic = |key: &str, rule: Option<&str>| TopicConfig {
+            key: key.to_string(),
+            label: key.to_uppercase(),
+            description: String::new(),
+            color: String::new(),
+            enabled: true,
+            arxiv_categories: None,
+            max_papers_per_day: None,
+            deep_analysis_count: None,
+            quick_score_threshold: None,
+            keywords: None,
+            negative_keywords: None,
+            allowed_authors: None,
+            category_weights: None,
+            rule: rule.map(str::to_string),
+        };
+        let paper = entry("DPO for robotics", "Preference learning.", &["cs.RO"]);
+        let topics = vec![topic("align", Some("DPO AND NOT robotics")), topic("robot", Some("category:cs.RO"))];
+        let outcome = TopicRules::compile(&topics).unwrap().evaluate(&paper);
+
+        assert!(!outcome.allows("align"));
+        assert_eq!(outcome.allowed_topics(&topics).len(), 1);
+        assert_eq!(outcome.rejection(&topics), None);
+        assert_eq!(outcome.reason_for(&["robot".to_string()]).as_deref(), Some("Matched rule for ROBOT: is in cs.RO"));
+
+        let rejected = outcome.rejection(&topics[..1]);
+        assert_eq!(rejected.as_deref(), Some("Failed rule for ALIGN: mentions \"robotics\""));
+        // Topics without a rule keep every paper
+        assert_eq!(outcome.rejection(&[topics[0].clone(), topic("other", None)]), None);
+
+        assert!(TopicRules::compile(&[topic("bad", Some("(DPO"))]).unwrap_err().contains("BAD"));
+    }
+}
@@ -154,11 +154,17 @@ impl ScheduleRunRepository {
         Self { pool: pool.clone() }
     }
 
-    /// Save a schedule run record
+    /// Save a schedule run record, updating it if it was saved before
     pub async fn save(&self, run: &ScheduleRun) -> Result<()> {
         sqlx::query(
             "INSERT INTO schedule_runs (id, started_at, completed_at, status, papers_fetched, papers_saved, error_message)
-             VALUES (?, ?, ?, ?, ?, ?, ?)"
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                completed_at = excluded.completed_at,
+                status = excluded.status,
+                papers_fetched = excluded.papers_fetched,
+                papers_saved = excluded.papers_saved,
+                error_message = excluded.error_message"
         )
         .bind(&run.id)
         .bind(&run.started_at)
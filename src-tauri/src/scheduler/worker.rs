@@ -5,9 +5,10 @@
 
 #![allow(dead_code)]
 
+use crate::budget::{self, BudgetStage};
 use crate::database::SettingsRepository;
 use crate::fetch::FetchManager;
-use crate::models::{FetchOptions, LLMProvider, ScheduleRun, ScheduleRunStatus};
+use crate::models::{FetchOptions, ScheduleRun, ScheduleRunStatus};
 use sqlx::SqlitePool;
 use std::path::PathBuf;
 use thiserror::Error;
@@ -32,6 +33,9 @@ pub enum WorkerError {
     #[error("Schedule not enabled")]
     NotEnabled,
 
+    #[error("LLM budget exhausted: {0}")]
+    BudgetExceeded(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
@@ -99,15 +103,31 @@ impl ScheduledFetchWorker {
         }
 
         // Check if API keys are configured
-        let api_key = match settings.llm_provider {
-            crate::models::LLMProvider::Glm => settings.glm_api_key,
-            crate::models::LLMProvider::Claude => settings.claude_api_key,
-        };
-
-        let api_key = api_key.ok_or_else(|| {
-            self.log("No API key configured");
-            WorkerError::Settings("No API key configured".to_string())
+        let backend = crate::llm::get_backend(&settings.llm_provider)
+            .map_err(|e| WorkerError::Settings(e.to_string()))?;
+        backend.validate_config(&settings).map_err(|e| {
+            self.log(&e);
+            WorkerError::Settings(e)
         })?;
+        let provider_config = backend.provider_config(&settings);
+        let api_key = provider_config.api_key.clone().unwrap_or_default();
+
+        // Skip the run while the LLM budget is used up. The run is recorded as
+        // completed so that budget stops never auto-disable the schedule.
+        if let Some(config) = &settings.budget {
+            let status = budget::current_status(&self.pool, config)
+                .await
+                .map_err(|e| WorkerError::Database(e.to_string()))?;
+            if status.stage == BudgetStage::Exhausted {
+                let reason = status.reason.unwrap_or_default();
+                self.log(&format!("Skipping fetch: {}", reason));
+                run.status = ScheduleRunStatus::Completed;
+                run.error_message = Some(format!("Skipped: {}", reason));
+                run.completed_at = Some(chrono::Utc::now().to_rfc3339());
+                self.save_run(&run).await?;
+                return Err(WorkerError::BudgetExceeded(reason));
+            }
+        }
 
         // Check if topics are configured
         if settings.topics.is_empty() {
@@ -146,14 +166,9 @@ impl ScheduledFetchWorker {
         let fetch_options = FetchOptions {
             api_key,
             llm_provider: settings.llm_provider.clone(),
-            quick_model: match settings.llm_provider {
-                LLMProvider::Glm => settings.glm_quick_model.clone(),
-                LLMProvider::Claude => settings.claude_quick_model.clone(),
-            },
-            deep_model: match settings.llm_provider {
-                LLMProvider::Glm => settings.glm_deep_model.clone(),
-                LLMProvider::Claude => settings.claude_deep_model.clone(),
-            },
+            quick_model: provider_config.quick_model,
+            deep_model: provider_config.deep_model,
+            base_url: provider_config.base_url,
             categories: all_categories,
             max_papers,
             days_back: Some(1), // Fetch papers from last day
@@ -168,6 +183,8 @@ impl ScheduledFetchWorker {
             date_to: None,
             fetch_by_id: false, // Scheduled fetch always uses category mode
             arxiv_ids: None, // Not used for scheduled fetch
+            topic_thresholds: None,
+            watchlist: None,
         };
 
         // Execute fetch without UI events
@@ -186,8 +203,17 @@ impl ScheduledFetchWorker {
         run.status = ScheduleRunStatus::Completed;
         run.completed_at = Some(chrono::Utc::now().to_rfc3339());
 
+        // Note a fetch cut short by the budget
+        if let Some(reason) = &fetch_result.budget_exhausted {
+            run.error_message = Some(format!("Stopped early: {}", reason));
+        }
+
         self.save_run(&run).await?;
 
+        if let Err(e) = crate::embeddings::refresh_embeddings(&self.pool).await {
+            self.log(&format!("Failed to embed papers: {}", e));
+        }
+
         let duration = start_time.elapsed();
         self.log(&format!(
             "[{}] Completed: {} papers fetched, {} saved in {:.2}s",
@@ -323,6 +349,12 @@ mod tests {
         assert_eq!(err.to_string(), "Schedule not enabled");
     }
 
+    #[test]
+    fn test_worker_error_budget() {
+        let err = WorkerError::BudgetExceeded("Daily cost budget exhausted ($1.02 of $1.00)".to_string());
+        assert_eq!(err.to_string(), "LLM budget exhausted: Daily cost budget exhausted ($1.02 of $1.00)");
+    }
+
     #[test]
     fn test_worker_error_from_io() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "file not found");
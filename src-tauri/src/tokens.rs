@@ -0,0 +1,139 @@
+//! Token counting and context windows per model family
+//!
+//! OpenAI models are counted with their own BPE vocabulary. Claude, GLM and
+//! unknown models have no public tokenizer, so they are counted with
+//! `cl100k_base` and scaled up by a safety margin. Counts are used to size
+//! prompts, so they err on the high side.
+
+use tiktoken_rs::{cl100k_base_singleton, o200k_base_singleton, CoreBPE};
+
+/// Context window assumed for models this module does not know
+pub const DEFAULT_CONTEXT_WINDOW: usize = 32_768;
+
+/// Tokenizer family of a model, detected from its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFamily {
+    /// GPT-4o, GPT-4.1, GPT-5 and the o-series (`o200k_base`)
+    OpenAiO200k,
+    /// GPT-4, GPT-4 Turbo and GPT-3.5 (`cl100k_base`)
+    OpenAiCl100k,
+    Claude,
+    Glm,
+    Other,
+}
+
+impl ModelFamily {
+    pub fn detect(model: &str) -> Self {
+        let model = model.to_lowercase();
+        // Gateways often prefix the vendor, e.g. "openai/gpt-4o"
+        let name = model.rsplit('/').next().unwrap_or(&model);
+
+        if name.starts_with("claude") {
+            ModelFamily::Claude
+        } else if name.starts_with("glm") || name.starts_with("chatglm") {
+            ModelFamily::Glm
+        } else if name.starts_with("gpt-4o")
+            || name.starts_with("gpt-4.1")
+            || name.starts_with("gpt-5")
+            || is_o_series(name)
+        {
+            ModelFamily::OpenAiO200k
+        } else if name.starts_with("gpt-4") || name.starts_with("gpt-3.5") {
+            ModelFamily::OpenAiCl100k
+        } else {
+            ModelFamily::Other
+        }
+    }
+
+    /// Factor applied to the `cl100k_base` count of families without a public tokenizer
+    fn scale(self) -> f64 {
+        match self {
+            ModelFamily::OpenAiO200k | ModelFamily::OpenAiCl100k => 1.0,
+            ModelFamily::Glm => 1.0,
+            ModelFamily::Claude => 1.15,
+            ModelFamily::Other => 1.2,
+        }
+    }
+
+    fn bpe(self) -> &'static CoreBPE {
+        match self {
+            ModelFamily::OpenAiO200k => o200k_base_singleton(),
+            _ => cl100k_base_singleton(),
+        }
+    }
+}
+
+/// "o1", "o3-mini", "o4-mini", ...
+fn is_o_series(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next() == Some('o') && chars.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Number of tokens `text` takes up for `model`
+pub fn count_tokens(model: &str, text: &str) -> usize {
+    let family = ModelFamily::detect(model);
+    let tokens = family.bpe().encode_ordinary(text).len();
+    (tokens as f64 * family.scale()).ceil() as usize
+}
+
+/// Model-independent token count, for sizes shown before a model is chosen
+pub fn estimate_tokens(text: &str) -> usize {
+    cl100k_base_singleton().encode_ordinary(text).len()
+}
+
+/// Context window (prompt plus reply) of `model` in tokens
+pub fn context_window(model: &str) -> usize {
+    let model = model.to_lowercase();
+    let name = model.rsplit('/').next().unwrap_or(&model);
+
+    match ModelFamily::detect(name) {
+        ModelFamily::Claude => 200_000,
+        ModelFamily::Glm => 128_000,
+        ModelFamily::OpenAiO200k if name.starts_with("gpt-4.1") => 1_047_576,
+        ModelFamily::OpenAiO200k if name.starts_with("gpt-5") => 400_000,
+        ModelFamily::OpenAiO200k if is_o_series(name) && !name.starts_with("o1-mini") => 200_000,
+        ModelFamily::OpenAiO200k => 128_000,
+        ModelFamily::OpenAiCl100k if name.starts_with("gpt-4-turbo") || name.contains("preview") => 128_000,
+        ModelFamily::OpenAiCl100k if name.starts_with("gpt-4-32k") => 32_768,
+        ModelFamily::OpenAiCl100k if name.starts_with("gpt-4") => 8_192,
+        ModelFamily::OpenAiCl100k => 16_385,
+        ModelFamily::Other => DEFAULT_CONTEXT_WINDOW,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_family() {
+        assert_eq!(ModelFamily::detect("gpt-4o-mini"), ModelFamily::OpenAiO200k);
+        assert_eq!(ModelFamily::detect("openai/o3-mini"), ModelFamily::OpenAiO200k);
+        assert_eq!(ModelFamily::detect("gpt-4-turbo"), ModelFamily::OpenAiCl100k);
+        assert_eq!(ModelFamily::detect("claude-3-5-sonnet-20241022"), ModelFamily::Claude);
+        assert_eq!(ModelFamily::detect("GLM-4-Plus"), ModelFamily::Glm);
+        assert_eq!(ModelFamily::detect("llama3.1:70b"), ModelFamily::Other);
+        assert_eq!(ModelFamily::detect("olmo-2"), ModelFamily::Other);
+    }
+
+    #[test]
+    fn test_count_tokens() {
+        let text = "Mixture-of-experts models route each token to a few experts.";
+        let base = estimate_tokens(text);
+        assert!(base > 5 && base < text.len() / 2);
+        assert_eq!(count_tokens("gpt-4", text), base);
+        assert!(count_tokens("claude-3-5-haiku-20241022", text) > base);
+        assert!(count_tokens("local-model", text) >= count_tokens("claude-3-5-haiku-20241022", text));
+        assert_eq!(count_tokens("gpt-4o", ""), 0);
+    }
+
+    #[test]
+    fn test_context_window() {
+        assert_eq!(context_window("claude-3-5-sonnet-20241022"), 200_000);
+        assert_eq!(context_window("glm-4-plus"), 128_000);
+        assert_eq!(context_window("gpt-4o"), 128_000);
+        assert_eq!(context_window("gpt-4"), 8_192);
+        assert_eq!(context_window("gpt-4-turbo"), 128_000);
+        assert_eq!(context_window("mistral-7b"), DEFAULT_CONTEXT_WINDOW);
+    }
+}
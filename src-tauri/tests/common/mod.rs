@@ -0,0 +1,515 @@
+//! Offline stand-ins for ArXiv and the LLM provider
+//!
+//! Both are tiny HTTP servers on localhost fed from `tests/fixtures`:
+//! - `ArxivStandIn` serves a recorded Atom feed for `/api/query`, LaTeXML HTML
+//!   for `/html/{id}` when a fixture exists, and 404 for everything else.
+//! - `MockLlm` speaks the OpenAI chat completions API and answers with the
+//!   canned JSON for the paper named in the prompt. A fixture set overrides
+//!   individual replies from `llm/{set}/`. Every reply reports the same
+//!   token usage (`MOCK_PROMPT_TOKENS` / `MOCK_COMPLETION_TOKENS`).
+
+#![allow(dead_code)]
+
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+use tauri_app_lib::{FetchManager, FetchOptions, FetchRunResult, LLMProvider, SettingsRepository, TopicConfig};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Token usage reported with every mock LLM reply
+pub const MOCK_PROMPT_TOKENS: i64 = 1000;
+pub const MOCK_COMPLETION_TOKENS: i64 = 100;
+
+/// Feed served by the ArXiv stand-in of `library`
+pub const FEED: &str = "query_cs_lg.xml";
+
+/// Papers in `fixtures/arxiv/query_cs_lg.xml`: (arXiv ID, title)
+pub const FEED_PAPERS: &[(&str, &str)] = &[
+    ("2401.00001", "Sparse Mixture-of-Experts Routing for Efficient Language Modeling"),
+    ("2401.00002", "Diffusion Policies for Dexterous Robotic Manipulation"),
+    ("2401.00003", "On the Tax Treatment of Stochastic Parrots"),
+];
+
+/// Read a fixture file relative to `tests/fixtures`
+pub fn fixture(path: &str) -> Option<String> {
+    let full_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(path);
+    std::fs::read_to_string(full_path).ok()
+}
+
+/// A parsed HTTP request
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+/// A canned HTTP response
+pub struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+    delay: Option<Duration>,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Self {
+        Self { status: 200, content_type, body, delay: None }
+    }
+
+    fn not_found() -> Self {
+        Self { status: 404, content_type: "text/plain", body: "Not Found".to_string(), delay: None }
+    }
+}
+
+/// Serve `handler` on a random local port, one request per connection
+async fn serve<F>(handler: F) -> String
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else {
+                break;
+            };
+            let handler = handler.clone();
+
+            tokio::spawn(async move {
+                let Some(request) = read_request(&mut socket).await else {
+                    return;
+                };
+                let response = handler(request);
+                if let Some(delay) = response.delay {
+                    tokio::time::sleep(delay).await;
+                }
+
+                let head = format!(
+                    "HTTP/1.1 {} MOCK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    response.status,
+                    response.content_type,
+                    response.body.len()
+                );
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(response.body.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+/// Read request line, headers and a Content-Length body
+async fn read_request(socket: &mut tokio::net::TcpStream) -> Option<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        let n = socket.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let content_length = head
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse::<usize>().ok())
+                .flatten()
+        })
+        .unwrap_or(0);
+
+    while buf.len() < header_end + content_length {
+        let n = socket.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let mut request_line = head.lines().next()?.split_whitespace();
+    Some(Request {
+        method: request_line.next()?.to_string(),
+        path: request_line.next()?.to_string(),
+        body: String::from_utf8_lossy(&buf[header_end..]).to_string(),
+    })
+}
+
+/// Local replacement for export.arxiv.org and arxiv.org
+pub struct ArxivStandIn {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl ArxivStandIn {
+    /// Serve `fixtures/arxiv/{feed}` for every API query
+    pub async fn start(feed: &str) -> Self {
+        let feed_xml = fixture(&format!("arxiv/{}", feed)).expect("missing feed fixture");
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+
+        let base_url = serve(move |request| {
+            log.lock().unwrap().push(request.path.clone());
+
+            if request.path.starts_with("/api/query") {
+                return Response::ok("application/atom+xml", feed_xml.clone());
+            }
+            if let Some(id) = request.path.strip_prefix("/html/") {
+                if let Some(html) = fixture(&format!("html/{}.html", id)) {
+                    return Response::ok("text/html", html);
+                }
+            }
+            Response::not_found()
+        })
+        .await;
+
+        Self { base_url, requests }
+    }
+
+    /// Paths requested so far
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Fixture-driven OpenAI-compatible LLM
+pub struct MockLlm {
+    pub base_url: String,
+    relevance_calls: Arc<AtomicUsize>,
+    analysis_calls: Arc<AtomicUsize>,
+    structured_calls: Arc<AtomicUsize>,
+    followup_calls: Arc<AtomicUsize>,
+    upstream_calls: Arc<AtomicUsize>,
+    relevance_prompts: Arc<Mutex<Vec<String>>>,
+    analysis_prompts: Arc<Mutex<Vec<String>>>,
+    translation_prompts: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockLlm {
+    pub async fn start() -> Self {
+        Self::start_with(None, None).await
+    }
+
+    /// Delay every answer, so a fetch can be cancelled mid-flight
+    pub async fn start_with_delay(delay: Option<Duration>) -> Self {
+        Self::start_with(delay, None).await
+    }
+
+    /// Prefer replies from `fixtures/llm/{set}/` over the shared ones
+    pub async fn start_with_fixture_set(set: &'static str) -> Self {
+        Self::start_with(None, Some(set)).await
+    }
+
+    async fn start_with(delay: Option<Duration>, fixture_set: Option<&'static str>) -> Self {
+        let relevance_calls = Arc::new(AtomicUsize::new(0));
+        let analysis_calls = Arc::new(AtomicUsize::new(0));
+        let structured_calls = Arc::new(AtomicUsize::new(0));
+        let followup_calls = Arc::new(AtomicUsize::new(0));
+        let upstream_calls = Arc::new(AtomicUsize::new(0));
+        let relevance_prompts = Arc::new(Mutex::new(Vec::new()));
+        let analysis_prompts = Arc::new(Mutex::new(Vec::new()));
+        let translation_prompts = Arc::new(Mutex::new(Vec::new()));
+        let relevance = relevance_calls.clone();
+        let analysis = analysis_calls.clone();
+        let structured = structured_calls.clone();
+        let followup = followup_calls.clone();
+        let upstream = upstream_calls.clone();
+        let relevance_log = relevance_prompts.clone();
+        let prompts = analysis_prompts.clone();
+        let translations = translation_prompts.clone();
+
+        let base_url = serve(move |request| {
+            if request.method != "POST" || request.path != "/v1/chat/completions" {
+                return Response::not_found();
+            }
+
+            let body: serde_json::Value = serde_json::from_str(&request.body).unwrap_or_default();
+            let prompt = body["messages"][0]["content"].as_str().unwrap_or_default();
+            if body["response_format"]["type"] == "json_schema" {
+                structured.fetch_add(1, Ordering::SeqCst);
+            }
+            if prompt.contains("UPSTREAM ANALYSIS") {
+                upstream.fetch_add(1, Ordering::SeqCst);
+            }
+
+            let kind = if prompt.contains("relevance classifier") {
+                relevance.fetch_add(1, Ordering::SeqCst);
+                relevance_log.lock().unwrap().push(prompt.to_string());
+                "relevance"
+            } else if prompt.contains("RETRY FAILED FIELDS") {
+                followup.fetch_add(1, Ordering::SeqCst);
+                "followup"
+            } else if prompt.contains("TRANSLATE ANALYSIS") {
+                translations.lock().unwrap().push(prompt.to_string());
+                "translation"
+            } else {
+                analysis.fetch_add(1, Ordering::SeqCst);
+                prompts.lock().unwrap().push(prompt.to_string());
+                "standard"
+            };
+
+            // The paper asked about comes last; earlier titles are examples
+            let content = FEED_PAPERS
+                .iter()
+                .filter_map(|paper| Some((prompt.rfind(paper.1)?, paper)))
+                .max_by_key(|(position, _)| *position)
+                .map(|(_, paper)| paper)
+                .and_then(|(id, _)| {
+                    let name = format!("{}_{}.json", kind, id);
+                    fixture_set
+                        .and_then(|set| fixture(&format!("llm/{}/{}", set, name)))
+                        .or_else(|| fixture(&format!("llm/{}", name)))
+                });
+
+            let mut response = match content {
+                Some(content) => Response::ok("application/json", chat_completion(&content)),
+                None => Response {
+                    status: 400,
+                    content_type: "application/json",
+                    body: r#"{"error":{"message":"no fixture for prompt"}}"#.to_string(),
+                    delay: None,
+                },
+            };
+            response.delay = delay;
+            response
+        })
+        .await;
+
+        Self {
+            base_url: format!("{}/v1", base_url),
+            relevance_calls,
+            analysis_calls,
+            structured_calls,
+            followup_calls,
+            upstream_calls,
+            relevance_prompts,
+            analysis_prompts,
+            translation_prompts,
+        }
+    }
+
+    pub fn relevance_calls(&self) -> usize {
+        self.relevance_calls.load(Ordering::SeqCst)
+    }
+
+    pub fn analysis_calls(&self) -> usize {
+        self.analysis_calls.load(Ordering::SeqCst)
+    }
+
+    /// Requests that carried a `json_schema` response format
+    pub fn structured_calls(&self) -> usize {
+        self.structured_calls.load(Ordering::SeqCst)
+    }
+
+    /// Follow-up requests for blocks that failed validation
+    pub fn followup_calls(&self) -> usize {
+        self.followup_calls.load(Ordering::SeqCst)
+    }
+
+    /// Requests that carried upstream block outputs as context
+    pub fn upstream_calls(&self) -> usize {
+        self.upstream_calls.load(Ordering::SeqCst)
+    }
+
+    /// Prompts of the relevance requests so far
+    pub fn relevance_prompts(&self) -> Vec<String> {
+        self.relevance_prompts.lock().unwrap().clone()
+    }
+
+    /// Prompts of the analysis requests so far (follow-ups excluded)
+    pub fn analysis_prompts(&self) -> Vec<String> {
+        self.analysis_prompts.lock().unwrap().clone()
+    }
+
+    /// Prompts of the translation requests so far
+    pub fn translation_prompts(&self) -> Vec<String> {
+        self.translation_prompts.lock().unwrap().clone()
+    }
+}
+
+/// Wrap message content in a chat completions response
+fn chat_completion(content: &str) -> String {
+    serde_json::json!({
+        "id": "chatcmpl-mock",
+        "object": "chat.completion",
+        "model": "mock",
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": "stop"
+        }],
+        "usage": {
+            "prompt_tokens": MOCK_PROMPT_TOKENS,
+            "completion_tokens": MOCK_COMPLETION_TOKENS,
+            "total_tokens": MOCK_PROMPT_TOKENS + MOCK_COMPLETION_TOKENS
+        }
+    })
+    .to_string()
+}
+
+/// Scratch directory removed on drop
+pub struct TestDir {
+    path: PathBuf,
+    _lock: Option<tokio::sync::MutexGuard<'static, ()>>,
+}
+
+impl TestDir {
+    pub fn new() -> Self {
+        Self::with_lock(None)
+    }
+
+    /// Scratch directory that holds `pipeline_lock` until dropped
+    pub async fn locked() -> Self {
+        Self::with_lock(Some(pipeline_lock().await))
+    }
+
+    fn with_lock(lock: Option<tokio::sync::MutexGuard<'static, ()>>) -> Self {
+        let path = std::env::temp_dir().join(format!("paperfuse-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path, _lock: lock }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+static ISOLATE_HOME: Once = Once::new();
+static PIPELINE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Serialize pipeline tests and keep them out of the real home directory
+///
+/// The LLM response cache and the default LaTeX folder live under `$HOME`
+/// and are shared by every test in the process.
+pub async fn pipeline_lock() -> tokio::sync::MutexGuard<'static, ()> {
+    ISOLATE_HOME.call_once(|| {
+        let home = std::env::temp_dir().join(format!("paperfuse-home-{}", std::process::id()));
+        std::fs::create_dir_all(&home).unwrap();
+        std::env::set_var("HOME", home);
+    });
+    PIPELINE_LOCK.lock().await
+}
+
+/// Fresh on-disk database with the full schema
+pub async fn setup_db(dir: &TestDir) -> SqlitePool {
+    let options = SqliteConnectOptions::new()
+        .filename(dir.path().join("paperfuse.db"))
+        .create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(4)
+        .connect_with(options)
+        .await
+        .expect("Failed to open test database");
+
+    tauri_app_lib::create_schema(&pool).await.expect("Failed to create schema");
+    pool
+}
+
+/// Database plus a LaTeX download folder inside the test directory
+pub async fn setup_pipeline_db(dir: &TestDir) -> SqlitePool {
+    let pool = setup_db(dir).await;
+    let latex_dir = dir.path().join("latex");
+    SettingsRepository::new(&pool)
+        .set("latex_download_path", &latex_dir.to_string_lossy())
+        .await
+        .unwrap();
+    pool
+}
+
+/// Empty library for a pipeline test: locked scratch directory, database,
+/// ArXiv stand-in serving `FEED`, `llm`, and a fetch manager using both
+pub async fn library(llm: MockLlm) -> (TestDir, SqlitePool, ArxivStandIn, MockLlm, FetchManager) {
+    let dir = TestDir::locked().await;
+    let pool = setup_pipeline_db(&dir).await;
+    let arxiv = ArxivStandIn::start(FEED).await;
+    let manager = FetchManager::new(pool.clone()).with_arxiv_base_url(&arxiv.base_url);
+    (dir, pool, arxiv, llm, manager)
+}
+
+/// `library` with the default mock LLM, after one fetch
+pub async fn fetched_library() -> (TestDir, SqlitePool, ArxivStandIn, MockLlm, FetchManager) {
+    let (dir, pool, arxiv, llm, manager) = library(MockLlm::start().await).await;
+    fetch(&manager, &llm).await;
+    (dir, pool, arxiv, llm, manager)
+}
+
+/// Fetch the feed with the default options and topics
+pub async fn fetch(manager: &FetchManager, llm: &MockLlm) -> FetchRunResult {
+    manager
+        .fetch_papers(fetch_options(llm), topics(), None)
+        .await
+        .expect("fetch failed")
+}
+
+pub fn topics() -> Vec<TopicConfig> {
+    let topic = |key: &str, label: &str, categories: &[&str]| TopicConfig {
+        key: key.to_string(),
+        label: label.to_string(),
+        description: String::new(),
+        color: "blue".to_string(),
+        enabled: true,
+        arxiv_categories: Some(categories.iter().map(|c| c.to_string()).collect()),
+        max_papers_per_day: None,
+        deep_analysis_count: None,
+        quick_score_threshold: None,
+        keywords: None,
+        negative_keywords: None,
+        allowed_authors: None,
+        category_weights: None,
+        rule: None,
+    };
+
+    vec![
+        topic("llm", "Large Language Models", &["cs.CL", "cs.LG"]),
+        topic("robotics", "Robotics", &["cs.RO"]),
+    ]
+}
+
+/// Fetch options pointing the OpenAI-compatible provider at `llm`
+pub fn fetch_options(llm: &MockLlm) -> FetchOptions {
+    FetchOptions {
+        api_key: String::new(),
+        llm_provider: LLMProvider::OpenAiCompatible,
+        quick_model: Some("mock-quick".to_string()),
+        deep_model: Some("mock-deep".to_string()),
+        base_url: Some(llm.base_url.clone()),
+        categories: vec!["cs.LG".to_string()],
+        max_papers: 10,
+        days_back: None,
+        date_from: None,
+        date_to: None,
+        min_relevance: 50,
+        deep_analysis: true,
+        deep_analysis_threshold: Some(70),
+        analysis_mode: Some("standard".to_string()),
+        async_mode: None,
+        max_concurrent: None,
+        language: Some("en".to_string()),
+        fetch_by_id: false,
+        arxiv_ids: None,
+        topic_thresholds: None,
+        watchlist: None,
+    }
+}
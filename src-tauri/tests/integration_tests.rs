@@ -1,32 +1,805 @@
 //! Integration tests for PaperFuse Desktop
 //!
-//! These tests verify the full integration between Rust backend and Tauri commands
-
-#[cfg(test)]
-mod integration_tests {
-    // Note: These tests require a running Tauri application
-    // They can be run using `cargo tauri test` or similar
-
-    #[test]
-    fn test_placeholder() {
-        // Placeholder for future integration tests
-        // Examples of what to test:
-        // - Full fetch pipeline with ArXiv + LLM + Database
-        // - Schedule enable/disable cycle
-        // - Paper CRUD workflow
-        // - Settings persistence
-        assert!(true);
+//! Run the full fetch pipeline (ArXiv -> relevance -> deep analysis -> database)
+//! against the local ArXiv stand-in and mock LLM in `common`, with no network.
+
+mod common;
+
+use common::{
+    fetch, fetch_options, fetched_library, library, topics, MockLlm, MOCK_COMPLETION_TOKENS, MOCK_PROMPT_TOKENS,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri_app_lib::{
+    hybrid_search, recommend, refresh_embeddings, relevance_report, similar_papers, translate_analysis, update_embeddings, AnalysisBlockRepository,
+    AnalysisRunRepository, AuthorRepository, CitationRepository, ClassificationCacheRepository, CollectionRepository, CreateCollection, CustomBlockInput,
+    CustomBlockRepository, Embedder, EmbeddingRepository, FeedbackRepository, FetchError, FetchHistoryRepository, InteractionRepository,
+    LLMProvider, LlmClient, LlmUsageRepository, ModelPrice, NewAnalysisRun, PaperRepository, SettingsRepository,
+    WatchlistConfig, WatchlistMode,
+};
+
+#[tokio::test]
+async fn test_full_fetch_pipeline() {
+    let (_dir, pool, arxiv, llm, manager) = library(MockLlm::start().await).await;
+    let result = fetch(&manager, &llm).await;
+
+    assert!(result.errors.is_empty(), "unexpected errors: {:?}", result.errors);
+    assert_eq!(result.papers_fetched, 3);
+    assert_eq!(result.papers_analyzed, 3);
+    assert_eq!(result.papers_saved, 2);
+    assert_eq!(result.papers_filtered, 1);
+    assert_eq!(result.papers_cache_hits, 0);
+    assert_eq!(llm.relevance_calls(), 3);
+    assert_eq!(llm.analysis_calls(), 2);
+    assert_eq!(llm.structured_calls(), 2);
+
+    let repo = PaperRepository::new(&pool);
+
+    // HTML available: analysed from the LaTeXML page
+    let moe = repo.get_by_id("2401.00001").await.unwrap();
+    assert_eq!(moe.filter_score, Some(92));
+    assert_eq!(moe.topics, vec!["llm".to_string()]);
+    assert_eq!(moe.content_source.as_deref(), Some("html"));
+    assert_eq!(moe.analysis_mode.as_deref(), Some("standard"));
+    assert!(moe.is_deep_analyzed);
+    assert!(!moe.analysis_incomplete);
+    assert!(moe.code_available);
+    assert!(moe.ai_summary.unwrap().contains("load-balanced router"));
+    assert_eq!(moe.key_insights.map(|k| k.len()), Some(2));
+    // Quality assessment only runs in full mode by default
+    assert_eq!(moe.novelty_score, None);
+
+    // Every block's output is also kept per block
+    let blocks = moe.analysis_blocks.expect("block results not loaded");
+    let block_ids: Vec<&str> = blocks.iter().map(|b| b.block_id.as_str()).collect();
+    assert_eq!(block_ids, vec!["ai_summary", "code_links", "engineering_notes", "key_insights", "topics"]);
+    assert_eq!(blocks[1].json["code_available"], true);
+    assert_eq!(blocks[4].json["suggested_topics"][0], "llm");
+
+    // No HTML and no e-print: analysed from the abstract only
+    let diffusion = repo.get_by_id("2401.00002").await.unwrap();
+    assert_eq!(diffusion.filter_score, Some(74));
+    assert!(diffusion.is_deep_analyzed);
+    assert!(diffusion.analysis_incomplete);
+    assert!(diffusion.engineering_notes.unwrap().contains("denoising"));
+
+    // Below min_relevance: never saved
+    assert!(repo.get_by_id("2401.00003").await.is_err());
+
+    let requests = arxiv.requests();
+    assert!(requests.iter().any(|p| p.starts_with("/api/query")));
+    assert!(requests.contains(&"/html/2401.00001".to_string()));
+    assert!(requests.contains(&"/e-print/2401.00002".to_string()));
+}
+
+#[tokio::test]
+async fn test_references_link_library_papers() {
+    let (_dir, pool, _arxiv, _llm, _manager) = fetched_library().await;
+
+    // The HTML bibliography cites 2401.00002 by arXiv ID
+    let citations = CitationRepository::new(&pool);
+    let references = citations.get_for_paper("2401.00001").await.unwrap();
+    assert_eq!(references.len(), 1);
+    assert_eq!(references[0].cited_arxiv_id.as_deref(), Some("2401.00002"));
+
+    let cited = citations.cited_papers("2401.00001").await.unwrap();
+    assert_eq!(cited.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(), vec!["2401.00002"]);
+    let citing = citations.citing_papers("2401.00002").await.unwrap();
+    assert_eq!(citing.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(), vec!["2401.00001"]);
+    assert!(citations.citing_papers("2401.00001").await.unwrap().is_empty());
+
+    let graph = citations.graph().await.unwrap();
+    assert_eq!(graph.nodes.len(), 2);
+    assert_eq!(graph.edges.len(), 1);
+    assert_eq!(graph.edges[0].source, "2401.00001");
+    assert_eq!(graph.edges[0].target, "2401.00002");
+
+    // Deleting the citing paper drops its references
+    PaperRepository::new(&pool).delete("2401.00001").await.unwrap();
+    assert!(citations.get_for_paper("2401.00001").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_hybrid_search_embeds_library_papers() {
+    let (_dir, pool, _arxiv, _llm, _manager) = fetched_library().await;
+
+    let settings = SettingsRepository::new(&pool).get_all().await.unwrap();
+    let embedder = Embedder::from_settings(&settings).unwrap();
+
+    // Searching never embeds; papers without a vector are found by keyword only
+    let hits = hybrid_search(&pool, &embedder, "expert routing", 5, 0.5).await.unwrap();
+    assert_eq!(hits[0].paper.id, "2401.00001");
+    assert_eq!(hits[0].semantic_score, None);
+    assert!(EmbeddingRepository::new(&pool).vectors(&embedder.model_id()).await.unwrap().is_empty());
+
+    // Both saved papers get a vector, and unchanged papers are not embedded again
+    assert_eq!(refresh_embeddings(&pool).await.unwrap(), 2);
+    let vectors = EmbeddingRepository::new(&pool).vectors(&embedder.model_id()).await.unwrap();
+    assert_eq!(vectors.len(), 2);
+    assert_eq!(update_embeddings(&pool, &embedder).await.unwrap(), 0);
+
+    let hits = hybrid_search(&pool, &embedder, "expert routing", 5, 0.5).await.unwrap();
+    assert_eq!(hits[0].paper.id, "2401.00001");
+    assert!(hits[0].keyword_score.is_some());
+    assert!(hits[0].semantic_score.is_some());
+
+    // Similarity alone still finds a paper through related word forms
+    let hits = hybrid_search(&pool, &embedder, "dexterity manipulators", 5, 1.0).await.unwrap();
+    assert_eq!(hits[0].paper.id, "2401.00002");
+    assert_eq!(hits[0].keyword_score, None);
+}
+
+#[tokio::test]
+async fn test_recommendations_follow_kept_papers() {
+    let (_dir, pool, _arxiv, _llm, _manager) = fetched_library().await;
+    let embedder = Embedder::from_settings(&SettingsRepository::new(&pool).get_all().await.unwrap()).unwrap();
+    // A paper that is not embedded yet has no similar papers
+    assert!(similar_papers(&pool, &embedder, "2401.00001", 10).await.unwrap().is_empty());
+    update_embeddings(&pool, &embedder).await.unwrap();
+
+    let collections = CollectionRepository::new(pool.clone());
+    let collection = collections
+        .create(CreateCollection { name: "Reading".to_string(), description: None, color: None })
+        .await
+        .unwrap();
+    collections.add_paper(&collection.id, "2401.00001").await.unwrap();
+
+    // The kept paper is no longer a candidate, the other fetched paper is
+    let recommendations = recommend(&pool, &embedder, 7, 10).await.unwrap();
+    let ids: Vec<&str> = recommendations.iter().map(|r| r.paper.id.as_str()).collect();
+    assert_eq!(ids, vec!["2401.00002"]);
+
+    // Opened papers are not recommended again
+    InteractionRepository::new(&pool).record_open("2401.00002").await.unwrap();
+    assert!(recommend(&pool, &embedder, 7, 10).await.unwrap().is_empty());
+
+    // Spam never shows up as similar
+    PaperRepository::new(&pool).toggle_spam("2401.00002", true).await.unwrap();
+    assert!(similar_papers(&pool, &embedder, "2401.00001", 10).await.unwrap().is_empty());
+    assert!(similar_papers(&pool, &embedder, "9999.99999", 10).await.is_err());
+
+    // Deleting a paper forgets its opens
+    PaperRepository::new(&pool).delete("2401.00002").await.unwrap();
+    let signals = InteractionRepository::new(&pool).signals().await.unwrap();
+    assert_eq!(signals.len(), 1);
+    assert_eq!(signals[0].collection_count, 1);
+}
+
+#[tokio::test]
+async fn test_relevance_feedback_guides_next_fetch() {
+    let (_dir, pool, _arxiv, llm, manager) = fetched_library().await;
+    assert!(llm.relevance_prompts().iter().all(|p| !p.contains("already judged")));
+
+    let feedback = FeedbackRepository::new(&pool);
+    feedback.set("2401.00001", "llm", true).await.unwrap();
+    feedback.set("2401.00002", "robotics", true).await.unwrap();
+    // Changing a verdict replaces it
+    feedback.set("2401.00002", "robotics", false).await.unwrap();
+    assert_eq!(feedback.get_for_paper("2401.00002").await.unwrap().len(), 1);
+
+    // Both papers passed the threshold, but only one was wanted
+    let report = relevance_report(&pool, 50).await.unwrap();
+    assert_eq!(report.overall.feedback_count, 2);
+    assert_eq!(report.overall.precision, Some(0.5));
+    assert_eq!(report.overall.recall, Some(1.0));
+    assert_eq!(report.topics.len(), 2);
+    // Raising the threshold above the unwanted paper's score drops it
+    let report = relevance_report(&pool, 80).await.unwrap();
+    assert_eq!(report.overall.precision, Some(1.0));
+
+    // The next fetch shows the judged papers to the relevance model and
+    // applies per-topic thresholds
+    PaperRepository::new(&pool).delete("2401.00001").await.unwrap();
+    ClassificationCacheRepository::new(&pool).clear_all().await.unwrap();
+    let mut options = fetch_options(&llm);
+    options.topic_thresholds = Some([("llm".to_string(), 95)].into_iter().collect());
+    manager.fetch_papers(options, topics(), None).await.expect("refetch failed");
+
+    let prompt = llm
+        .relevance_prompts()
+        .into_iter()
+        .rfind(|p| p.contains("Sparse Mixture-of-Experts Routing"))
+        .unwrap();
+    assert!(prompt.contains(
+        "- [NOT RELEVANT] Topic: robotics | Title: Diffusion Policies for Dexterous Robotic Manipulation"
+    ));
+    assert!(PaperRepository::new(&pool).get_by_id("2401.00001").await.is_err());
+}
+
+#[tokio::test]
+async fn test_prefilter_decides_clear_cut_papers_locally() {
+    let (_dir, pool, _arxiv, llm, manager) = library(MockLlm::start().await).await;
+    SettingsRepository::new(&pool)
+        .set("prefilter", r#"{"enabled":true,"floor":5,"ceiling":40}"#)
+        .await
+        .unwrap();
+    let mut topics = topics();
+    topics[0].keywords = Some(vec!["mixture".to_string(), "experts".to_string(), "language model".to_string()]);
+    topics[0].negative_keywords = Some(vec!["tax".to_string()]);
+    topics[1].keywords = Some(vec!["manipulation".to_string(), "policies".to_string()]);
+
+    let result = manager
+        .fetch_papers(fetch_options(&llm), topics, None)
+        .await
+        .expect("fetch failed");
+
+    // The MoE paper is accepted and the tax paper dropped locally; only the
+    // robotics paper, in between, goes to the LLM
+    assert!(result.errors.is_empty(), "unexpected errors: {:?}", result.errors);
+    assert_eq!(llm.relevance_calls(), 1);
+    assert!(llm.relevance_prompts()[0].contains("Diffusion Policies"));
+    assert_eq!(result.papers_saved, 2);
+    assert_eq!(result.papers_filtered, 1);
+    assert_eq!(manager.get_status().await.llm_calls_saved, 2);
+
+    let paper = PaperRepository::new(&pool).get_by_id("2401.00001").await.unwrap();
+    assert!(paper.filter_reason.unwrap().starts_with("Pre-filter: "));
+    assert_eq!(paper.topics, vec!["llm"]);
+}
+
+#[tokio::test]
+async fn test_topic_rules_gate_papers_before_relevance() {
+    let (_dir, pool, _arxiv, llm, manager) = library(MockLlm::start().await).await;
+    let mut topics = topics();
+    topics[0].rule = Some(r#"(mixture OR "language model") AND NOT robotic"#.to_string());
+    topics[1].rule = Some("category:cs.RO".to_string());
+
+    let result = manager
+        .fetch_papers(fetch_options(&llm), topics.clone(), None)
+        .await
+        .expect("fetch failed");
+
+    // The tax paper matches no topic's rule and never reaches the LLM
+    assert!(result.errors.is_empty(), "unexpected errors: {:?}", result.errors);
+    assert_eq!(llm.relevance_calls(), 2);
+    // Rule drops are filtered papers, not pre-filter decisions
+    assert_eq!(result.papers_filtered, 1);
+    assert_eq!(result.llm_calls_saved, 0);
+
+    // Topics whose rule failed are not offered to the relevance model
+    let prompt = llm
+        .relevance_prompts()
+        .into_iter()
+        .find(|p| p.contains("Sparse Mixture-of-Experts Routing"))
+        .unwrap();
+    assert!(!prompt.contains(r#""robotics""#));
+
+    let repo = PaperRepository::new(&pool);
+    let reason = repo.get_by_id("2401.00001").await.unwrap().filter_reason.unwrap();
+    assert!(
+        reason.contains(r#"Matched rule for Large Language Models: mentions "mixture" and does not mention "robotic""#),
+        "{}",
+        reason
+    );
+    let reason = repo.get_by_id("2401.00002").await.unwrap().filter_reason.unwrap();
+    assert!(reason.contains("Matched rule for Robotics: is in cs.RO"), "{}", reason);
+
+    // A broken rule stops the fetch before anything is requested
+    topics[1].rule = Some("(category:cs.RO".to_string());
+    let err = manager.fetch_papers(fetch_options(&llm), topics, None).await.unwrap_err();
+    assert_eq!(err.error_type(), "rule");
+}
+
+#[tokio::test]
+async fn test_watchlist_keeps_papers_and_finds_them_by_author() {
+    let (dir, pool, _arxiv, llm, manager) = library(MockLlm::start().await).await;
+
+    // Scores are 92, 74 and 8, so nothing passes 95 on its own
+    let watchlist = WatchlistConfig {
+        authors: vec!["Hopper, G. M.".to_string()],
+        affiliations: vec!["analytical engine".to_string()],
+        mode: WatchlistMode::Bypass,
+        boost: 25,
+    };
+    let mut options = fetch_options(&llm);
+    options.min_relevance = 95;
+    options.watchlist = Some(watchlist.clone());
+
+    let result = manager.fetch_papers(options.clone(), topics(), None).await.expect("fetch failed");
+    assert!(result.errors.is_empty(), "unexpected errors: {:?}", result.errors);
+    assert_eq!(result.papers_saved, 2);
+    assert_eq!(result.papers_filtered, 1);
+
+    let repo = PaperRepository::new(&pool);
+    let paper = repo.get_by_id("2401.00002").await.unwrap();
+    assert_eq!(paper.filter_score, Some(74));
+    let reason = paper.filter_reason.unwrap();
+    assert!(reason.contains("(watched author Grace Hopper)"), "{}", reason);
+    let reason = repo.get_by_id("2401.00001").await.unwrap().filter_reason.unwrap();
+    assert!(reason.contains("Ada Lovelace at watched affiliation analytical engine"), "{}", reason);
+
+    // Saved papers are found by author, with initials and either name order
+    let authors = AuthorRepository::new(&pool);
+    let ids = |papers: Vec<tauri_app_lib::Paper>| papers.into_iter().map(|p| p.id).collect::<Vec<_>>();
+    assert_eq!(ids(authors.papers_by_author("A. Lovelace", 20, 0).await.unwrap()), vec!["2401.00001"]);
+    assert_eq!(ids(authors.papers_by_author("hopper, grace", 20, 0).await.unwrap()), vec!["2401.00002"]);
+    assert!(authors.papers_by_author("B. Lovelace", 20, 0).await.unwrap().is_empty());
+
+    // Papers without author rows are indexed at startup
+    sqlx::query("DELETE FROM paper_authors").execute(&pool).await.unwrap();
+    assert!(authors.papers_by_author("Turing", 20, 0).await.unwrap().is_empty());
+    tauri_app_lib::create_schema(&pool).await.unwrap();
+    assert_eq!(ids(authors.papers_by_author("Turing", 20, 0).await.unwrap()), vec!["2401.00001"]);
+    assert_eq!(authors.index_missing().await.unwrap(), 0);
+
+    // In boost mode the score is raised instead, and still has to pass
+    options.watchlist = Some(WatchlistConfig { mode: WatchlistMode::Boost, ..watchlist });
+    // A fresh library; the first one holds the pipeline lock until dropped
+    drop((dir, pool, manager));
+    let (_dir, pool, _arxiv, _llm, manager) = library(llm).await;
+    let result = manager.fetch_papers(options, topics(), None).await.expect("fetch failed");
+    assert_eq!(result.papers_saved, 2);
+    let repo = PaperRepository::new(&pool);
+    assert_eq!(repo.get_by_id("2401.00001").await.unwrap().filter_score, Some(100));
+    assert_eq!(repo.get_by_id("2401.00002").await.unwrap().filter_score, Some(99));
+}
+
+#[tokio::test]
+async fn test_invalid_blocks_are_rerequested_and_recorded() {
+    let (_dir, pool, _arxiv, llm, manager) = library(MockLlm::start_with_fixture_set("partial").await).await;
+    let result = fetch(&manager, &llm).await;
+
+    assert!(result.errors.is_empty(), "unexpected errors: {:?}", result.errors);
+    assert_eq!(result.papers_saved, 2);
+    assert_eq!(llm.analysis_calls(), 2);
+    // Only the paper with invalid blocks gets a follow-up
+    assert_eq!(llm.followup_calls(), 1);
+
+    let repo = PaperRepository::new(&pool);
+
+    // key_insights was fixed by the follow-up, code_links failed twice
+    let moe = repo.get_by_id("2401.00001").await.unwrap();
+    assert!(moe.is_deep_analyzed);
+    assert!(moe.ai_summary.unwrap().contains("load-balanced router"));
+    assert!(moe.engineering_notes.is_some());
+    assert_eq!(moe.key_insights.map(|k| k.len()), Some(2));
+    assert!(!moe.code_available);
+    assert_eq!(moe.code_links, None);
+
+    let failed = moe.failed_blocks.expect("failed blocks not recorded");
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].block_id, "code_links");
+    assert!(failed[0].error.contains("code_available"), "{}", failed[0].error);
+
+    let diffusion = repo.get_by_id("2401.00002").await.unwrap();
+    assert!(diffusion.failed_blocks.is_none());
+}
+
+#[tokio::test]
+async fn test_per_block_execution() {
+    let (_dir, pool, _arxiv, llm, manager) = library(MockLlm::start_with_fixture_set("per_block").await).await;
+    let settings = SettingsRepository::new(&pool);
+    settings.set("block_execution_mode", "per_block").await.unwrap();
+    // Quality assessment depends on ai_summary
+    settings
+        .set(
+            "analysis_config",
+            r#"{"blocks":[{"blockId":"quality_assessment","enabled":true,"mode":"both"}]}"#,
+        )
+        .await
+        .unwrap();
+
+    let result = fetch(&manager, &llm).await;
+
+    assert!(result.errors.is_empty(), "unexpected errors: {:?}", result.errors);
+    assert_eq!(result.papers_saved, 2);
+    // Per paper: ai_summary + topics, quality_assessment, key_insights,
+    // code_links, engineering_notes
+    assert_eq!(llm.analysis_calls(), 10);
+    assert_eq!(llm.structured_calls(), 10);
+    // Only quality_assessment waits for an upstream block
+    assert_eq!(llm.upstream_calls(), 2);
+    assert_eq!(llm.followup_calls(), 0);
+
+    let repo = PaperRepository::new(&pool);
+    let moe = repo.get_by_id("2401.00001").await.unwrap();
+    assert!(moe.ai_summary.unwrap().contains("load-balanced router"));
+    assert_eq!(moe.topics, vec!["llm".to_string()]);
+    assert_eq!(moe.novelty_score, Some(7));
+    assert!(moe.code_available);
+    assert!(moe.failed_blocks.is_none());
+}
+
+#[tokio::test]
+async fn test_per_block_skips_dependents_of_failed_blocks() {
+    let (_dir, pool, _arxiv, llm, manager) = library(MockLlm::start_with_fixture_set("per_block").await).await;
+    let settings = SettingsRepository::new(&pool);
+    settings.set("block_execution_mode", "per_block").await.unwrap();
+    // The per_block replies have no algorithms, which the flowchart depends on
+    settings
+        .set(
+            "analysis_config",
+            r#"{"blocks":[{"blockId":"algorithms","enabled":true,"mode":"both"},{"blockId":"flowchart","enabled":true,"mode":"both"}]}"#,
+        )
+        .await
+        .unwrap();
+
+    let result = fetch(&manager, &llm).await;
+
+    assert_eq!(result.papers_saved, 2);
+    // Per paper: the four default calls plus algorithms, never the flowchart
+    assert_eq!(llm.analysis_calls(), 10);
+
+    let moe = PaperRepository::new(&pool).get_by_id("2401.00001").await.unwrap();
+    let failed = moe.failed_blocks.unwrap();
+    let flowchart = failed.iter().find(|f| f.block_id == "flowchart").unwrap();
+    assert_eq!(flowchart.error, "upstream block algorithms failed");
+    assert!(failed.iter().any(|f| f.block_id == "algorithms"));
+}
+
+#[tokio::test]
+async fn test_custom_block_is_prompted_and_persisted() {
+    let (_dir, pool, _arxiv, llm, manager) = library(MockLlm::start_with_fixture_set("custom").await).await;
+    let block: CustomBlockInput = serde_json::from_value(serde_json::json!({
+        "id": "dataset_inventory",
+        "name": { "en": "Dataset Inventory", "zh": "" },
+        "instructions": "List every dataset the paper trains or evaluates on",
+        "jsonSchema": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "Dataset name" },
+                    "public": { "type": "boolean" }
+                }
+            }
+        }
+    }))
+    .unwrap();
+    CustomBlockRepository::new(&pool).create(block).await.unwrap();
+
+    let result = fetch(&manager, &llm).await;
+
+    assert!(result.errors.is_empty(), "unexpected errors: {:?}", result.errors);
+    assert_eq!(result.papers_saved, 2);
+    let prompts = llm.analysis_prompts();
+    assert_eq!(prompts.len(), 2);
+    for prompt in &prompts {
+        assert!(prompt.contains("List every dataset the paper trains or evaluates on"));
+        assert!(prompt.contains(r#""dataset_inventory": [{"name":"Dataset name","public":true}]"#));
     }
 
-    // Example integration test structure (commented out until needed):
-    //
-    // #[tokio::test]
-    // async fn test_full_fetch_pipeline() {
-    //     // This test would:
-    //     // 1. Set up a test database
-    //     // 2. Configure ArXiv and LLM mock responses
-    //     // 3. Run a complete fetch operation
-    //     // 4. Verify papers were saved correctly
-    //     // 5. Clean up test database
-    // }
+    let results = AnalysisBlockRepository::new(&pool);
+    let moe = results.get_history("2401.00001", "dataset_inventory").await.unwrap();
+    assert_eq!(moe.len(), 1);
+    assert_eq!(moe[0].depth, "standard");
+    assert_eq!(moe[0].model.as_deref(), Some("mock-deep"));
+    assert_eq!(moe[0].json["dataset_inventory"][1]["name"], "Internal routing traces");
+
+    // A reply that breaks the block's schema is recorded as a failed block
+    assert!(results.get_history("2401.00002", "dataset_inventory").await.unwrap().is_empty());
+    let diffusion = PaperRepository::new(&pool).get_by_id("2401.00002").await.unwrap();
+    assert!(diffusion.ai_summary.is_some());
+    let failed = diffusion.failed_blocks.expect("failed blocks not recorded");
+    assert_eq!(failed[0].block_id, "dataset_inventory");
+
+    // Deleting the paper drops its block results
+    PaperRepository::new(&pool).delete("2401.00001").await.unwrap();
+    assert!(results.get_latest_for_paper("2401.00001").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_block_results_keep_history() {
+    let (_dir, pool, _arxiv, _llm, _manager) = fetched_library().await;
+
+    // The fetch recorded one run per analyzed paper
+    let runs = AnalysisRunRepository::new(&pool);
+    let first = runs.list_for_paper("2401.00001").await.unwrap();
+    assert_eq!(first.len(), 1);
+    let first = &first[0];
+    assert_eq!(first.provider, "openai");
+    assert_eq!(first.model, "mock-deep");
+    assert!(!first.prompt_hash.is_empty());
+    assert!(first.result["ai_summary"].as_str().unwrap().contains("load-balanced router"));
+    assert!(first.restored_from.is_none());
+
+    // A later full run with another model
+    let rerun = vec![("ai_summary".to_string(), serde_json::json!({ "ai_summary": "Second opinion" }))];
+    let second = NewAnalysisRun {
+        paper_id: "2401.00001".to_string(),
+        provider: "claude".to_string(),
+        model: "other-model".to_string(),
+        depth: "full".to_string(),
+        language: "en".to_string(),
+        prompt_hash: first.prompt_hash.clone(),
+        restored_from: None,
+        translated_from: None,
+    };
+    let second = runs.record(&second, &rerun, &[]).await.unwrap();
+    assert_eq!(second.result, serde_json::json!({ "ai_summary": "Second opinion" }));
+
+    let listed = runs.list_for_paper("2401.00001").await.unwrap();
+    assert_eq!(listed.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), vec![second.id.as_str(), first.id.as_str()]);
+
+    let results = AnalysisBlockRepository::new(&pool);
+    let second_blocks = results.get_for_run(&second.id).await.unwrap();
+    assert_eq!(second_blocks.len(), 1);
+    assert_eq!(second_blocks[0].run_id.as_deref(), Some(second.id.as_str()));
+    assert_eq!(results.get_for_run(&first.id).await.unwrap().len(), 5);
+
+    let history = results.get_history("2401.00001", "ai_summary").await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].model.as_deref(), Some("other-model"));
+    assert_eq!(history[1].model.as_deref(), Some("mock-deep"));
+    assert!(history[1].json["ai_summary"].as_str().unwrap().contains("load-balanced router"));
+
+    // The paper sees the newest output of each block
+    let paper = PaperRepository::new(&pool).get_by_id("2401.00001").await.unwrap();
+    let blocks = paper.analysis_blocks.unwrap();
+    assert_eq!(blocks.len(), 5);
+    let summary = blocks.iter().find(|b| b.block_id == "ai_summary").unwrap();
+    assert_eq!(summary.depth, "full");
+    assert_eq!(summary.json["ai_summary"], "Second opinion");
+
+    // Deleting the paper drops its runs
+    PaperRepository::new(&pool).delete("2401.00001").await.unwrap();
+    assert!(runs.list_for_paper("2401.00001").await.unwrap().is_empty());
+    assert!(runs.get_by_id(&first.id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_translate_analysis_keeps_each_language() {
+    let (_dir, pool, _arxiv, llm, _manager) = fetched_library().await;
+
+    let client = LlmClient::new(
+        LLMProvider::OpenAiCompatible,
+        String::new(),
+        Some("mock-quick".to_string()),
+        Some("mock-deep".to_string()),
+    )
+    .unwrap()
+    .with_base_url(Some(llm.base_url.clone()));
+
+    let run = translate_analysis(&pool, &client, "2401.00001", None, "ja", true).await.unwrap();
+    assert_eq!(run.language, "ja");
+    assert_eq!(run.translated_from.as_deref(), Some("en"));
+    assert_eq!(run.depth, "standard");
+    assert!(run.result["ai_summary"].as_str().unwrap().contains("負荷分散"));
+    // Tags stay in English even when the reply translated them
+    assert_eq!(run.result["suggested_tags"], serde_json::json!(["mixture-of-experts", "routing"]));
+
+    // Only the stored analysis was sent, not the paper content
+    let prompts = llm.translation_prompts();
+    assert_eq!(prompts.len(), 1);
+    assert!(prompts[0].contains("load-balanced router"));
+    assert!(!prompts[0].contains("Paper Abstract"));
+    assert_eq!(llm.analysis_calls(), 2);
+
+    // Both languages are kept side by side
+    let runs = AnalysisRunRepository::new(&pool);
+    assert_eq!(runs.languages_for_paper("2401.00001").await.unwrap(), vec!["ja", "en"]);
+    let results = AnalysisBlockRepository::new(&pool);
+    let english = results.get_latest_for_language("2401.00001", "en").await.unwrap();
+    let japanese = results.get_latest_for_language("2401.00001", "ja").await.unwrap();
+    assert_eq!(english.len(), 5);
+    assert_eq!(japanese.len(), 5);
+    let summary = english.iter().find(|b| b.block_id == "ai_summary").unwrap();
+    assert!(summary.json["ai_summary"].as_str().unwrap().contains("load-balanced router"));
+
+    // The saved paper keeps the analysis it had
+    let paper = PaperRepository::new(&pool).get_by_id("2401.00001").await.unwrap();
+    assert!(paper.ai_summary.unwrap().contains("load-balanced router"));
+
+    let same = translate_analysis(&pool, &client, "2401.00001", Some("ja"), "ja", true).await;
+    assert!(same.unwrap_err().contains("already in Japanese"));
+    let unsupported = translate_analysis(&pool, &client, "2401.00001", None, "xx", true).await;
+    assert!(unsupported.unwrap_err().contains("Unsupported analysis language"));
+    let missing = translate_analysis(&pool, &client, "2401.00001", Some("de"), "fr", true).await;
+    assert!(missing.unwrap_err().contains("No German analysis"));
+    assert_eq!(llm.translation_prompts().len(), 1);
+}
+
+#[tokio::test]
+async fn test_token_usage_is_recorded_and_priced() {
+    let (_dir, pool, _arxiv, _llm, _manager) = fetched_library().await;
+
+    // One relevance call per paper plus a standard analysis for two of them
+    let usage = LlmUsageRepository::new(&pool);
+    let calls = usage.get_for_paper("2401.00001").await.unwrap();
+    assert_eq!(
+        calls.iter().map(|c| (c.analysis_type.as_str(), c.model.as_str())).collect::<Vec<_>>(),
+        vec![("relevance", "mock-quick"), ("standard", "mock-deep")]
+    );
+    assert!(calls.iter().all(|c| c.provider == "openai" && !c.estimated && c.fetch_id.is_some()));
+    assert_eq!(calls[0].topics, vec!["llm"]);
+    assert_eq!(calls[0].prompt_tokens, MOCK_PROMPT_TOKENS);
+    assert_eq!(calls[0].completion_tokens, MOCK_COMPLETION_TOKENS);
+
+    // Only the deep model has a price
+    usage
+        .set_price(&ModelPrice {
+            model: "mock-deep".to_string(),
+            input_per_million: 3.0,
+            output_per_million: 15.0,
+            updated_at: String::new(),
+        })
+        .await
+        .unwrap();
+    let deep_call_cost = (MOCK_PROMPT_TOKENS as f64 * 3.0 + MOCK_COMPLETION_TOKENS as f64 * 15.0) / 1_000_000.0;
+
+    let fetches = usage.summary_by_fetch(10).await.unwrap();
+    assert_eq!(fetches.len(), 1);
+    assert_eq!(fetches[0].key, calls[0].fetch_id);
+    assert_eq!(fetches[0].calls, 5);
+    assert_eq!(fetches[0].prompt_tokens, 5 * MOCK_PROMPT_TOKENS);
+    assert_eq!(fetches[0].unpriced_calls, 3);
+    assert!((fetches[0].cost - 2.0 * deep_call_cost).abs() < 1e-9);
+
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let days = usage.summary_by_day(Some(&today), None).await.unwrap();
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0].key.as_deref(), Some(today.as_str()));
+    assert_eq!(days[0].calls, 5);
+    assert!(usage.summary_by_day(None, Some("2000-01-01")).await.unwrap().is_empty());
+
+    // The robotics paper is also an LLM paper, so it counts toward both topics
+    let by_topic = usage.summary_by_topic(None, None).await.unwrap();
+    let summary: Vec<(Option<&str>, i64, i64)> = by_topic
+        .iter()
+        .map(|t| (t.key.as_deref(), t.calls, t.unpriced_calls))
+        .collect();
+    assert_eq!(summary, vec![(Some("llm"), 4, 2), (Some("robotics"), 2, 1), (None, 1, 1)]);
+    assert!((by_topic[1].cost - deep_call_cost).abs() < 1e-9);
+
+    // Usage outlives the paper
+    PaperRepository::new(&pool).delete("2401.00001").await.unwrap();
+    assert_eq!(usage.get_for_paper("2401.00001").await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_budget_stops_deep_analysis_then_relevance() {
+    let (_dir, pool, _arxiv, llm, manager) = library(MockLlm::start().await).await;
+
+    // Every mock call uses 1100 tokens: the first call passes the deep
+    // analysis cutoff and the second one uses up the budget
+    SettingsRepository::new(&pool)
+        .set("budget", r#"{"dailyTokenLimit":2000,"deepAnalysisCutoff":0.5}"#)
+        .await
+        .unwrap();
+
+    let result = fetch(&manager, &llm).await;
+
+    assert_eq!(llm.relevance_calls(), 2);
+    assert_eq!(llm.analysis_calls(), 0);
+    assert_eq!(result.papers_saved, 2);
+    assert_eq!(
+        result.errors,
+        vec![
+            "Daily token budget 55% used (1100 of 2000 tokens), deep analysis paused",
+            "Daily token budget exhausted (2200 of 2000 tokens), fetch stopped",
+        ]
+    );
+
+    let status = manager.get_status().await;
+    assert_eq!(status.status, "error");
+    assert_eq!(status.errors, result.errors);
+    assert!(status.current_step.contains("Daily token budget exhausted"));
+
+    // The stop is not recorded as a completed fetch, and keeps its counts
+    let reason = result.budget_exhausted.clone().expect("budget not reported as exhausted");
+    assert!(reason.contains("Daily token budget exhausted"), "{}", reason);
+    let history = FetchHistoryRepository::new(&pool).get_all(None).await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].status, "failed");
+    assert_eq!(history[0].papers_saved, 2);
+    assert_eq!(history[0].error_message.as_deref(), Some(reason.as_str()));
+
+    let moe = PaperRepository::new(&pool).get_by_id("2401.00001").await.unwrap();
+    assert_eq!(moe.filter_score, Some(92));
+    assert!(!moe.is_deep_analyzed);
+
+    // The next fetch makes no LLM call at all
+    let result = manager.fetch_papers(fetch_options(&llm), topics(), None).await.unwrap();
+    assert_eq!(llm.relevance_calls(), 2);
+    assert_eq!(result.papers_duplicates, 2);
+    assert_eq!(result.errors, vec!["Daily token budget exhausted (2200 of 2000 tokens), fetch stopped"]);
+}
+
+#[tokio::test]
+async fn test_budget_is_checked_before_each_block_call() {
+    let (_dir, pool, _arxiv, llm, manager) = library(MockLlm::start_with_fixture_set("per_block").await).await;
+    let settings = SettingsRepository::new(&pool);
+    settings.set("block_execution_mode", "per_block").await.unwrap();
+    settings
+        .set(
+            "analysis_config",
+            r#"{"blocks":[{"blockId":"quality_assessment","enabled":true,"mode":"both"}]}"#,
+        )
+        .await
+        .unwrap();
+    // Every mock call uses 1100 tokens: the relevance call leaves room for
+    // deep analysis, and the first block call to finish uses up the budget
+    settings
+        .set("budget", r#"{"dailyTokenLimit":2000,"deepAnalysisCutoff":1.0}"#)
+        .await
+        .unwrap();
+
+    let result = fetch(&manager, &llm).await;
+
+    // At most the first wave of three concurrent block calls is sent, never
+    // the two after it
+    assert_eq!(llm.relevance_calls(), 1);
+    assert!(llm.analysis_calls() <= 3, "{} block calls", llm.analysis_calls());
+    assert!(result.budget_exhausted.is_some());
+
+    let moe = PaperRepository::new(&pool).get_by_id("2401.00001").await.unwrap();
+    let failed = moe.failed_blocks.expect("no failed blocks recorded");
+    assert!(failed.iter().any(|b| b.error.contains("budget exhausted")), "{:?}", failed);
+}
+
+#[tokio::test]
+async fn test_refetch_uses_duplicates_and_classification_cache() {
+    let (_dir, pool, _arxiv, llm, manager) = fetched_library().await;
+    assert_eq!(llm.relevance_calls(), 3);
+
+    // Saved papers are skipped; the filtered one is re-scored from the cache
+    let second = manager.fetch_papers(fetch_options(&llm), topics(), None).await.unwrap();
+    assert_eq!(second.papers_duplicates, 2);
+    assert_eq!(second.papers_cache_hits, 1);
+    assert_eq!(second.papers_filtered, 1);
+    assert_eq!(second.papers_saved, 0);
+    assert_eq!(llm.relevance_calls(), 3);
+    assert_eq!(llm.analysis_calls(), 2);
+
+    // A deleted paper comes back with a cached score but a fresh deep analysis
+    let repo = PaperRepository::new(&pool);
+    repo.delete("2401.00001").await.unwrap();
+
+    let third = manager.fetch_papers(fetch_options(&llm), topics(), None).await.unwrap();
+    assert_eq!(third.papers_saved, 1);
+    assert_eq!(third.papers_duplicates, 1);
+    assert_eq!(third.papers_cache_hits, 2);
+    assert_eq!(llm.relevance_calls(), 3);
+    assert_eq!(llm.analysis_calls(), 3);
+    assert_eq!(repo.get_by_id("2401.00001").await.unwrap().filter_score, Some(92));
+}
+
+#[tokio::test]
+async fn test_async_mode_matches_sync_mode() {
+    let (_dir, pool, _arxiv, llm, manager) = library(MockLlm::start().await).await;
+    let mut options = fetch_options(&llm);
+    options.async_mode = Some("async".to_string());
+    options.max_concurrent = Some(2);
+
+    let result = manager.fetch_papers(options, topics(), None).await.unwrap();
+
+    assert!(result.errors.is_empty(), "unexpected errors: {:?}", result.errors);
+    assert_eq!(result.papers_saved, 2);
+    assert_eq!(result.papers_filtered, 1);
+    assert_eq!(llm.relevance_calls(), 3);
+
+    let repo = PaperRepository::new(&pool);
+    assert_eq!(repo.count().await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_cancel_fetch_mid_pipeline() {
+    let (_dir, pool, _arxiv, llm, manager) = library(MockLlm::start_with_delay(Some(Duration::from_millis(200))).await).await;
+    let manager = Arc::new(manager);
+    let fetch = {
+        let manager = manager.clone();
+        let options = fetch_options(&llm);
+        tokio::spawn(async move { manager.fetch_papers(options, topics(), None).await })
+    };
+
+    // Cancel while the first relevance call is in flight
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while llm.relevance_calls() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("LLM was never called");
+    manager.cancel_fetch().await.unwrap();
+
+    let result = fetch.await.unwrap();
+    assert!(matches!(result, Err(FetchError::Cancelled)), "got {:?}", result);
+    assert!(!manager.is_fetching().await);
+    assert!(llm.relevance_calls() < 3);
+
+    let repo = PaperRepository::new(&pool);
+    assert!(repo.count().await.unwrap() < 2);
+
+    // The manager is usable again after a cancelled run
+    assert!(manager.cancel_fetch().await.is_err());
 }
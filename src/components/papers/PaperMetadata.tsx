@@ -136,7 +136,7 @@ export function PaperMetadata({ paper, showScore = true, variant = 'default' }:
           : 'bg-purple-50 dark:bg-purple-900/20 text-purple-700 dark:text-purple-400';
         return (
           <div className={`flex items-center gap-1 px-2.5 py-0.5 rounded-full text-xs font-medium ${className}`}>
-            <span>{t('papers.detail.metadata.contentSource')}: {isHtml ? 'HTML' : 'LaTeX'}</span>
+            <span>{t('papers.detail.metadata.contentSource')}: {isHtml ? 'HTML' : paper.content_source === 'pdf' ? 'PDF' : 'LaTeX'}</span>
             {paper.estimated_tokens != null && (
               <span className="opacity-75">
                 {formatTokenCount(paper.estimated_tokens)}
@@ -106,7 +106,7 @@ export interface Paper {
   related_papers?: RelatedPaper[];
 
   // HTML parsing fields
-  content_source?: 'html' | 'latex' | 'abstract' | null;
+  content_source?: 'html' | 'latex' | 'pdf' | 'abstract' | null;
   estimated_tokens?: number | null;
   available_sections?: string[] | null;
 }